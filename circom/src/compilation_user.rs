@@ -1,9 +1,10 @@
 use ansi_term::Colour;
 use compiler::compiler_interface;
-use compiler::compiler_interface::{Config, VCP};
+use compiler::compiler_interface::{Config, TagMeaning, VCP};
 use program_structure::error_definition::Report;
 use program_structure::error_code::ReportCode;
 use program_structure::file_definition::FileLibrary;
+use std::collections::BTreeMap;
 use crate::VERSION;
 
 
@@ -21,18 +22,30 @@ pub struct CompilerConfig {
     pub c_flag: bool,
     pub debug_output: bool,
     pub produce_input_log: bool,
+    pub trace_signals: bool,
+    pub trace_templates: Vec<String>,
+    pub c_unit_size: Option<usize>,
+    pub check_tags: BTreeMap<String, TagMeaning>,
     pub vcp: VCP,
 }
 
 pub fn compile(config: CompilerConfig) -> Result<(), ()> {
     let circuit = compiler_interface::run_compiler(
         config.vcp,
-        Config { debug_output: config.debug_output, produce_input_log: config.produce_input_log, wat_flag: config.wat_flag },
+        Config {
+            debug_output: config.debug_output,
+            produce_input_log: config.produce_input_log,
+            wat_flag: config.wat_flag,
+            trace_signals: config.trace_signals,
+            trace_templates: config.trace_templates,
+            c_unit_size: config.c_unit_size,
+            check_tags: config.check_tags,
+        },
         VERSION
     )?;
 
     if config.c_flag {
-        compiler_interface::write_c(&circuit, &config.c_folder, &config.c_run_name, &config.c_file, &config.dat_file)?;
+        let units = compiler_interface::write_c(&circuit, &config.c_folder, &config.c_run_name, &config.c_file, &config.dat_file)?;
         println!(
             "{} {} and {}",
             Colour::Green.paint("Written successfully:"),
@@ -40,7 +53,7 @@ pub fn compile(config: CompilerConfig) -> Result<(), ()> {
             config.dat_file
         );
         println!(
-            "{} {}/{}, {}, {}, {}, {}, {}, {} and {}",
+            "{} {}/{}, {}, {}, {}, {}, {}, {}, {}, {} and {}",
             Colour::Green.paint("Written successfully:"),
 	    &config.c_folder,
             "main.cpp".to_string(),
@@ -50,8 +63,24 @@ pub fn compile(config: CompilerConfig) -> Result<(), ()> {
             "fr.hpp".to_string(),
             "fr.cpp".to_string(),
             "fr.asm".to_string(),
+            "fr_portable.hpp".to_string(),
+            "fr_portable.cpp".to_string(),
             "Makefile".to_string()
         );
+        // the field arithmetic chosen by the makefile
+        println!(
+            "    the Makefile builds fr.asm with nasm on x86_64 and fr_portable.cpp otherwise (FR=asm or FR=portable forces one)"
+        );
+        if units > 0 {
+            println!(
+                "{} {}/{}_0.cpp ... {}_{}.cpp",
+                Colour::Green.paint("Written successfully:"),
+                &config.c_folder,
+                &config.c_run_name,
+                &config.c_run_name,
+                units - 1
+            );
+        }
     }
 
     match (config.wat_flag, config.wasm_flag) {
@@ -2,6 +2,7 @@ use ansi_term::Colour;
 use compiler::hir::very_concrete_program::VCP;
 use constraint_writers::debug_writer::DebugWriter;
 use constraint_writers::ConstraintExporter;
+use program_structure::budget::Budgets;
 use program_structure::program_archive::ProgramArchive;
 
 
@@ -10,6 +11,9 @@ pub struct ExecutionConfig {
     pub sym: String,
     pub json_constraints: String,
     pub no_rounds: usize,
+    pub keep: Vec<String>,
+    pub memory_limit: Option<usize>,
+    pub budgets: Budgets,
     pub flag_s: bool,
     pub flag_f: bool,
     pub flag_p: bool,
@@ -19,7 +23,12 @@ pub struct ExecutionConfig {
     pub sym_flag: bool,
     pub r1cs_flag: bool,
     pub json_substitution_flag: bool,
+    pub json_substitutions: String,
     pub json_constraint_flag: bool,
+    pub certificate_flag: bool,
+    pub certificate: String,
+    pub simplification_stats_flag: bool,
+    pub simplification_stats: String,
     pub prime: String,
 }
 
@@ -31,7 +40,20 @@ pub fn execute_project(
     let debug = DebugWriter::new(config.json_constraints).unwrap();
     let build_config = BuildConfig {
         no_rounds: config.no_rounds,
-        flag_json_sub: config.json_substitution_flag,
+        keep: config.keep,
+        memory_limit: config.memory_limit,
+        budgets: config.budgets,
+        json_substitutions: if config.json_substitution_flag && !config.flag_f {
+            Some(config.json_substitutions.clone())
+        } else {
+            None
+        },
+        certificate: if config.certificate_flag && !config.flag_f { Some(config.certificate.clone()) } else { None },
+        simplification_stats: if config.simplification_stats_flag && !config.flag_f {
+            Some(config.simplification_stats.clone())
+        } else {
+            None
+        },
         flag_s: config.flag_s,
         flag_f: config.flag_f,
         flag_p: config.flag_p,
@@ -51,9 +73,32 @@ pub fn execute_project(
     if config.json_constraint_flag {
         generate_json_constraints(&debug, exporter.as_ref())?;
     }
+    if config.json_substitution_flag && !config.flag_f {
+        println!("{} {}", Colour::Green.paint("Written successfully:"), config.json_substitutions);
+    }
+    if config.certificate_flag && !config.flag_f {
+        println!("{} {}", Colour::Green.paint("Written successfully:"), config.certificate);
+    }
+    if config.simplification_stats_flag && !config.flag_f {
+        println!("{} {}", Colour::Green.paint("Written successfully:"), config.simplification_stats);
+    }
     Result::Ok(vcp)
 }
 
+pub fn check_certificate(files: &[String]) -> Result<(), ()> {
+    use constraint_list::certificate;
+    match certificate::check_certificate(&files[0], &files[1], &files[2]) {
+        Result::Ok(summary) => {
+            println!("{} {}", Colour::Green.paint("Valid certificate:"), summary);
+            Result::Ok(())
+        }
+        Result::Err(message) => {
+            eprintln!("{} {}", Colour::Red.paint("Invalid certificate:"), message);
+            Result::Err(())
+        }
+    }
+}
+
 fn generate_output_r1cs(file: &str, exporter: &dyn ConstraintExporter, custom_gates: bool) -> Result<(), ()> {
     if let Result::Ok(()) = exporter.r1cs(file, custom_gates) {
         println!("{} {}", Colour::Green.paint("Written successfully:"), file);
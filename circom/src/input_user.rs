@@ -1,9 +1,16 @@
-use std::path::PathBuf;
+use compiler::compiler_interface::TagMeaning;
+use parser::Defines;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
+#[derive(Clone)]
 pub struct Input {
     pub input_program: PathBuf,
     pub out_r1cs: PathBuf,
     pub out_json_constraints: PathBuf,
+    pub out_certificate: PathBuf,
+    pub out_simplification_stats: PathBuf,
+    pub out_json_substitutions: PathBuf,
     pub out_wat_code: PathBuf,
     pub out_wasm_code: PathBuf,
     pub out_wasm_name: String,
@@ -21,7 +28,14 @@ pub struct Input {
     pub sym_flag: bool,
     pub json_constraint_flag: bool,
     pub json_substitution_flag: bool,
+    pub certificate_flag: bool,
+    pub simplification_stats_flag: bool,
+    pub check_certificate: Option<Vec<String>>,
     pub main_inputs_flag: bool,
+    pub trace_signals_flag: bool,
+    pub trace_templates: Vec<String>,
+    pub c_unit_size: Option<usize>,
+    pub check_tags: BTreeMap<String, TagMeaning>,
     pub print_ir_flag: bool,
     pub fast_flag: bool,
     pub reduced_simplification_flag: bool,
@@ -29,9 +43,15 @@ pub struct Input {
     pub flag_old_heuristics: bool,
     pub inspect_constraints_flag: bool,
     pub no_rounds: usize,
+    pub keep: Vec<String>,
+    pub memory_limit: Option<usize>,
     pub flag_verbose: bool,
     pub prime: String,
-    pub link_libraries : Vec<PathBuf>
+    pub link_libraries : Vec<PathBuf>,
+    pub manifest: Option<PathBuf>,
+    pub defines: Defines,
+    pub mains: Vec<String>,
+    pub public: Vec<String>,
 }
 
 
@@ -43,19 +63,33 @@ const JS: &'static str = "js";
 const DAT: &'static str = "dat";
 const SYM: &'static str = "sym";
 const JSON: &'static str = "json";
+const JSONL: &str = "jsonl";
 
 
 impl Input {
     pub fn new() -> Result<Input, ()> {
         use input_processing::SimplificationStyle;
         let matches = input_processing::view();
-        let input = input_processing::get_input(&matches)?;
+        let check_certificate = input_processing::get_check_certificate(&matches);
+        // checking a certificate does not compile any circuit
+        let input = if check_certificate.is_some() {
+            PathBuf::from(matches.value_of("input").unwrap())
+        } else {
+            input_processing::get_input(&matches)?
+        };
         let file_name = input.file_stem().unwrap().to_str().unwrap().to_string();
         let output_path = input_processing::get_output_path(&matches)?;
         let output_c_path = Input::build_folder(&output_path, &file_name, CPP);
         let output_js_path = Input::build_folder(&output_path, &file_name, JS);
         let o_style = input_processing::get_simplification_style(&matches)?;
         let link_libraries = input_processing::get_link_libraries(&matches);
+        let manifest = input_processing::get_manifest(&matches, &input);
+        let defines = input_processing::get_defines(&matches)?;
+        let (mains, public) = input_processing::get_mains(&matches)?;
+        let trace_templates = input_processing::get_trace_templates(&matches);
+        let c_unit_size = input_processing::get_c_unit_size(&matches)?;
+        let check_tags = input_processing::get_check_tags(&matches)?;
+        let memory_limit = input_processing::get_memory_limit(&matches)?;
         Result::Ok(Input {
             //field: P_BN128,
             input_program: input,
@@ -74,16 +108,36 @@ impl Input {
                 &format!("{}_constraints", file_name),
                 JSON,
             ),
+            out_certificate: Input::build_output(&output_path, &format!("{}_certificate", file_name), JSON),
+            out_simplification_stats: Input::build_output(
+                &output_path,
+                &format!("{}_simplification", file_name),
+                JSON,
+            ),
+            out_json_substitutions: Input::build_output(
+                &output_path,
+                &format!("{}_substitutions", file_name),
+                JSONL,
+            ),
             wat_flag:input_processing::get_wat(&matches),
             wasm_flag: input_processing::get_wasm(&matches),
             c_flag: input_processing::get_c(&matches),
             r1cs_flag: input_processing::get_r1cs(&matches),
             sym_flag: input_processing::get_sym(&matches),
             main_inputs_flag: input_processing::get_main_inputs_log(&matches),
+            trace_signals_flag: input_processing::get_trace_signals(&matches) || !trace_templates.is_empty(),
+            trace_templates,
+            c_unit_size,
+            check_tags,
             json_constraint_flag: input_processing::get_json_constraints(&matches),
             json_substitution_flag: input_processing::get_json_substitutions(&matches),
+            certificate_flag: input_processing::get_certificate(&matches),
+            simplification_stats_flag: input_processing::get_simplification_stats(&matches),
+            check_certificate,
             print_ir_flag: input_processing::get_ir(&matches),
             no_rounds: if let SimplificationStyle::O2(r) = o_style { r } else { 0 },
+            keep: input_processing::get_keep(&matches),
+            memory_limit,
             fast_flag: o_style == SimplificationStyle::O0,
             reduced_simplification_flag: o_style == SimplificationStyle::O1,
             parallel_simplification_flag: input_processing::get_parallel_simplification(&matches),
@@ -91,10 +145,38 @@ impl Input {
             flag_old_heuristics: input_processing::get_flag_old_heuristics(&matches),
             flag_verbose: input_processing::get_flag_verbose(&matches), 
             prime: input_processing::get_prime(&matches)?,
-            link_libraries
+            link_libraries,
+            manifest,
+            defines,
+            mains,
+            public,
         })
     }
 
+    // the same options with the outputs named after a main component
+    pub fn for_main(&self, name: &str) -> Input {
+        let output_path = self.out_r1cs.parent().map_or_else(PathBuf::new, Path::to_path_buf);
+        let output_c_path = Input::build_folder(&output_path, name, CPP);
+        let output_js_path = Input::build_folder(&output_path, name, JS);
+        Input {
+            out_r1cs: Input::build_output(&output_path, name, R1CS),
+            out_wat_code: Input::build_output(&output_js_path, name, WAT),
+            out_wasm_code: Input::build_output(&output_js_path, name, WASM),
+            out_js_folder: output_js_path.clone(),
+            out_wasm_name: name.to_string(),
+            out_c_folder: output_c_path.clone(),
+            out_c_run_name: name.to_string(),
+            out_c_code: Input::build_output(&output_c_path, name, CPP),
+            out_c_dat: Input::build_output(&output_c_path, name, DAT),
+            out_sym: Input::build_output(&output_path, name, SYM),
+            out_json_constraints: Input::build_output(&output_path, &format!("{}_constraints", name), JSON),
+            out_certificate: Input::build_output(&output_path, &format!("{}_certificate", name), JSON),
+            out_simplification_stats: Input::build_output(&output_path, &format!("{}_simplification", name), JSON),
+            out_json_substitutions: Input::build_output(&output_path, &format!("{}_substitutions", name), JSONL),
+            ..self.clone()
+        }
+    }
+
     fn build_folder(output_path: &PathBuf, filename: &str, ext: &str) -> PathBuf {
         let mut file = output_path.clone();
 	    let folder_name = format!("{}_{}",filename,ext);
@@ -112,6 +194,22 @@ impl Input {
         &self.link_libraries
     }
 
+    pub fn get_manifest(&self) -> Option<&PathBuf> {
+        self.manifest.as_ref()
+    }
+
+    pub fn get_defines(&self) -> &Defines {
+        &self.defines
+    }
+
+    pub fn get_mains(&self) -> &Vec<String> {
+        &self.mains
+    }
+
+    pub fn get_public(&self) -> &Vec<String> {
+        &self.public
+    }
+
     pub fn input_file(&self) -> &str {
         &self.input_program.to_str().unwrap()
     }
@@ -150,6 +248,12 @@ impl Input {
     pub fn json_constraints_file(&self) -> &str {
         self.out_json_constraints.to_str().unwrap()
     }
+    pub fn json_substitutions_file(&self) -> &str {
+        self.out_json_substitutions.to_str().unwrap()
+    }
+    pub fn certificate_file(&self) -> &str {
+        self.out_certificate.to_str().unwrap()
+    }
     pub fn wasm_flag(&self) -> bool {
         self.wasm_flag
     }
@@ -171,9 +275,33 @@ impl Input {
     pub fn json_substitutions_flag(&self) -> bool {
         self.json_substitution_flag
     }
+    pub fn certificate_flag(&self) -> bool {
+        self.certificate_flag
+    }
+    pub fn simplification_stats_file(&self) -> &str {
+        self.out_simplification_stats.to_str().unwrap()
+    }
+    pub fn simplification_stats_flag(&self) -> bool {
+        self.simplification_stats_flag
+    }
+    pub fn check_certificate(&self) -> Option<&Vec<String>> {
+        self.check_certificate.as_ref()
+    }
     pub fn main_inputs_flag(&self) -> bool {
         self.main_inputs_flag
     }
+    pub fn trace_signals_flag(&self) -> bool {
+        self.trace_signals_flag
+    }
+    pub fn trace_templates(&self) -> &Vec<String> {
+        &self.trace_templates
+    }
+    pub fn c_unit_size(&self) -> Option<usize> {
+        self.c_unit_size
+    }
+    pub fn check_tags(&self) -> &BTreeMap<String, TagMeaning> {
+        &self.check_tags
+    }
     pub fn sym_flag(&self) -> bool {
         self.sym_flag
     }
@@ -198,6 +326,12 @@ impl Input {
     pub fn no_rounds(&self) -> usize {
         self.no_rounds
     }
+    pub fn keep(&self) -> &Vec<String> {
+        &self.keep
+    }
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
     pub fn prime(&self) -> String{
         self.prime.clone()
     }
@@ -205,6 +339,9 @@ impl Input {
 mod input_processing {
     use ansi_term::Colour;
     use clap::{App, Arg, ArgMatches};
+    use compiler::compiler_interface::TagMeaning;
+    use parser::Defines;
+    use std::collections::BTreeMap;
     use std::path::{Path, PathBuf};
     use crate::VERSION;
 
@@ -259,6 +396,19 @@ mod input_processing {
         matches.is_present("print_json_sub")
     }
 
+    pub fn get_certificate(matches: &ArgMatches) -> bool {
+        matches.is_present("certificate")
+    }
+
+    pub fn get_simplification_stats(matches: &ArgMatches) -> bool {
+        matches.is_present("simplification_stats")
+    }
+
+    // the original r1cs, the simplified r1cs and the certificate
+    pub fn get_check_certificate(matches: &ArgMatches) -> Option<Vec<String>> {
+        matches.values_of("check_certificate").map(|files| files.map(|file| file.to_string()).collect())
+    }
+
     pub fn get_sym(matches: &ArgMatches) -> bool {
         matches.is_present("print_sym")
     }
@@ -283,6 +433,89 @@ mod input_processing {
         matches.is_present("main_inputs_log")
     }
 
+    pub fn get_c_unit_size(matches: &ArgMatches) -> Result<Option<usize>, ()> {
+        match matches.value_of("c_unit_size") {
+            Some(size) => match size.parse::<usize>() {
+                Ok(size) => Ok(Some(size)),
+                Err(_) => {
+                    eprintln!("{}", Colour::Red.paint("invalid size of the C++ translation units"));
+                    Err(())
+                }
+            },
+            None => Ok(None),
+        }
+    }
+
+    // The limit is given in megabytes
+    pub fn get_memory_limit(matches: &ArgMatches) -> Result<Option<usize>, ()> {
+        match matches.value_of("memory_limit") {
+            Some(limit) => match limit.parse::<usize>() {
+                Ok(limit) if limit > 0 => Ok(Some(limit * 1024 * 1024)),
+                _ => {
+                    eprintln!("{}", Colour::Red.paint("invalid memory limit"));
+                    Err(())
+                }
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_trace_signals(matches: &ArgMatches) -> bool {
+        matches.is_present("trace_signals")
+    }
+
+    pub fn get_trace_templates(matches: &ArgMatches) -> Vec<String> {
+        let mut trace_templates = Vec::new();
+        if let Some(templates) = matches.values_of("trace_templates") {
+            for template in templates {
+                trace_templates.push(template.to_string());
+            }
+        }
+        trace_templates
+    }
+
+    pub fn get_keep(matches: &ArgMatches) -> Vec<String> {
+        let mut keep = Vec::new();
+        if let Some(patterns) = matches.values_of("keep") {
+            for pattern in patterns {
+                keep.push(pattern.to_string());
+            }
+        }
+        keep
+    }
+
+    // --check_tags checks the tags binary, maxbit and max with their usual
+    // meaning, --check_tag tag=meaning gives the meaning of any other tag
+    pub fn get_check_tags(matches: &ArgMatches) -> Result<BTreeMap<String, TagMeaning>, ()> {
+        let mut check_tags = BTreeMap::new();
+        if matches.is_present("check_tags") {
+            for tag in ["binary", "maxbit", "max"] {
+                check_tags.insert(tag.to_string(), TagMeaning::from_name(tag).unwrap());
+            }
+        }
+        if let Some(tags) = matches.values_of("check_tag") {
+            for tag in tags {
+                let (name, meaning) = match tag.split_once('=') {
+                    Some((name, meaning)) => (name, meaning),
+                    None => (tag, tag),
+                };
+                match TagMeaning::from_name(meaning) {
+                    Some(meaning) => {
+                        check_tags.insert(name.to_string(), meaning);
+                    }
+                    None => {
+                        eprintln!(
+                            "{}",
+                            Colour::Red.paint(format!("unknown meaning of the tag {}: {} (expected binary, maxbit or max)", name, meaning))
+                        );
+                        return Err(());
+                    }
+                }
+            }
+        }
+        Ok(check_tags)
+    }
+
     pub fn get_parallel_simplification(matches: &ArgMatches) -> bool {
         matches.is_present("parallel_simplification")
     }
@@ -364,6 +597,23 @@ mod input_processing {
                     .help("Maximum number of rounds of the simplification process")
                     .display_order(500)
             )
+            .arg(
+                Arg::with_name("keep")
+                    .long("keep")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .display_order(510)
+                    .help("Keeps the signals whose name matches the pattern through the simplification, * matches any sequence (e.g. main.b1.out[*])"),
+            )
+            .arg(
+                Arg::with_name("memory_limit")
+                    .long("memory_limit")
+                    .takes_value(true)
+                    .display_order(520)
+                    .value_name("MB")
+                    .help("Simplifies the constraints by clusters and spills them to disk to use about the given number of MB"),
+            )
             .arg(
                 Arg::with_name("output")
                     .short("o")
@@ -399,9 +649,31 @@ mod input_processing {
                 Arg::with_name("print_json_sub")
                     .long("jsons")
                     .takes_value(false)
-                    .hidden(true)
                     .display_order(100)
-                    .help("Outputs the substitution in json format"),
+                    .help("Outputs the substitutions of the simplification in JSON Lines format"),
+            )
+            .arg(
+                Arg::with_name("certificate")
+                    .long("certificate")
+                    .takes_value(false)
+                    .display_order(102)
+                    .help("Outputs the substitutions of the simplification, to check it with --check_certificate"),
+            )
+            .arg(
+                Arg::with_name("simplification_stats")
+                    .long("simplification_stats")
+                    .takes_value(false)
+                    .display_order(103)
+                    .help("Outputs what each phase of the simplification removed and the templates that lost more constraints in JSON format"),
+            )
+            .arg(
+                Arg::with_name("check_certificate")
+                    .long("check_certificate")
+                    .takes_value(true)
+                    .number_of_values(3)
+                    .value_names(&["original_r1cs", "simplified_r1cs", "certificate"])
+                    .display_order(104)
+                    .help("Checks that the simplified r1cs is equivalent to the one compiled with --O0"),
             )
             .arg(
                 Arg::with_name("print_sym")
@@ -440,6 +712,38 @@ mod input_processing {
                 .display_order(330) 
                 .help("Adds directory to library search path"),
             )
+            .arg(
+                Arg::with_name("define")
+                    .short("D")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .display_order(332)
+                    .help("Defines a constant NAME=value visible in templates, functions and the conditions of #if"),
+            )
+            .arg(
+                Arg::with_name("manifest")
+                    .long("manifest")
+                    .takes_value(true)
+                    .display_order(335)
+                    .help("Project manifest with the packages used by the includes. By default, the circom.json in the directory of the input file or its ancestors"),
+            )
+            .arg(
+                Arg::with_name("main")
+                    .long("main")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .display_order(337)
+                    .help("Builds the given main component instead of the one of the sources: a template call like Tree(20) or the name of a main of the manifest. Can be repeated to build several mains"),
+            )
+            .arg(
+                Arg::with_name("public")
+                    .long("public")
+                    .takes_value(true)
+                    .display_order(338)
+                    .help("Comma-separated public inputs of the template calls given with --main"),
+            )
             .arg(
                 Arg::with_name("print_c")
                     .long("c")
@@ -448,6 +752,45 @@ mod input_processing {
                     .display_order(150)
                     .help("Compiles the circuit to c"),
             )
+            .arg(
+                Arg::with_name("c_unit_size")
+                    .long("c_unit_size")
+                    .takes_value(true)
+                    .display_order(155)
+                    .help("Splits the generated C++ code in translation units of about the given size in KB (0: one unit per template and function)"),
+            )
+            .arg(
+                Arg::with_name("trace_signals")
+                    .long("trace_signals")
+                    .takes_value(false)
+                    .display_order(160)
+                    .help("Instruments the witness generator to trace every signal assignment"),
+            )
+            .arg(
+                Arg::with_name("trace_templates")
+                    .long("trace_template")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .display_order(170)
+                    .help("Only traces the signal assignments of the given template (implies --trace_signals)"),
+            )
+            .arg(
+                Arg::with_name("check_tags")
+                    .long("check_tags")
+                    .takes_value(false)
+                    .display_order(175)
+                    .help("Checks in the witness generator the tags binary, maxbit and max of every assigned signal"),
+            )
+            .arg(
+                Arg::with_name("check_tag")
+                    .long("check_tag")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .display_order(176)
+                    .help("Checks in the witness generator the given tag with the meaning binary, maxbit or max (tag=meaning)"),
+            )
             .arg(
                 Arg::with_name("parallel_simplification")
                     .long("parallel")
@@ -500,4 +843,48 @@ mod input_processing {
         }
         link_libraries
     }
+
+    // the manifest given in the command line, or else the circom.json
+    // found in the directory of the main file or its ancestors
+    pub fn get_manifest(matches: &ArgMatches, input: &Path) -> Option<PathBuf> {
+        match matches.value_of("manifest") {
+            Some(path) => Some(Path::new(path).to_path_buf()),
+            None => parser::manifest::Manifest::find(input),
+        }
+    }
+
+    // -D NAME=value, where NAME alone stands for NAME=1
+    pub fn get_defines(matches: &ArgMatches) -> Result<Defines, ()> {
+        let mut defines = Defines::new();
+        for define in matches.values_of("define").into_iter().flatten() {
+            let (name, value) = define.split_once('=').unwrap_or((define, "1"));
+            let is_identifier = name.chars().enumerate().all(|(i, c)| {
+                c == '_' || c == '$' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit())
+            });
+            let value = parser::parse_define_value(value);
+            match value {
+                Some(value) if is_identifier && !name.is_empty() => {
+                    defines.insert(name.to_string(), value);
+                }
+                _ => {
+                    eprintln!("{}", Colour::Red.paint(format!("invalid define {}, expected -D NAME=value with an integer value", define)));
+                    return Err(());
+                }
+            }
+        }
+        Ok(defines)
+    }
+
+    pub fn get_mains(matches: &ArgMatches) -> Result<(Vec<String>, Vec<String>), ()> {
+        let mains: Vec<String> = matches.values_of("main").into_iter().flatten().map(str::to_string).collect();
+        let public: Vec<String> = match matches.value_of("public") {
+            Some(signals) => signals.split(',').map(|signal| signal.trim().to_string()).filter(|signal| !signal.is_empty()).collect(),
+            None => Vec::new(),
+        };
+        if mains.is_empty() && matches.is_present("public") {
+            eprintln!("{}", Colour::Red.paint("--public requires a main component given with --main"));
+            return Err(());
+        }
+        Ok((mains, public))
+    }
 }
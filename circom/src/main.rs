@@ -9,6 +9,8 @@ const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
 use ansi_term::Colour;
 use input_user::Input;
+use program_structure::budget::Budgets;
+use program_structure::program_archive::ProgramArchive;
 fn main() {
     let result = start();
     if result.is_err() {
@@ -21,14 +23,41 @@ fn main() {
 }
 
 fn start() -> Result<(), ()> {
-    use compilation_user::CompilerConfig;
-    use execution_user::ExecutionConfig;
     let user_input = Input::new()?;
-    let mut program_archive = parser_user::parse_project(&user_input)?;
+    if let Some(files) = user_input.check_certificate() {
+        return execution_user::check_certificate(files);
+    }
+    let (mut program_archive, budgets) = parser_user::parse_project(&user_input)?;
     type_analysis_user::analyse_project(&mut program_archive)?;
 
+    // the mains share the parsing and the type analysis, each one is
+    // executed and compiled on its own with the outputs named after it
+    let names: Vec<Option<String>> = program_archive.get_mains().iter().map(|main| main.name.clone()).collect();
+    for (index, name) in names.iter().enumerate() {
+        let mut main_archive = program_archive.clone();
+        main_archive.select_main(index);
+        match name {
+            Some(name) => {
+                if names.len() > 1 {
+                    println!("{}", Colour::Green.paint(format!("main component {}:", name)));
+                }
+                build_main(&user_input.for_main(name), main_archive, &budgets)?;
+            }
+            None => build_main(&user_input, main_archive, &budgets)?,
+        }
+    }
+    Result::Ok(())
+}
+
+fn build_main(user_input: &Input, program_archive: ProgramArchive, budgets: &Budgets) -> Result<(), ()> {
+    use compilation_user::CompilerConfig;
+    use execution_user::ExecutionConfig;
+
     let config = ExecutionConfig {
         no_rounds: user_input.no_rounds(),
+        keep: user_input.keep().clone(),
+        memory_limit: user_input.memory_limit(),
+        budgets: budgets.clone(),
         flag_p: user_input.parallel_simplification_flag(),
         flag_s: user_input.reduced_simplification_flag(),
         flag_f: user_input.unsimplified_flag(),
@@ -38,6 +67,11 @@ fn start() -> Result<(), ()> {
         r1cs_flag: user_input.r1cs_flag(),
         json_constraint_flag: user_input.json_constraints_flag(),
         json_substitution_flag: user_input.json_substitutions_flag(),
+        json_substitutions: user_input.json_substitutions_file().to_string(),
+        certificate_flag: user_input.certificate_flag(),
+        certificate: user_input.certificate_file().to_string(),
+        simplification_stats_flag: user_input.simplification_stats_flag(),
+        simplification_stats: user_input.simplification_stats_file().to_string(),
         sym_flag: user_input.sym_flag(),
         sym: user_input.sym_file().to_string(),
         r1cs: user_input.r1cs_file().to_string(),
@@ -60,6 +94,10 @@ fn start() -> Result<(), ()> {
         wat_file: user_input.wat_file().to_string(),
         wasm_file: user_input.wasm_file().to_string(),
         produce_input_log: user_input.main_inputs_flag(),
+        trace_signals: user_input.trace_signals_flag(),
+        trace_templates: user_input.trace_templates().clone(),
+        c_unit_size: user_input.c_unit_size(),
+        check_tags: user_input.check_tags().clone(),
     };
     compilation_user::compile(compilation_config)?;
     Result::Ok(())
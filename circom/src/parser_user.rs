@@ -1,12 +1,30 @@
 use super::input_user::Input;
+use parser::manifest::{Manifest, Package};
+use parser::{Defines, MainDeclaration};
+use ansi_term::Colour;
+use std::collections::HashSet;
+use program_structure::budget::Budgets;
+use program_structure::constants::UsefulConstants;
 use program_structure::error_definition::Report;
+use program_structure::file_definition::FileLibrary;
 use program_structure::program_archive::ProgramArchive;
 use crate::VERSION;
 
 
-pub fn parse_project(input_info: &Input) -> Result<ProgramArchive, ()> {
+// The budgets of the manifest are returned with the program, the ones given
+// in the templates are kept in the program
+pub fn parse_project(input_info: &Input) -> Result<(ProgramArchive, Budgets), ()> {
     let initial_file = input_info.input_file().to_string();
-    let result_program_archive = parser::run_parser(initial_file, VERSION, input_info.get_link_libraries().to_vec());
+    let (packages, defines, mains, budgets) = load_manifest(input_info)?;
+    let result_program_archive = parser::run_parser(
+        initial_file,
+        VERSION,
+        input_info.get_link_libraries().to_vec(),
+        packages,
+        &defines,
+        UsefulConstants::new(&input_info.prime()).get_p(),
+        mains,
+    );
     match result_program_archive {
         Result::Err((file_library, report_collection)) => {
             Report::print_reports(&report_collection, &file_library);
@@ -14,7 +32,58 @@ pub fn parse_project(input_info: &Input) -> Result<ProgramArchive, ()> {
         }
         Result::Ok((program_archive, warnings)) => {
             Report::print_reports(&warnings, &program_archive.file_library);
-            Result::Ok(program_archive)
+            Result::Ok((program_archive, budgets))
         }
     }
 }
+
+// packages, defines, mains and budgets of the manifest, the defines of the
+// command line override those of the manifest
+fn load_manifest(input_info: &Input) -> Result<(Vec<Package>, Defines, Vec<MainDeclaration>, Budgets), ()> {
+    let manifest = match input_info.get_manifest() {
+        Some(file) => Manifest::load(file),
+        None => Ok(Manifest::default()),
+    };
+    let manifest = manifest.map_err(|reports| Report::print_reports(&reports, &FileLibrary::new()))?;
+    let loaded = manifest.resolve_packages().and_then(|resolved| Ok((resolved, manifest.defines()?)));
+    let ((packages, lock_file), mut defines) =
+        loaded.map_err(|reports| Report::print_reports(&reports, &FileLibrary::new()))?;
+    if let Some(lock_file) = lock_file {
+        println!("{} {}", Colour::Green.paint("Packages pinned in:"), lock_file.display());
+    }
+    defines.extend(input_info.get_defines().clone());
+    let mains = main_declarations(input_info, &manifest)?;
+    Ok((packages, defines, mains, manifest.budgets()))
+}
+
+// The mains given with --main, which are template calls or names of mains
+// of the manifest, or else the mains of the manifest for the input file.
+// Without any of them the main component of the sources is built.
+fn main_declarations(input_info: &Input, manifest: &Manifest) -> Result<Vec<MainDeclaration>, ()> {
+    let mut declarations = if input_info.get_mains().is_empty() {
+        manifest.mains_of(&input_info.input_program)
+    } else {
+        let declaration = |main: &String| match manifest.main(main) {
+            Some(declaration) => declaration,
+            None => MainDeclaration {
+                name: None,
+                template_call: if main.contains('(') { main.clone() } else { format!("{}()", main) },
+                public: input_info.get_public().clone(),
+            },
+        };
+        input_info.get_mains().iter().map(declaration).collect()
+    };
+    // the outputs of each main are named after it when there are several
+    if declarations.len() > 1 {
+        let mut names = HashSet::new();
+        for declaration in declarations.iter_mut() {
+            let name = declaration.name.clone().unwrap_or_else(|| declaration.derived_name());
+            declaration.name = Some(name.clone());
+            if !names.insert(name.clone()) {
+                eprintln!("{}", Colour::Red.paint(format!("the main component {} is given twice", name)));
+                return Err(());
+            }
+        }
+    }
+    Ok(declarations)
+}
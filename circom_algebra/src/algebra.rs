@@ -1379,17 +1379,64 @@ where
     HashMap::contains_key(expr, &cq) && HashMap::len(expr) == 1
 }
 
-pub fn normalize(c: Constraint<usize>, _field: &BigInt) -> Constraint<usize> {
-    use std::collections::LinkedList;
-    let _a: LinkedList<_> = c.a.iter().clone().collect();
-    let _b: LinkedList<_> = c.b.iter().clone().collect();
-    let _c: LinkedList<_> = c.c.iter().clone().collect();
-    todo!()
+// Canonical form of a constraint: the terms of A, B and C sorted by signal,
+// without zeros, and the constraint scaled so that the leading coefficients
+// of A and B (of C when it is linear) are 1, with A <= B. Constraints with
+// the same canonical form are multiples of each other.
+pub type CanonicalConstraint = (Vec<(usize, BigInt)>, Vec<(usize, BigInt)>, Vec<(usize, BigInt)>);
+
+pub fn canonical_form(c: &Constraint<usize>, field: &BigInt) -> CanonicalConstraint {
+    fn sorted_terms(expr: &HashMap<usize, BigInt>, field: &BigInt) -> Vec<(usize, BigInt)> {
+        let mut terms: Vec<_> = expr
+            .iter()
+            .map(|(signal, value)| (*signal, ((value % field) + field) % field))
+            .filter(|(_, value)| !value.is_zero())
+            .collect();
+        terms.sort_by_key(|(signal, _)| *signal);
+        terms
+    }
+    fn leading_inverse(terms: &[(usize, BigInt)], field: &BigInt) -> BigInt {
+        match terms.first() {
+            Some((_, value)) => match modular_arithmetic::div(&BigInt::from(1), value, field) {
+                Ok(inverse) => inverse,
+                Err(_) => unreachable!(),
+            },
+            None => BigInt::from(1),
+        }
+    }
+    fn scale(terms: &mut [(usize, BigInt)], factor: &BigInt, field: &BigInt) {
+        for (_, value) in terms {
+            *value = modular_arithmetic::mul(value, factor, field);
+        }
+    }
+
+    let mut a = sorted_terms(&c.a, field);
+    let mut b = sorted_terms(&c.b, field);
+    let mut c = sorted_terms(&c.c, field);
+    if a.is_empty() || b.is_empty() {
+        let inverse = leading_inverse(&c, field);
+        scale(&mut c, &inverse, field);
+        return (Vec::new(), Vec::new(), c);
+    }
+    let inverse_a = leading_inverse(&a, field);
+    let inverse_b = leading_inverse(&b, field);
+    scale(&mut a, &inverse_a, field);
+    scale(&mut b, &inverse_b, field);
+    scale(&mut c, &modular_arithmetic::mul(&inverse_a, &inverse_b, field), field);
+    if b < a {
+        std::mem::swap(&mut a, &mut b);
+    }
+    (a, b, c)
+}
+
+pub fn normalize(c: Constraint<usize>, field: &BigInt) -> Constraint<usize> {
+    let (a, b, c) = canonical_form(&c, field);
+    Constraint::new(a.into_iter().collect(), b.into_iter().collect(), c.into_iter().collect())
 }
 
 #[cfg(test)]
 mod test {
-    use crate::algebra::{ArithmeticExpression, Constraint, Substitution};
+    use crate::algebra::{canonical_form, ArithmeticExpression, Constraint, Substitution};
     use crate::modular_arithmetic;
     use num_bigint::BigInt;
     use std::collections::HashMap;
@@ -1484,6 +1531,7 @@ mod test {
         let expected_y_c = BigInt::from(3);
         let expected_constant_c = BigInt::from(7);
         C::apply_substitution(&mut constraint, &substitution, &field);
+        C::remove_zero_value_coefficients(&mut constraint);
         let y_c = constraint.c.get(&y).unwrap();
         let constant_c = constraint.c.get(&constant).unwrap();
         assert!(constraint.a.is_empty());
@@ -1491,4 +1539,34 @@ mod test {
         assert_eq!(*y_c, expected_y_c);
         assert_eq!(*constant_c, expected_constant_c);
     }
+
+    #[test]
+    fn algebra_constraint_canonical_form() {
+        let field = BigInt::parse_bytes(FIELD.as_bytes(), 10)
+            .expect("generating the big int was not possible");
+        let constant = C::constant_coefficient();
+        let x = 1;
+        let y = 2;
+        let raw = |terms: &[(usize, i64)]| -> HashMap<usize, BigInt> {
+            terms.iter().map(|(s, v)| (*s, BigInt::from(*v))).collect()
+        };
+        // constraint: (2x + 2) * (3y) - 6 = 0
+        let original = C::new(raw(&[(x, 2), (constant, 2)]), raw(&[(y, 3)]), raw(&[(constant, 6)]));
+        // constraint: (5y) * (x + 1) - 5 = 0, the same one multiplied by 5 / 6
+        let multiple = C::new(raw(&[(y, 5)]), raw(&[(x, 1), (constant, 1)]), raw(&[(constant, 5)]));
+        // constraint: (x + 1) * y - 2 = 0
+        let different = C::new(raw(&[(x, 1), (constant, 1)]), raw(&[(y, 1)]), raw(&[(constant, 2)]));
+        let canonical = canonical_form(&original, &field);
+        assert_eq!(canonical, canonical_form(&multiple, &field));
+        assert_ne!(canonical, canonical_form(&different, &field));
+        let (a, b, c) = canonical;
+        assert_eq!(a, vec![(constant, BigInt::from(1)), (x, BigInt::from(1))]);
+        assert_eq!(b, vec![(y, BigInt::from(1))]);
+        assert_eq!(c, vec![(constant, BigInt::from(1))]);
+
+        // linear constraints: 3x - 6y + 9 = 0 and 256x + 2y - 3 = 0
+        let linear = C::new(HashMap::new(), HashMap::new(), raw(&[(x, 3), (y, -6), (constant, 9)]));
+        let negated = C::new(HashMap::new(), HashMap::new(), raw(&[(x, 256), (y, 2), (constant, -3)]));
+        assert_eq!(canonical_form(&linear, &field), canonical_form(&negated, &field));
+    }
 }
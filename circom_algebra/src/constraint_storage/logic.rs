@@ -1,4 +1,6 @@
+use super::paging::{decode_number, encode_number};
 use super::*;
+use crate::algebra::Substitution;
 use std::collections::HashMap;
 
 pub fn code_expression(expr: HashMap<S, BigInt>, tracker: &mut FieldTracker) -> CompressedExpr {
@@ -32,3 +34,58 @@ pub fn decode_constraint(constraint: &CompressedConstraint, tracker: &FieldTrack
     let (a, b, c) = constraint;
     C { a: decode_expr(a, tracker), b: decode_expr(b, tracker), c: decode_expr(c, tracker) }
 }
+
+// With a memory limit the coefficients are written in the constraints, as
+// the number of bytes followed by the bytes, after the signal of each term
+fn encode_raw_expression(expr: &HashMap<S, BigInt>, encoded: &mut Vec<u8>) {
+    encode_number(expr.len(), encoded);
+    for (var, coeff) in expr {
+        let raw_coeff = coeff.to_signed_bytes_le();
+        encode_number(*var, encoded);
+        encode_number(raw_coeff.len(), encoded);
+        encoded.extend_from_slice(&raw_coeff);
+    }
+}
+
+fn decode_raw_expression(encoded: &[u8], position: &mut usize) -> HashMap<S, BigInt> {
+    let len = decode_number(encoded, position);
+    let mut expr = HashMap::with_capacity(len);
+    for _ in 0..len {
+        let var = decode_number(encoded, position);
+        let size = decode_number(encoded, position);
+        let end = std::cmp::min(*position + size, encoded.len());
+        expr.insert(var, BigInt::from_signed_bytes_le(&encoded[*position..end]));
+        *position = end;
+    }
+    expr
+}
+
+pub fn encode_raw_constraint(constraint: &C) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    encode_raw_expression(&constraint.a, &mut encoded);
+    encode_raw_expression(&constraint.b, &mut encoded);
+    encode_raw_expression(&constraint.c, &mut encoded);
+    encoded
+}
+
+pub fn decode_raw_constraint(encoded: &[u8]) -> C {
+    let mut position = 0;
+    let a = decode_raw_expression(encoded, &mut position);
+    let b = decode_raw_expression(encoded, &mut position);
+    let c = decode_raw_expression(encoded, &mut position);
+    C { a, b, c }
+}
+
+pub fn encode_raw_substitution(substitution: &Substitution<S>) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    encode_number(substitution.from, &mut encoded);
+    encode_raw_expression(&substitution.to, &mut encoded);
+    encoded
+}
+
+pub fn decode_raw_substitution(encoded: &[u8]) -> Substitution<S> {
+    let mut position = 0;
+    let from = decode_number(encoded, &mut position);
+    let to = decode_raw_expression(encoded, &mut position);
+    Substitution { from, to }
+}
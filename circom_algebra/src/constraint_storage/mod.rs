@@ -1,9 +1,15 @@
-use crate::algebra::Constraint;
+use crate::algebra::{Constraint, Substitution};
 use crate::num_bigint::BigInt;
 use constant_tracking::{ConstantTracker, CID};
+use paging::Pages;
+use std::cell::RefCell;
 use std::collections::LinkedList;
+use std::ops::Range;
 
 mod logic;
+mod paging;
+
+pub use paging::MemoryLimit;
 
 type RawField = Vec<u8>;
 type FieldTracker = ConstantTracker<RawField>;
@@ -13,57 +19,282 @@ type C = Constraint<usize>;
 type CompressedExpr = Vec<(CID, S)>;
 type CompressedConstraint = (CompressedExpr, CompressedExpr, CompressedExpr); // A, B, C
 
+// With a memory limit the constraints are kept with their coefficients in
+// pages that are spilled to disk, so the field tracker does not grow with
+// the coefficients of the constraints
+enum Constraints {
+    InMemory(Vec<CompressedConstraint>),
+    Paged(RefCell<Pages>),
+}
+
 pub type ConstraintID = usize;
 pub struct ConstraintStorage {
     field_tracker: FieldTracker,
-    constraints: Vec<CompressedConstraint>,
+    constraints: Constraints,
 }
 
 impl ConstraintStorage {
     pub fn new() -> ConstraintStorage {
-        ConstraintStorage { field_tracker: FieldTracker::new(), constraints: Vec::new() }
+        ConstraintStorage { field_tracker: FieldTracker::new(), constraints: Constraints::InMemory(Vec::new()) }
+    }
+
+    pub fn with_memory_limit(memory: &MemoryLimit) -> ConstraintStorage {
+        ConstraintStorage {
+            field_tracker: FieldTracker::new(),
+            constraints: Constraints::Paged(RefCell::new(Pages::new(memory))),
+        }
     }
 
     pub fn add_constraint(&mut self, constraint: C) -> ConstraintID {
-        let id = self.constraints.len();
-        let compressed = logic::code_constraint(constraint, &mut self.field_tracker);
-        self.constraints.push(compressed);
-        id
+        match &mut self.constraints {
+            Constraints::InMemory(constraints) => {
+                let id = constraints.len();
+                let compressed = logic::code_constraint(constraint, &mut self.field_tracker);
+                constraints.push(compressed);
+                id
+            }
+            Constraints::Paged(pages) => {
+                let pages = pages.get_mut();
+                let id = pages.len();
+                pages.push(logic::encode_raw_constraint(&constraint));
+                id
+            }
+        }
     }
 
     pub fn read_constraint(&self, id: ConstraintID) -> Option<C> {
-        if id < self.constraints.len() {
-            Some(logic::decode_constraint(&self.constraints[id], &self.field_tracker))
-        } else {
-            None
+        match &self.constraints {
+            Constraints::InMemory(constraints) if id < constraints.len() => {
+                Some(logic::decode_constraint(&constraints[id], &self.field_tracker))
+            }
+            Constraints::Paged(pages) if id < pages.borrow().len() => {
+                Some(pages.borrow_mut().with(id, logic::decode_raw_constraint))
+            }
+            _ => None,
         }
     }
 
     pub fn replace(&mut self, id: ConstraintID, new: C) {
-        if id < self.constraints.len() {
-            self.constraints[id] = logic::code_constraint(new, &mut self.field_tracker);
+        match &mut self.constraints {
+            Constraints::InMemory(constraints) if id < constraints.len() => {
+                constraints[id] = logic::code_constraint(new, &mut self.field_tracker);
+            }
+            Constraints::Paged(pages) if id < pages.borrow().len() => {
+                pages.get_mut().replace(id, logic::encode_raw_constraint(&new));
+            }
+            _ => {}
         }
     }
 
     pub fn extract_with(&mut self, filter: &dyn Fn(&C) -> bool) -> LinkedList<C> {
-        let old = std::mem::take(&mut self.constraints);
         let mut removed = LinkedList::new();
-        for c in old {
-            let decoded = logic::decode_constraint(&c, &self.field_tracker);
-            if filter(&decoded) {
-                removed.push_back(decoded);
-            } else {
-                self.constraints.push(c);
+        match &mut self.constraints {
+            Constraints::InMemory(constraints) => {
+                for c in std::mem::take(constraints) {
+                    let decoded = logic::decode_constraint(&c, &self.field_tracker);
+                    if filter(&decoded) {
+                        removed.push_back(decoded);
+                    } else {
+                        constraints.push(c);
+                    }
+                }
+            }
+            Constraints::Paged(pages) => {
+                let old = pages.get_mut();
+                let mut kept = Pages::new(old.memory());
+                for id in 0..old.len() {
+                    let (decoded, c) = old.with(id, |c| (logic::decode_raw_constraint(c), c.to_vec()));
+                    if filter(&decoded) {
+                        removed.push_back(decoded);
+                    } else {
+                        kept.push(c);
+                    }
+                }
+                *old = kept;
             }
         }
         removed
     }
 
-    pub fn get_ids(&self) -> Vec<ConstraintID> {
-        (0..self.constraints.len()).collect()
+    pub fn get_ids(&self) -> Range<ConstraintID> {
+        match &self.constraints {
+            Constraints::InMemory(constraints) => 0..constraints.len(),
+            Constraints::Paged(pages) => 0..pages.borrow().len(),
+        }
     }
 
     pub fn no_constants(&self) -> CID {
         self.field_tracker.next_id()
     }
+
+    // The first error found when the constraints were spilled to disk or
+    // read from it, after it the constraints that could not be read are empty
+    pub fn spill_error(&self) -> Option<String> {
+        match &self.constraints {
+            Constraints::InMemory(_) => None,
+            Constraints::Paged(pages) => pages.borrow().memory().error(),
+        }
+    }
+}
+
+// Substitutions kept in pages that are spilled to disk, they are found by
+// the signal they remove
+pub struct SubstitutionStorage {
+    substitutions: RefCell<Pages>,
+    // position of the substitution of each signal, usize::MAX when the
+    // signal has none
+    index: Vec<usize>,
+    len: usize,
+}
+
+impl SubstitutionStorage {
+    pub fn new(memory: &MemoryLimit) -> SubstitutionStorage {
+        SubstitutionStorage { substitutions: RefCell::new(Pages::new(memory)), index: Vec::new(), len: 0 }
+    }
+
+    // A substitution of a signal that already has one replaces it
+    pub fn add(&mut self, substitution: &Substitution<S>) {
+        let pages = self.substitutions.get_mut();
+        if self.index.len() <= substitution.from {
+            self.index.resize(substitution.from + 1, usize::MAX);
+        }
+        if self.index[substitution.from] == usize::MAX {
+            self.len += 1;
+        }
+        self.index[substitution.from] = pages.len();
+        pages.push(logic::encode_raw_substitution(substitution));
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, signal: S) -> bool {
+        self.index.get(signal).is_some_and(|position| *position != usize::MAX)
+    }
+
+    pub fn get(&self, signal: S) -> Option<Substitution<S>> {
+        if self.contains(signal) {
+            Some(self.substitutions.borrow_mut().with(self.index[signal], logic::decode_raw_substitution))
+        } else {
+            None
+        }
+    }
+
+    pub fn remove(&mut self, signal: S) {
+        if self.contains(signal) {
+            self.index[signal] = usize::MAX;
+            self.len -= 1;
+        }
+    }
+
+    // Removes the substitutions of the signals that are not kept
+    pub fn retain(&mut self, keep: impl Fn(S) -> bool) {
+        for (signal, position) in self.index.iter_mut().enumerate() {
+            if *position != usize::MAX && !keep(signal) {
+                *position = usize::MAX;
+                self.len -= 1;
+            }
+        }
+    }
+
+    // Like fast_encoded_constraint_substitution, returns whether a
+    // substitution was applied
+    pub fn apply_to(&self, constraint: &mut C, field: &BigInt) -> bool {
+        let mut applied_substitution = false;
+        for signal in C::take_cloned_signals(constraint) {
+            if let Some(substitution) = self.get(signal) {
+                let (from, to) = Substitution::decompose(substitution);
+                C::apply_substitution(constraint, &Substitution::new(from, to).unwrap(), field);
+                applied_substitution = true;
+            }
+        }
+        applied_substitution
+    }
+
+    // The substitutions in the order they were added, without the ones that
+    // were replaced or removed
+    pub fn for_each_in_order(&self, mut f: impl FnMut(Substitution<S>)) {
+        let mut substitutions = self.substitutions.borrow_mut();
+        for position in 0..substitutions.len() {
+            let substitution = substitutions.with(position, logic::decode_raw_substitution);
+            if self.index.get(substitution.from) == Some(&position) {
+                f(substitution);
+            }
+        }
+    }
+
+    pub fn spill_error(&self) -> Option<String> {
+        self.substitutions.borrow().memory().error()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ConstraintStorage, MemoryLimit, SubstitutionStorage, C};
+    use crate::algebra::Substitution;
+    use num_bigint::BigInt;
+    use std::collections::HashMap;
+
+    // x_id = -id + id * x_{id + 1} with big coefficients
+    fn constraint(id: usize) -> C {
+        let value = BigInt::from(id) << 200;
+        let terms = vec![(C::constant_coefficient(), -value.clone()), (id, BigInt::from(-1)), (id + 1, value)];
+        C { a: HashMap::new(), b: HashMap::new(), c: terms.into_iter().collect() }
+    }
+
+    #[test]
+    fn storage_with_memory_limit() {
+        let memory = MemoryLimit::new(16 * 1024);
+        let mut storage = ConstraintStorage::with_memory_limit(&memory);
+        for id in 1..5000 {
+            assert_eq!(storage.add_constraint(constraint(id)), id - 1);
+        }
+        storage.replace(10, C::empty());
+        let removed = storage.extract_with(&|c| c.is_empty());
+        assert_eq!(removed.len(), 1);
+        assert_eq!(storage.get_ids(), 0..4998);
+        for c_id in storage.get_ids() {
+            let id = if c_id < 10 { c_id + 1 } else { c_id + 2 };
+            assert_eq!(storage.read_constraint(c_id).unwrap().c(), constraint(id).c());
+        }
+        assert!(storage.spill_error().is_none());
+    }
+
+    #[test]
+    fn substitutions_with_memory_limit() {
+        let memory = MemoryLimit::new(16 * 1024);
+        let mut substitutions = SubstitutionStorage::new(&memory);
+        let substitution = |from: usize, to: usize| {
+            let to: HashMap<_, _> = vec![(to, BigInt::from(from) << 100)].into_iter().collect();
+            Substitution { from, to }
+        };
+        for from in (1..5000).rev() {
+            substitutions.add(&substitution(from, from + 1));
+        }
+        substitutions.add(&substitution(7, 3));
+        substitutions.remove(8);
+        substitutions.retain(|signal| signal % 1000 != 0);
+        assert_eq!(substitutions.len(), 4999 - 5);
+        assert!(!substitutions.contains(8) && !substitutions.contains(2000));
+        assert_eq!(substitutions.get(7).unwrap().to(), substitution(7, 3).to());
+        assert_eq!(substitutions.get(9).unwrap().to(), substitution(9, 10).to());
+        let mut in_order = Vec::new();
+        substitutions.for_each_in_order(|substitution| in_order.push(*substitution.from()));
+        assert_eq!(in_order.len(), substitutions.len());
+        assert_eq!((in_order[0], *in_order.last().unwrap()), (4999, 7));
+
+        // x_9 * x_1 = 0 becomes (2^100 * 9) x_10 * x_1 = 0
+        let a: HashMap<_, _> = vec![(9, BigInt::from(1))].into_iter().collect();
+        let b: HashMap<_, _> = vec![(1, BigInt::from(1))].into_iter().collect();
+        let mut constraint = C { a, b, c: HashMap::new() };
+        let field = BigInt::from(1) << 255;
+        assert!(substitutions.apply_to(&mut constraint, &field));
+        assert!(constraint.a().contains_key(&10) && !constraint.a().contains_key(&9));
+        assert!(substitutions.spill_error().is_none());
+    }
 }
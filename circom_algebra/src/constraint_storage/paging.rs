@@ -0,0 +1,345 @@
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+// Number of items of each page
+const PAGE_LEN: usize = 1024;
+// Estimation of the memory used by an item besides its bytes
+const ITEM_BYTES: usize = std::mem::size_of::<Vec<u8>>() + 16;
+
+static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+
+// Bytes that the pages of all the storages created with it may keep in
+// memory, and the first error found when a page was written to or read from
+// disk. After an error the pages are no longer spilled and the ones that
+// could not be read are empty, so the error must be reported.
+#[derive(Clone)]
+pub struct MemoryLimit {
+    shared: Arc<SharedLimit>,
+}
+
+struct SharedLimit {
+    limit: usize,
+    loaded: AtomicUsize,
+    directory: PathBuf,
+    error: Mutex<Option<String>>,
+}
+
+impl MemoryLimit {
+    pub fn new(limit: usize) -> MemoryLimit {
+        MemoryLimit::in_directory(limit, std::env::temp_dir())
+    }
+
+    fn in_directory(limit: usize, directory: PathBuf) -> MemoryLimit {
+        let shared = SharedLimit { limit, loaded: AtomicUsize::new(0), directory, error: Mutex::new(None) };
+        MemoryLimit { shared: Arc::new(shared) }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.shared.limit
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.shared.error.lock().unwrap().clone()
+    }
+
+    fn record_error(&self, error: String) {
+        self.shared.error.lock().unwrap().get_or_insert(error);
+    }
+
+    fn exceeded(&self) -> bool {
+        self.shared.loaded.load(Ordering::Relaxed) > self.shared.limit
+    }
+
+    fn add(&self, bytes: usize) {
+        self.shared.loaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn remove(&self, bytes: usize) {
+        self.shared.loaded.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+struct Page {
+    // None when the page is only in the spill file
+    items: Option<Vec<Vec<u8>>>,
+    len: usize,
+    bytes: usize,
+    // the copy in the spill file is outdated or does not exist
+    modified: bool,
+    // offset and capacity of the space of the page in the spill file
+    location: Option<(u64, usize)>,
+}
+
+impl Page {
+    fn new() -> Page {
+        Page { items: Some(Vec::with_capacity(PAGE_LEN)), len: 0, bytes: 0, modified: true, location: None }
+    }
+}
+
+struct SpillFile {
+    path: PathBuf,
+    file: File,
+    end: u64,
+}
+
+impl SpillFile {
+    fn new(directory: &Path) -> std::io::Result<SpillFile> {
+        let name = format!(
+            "circom_constraints_{}_{}.tmp",
+            std::process::id(),
+            NEXT_FILE.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = directory.join(name);
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path)?;
+        Ok(SpillFile { path, file, end: 0 })
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// Encoded items split in pages. When the pages in memory of all the storages
+// with the same limit take more bytes than it, the pages of this one that
+// were loaded first are written to a spill file and loaded again when they
+// are used.
+pub struct Pages {
+    pages: Vec<Page>,
+    memory: MemoryLimit,
+    loaded: VecDeque<usize>,
+    file: Option<SpillFile>,
+}
+
+impl Pages {
+    pub fn new(memory: &MemoryLimit) -> Pages {
+        Pages { pages: Vec::new(), memory: memory.clone(), loaded: VecDeque::new(), file: None }
+    }
+
+    pub fn memory(&self) -> &MemoryLimit {
+        &self.memory
+    }
+
+    pub fn len(&self) -> usize {
+        match self.pages.last() {
+            Some(last) => (self.pages.len() - 1) * PAGE_LEN + last.len,
+            None => 0,
+        }
+    }
+
+    pub fn push(&mut self, item: Vec<u8>) {
+        if self.pages.last().is_none_or(|page| page.len == PAGE_LEN) {
+            self.pages.push(Page::new());
+            self.loaded.push_back(self.pages.len() - 1);
+        }
+        let page = self.pages.len() - 1;
+        self.load(page);
+        let bytes = item.len() + ITEM_BYTES;
+        let last = &mut self.pages[page];
+        last.items.as_mut().unwrap().push(item);
+        last.len += 1;
+        last.bytes += bytes;
+        last.modified = true;
+        self.memory.add(bytes);
+        self.evict(page);
+    }
+
+    pub fn with<R>(&mut self, id: usize, f: impl FnOnce(&[u8]) -> R) -> R {
+        let (page, position) = (id / PAGE_LEN, id % PAGE_LEN);
+        self.load(page);
+        let result = f(&self.pages[page].items.as_ref().unwrap()[position]);
+        self.evict(page);
+        result
+    }
+
+    pub fn replace(&mut self, id: usize, item: Vec<u8>) {
+        let (page, position) = (id / PAGE_LEN, id % PAGE_LEN);
+        self.load(page);
+        let current = &mut self.pages[page];
+        let old = std::mem::replace(&mut current.items.as_mut().unwrap()[position], item);
+        let new_bytes = current.items.as_ref().unwrap()[position].len();
+        current.bytes = current.bytes + new_bytes - old.len();
+        current.modified = true;
+        self.memory.add(new_bytes);
+        self.memory.remove(old.len());
+        self.evict(page);
+    }
+
+    // A page that cannot be read is left with empty items
+    fn load(&mut self, page: usize) {
+        if self.pages[page].items.is_some() {
+            return;
+        }
+        let (offset, size) = self.pages[page].location.unwrap();
+        let mut encoded = vec![0; size];
+        let file = &mut self.file.as_mut().unwrap().file;
+        let read = file.seek(SeekFrom::Start(offset)).and_then(|_| file.read_exact(&mut encoded));
+        let current = &mut self.pages[page];
+        let items = match read {
+            Ok(()) => decode_page(&encoded),
+            Err(error) => {
+                self.memory.record_error(format!("the spilled constraints could not be read: {}", error));
+                vec![Vec::new(); current.len]
+            }
+        };
+        current.bytes = items.iter().map(|item| item.len() + ITEM_BYTES).sum();
+        current.items = Some(items);
+        current.modified = false;
+        self.memory.add(current.bytes);
+        self.loaded.push_back(page);
+    }
+
+    // The page in use is never spilled, and after an error the pages stay
+    // in memory
+    fn evict(&mut self, in_use: usize) {
+        while self.memory.exceeded() && self.loaded.len() > 1 && self.memory.error().is_none() {
+            let page = self.loaded.pop_front().unwrap();
+            if page == in_use {
+                self.loaded.push_back(page);
+                continue;
+            }
+            if let Err(error) = self.spill(page) {
+                self.memory.record_error(format!("the constraints could not be spilled to disk: {}", error));
+                self.loaded.push_back(page);
+            }
+        }
+    }
+
+    fn spill(&mut self, page: usize) -> std::io::Result<()> {
+        if self.file.is_none() {
+            self.file = Some(SpillFile::new(&self.memory.shared.directory)?);
+        }
+        let spill_file = self.file.as_mut().unwrap();
+        let current = &mut self.pages[page];
+        if current.modified {
+            let encoded = encode_page(current.items.as_ref().unwrap());
+            let offset = match current.location {
+                Some((offset, capacity)) if encoded.len() <= capacity => offset,
+                _ => spill_file.end,
+            };
+            spill_file.file.seek(SeekFrom::Start(offset))?;
+            spill_file.file.write_all(&encoded)?;
+            let capacity = match current.location {
+                Some((old, capacity)) if old == offset => capacity,
+                _ => {
+                    spill_file.end += encoded.len() as u64;
+                    encoded.len()
+                }
+            };
+            current.location = Some((offset, capacity));
+            current.modified = false;
+        }
+        current.items = None;
+        self.memory.remove(current.bytes);
+        Ok(())
+    }
+}
+
+impl Drop for Pages {
+    fn drop(&mut self) {
+        for page in &self.pages {
+            if page.items.is_some() {
+                self.memory.remove(page.bytes);
+            }
+        }
+    }
+}
+
+// Numbers are written in LEB128, the items of a page are their number
+// followed by the length and the bytes of each item
+pub fn encode_number(mut number: usize, encoded: &mut Vec<u8>) {
+    while number >= 0x80 {
+        encoded.push((number as u8) | 0x80);
+        number >>= 7;
+    }
+    encoded.push(number as u8);
+}
+
+// Truncated input is read as zeros
+pub fn decode_number(encoded: &[u8], position: &mut usize) -> usize {
+    let mut number = 0;
+    let mut shift = 0;
+    while let Some(byte) = encoded.get(*position) {
+        *position += 1;
+        number |= ((byte & 0x7f) as usize) << shift;
+        if *byte < 0x80 {
+            break;
+        }
+        shift += 7;
+    }
+    number
+}
+
+fn encode_page(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    encode_number(items.len(), &mut encoded);
+    for item in items {
+        encode_number(item.len(), &mut encoded);
+        encoded.extend_from_slice(item);
+    }
+    encoded
+}
+
+fn decode_page(encoded: &[u8]) -> Vec<Vec<u8>> {
+    let mut position = 0;
+    let len = decode_number(encoded, &mut position);
+    let mut items = Vec::with_capacity(PAGE_LEN);
+    for _ in 0..len {
+        let size = decode_number(encoded, &mut position);
+        let end = std::cmp::min(position + size, encoded.len());
+        items.push(encoded[position..end].to_vec());
+        position = end;
+    }
+    items
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MemoryLimit, Pages, PAGE_LEN};
+
+    fn item(id: usize) -> Vec<u8> {
+        (0..id % 50).map(|byte| (id + byte) as u8).collect()
+    }
+
+    #[test]
+    fn paging_spill_and_reload() {
+        // only a page fits in memory
+        let memory = MemoryLimit::new(PAGE_LEN * 60);
+        let mut pages = Pages::new(&memory);
+        let no_items = 3 * PAGE_LEN + 7;
+        for id in 0..no_items {
+            pages.push(item(id));
+        }
+        assert!(pages.file.is_some());
+        assert_eq!(pages.len(), no_items);
+        for id in (0..no_items).step_by(PAGE_LEN / 2) {
+            pages.replace(id, item(id + 1_000_000));
+        }
+        for id in 0..no_items {
+            let expected = if id % (PAGE_LEN / 2) == 0 { item(id + 1_000_000) } else { item(id) };
+            assert!(pages.with(id, |stored| stored == expected.as_slice()));
+        }
+        assert!(memory.error().is_none());
+        drop(pages);
+        assert_eq!(memory.shared.loaded.load(std::sync::atomic::Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn paging_error_keeps_the_pages() {
+        let directory = std::env::temp_dir().join("circom_missing_directory").join("spill");
+        let memory = MemoryLimit::in_directory(PAGE_LEN, directory);
+        let mut pages = Pages::new(&memory);
+        for id in 0..2 * PAGE_LEN {
+            pages.push(item(id));
+        }
+        assert!(memory.error().is_some());
+        for id in 0..2 * PAGE_LEN {
+            assert!(pages.with(id, |stored| stored == item(id).as_slice()));
+        }
+    }
+}
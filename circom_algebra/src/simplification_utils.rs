@@ -1,6 +1,7 @@
 use super::num_bigint::BigInt;
 use crate::num_traits::ToPrimitive;
 use crate::modular_arithmetic;
+use crate::sparse_elimination;
 use std::collections::{BTreeMap, HashMap, HashSet, LinkedList};
 use std::mem::replace;
 
@@ -545,30 +546,22 @@ where
     T: AsRef<HashSet<usize>>,
 {
     //debug_new_substitutions(&config);
-    let min = 350;
-    let max = 1000000;
-    let apply_less_ocurrences = 
-        config.constraints.len() >= min && 
-        config.constraints.len() < max && 
-        !config.use_old_heuristics;
-
     let field = config.field;
     let mut constraints = config.constraints;
-    let mut holder = SHNotNormalized::new();
-    let normalized_holder: SH;
-    let non_overlapping: HashMap<usize, S>;
+    // sorted by signal, so they are logged in the same order in every run
+    let non_overlapping: BTreeMap<usize, S>;
 
-    if apply_less_ocurrences{
-        let mut signals = SignalDefinition4 { forbidden: config.forbidden.as_ref(), deleted_symbols: HashSet::new(),  order_signals: LinkedList::new() };
-        substitution_process_4(&mut signals, &mut constraints, &mut holder, config.num_signals, &field);
-        normalized_holder = normalize_substitutions(holder, &field);
-        non_overlapping = create_nonoverlapping_substitutions_4(normalized_holder, &signals, &field);
-    }
-    else{
+    if config.use_old_heuristics {
+        let mut holder = SHNotNormalized::new();
         let mut signals = SignalDefinition { forbidden: config.forbidden.as_ref(), deleted_symbols: HashSet::new() };
         substitution_process_3(&mut signals, &mut constraints, &mut holder, &field);
-        normalized_holder = normalize_substitutions(holder, &field);
-        non_overlapping = create_nonoverlapping_substitutions(normalized_holder, &field);
+        let normalized_holder = normalize_substitutions(holder, &field);
+        non_overlapping = create_nonoverlapping_substitutions(normalized_holder, &field).into_iter().collect();
+    }
+    else{
+        let eliminated = sparse_elimination::eliminate(constraints, config.forbidden.as_ref(), &field);
+        constraints = eliminated.constraints;
+        non_overlapping = eliminated.substitutions;
     }
     let mut substitutions = LinkedList::new();
     let mut removed = LinkedList::new();
@@ -599,6 +592,10 @@ where
         LinkedList::push_back(&mut substitutions_4, v);
     }
 
+    // build the subs using the sparse elimination
+    let eliminated = sparse_elimination::eliminate(config.constraints.clone(), config.forbidden.as_ref(), &field);
+    let substitutions_5: LinkedList<S> = eliminated.substitutions.into_values().collect();
+
     // build the subs using the multi-inv and taking the bigger signal 
     let mut signals_3 =
         SignalDefinition { forbidden: config.forbidden.as_ref(), deleted_symbols: HashSet::new() };
@@ -647,6 +644,8 @@ where
     //check_substitutions(&substitutions_2, &substitutions_3, &field);
     // To compare the second with the third version
     check_substitutions(&substitutions_3, &substitutions_4, &field);
+    // To compare the fourth with the sparse elimination
+    check_substitutions(&substitutions_4, &substitutions_5, &field);
 }
 
 #[allow(dead_code)]
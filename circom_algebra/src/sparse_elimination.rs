@@ -0,0 +1,422 @@
+use super::num_bigint::BigInt;
+use crate::modular_arithmetic;
+use crate::num_traits::{One, Zero};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, LinkedList};
+
+type C = crate::algebra::Constraint<usize>;
+type S = crate::algebra::Substitution<usize>;
+type A = crate::algebra::ArithmeticExpression<usize>;
+
+// The non-zero coefficients of a linear constraint, sorted by column
+type Row = Vec<(usize, BigInt)>;
+
+// Number of rows, the shortest ones, among which each pivot is chosen
+const SEARCH_ROWS: usize = 4;
+
+// Sparse matrix of the linear constraints of a cluster. The columns are the
+// signals of the cluster (numbered in the order they appear) and the rows
+// are active until they are taken as pivot or have no signal that can be
+// removed.
+struct Matrix {
+    rows: Vec<Row>,
+    active: Vec<bool>,
+    // rows where each column has appeared, it may contain rows that do
+    // not have the column anymore
+    columns: Vec<Vec<usize>>,
+    // number of active rows where each column appears
+    counts: Vec<usize>,
+    eliminable: Vec<bool>,
+    signals: Vec<usize>,
+}
+
+impl Matrix {
+    // The constraints are sorted by their signals and coefficients, so the
+    // rows, the columns and hence the pivots do not depend on the order in
+    // which the cluster lists them
+    fn new(constraints: LinkedList<C>, forbidden: &HashSet<usize>, field: &BigInt) -> Matrix {
+        let mut sorted: Vec<Vec<(usize, BigInt)>> = Vec::with_capacity(constraints.len());
+        for constraint in constraints {
+            debug_assert!(C::is_linear(&constraint));
+            let mut terms: Vec<_> = constraint
+                .c
+                .into_iter()
+                .map(|(signal, value)| (signal, ((value % field) + field) % field))
+                .filter(|(_, value)| !value.is_zero())
+                .collect();
+            terms.sort();
+            sorted.push(terms);
+        }
+        sorted.sort();
+        let mut to_column = HashMap::new();
+        let mut signals = Vec::new();
+        let mut rows = Vec::with_capacity(sorted.len());
+        for terms in sorted {
+            let mut row = Row::with_capacity(terms.len());
+            for (signal, value) in terms {
+                let column = *to_column.entry(signal).or_insert_with(|| {
+                    signals.push(signal);
+                    signals.len() - 1
+                });
+                row.push((column, value));
+            }
+            row.sort_by_key(|(column, _)| *column);
+            rows.push(row);
+        }
+        let mut columns = vec![Vec::new(); signals.len()];
+        let mut counts = vec![0; signals.len()];
+        for (r, row) in rows.iter().enumerate() {
+            for (column, _) in row {
+                columns[*column].push(r);
+                counts[*column] += 1;
+            }
+        }
+        let constant = C::constant_coefficient();
+        let eliminable =
+            signals.iter().map(|s| *s != constant && !forbidden.contains(s)).collect();
+        let active = vec![true; rows.len()];
+        Matrix { rows, active, columns, counts, eliminable, signals }
+    }
+
+    // Markowitz pivoting: among the shortest active rows, the pivot is the
+    // entry that minimizes (row length - 1) * (column count - 1), a bound
+    // of the fill-in produced by eliminating it. Returns the pivots
+    // (column, row) in the order they are eliminated.
+    fn eliminate(&mut self, field: &BigInt) -> Vec<(usize, usize)> {
+        let mut heap = BinaryHeap::with_capacity(self.rows.len());
+        for (r, row) in self.rows.iter().enumerate() {
+            heap.push(Reverse((row.len(), r)));
+        }
+        let mut pivots = Vec::new();
+        loop {
+            let mut candidates = Vec::with_capacity(SEARCH_ROWS);
+            while candidates.len() < SEARCH_ROWS {
+                match heap.pop() {
+                    Some(Reverse((len, r))) => {
+                        if self.active[r] && self.rows[r].len() == len && !candidates.contains(&r) {
+                            candidates.push(r);
+                        }
+                    }
+                    None => break,
+                }
+            }
+            if candidates.is_empty() {
+                break;
+            }
+            let mut best: Option<(usize, usize, usize)> = None;
+            for r in &candidates {
+                match self.pivot_column(*r) {
+                    Some(column) => {
+                        let cost = (self.rows[*r].len() - 1) * (self.counts[column] - 1);
+                        if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                            best = Some((cost, *r, column));
+                        }
+                    }
+                    None => self.deactivate(*r),
+                }
+            }
+            for r in candidates {
+                if self.active[r] && best.is_none_or(|(_, pivot, _)| pivot != r) {
+                    heap.push(Reverse((self.rows[r].len(), r)));
+                }
+            }
+            if let Some((_, r, column)) = best {
+                self.pivot(r, column, &mut heap, field);
+                pivots.push((column, r));
+            }
+        }
+        pivots
+    }
+
+    // The column of the row that appears in less active rows, the biggest
+    // signal in case of a tie
+    fn pivot_column(&self, r: usize) -> Option<usize> {
+        let mut ret: Option<usize> = None;
+        for (column, _) in &self.rows[r] {
+            let column = *column;
+            if !self.eliminable[column] {
+                continue;
+            }
+            ret = match ret {
+                Some(prev)
+                    if self.counts[prev] < self.counts[column]
+                        || (self.counts[prev] == self.counts[column]
+                            && self.signals[prev] > self.signals[column]) =>
+                {
+                    Some(prev)
+                }
+                _ => Some(column),
+            };
+        }
+        ret
+    }
+
+    fn deactivate(&mut self, r: usize) {
+        self.active[r] = false;
+        for (column, _) in &self.rows[r] {
+            self.counts[*column] -= 1;
+        }
+    }
+
+    fn pivot(
+        &mut self,
+        p: usize,
+        column: usize,
+        heap: &mut BinaryHeap<Reverse<(usize, usize)>>,
+        field: &BigInt,
+    ) {
+        self.deactivate(p);
+        let mut row = std::mem::take(&mut self.rows[p]);
+        let position = row.binary_search_by(|(c, _)| c.cmp(&column)).unwrap();
+        if !row[position].1.is_one() {
+            let inverse = match modular_arithmetic::div(&BigInt::one(), &row[position].1, field) {
+                Ok(inverse) => inverse,
+                Err(_) => unreachable!(),
+            };
+            for (_, value) in &mut row {
+                *value = modular_arithmetic::mul(value, &inverse, field);
+            }
+        }
+        for r in std::mem::take(&mut self.columns[column]) {
+            if !self.active[r] {
+                continue;
+            }
+            if let Ok(position) = self.rows[r].binary_search_by(|(c, _)| c.cmp(&column)) {
+                let factor = self.rows[r][position].1.clone();
+                self.subtract(r, &row, &factor, field);
+                heap.push(Reverse((self.rows[r].len(), r)));
+            }
+        }
+        self.rows[p] = row;
+    }
+
+    // rows[r] -= factor * pivot, updating the columns of the fill-in and the
+    // cancellations
+    fn subtract(&mut self, r: usize, pivot: &Row, factor: &BigInt, field: &BigInt) {
+        let old = std::mem::take(&mut self.rows[r]);
+        let mut new = Row::with_capacity(old.len() + pivot.len());
+        let mut old = old.into_iter().peekable();
+        let mut pivot = pivot.iter().peekable();
+        loop {
+            let next_old = old.peek().map(|(c, _)| *c);
+            let next_pivot = pivot.peek().map(|(c, _)| *c);
+            match (next_old, next_pivot) {
+                (None, None) => break,
+                (Some(o), Some(q)) if o == q => {
+                    let (column, value) = old.next().unwrap();
+                    let (_, pivot_value) = pivot.next().unwrap();
+                    let product = modular_arithmetic::mul(factor, pivot_value, field);
+                    let value = modular_arithmetic::sub(&value, &product, field);
+                    if value.is_zero() {
+                        self.counts[column] -= 1;
+                    } else {
+                        new.push((column, value));
+                    }
+                }
+                (Some(o), q) if q.is_none_or(|q| o < q) => {
+                    new.push(old.next().unwrap());
+                }
+                _ => {
+                    let (column, pivot_value) = pivot.next().unwrap();
+                    let product = modular_arithmetic::mul(factor, pivot_value, field);
+                    new.push((*column, modular_arithmetic::prefix_sub(&product, field)));
+                    self.columns[*column].push(r);
+                    self.counts[*column] += 1;
+                }
+            }
+        }
+        self.rows[r] = new;
+    }
+
+    // Back substitution: the pivot row of a column only has columns of
+    // later pivots, so going backwards each one is replaced by its already
+    // reduced row
+    fn reduce(&mut self, pivots: &[(usize, usize)], field: &BigInt) {
+        let mut pivot_row = vec![None; self.signals.len()];
+        for (column, r) in pivots {
+            pivot_row[*column] = Some(*r);
+        }
+        for (column, r) in pivots.iter().rev() {
+            let needs_reduction = self.rows[*r]
+                .iter()
+                .any(|(c, _)| c != column && pivot_row[*c].is_some());
+            if !needs_reduction {
+                continue;
+            }
+            let mut reduced: HashMap<usize, BigInt> = HashMap::with_capacity(self.rows[*r].len());
+            for (c, value) in &self.rows[*r] {
+                match pivot_row[*c] {
+                    Some(other) if c != column => {
+                        for (k, w) in &self.rows[other] {
+                            if k != c {
+                                *reduced.entry(*k).or_insert_with(BigInt::zero) -= value * w;
+                            }
+                        }
+                    }
+                    _ => *reduced.entry(*c).or_insert_with(BigInt::zero) += value,
+                }
+            }
+            let mut row: Row = reduced
+                .into_iter()
+                .map(|(c, value)| (c, ((value % field) + field) % field))
+                .filter(|(_, value)| !value.is_zero())
+                .collect();
+            row.sort_by_key(|(column, _)| *column);
+            self.rows[*r] = row;
+        }
+    }
+}
+
+pub struct Eliminated {
+    pub constraints: LinkedList<C>,
+    pub substitutions: BTreeMap<usize, S>,
+}
+
+// Gaussian elimination of the linear constraints of a cluster. Every signal
+// that is not forbidden and can be cleared from a constraint is removed: the
+// substitutions express them in terms of the signals that remain, and the
+// constraints that remain only have forbidden signals.
+pub fn eliminate(constraints: LinkedList<C>, forbidden: &HashSet<usize>, field: &BigInt) -> Eliminated {
+    let mut matrix = Matrix::new(constraints, forbidden, field);
+    let pivots = matrix.eliminate(field);
+    matrix.reduce(&pivots, field);
+
+    let mut is_pivot = vec![false; matrix.rows.len()];
+    let mut substitutions = BTreeMap::new();
+    for (column, r) in pivots {
+        is_pivot[r] = true;
+        let mut to = HashMap::with_capacity(matrix.rows[r].len());
+        for (c, value) in std::mem::take(&mut matrix.rows[r]) {
+            if c != column {
+                to.insert(matrix.signals[c], modular_arithmetic::prefix_sub(&value, field));
+            }
+        }
+        let signal = matrix.signals[column];
+        substitutions.insert(signal, S::new(signal, A::hashmap_into_arith(to)).unwrap());
+    }
+    let signals = matrix.signals;
+    let mut remaining = LinkedList::new();
+    for (r, row) in matrix.rows.into_iter().enumerate() {
+        if is_pivot[r] || row.is_empty() {
+            continue;
+        }
+        let c = row.into_iter().map(|(column, value)| (signals[column], value)).collect();
+        remaining.push_back(C { a: HashMap::new(), b: HashMap::new(), c });
+    }
+    Eliminated { constraints: remaining, substitutions }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{eliminate, C, S};
+    use num_bigint::BigInt;
+    use std::collections::{BTreeMap, HashMap, HashSet};
+    const FIELD: &str = "257";
+
+    fn linear(coefficients: &[(usize, i64)]) -> C {
+        let c = coefficients.iter().map(|(s, v)| (*s, BigInt::from(*v))).collect();
+        C { a: HashMap::new(), b: HashMap::new(), c }
+    }
+
+    fn is_zero(mut constraint: C, substitutions: &BTreeMap<usize, S>, field: &BigInt) -> bool {
+        for substitution in substitutions.values() {
+            C::apply_substitution(&mut constraint, substitution, field);
+        }
+        C::fix_constraint(&mut constraint, field);
+        C::remove_zero_value_coefficients(&mut constraint);
+        constraint.is_empty()
+    }
+
+    #[test]
+    fn sparse_elimination_chain() {
+        let field = BigInt::parse_bytes(FIELD.as_bytes(), 10).unwrap();
+        // 0 = 1 + x2 - x1, 0 = 2 * x3 - x2, 0 = x3 + x4 - 5 * x5, x5 forbidden
+        let original = vec![
+            linear(&[(0, 1), (2, 1), (1, -1)]),
+            linear(&[(3, 2), (2, -1)]),
+            linear(&[(3, 1), (4, 1), (5, -5)]),
+        ];
+        let forbidden: HashSet<usize> = [0, 5].iter().cloned().collect();
+        let eliminated = eliminate(original.iter().cloned().collect(), &forbidden, &field);
+        assert!(eliminated.constraints.is_empty());
+        assert_eq!(eliminated.substitutions.len(), 3);
+        for (signal, substitution) in &eliminated.substitutions {
+            assert!(!forbidden.contains(signal));
+            for other in substitution.to().keys() {
+                assert!(!eliminated.substitutions.contains_key(other));
+            }
+        }
+        for constraint in original {
+            assert!(is_zero(constraint, &eliminated.substitutions, &field));
+        }
+    }
+
+    #[test]
+    fn sparse_elimination_is_independent_of_the_order() {
+        let field = BigInt::parse_bytes(FIELD.as_bytes(), 10).unwrap();
+        // x1 = x2 = ... = x6 leaves one of the signals, which one depends on
+        // the pivots
+        let mut original = Vec::new();
+        for s in 1..=5 {
+            original.push(linear(&[(s, 1), (s + 1, -1)]));
+        }
+        let forbidden: HashSet<usize> = [0].iter().cloned().collect();
+        let substitutions = |constraints: &[C]| {
+            let eliminated = eliminate(constraints.iter().cloned().collect(), &forbidden, &field);
+            let mut substitutions: Vec<_> = eliminated
+                .substitutions
+                .into_iter()
+                .map(|(signal, substitution)| {
+                    let mut to: Vec<_> = substitution.to().iter().map(|(s, v)| (*s, v.clone())).collect();
+                    to.sort();
+                    (signal, to)
+                })
+                .collect();
+            substitutions.sort();
+            let mut remaining: Vec<_> = eliminated
+                .constraints
+                .into_iter()
+                .map(|constraint| {
+                    let mut c: Vec<_> = constraint.c.into_iter().collect();
+                    c.sort();
+                    c
+                })
+                .collect();
+            remaining.sort();
+            (substitutions, remaining)
+        };
+        let expected = substitutions(&original);
+        for shift in 1..original.len() {
+            let mut permuted = original.clone();
+            permuted.rotate_left(shift);
+            assert_eq!(substitutions(&permuted), expected);
+            permuted.reverse();
+            assert_eq!(substitutions(&permuted), expected);
+        }
+    }
+
+    #[test]
+    fn sparse_elimination_remaining_constraints() {
+        let field = BigInt::parse_bytes(FIELD.as_bytes(), 10).unwrap();
+        // x1 = x2 + x3 and x1 - x2 = x3 + x4 with x1, x2 and x3 forbidden
+        let original = vec![
+            linear(&[(1, 1), (2, -1), (3, -1)]),
+            linear(&[(1, 1), (2, -1), (3, -1), (4, -1)]),
+        ];
+        let forbidden: HashSet<usize> = [0, 1, 2, 3].iter().cloned().collect();
+        let eliminated = eliminate(original.iter().cloned().collect(), &forbidden, &field);
+        assert_eq!(eliminated.constraints.len(), 1);
+        assert_eq!(eliminated.substitutions.len(), 1);
+        assert!(eliminated.substitutions.contains_key(&4));
+        // the remaining constraint, solved for x1, with the substitution of
+        // x4 satisfy all the original constraints
+        let mut substitutions = eliminated.substitutions;
+        let remaining = eliminated.constraints.into_iter().next().unwrap();
+        substitutions.insert(1, C::clear_signal_from_linear(remaining, &1, &field));
+        let x1 = substitutions[&1].clone();
+        S::apply_substitution(substitutions.get_mut(&4).unwrap(), &x1, &field);
+        for constraint in original {
+            assert!(is_zero(constraint, &substitutions, &field));
+        }
+    }
+}
@@ -0,0 +1,16 @@
+#ifndef __FR_PORTABLE_H
+#define __FR_PORTABLE_H
+
+// Constants of the bls12381 prime field used by the portable implementation
+// of the field arithmetic (fr_portable.cpp). The raw values are stored in
+// little endian 64 bit limbs and R = 2^256 is the Montgomery factor.
+
+#define Fr_NBITS 255
+#define Fr_RAW_Q { 0xffffffff00000001, 0x53bda402fffe5bfe, 0x3339d80809a1d805, 0x73eda753299d7d48 }
+#define Fr_RAW_HALF { 0x7fffffff80000000, 0xa9ded2017fff2dff, 0x199cec0404d0ec02, 0x39f6d3a994cebea4 }
+#define Fr_RAW_R2 { 0xc999e990f3f29c6d, 0x2b6cedcb87925c23, 0x05d314967254398f, 0x0748d9d99f59ff11 }
+#define Fr_RAW_R3 { 0xc62c1807439b73af, 0x1b3e0d188cf06990, 0x73d13c71c7b5f418, 0x6e2a5bb9c8db33e9 }
+#define Fr_LBO_MASK 0x7fffffffffffffff
+#define Fr_NP 0xfffffffeffffffff
+
+#endif // __FR_PORTABLE_H
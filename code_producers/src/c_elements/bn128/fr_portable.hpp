@@ -0,0 +1,16 @@
+#ifndef __FR_PORTABLE_H
+#define __FR_PORTABLE_H
+
+// Constants of the bn128 prime field used by the portable implementation
+// of the field arithmetic (fr_portable.cpp). The raw values are stored in
+// little endian 64 bit limbs and R = 2^256 is the Montgomery factor.
+
+#define Fr_NBITS 254
+#define Fr_RAW_Q { 0x43e1f593f0000001, 0x2833e84879b97091, 0xb85045b68181585d, 0x30644e72e131a029 }
+#define Fr_RAW_HALF { 0xa1f0fac9f8000000, 0x9419f4243cdcb848, 0xdc2822db40c0ac2e, 0x183227397098d014 }
+#define Fr_RAW_R2 { 0x1bb8e645ae216da7, 0x53fe3ab1e35c59e3, 0x8c49833d53bb8085, 0x0216d0b17f4e44a5 }
+#define Fr_RAW_R3 { 0x5e94d8e1b4bf0040, 0x2a489cbe1cfbb6b8, 0x893cc664a19fcfed, 0x0cf8594b7fcc657c }
+#define Fr_LBO_MASK 0x3fffffffffffffff
+#define Fr_NP 0xc2e1f593efffffff
+
+#endif // __FR_PORTABLE_H
@@ -3,7 +3,7 @@ use num_bigint_dig::{BigInt, Sign};
 use serde_json::json;
 use std::fs::File;
 use std::io::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 // Types
 const T_U64: &str = "u64";
@@ -393,15 +393,107 @@ pub fn generate_my_trace() -> String {
     format!("{}->getTrace({})", CIRCOM_CALC_WIT, MY_ID)
 }
 
-pub fn build_failed_assert_message(line: usize) -> String{
-    
-    format!("std::cout << \"Failed assert in template/function \" << {} << \" line {}. \" <<  \"Followed trace of components: \" << {} << std::endl" ,
-        MY_TEMPLATE_NAME,
-        line,
-        generate_my_trace()
+fn escape_c_string(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// literal with the given text
+pub fn build_c_string(value: &str) -> String {
+    format!("\"{}\"", escape_c_string(value))
+}
+
+// the message is an expression of type std::string or a literal
+pub fn build_failed_assert_error(message: &str, signal: &str, file: &str, line: usize) -> String {
+    format!("throw Circom_RuntimeError({}, {}->componentMemory[{}].templateName, {}, \"{}\", \"{}\", {})",
+        message,
+        CIRCOM_CALC_WIT,
+        MY_ID,
+        generate_my_trace(),
+        signal,
+        escape_c_string(file),
+        line
      )
 }
 
+// reports the assignment of size signals of the component cmp starting at dest
+pub fn build_signal_trace(cmp: &str, dest: &str, size: usize, file: &str, line: usize) -> String {
+    format!("{}->traceSignal({}, {}, {}, \"{}\", {});",
+        CIRCOM_CALC_WIT,
+        cmp,
+        dest,
+        size,
+        escape_c_string(file),
+        line
+    )
+}
+
+// table with the signals of every template instance, used by the witness
+// calculator to name the signals in the trace of assignments
+pub fn generate_trace_signal_table(producer: &CProducer) -> Vec<String> {
+    let mut code = vec![];
+    if !producer.needs_signal_trace() {
+        code.push("TraceSignalDefList* get_trace_signal_table() {return NULL;}\n".to_string());
+        return code;
+    }
+    let mut table = vec![];
+    for (t, template) in producer.get_template_debug_list().iter().enumerate() {
+        if template.signals.is_empty() {
+            table.push("{0,NULL}".to_string());
+            continue;
+        }
+        let mut defs = vec![];
+        for (s, signal) in template.signals.iter().enumerate() {
+            let size = signal.lengths.iter().fold(1, |p, c| p * (*c));
+            let lengths = if signal.lengths.is_empty() {
+                "NULL".to_string()
+            } else {
+                let name = format!("_traceLengths_{}_{}", t, s);
+                let values: Vec<String> = signal.lengths.iter().map(|l| l.to_string()).collect();
+                code.push(format!("u32 {}[{}] = {{{}}};", name, signal.lengths.len(), values.join(",")));
+                name
+            };
+            defs.push(format!("{{\"{}\",{},{},{},{}}}",
+                escape_c_string(&signal.name), signal.offset, size, signal.lengths.len(), lengths));
+        }
+        code.push(format!("TraceSignalDef _traceSignals_{}[{}] = {{{}}};", t, defs.len(), defs.join(",")));
+        table.push(format!("{{{},_traceSignals_{}}}", defs.len(), t));
+    }
+    code.push(format!("TraceSignalDefList _traceSignalTable[{}] = {{{}}};", table.len(), table.join(",")));
+    code.push("TraceSignalDefList* get_trace_signal_table() {return _traceSignalTable;}\n".to_string());
+    code
+}
+
+// the inputs of the main component and its template, used by the witness
+// calculator to name the input signals in the errors raised while they are set
+pub fn generate_main_input_table(producer: &CProducer) -> Vec<String> {
+    let mut code = vec![];
+    let main = producer.get_template_debug_list().get(producer.get_main_template_id());
+    let template_name = main.map_or("", |main| &main.name);
+    code.push(format!("const char* get_main_template_name() {{return {};}}\n", build_c_string(template_name)));
+    let mut defs = vec![];
+    for (name, _, size) in producer.get_main_input_list() {
+        let lengths = main
+            .and_then(|main| main.signals.iter().find(|signal| signal.name == *name))
+            .map_or(Vec::new(), |signal| signal.lengths.clone());
+        let lengths_name = if lengths.is_empty() {
+            "NULL".to_string()
+        } else {
+            let lengths_name = format!("_inputLengths_{}", defs.len());
+            let values: Vec<String> = lengths.iter().map(|l| l.to_string()).collect();
+            code.push(format!("u32 {}[{}] = {{{}}};", lengths_name, lengths.len(), values.join(",")));
+            lengths_name
+        };
+        defs.push(format!("{{{},0,{},{},{}}}", build_c_string(name), size, lengths.len(), lengths_name));
+    }
+    if defs.is_empty() {
+        code.push("TraceSignalDefList _inputSignalTable = {0,NULL};".to_string());
+    } else {
+        code.push(format!("TraceSignalDef _inputSignals[{}] = {{{}}};", defs.len(), defs.join(",")));
+        code.push(format!("TraceSignalDefList _inputSignalTable = {{{},_inputSignals}};", defs.len()));
+    }
+    code.push("TraceSignalDefList* get_main_input_signal_table() {return &_inputSignalTable;}\n".to_string());
+    code
+}
 
 pub fn build_conditional(
     cond: Vec<String>,
@@ -900,14 +992,55 @@ pub fn generate_fr_asm_file(c_folder: &PathBuf, prime: &String) -> std::io::Resu
     Ok(())
 }
 
+pub fn generate_fr_portable_hpp_file(c_folder: &Path, prime: &str) -> std::io::Result<()> {
+    use std::io::BufWriter;
+    let mut file_path = c_folder.to_path_buf();
+    file_path.push("fr_portable");
+    file_path.set_extension("hpp");
+    let file_name = file_path.to_str().unwrap();
+    let mut c_file = BufWriter::new(File::create(file_name).unwrap());
+    let mut code = "".to_string();
+    let file = match prime {
+        "bn128" => include_str!("bn128/fr_portable.hpp"),
+        "bls12381" => include_str!("bls12381/fr_portable.hpp"),
+        "goldilocks" => include_str!("goldilocks/fr_portable.hpp"),
+        _ => unreachable!(),
+    };
+    for line in file.lines() {
+        code = format!("{}{}\n", code, line);
+    }
+    c_file.write_all(code.as_bytes())?;
+    c_file.flush()?;
+    Ok(())
+}
+
+pub fn generate_fr_portable_cpp_file(c_folder: &Path) -> std::io::Result<()> {
+    use std::io::BufWriter;
+    let mut file_path = c_folder.to_path_buf();
+    file_path.push("fr_portable");
+    file_path.set_extension("cpp");
+    let file_name = file_path.to_str().unwrap();
+    let mut c_file = BufWriter::new(File::create(file_name).unwrap());
+    let mut code = "".to_string();
+    let file = include_str!("common/fr_portable.cpp");
+    for line in file.lines() {
+        code = format!("{}{}\n", code, line);
+    }
+    c_file.write_all(code.as_bytes())?;
+    c_file.flush()?;
+    Ok(())
+}
+
 pub fn generate_make_file(
     c_folder: &PathBuf,
     run_name: &str,
     producer: &CProducer,
+    units: usize,
 ) -> std::io::Result<()> {
     use std::io::BufWriter;
 
     let makefile_template: &str = include_str!("common/makefile");
+    let units: Vec<String> = (0..units).map(|i| format!("{}_{}.o", run_name, i)).collect();
 
     let template = handlebars::Handlebars::new();
     let code = template
@@ -916,6 +1049,7 @@ pub fn generate_make_file(
             &json!({
                 "run_name": run_name,
                 "has_parallelism": producer.has_parallelism,
+                "units": units,
             }),
         )
         .expect("must render");
@@ -1,6 +1,7 @@
 #include <iomanip>
 #include <sstream>
 #include <assert.h>
+#include <iostream>
 #include "calcwit.hpp"
 
 extern void run(Circom_CalcWit* ctx);
@@ -23,6 +24,32 @@ u64 fnv1a(std::string s) {
   return hash;
 }
 
+std::string Fr_element2string(PFrElement pE, bool hex) {
+  char *value = Fr_element2str(pE);
+  std::string res;
+  if (hex) {
+    mpz_t r;
+    mpz_init_set_str(r, value, 10);
+    char *hex_value = mpz_get_str(0, 16, r);
+    res = std::string("0x") + hex_value;
+    mpz_clear(r);
+    free(hex_value);
+  } else {
+    res = value;
+  }
+  delete [] value;
+  return res;
+}
+
+Circom_RuntimeError::Circom_RuntimeError(std::string msg, std::string aTemplateName, std::string aComponentPath,
+                                         std::string aSignalName, std::string aFileName, uint aLine)
+  : std::runtime_error(msg + "\n  component: " + aComponentPath + " (template " + aTemplateName + ")"
+                       + (aSignalName.empty() ? "" : "\n  signal: " + aSignalName)
+                       + (aFileName.empty() ? "" : "\n  location: " + aFileName + ":" + std::to_string(aLine))),
+    message(msg), templateName(aTemplateName), componentPath(aComponentPath), signalName(aSignalName),
+    fileName(aFileName), line(aLine) {
+}
+
 Circom_CalcWit::Circom_CalcWit (Circom_Circuit *aCircuit, uint maxTh) {
   circuit = aCircuit;
   inputSignalAssignedCounter = get_main_input_signal_no();
@@ -41,6 +68,7 @@ Circom_CalcWit::Circom_CalcWit (Circom_Circuit *aCircuit, uint maxTh) {
   // parallelism
   numThread = 0;
 
+  traceStream = &std::cout;
 }
 
 Circom_CalcWit::~Circom_CalcWit() {
@@ -56,38 +84,50 @@ uint Circom_CalcWit::getInputSignalHashPosition(u64 h) {
     while (pos != inipos) {
       if (circuit->InputHashMap[pos].hash==h) return pos;
       if (circuit->InputHashMap[pos].hash==0) {
-	fprintf(stderr, "Signal not found\n");
-	assert(false);
+	throw inputSignalError("Signal not found", "");
       }
       pos = (pos+1)%n; 
     }
-    fprintf(stderr, "Signals not found\n");
-    assert(false);
+    throw inputSignalError("Signal not found", "");
   }
   return pos;
 }
 
 void Circom_CalcWit::tryRunCircuit(){ 
   if (inputSignalAssignedCounter == 0) {
-    run(this);
+    try {
+      run(this);
+    } catch (...) {
+      setRuntimeError(std::current_exception());
+    }
+    // the first error prevails over the ones it may have caused in other threads
+    if (runtimeError) {
+      std::rethrow_exception(runtimeError);
+    }
+  }
+}
+
+void Circom_CalcWit::setRuntimeError(std::exception_ptr e){
+  std::lock_guard<std::mutex> lock(errorMutex);
+  if (!runtimeError) {
+    runtimeError = e;
   }
 }
 
 void Circom_CalcWit::setInputSignal(u64 h, uint i,  FrElement & val){
   if (inputSignalAssignedCounter == 0) {
-    fprintf(stderr, "No more signals to be assigned\n");
-    assert(false);
+    throw inputSignalError("No more signals to be assigned", getInputSignalName(h, i));
   }
   uint pos = getInputSignalHashPosition(h);
   if (i >= circuit->InputHashMap[pos].signalsize) {
-    fprintf(stderr, "Input signal array access exceeds the size\n");
-    assert(false);
+    TraceSignalDef *def = getInputSignalDef(h);
+    throw inputSignalError("Input signal array access exceeds the size: element " + std::to_string(i) + " of "
+                           + std::to_string(circuit->InputHashMap[pos].signalsize), def == NULL ? "" : def->name);
   }
   
   uint si = circuit->InputHashMap[pos].signalid+i;
   if (inputSignalAssigned[si-get_main_input_signal_start()]) {
-    fprintf(stderr, "Signal assigned twice: %d\n", si);
-    assert(false);
+    throw inputSignalError("Signal assigned twice", getInputSignalName(h, i));
   }
   signalValues[si] = val;
   inputSignalAssigned[si-get_main_input_signal_start()] = true;
@@ -112,6 +152,26 @@ std::string Circom_CalcWit::getTrace(u64 id_cmp){
 
 }
 
+TraceSignalDef* Circom_CalcWit::getInputSignalDef(u64 h){
+  TraceSignalDefList *list = get_main_input_signal_table();
+  for (uint k = 0; k < list->len; k++) {
+    if (fnv1a(list->defs[k].name) == h) {
+      return &list->defs[k];
+    }
+  }
+  return NULL;
+}
+
+std::string Circom_CalcWit::getInputSignalName(u64 h, uint i){
+  TraceSignalDef *def = getInputSignalDef(h);
+  if (def == NULL) return "";
+  return def->name + generate_position_array(def->lengths, def->len, i);
+}
+
+Circom_RuntimeError Circom_CalcWit::inputSignalError(std::string msg, std::string signalName){
+  return Circom_RuntimeError(msg, get_main_template_name(), "main", signalName, "", 0);
+}
+
 std::string Circom_CalcWit::generate_position_array(uint* dimensions, uint size_dimensions, uint index){
   std::string positions = "";
 
@@ -124,3 +184,36 @@ std::string Circom_CalcWit::generate_position_array(uint* dimensions, uint size_
   return positions;
 }
 
+
+std::string Circom_CalcWit::getSignalName(u32 templateId, u64 index){
+  TraceSignalDefList &list = get_trace_signal_table()[templateId];
+  for (uint i = 0; i < list.len; i++) {
+    TraceSignalDef &def = list.defs[i];
+    if (def.offset <= index && index < def.offset + def.size) {
+      return def.name + generate_position_array(def.lengths, def.len, index - def.offset);
+    }
+  }
+  return "?";
+}
+
+void Circom_CalcWit::traceSignal(u64 idCmp, PFrElement signal, uint size, const char *fileName, uint line){
+  u64 first = signal - &signalValues[componentMemory[idCmp].signalStart];
+  for (uint i = 0; i < size; i++) {
+    Circom_SignalTrace entry;
+    entry.componentPath = getTrace(idCmp);
+    entry.templateName = componentMemory[idCmp].templateName;
+    entry.signalName = getSignalName(componentMemory[idCmp].templateId, first + i);
+    char *value = Fr_element2str(&signal[i]);
+    entry.value = value;
+    delete [] value;
+    entry.fileName = fileName;
+    entry.line = line;
+    std::lock_guard<std::mutex> lock(traceMutex);
+    if (traceCallback) {
+      traceCallback(entry);
+    } else if (traceStream != NULL) {
+      *traceStream << entry.componentPath << "." << entry.signalName << " = " << entry.value
+                   << " (" << entry.templateName << " at " << entry.fileName << ":" << entry.line << ")\n";
+    }
+  }
+}
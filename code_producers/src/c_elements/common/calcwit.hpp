@@ -6,6 +6,10 @@
 #include <functional>
 #include <atomic>
 #include <memory>
+#include <exception>
+#include <stdexcept>
+#include <string>
+#include <ostream>
 
 #include "circom.hpp"
 #include "fr.hpp"
@@ -14,6 +18,38 @@
 
 u64 fnv1a(std::string s);
 
+// decimal or hexadecimal representation of the value, used by the messages
+// built from format strings
+std::string Fr_element2string(PFrElement pE, bool hex);
+
+// Error raised while computing the witness. Besides the message, it keeps
+// the template and instance path of the failing component, the signal
+// involved (empty if none) and the source location of the failure (the
+// file is empty for the errors raised while the inputs are set)
+class Circom_RuntimeError : public std::runtime_error {
+public:
+  std::string message;
+  std::string templateName;
+  std::string componentPath;
+  std::string signalName;
+  std::string fileName;
+  uint line;
+
+  Circom_RuntimeError(std::string msg, std::string aTemplateName, std::string aComponentPath,
+                      std::string aSignalName, std::string aFileName, uint aLine);
+};
+
+// Assignment of a signal, reported by the circuits compiled with
+// --trace_signals
+struct Circom_SignalTrace {
+  std::string componentPath;
+  std::string templateName;
+  std::string signalName;
+  std::string value;
+  std::string fileName;
+  uint line;
+};
+
 class Circom_CalcWit {
 
   bool *inputSignalAssigned;
@@ -36,6 +72,11 @@ public:
 
   uint maxThread;
 
+  // first error raised while running the circuit, also by the threads
+  // of parallel components
+  std::mutex errorMutex;
+  std::exception_ptr runtimeError;
+
   // Functions called by the circuit
   Circom_CalcWit(Circom_Circuit *aCircuit, uint numTh = NMUTEXES);
   ~Circom_CalcWit();
@@ -56,12 +97,33 @@ public:
 
   std::string getTrace(u64 id_cmp);
 
+  // name of the element i of the input signal of the main component with
+  // hash h, empty if there is no such input
+  std::string getInputSignalName(u64 h, uint i);
+
+  // error raised while setting the input signal with the given name
+  Circom_RuntimeError inputSignalError(std::string msg, std::string signalName);
+
+  void setRuntimeError(std::exception_ptr e);
+
+  // trace of the signal assignments: every assignment is passed to
+  // traceCallback if it is set, and written to traceStream otherwise
+  std::ostream *traceStream;
+  std::function<void(const Circom_SignalTrace &)> traceCallback;
+  std::mutex traceMutex;
+
+  void traceSignal(u64 idCmp, PFrElement signal, uint size, const char *fileName, uint line);
+
   std::string generate_position_array(uint* dimensions, uint size_dimensions, uint index);
 
 private:
   
   uint getInputSignalHashPosition(u64 h);
 
+  TraceSignalDef* getInputSignalDef(u64 h);
+
+  std::string getSignalName(u32 templateId, u64 index);
+
 };
 
 typedef void (*Circom_TemplateFunction)(uint __cIdx, Circom_CalcWit* __ctx); 
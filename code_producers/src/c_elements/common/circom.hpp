@@ -31,6 +31,22 @@ struct IODefPair {
     IODef* defs;
 };
 
+// signals declared by a template instance, used to name the signals in the
+// trace of assignments of circuits compiled with --trace_signals and the
+// inputs of the main component in the errors raised while they are set
+struct TraceSignalDef {
+  const char* name;
+  u32 offset;
+  u32 size;
+  u32 len;
+  u32 *lengths;
+};
+
+struct TraceSignalDefList {
+  u32 len;
+  TraceSignalDef* defs;
+};
+
 struct Circom_Circuit {
   //  const char *P;
   HashSignalInfo* InputHashMap;
@@ -81,5 +97,8 @@ uint get_size_of_input_hashmap();
 uint get_size_of_witness();
 uint get_size_of_constants();
 uint get_size_of_io_map();
+TraceSignalDefList* get_trace_signal_table();
+TraceSignalDefList* get_main_input_signal_table();
+const char* get_main_template_name();
 
 #endif  // __CIRCOM_H
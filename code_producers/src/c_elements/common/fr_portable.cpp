@@ -0,0 +1,600 @@
+// Portable implementation of the field arithmetic. It provides the same
+// functions as fr.asm written in plain C++, so the witness generator can be
+// built on any architecture and without nasm. The constants of the prime
+// are defined in fr_portable.hpp.
+
+#include "fr.hpp"
+#include "fr_portable.hpp"
+#include <string.h>
+
+#define Fr_MONTGOMERY 0x40000000
+
+FrElement Fr_q = {0, Fr_LONG, Fr_RAW_Q};
+FrElement Fr_R3 = {0, Fr_LONG, Fr_RAW_R3};
+FrRawElement Fr_rawq = Fr_RAW_Q;
+FrRawElement Fr_rawR3 = Fr_RAW_R3;
+
+static const FrRawElement Fr_rawHalf = Fr_RAW_HALF;
+static const FrRawElement Fr_rawR2 = Fr_RAW_R2;
+
+/*****************************************************************************/
+/* 64 bit word operations                                                    */
+/*****************************************************************************/
+
+// returns the low word of a*b + c + carry and leaves the high word in carry
+static inline uint64_t mac(uint64_t a, uint64_t b, uint64_t c, uint64_t &carry) {
+#ifdef __SIZEOF_INT128__
+    unsigned __int128 t = (unsigned __int128)a * b + c + carry;
+    carry = (uint64_t)(t >> 64);
+    return (uint64_t)t;
+#else
+    uint64_t a0 = a & 0xFFFFFFFF, a1 = a >> 32;
+    uint64_t b0 = b & 0xFFFFFFFF, b1 = b >> 32;
+    uint64_t p00 = a0 * b0, p01 = a0 * b1, p10 = a1 * b0, p11 = a1 * b1;
+    uint64_t mid = (p00 >> 32) + (p01 & 0xFFFFFFFF) + (p10 & 0xFFFFFFFF);
+    uint64_t lo = (p00 & 0xFFFFFFFF) | (mid << 32);
+    uint64_t hi = p11 + (p01 >> 32) + (p10 >> 32) + (mid >> 32);
+    lo += c;
+    hi += lo < c;
+    lo += carry;
+    hi += lo < carry;
+    carry = hi;
+    return lo;
+#endif
+}
+
+static inline uint64_t adc(uint64_t a, uint64_t b, uint64_t &carry) {
+    uint64_t r = a + b;
+    uint64_t c = r < a;
+    r += carry;
+    c += r < carry;
+    carry = c;
+    return r;
+}
+
+static inline uint64_t sbb(uint64_t a, uint64_t b, uint64_t &borrow) {
+    uint64_t r = a - b;
+    uint64_t c = a < b;
+    c += r < borrow;
+    r -= borrow;
+    borrow = c;
+    return r;
+}
+
+/*****************************************************************************/
+/* Raw operations                                                            */
+/*****************************************************************************/
+
+static inline int rawCmp(const uint64_t *a, const uint64_t *b) {
+    for (int i = Fr_N64 - 1; i >= 0; i--) {
+        if (a[i] != b[i]) return a[i] < b[i] ? -1 : 1;
+    }
+    return 0;
+}
+
+static inline void rawSubQ(uint64_t *r) {
+    uint64_t borrow = 0;
+    for (int i = 0; i < Fr_N64; i++) r[i] = sbb(r[i], Fr_rawq[i], borrow);
+}
+
+static inline void rawReduce(uint64_t *r) {
+    if (rawCmp(r, Fr_rawq) >= 0) rawSubQ(r);
+}
+
+// Montgomery multiplication (CIOS): r = a*b/R mod q
+static void rawMMul(uint64_t *r, const uint64_t *a, const uint64_t *b) {
+    uint64_t t[Fr_N64 + 2];
+    for (int i = 0; i < Fr_N64 + 2; i++) t[i] = 0;
+    for (int i = 0; i < Fr_N64; i++) {
+        uint64_t carry = 0;
+        for (int j = 0; j < Fr_N64; j++) t[j] = mac(a[j], b[i], t[j], carry);
+        uint64_t c = 0;
+        t[Fr_N64] = adc(t[Fr_N64], carry, c);
+        t[Fr_N64 + 1] = c;
+
+        uint64_t m = t[0] * (uint64_t)Fr_NP;
+        carry = 0;
+        mac(m, Fr_rawq[0], t[0], carry);
+        for (int j = 1; j < Fr_N64; j++) t[j - 1] = mac(m, Fr_rawq[j], t[j], carry);
+        c = 0;
+        t[Fr_N64 - 1] = adc(t[Fr_N64], carry, c);
+        t[Fr_N64] = t[Fr_N64 + 1] + c;
+    }
+    if (t[Fr_N64] != 0 || rawCmp(t, Fr_rawq) >= 0) rawSubQ(t);
+    for (int i = 0; i < Fr_N64; i++) r[i] = t[i];
+}
+
+void Fr_rawCopy(FrRawElement pRawResult, const FrRawElement pRawA) {
+    for (int i = 0; i < Fr_N64; i++) pRawResult[i] = pRawA[i];
+}
+
+void Fr_rawSwap(FrRawElement pRawResult, FrRawElement pRawA) {
+    for (int i = 0; i < Fr_N64; i++) {
+        uint64_t tmp = pRawResult[i];
+        pRawResult[i] = pRawA[i];
+        pRawA[i] = tmp;
+    }
+}
+
+void Fr_rawAdd(FrRawElement pRawResult, const FrRawElement pRawA, const FrRawElement pRawB) {
+    uint64_t carry = 0;
+    for (int i = 0; i < Fr_N64; i++) pRawResult[i] = adc(pRawA[i], pRawB[i], carry);
+    if (carry || rawCmp(pRawResult, Fr_rawq) >= 0) rawSubQ(pRawResult);
+}
+
+void Fr_rawSub(FrRawElement pRawResult, const FrRawElement pRawA, const FrRawElement pRawB) {
+    uint64_t borrow = 0;
+    for (int i = 0; i < Fr_N64; i++) pRawResult[i] = sbb(pRawA[i], pRawB[i], borrow);
+    if (borrow) {
+        uint64_t carry = 0;
+        for (int i = 0; i < Fr_N64; i++) pRawResult[i] = adc(pRawResult[i], Fr_rawq[i], carry);
+    }
+}
+
+void Fr_rawNeg(FrRawElement pRawResult, const FrRawElement pRawA) {
+    if (Fr_rawIsZero(pRawA)) {
+        for (int i = 0; i < Fr_N64; i++) pRawResult[i] = 0;
+        return;
+    }
+    uint64_t borrow = 0;
+    for (int i = 0; i < Fr_N64; i++) pRawResult[i] = sbb(Fr_rawq[i], pRawA[i], borrow);
+}
+
+void Fr_rawMMul(FrRawElement pRawResult, const FrRawElement pRawA, const FrRawElement pRawB) {
+    rawMMul(pRawResult, pRawA, pRawB);
+}
+
+void Fr_rawMSquare(FrRawElement pRawResult, const FrRawElement pRawA) {
+    rawMMul(pRawResult, pRawA, pRawA);
+}
+
+void Fr_rawMMul1(FrRawElement pRawResult, const FrRawElement pRawA, uint64_t pRawB) {
+    FrRawElement b;
+    for (int i = 0; i < Fr_N64; i++) b[i] = 0;
+    b[0] = pRawB;
+    rawMMul(pRawResult, pRawA, b);
+}
+
+void Fr_rawToMontgomery(FrRawElement pRawResult, const FrRawElement &pRawA) {
+    rawMMul(pRawResult, pRawA, Fr_rawR2);
+}
+
+static void rawFromMontgomery(uint64_t *r, const uint64_t *a) {
+    FrRawElement one;
+    for (int i = 0; i < Fr_N64; i++) one[i] = 0;
+    one[0] = 1;
+    rawMMul(r, a, one);
+}
+
+void Fr_rawFromMontgomery(FrRawElement pRawResult, const FrRawElement &pRawA) {
+    rawFromMontgomery(pRawResult, pRawA);
+}
+
+int Fr_rawIsEq(const FrRawElement pRawA, const FrRawElement pRawB) {
+    return rawCmp(pRawA, pRawB) == 0;
+}
+
+int Fr_rawIsZero(const FrRawElement pRawB) {
+    for (int i = 0; i < Fr_N64; i++) {
+        if (pRawB[i] != 0) return 0;
+    }
+    return 1;
+}
+
+/*****************************************************************************/
+/* Conversions                                                               */
+/*****************************************************************************/
+
+static inline bool isLong(PFrElement a) {
+    return a->type & Fr_LONG;
+}
+
+static inline bool isLongMontgomery(PFrElement a) {
+    return (a->type & Fr_LONGMONTGOMERY) == Fr_LONGMONTGOMERY;
+}
+
+// raw value in [0, q) of a 64 bit integer, |v| < q
+static void rawFromInt(FrRawElement r, int64_t v) {
+    for (int i = 0; i < Fr_N64; i++) r[i] = 0;
+    if (v >= 0) {
+        r[0] = (uint64_t)v;
+    } else {
+        r[0] = (uint64_t)0 - (uint64_t)v;
+        Fr_rawNeg(r, r);
+    }
+}
+
+// FrElement is packed, so the long value is copied instead of referenced
+static inline void rawLoad(FrRawElement r, PFrElement a) {
+    memcpy(r, (const void *)a->longVal, sizeof(FrRawElement));
+}
+
+static void rawNormal(FrRawElement r, PFrElement a) {
+    if (!isLong(a)) {
+        rawFromInt(r, a->shortVal);
+    } else {
+        rawLoad(r, a);
+        if (isLongMontgomery(a)) rawFromMontgomery(r, r);
+    }
+}
+
+static void rawMontgomery(FrRawElement r, PFrElement a) {
+    if (isLongMontgomery(a)) {
+        rawLoad(r, a);
+    } else {
+        rawNormal(r, a);
+        rawMMul(r, r, Fr_rawR2);
+    }
+}
+
+static void setLong(PFrElement r, const FrRawElement v, uint32_t type) {
+    r->shortVal = 0;
+    r->type = type;
+    memcpy((void *)r->longVal, v, sizeof(FrRawElement));
+}
+
+static void setInt(PFrElement r, int64_t v) {
+    if (v >= INT32_MIN && v <= INT32_MAX) {
+        r->shortVal = (int32_t)v;
+        r->type = Fr_SHORT;
+    } else {
+        FrRawElement raw;
+        rawFromInt(raw, v);
+        setLong(r, raw, Fr_LONG);
+    }
+}
+
+static void setBool(PFrElement r, int v) {
+    r->shortVal = v ? 1 : 0;
+    r->type = Fr_SHORT;
+}
+
+void Fr_copy(PFrElement r, PFrElement a) {
+    *r = *a;
+}
+
+void Fr_copyn(PFrElement r, PFrElement a, int n) {
+    for (int i = 0; i < n; i++) r[i] = a[i];
+}
+
+void Fr_toNormal(PFrElement r, PFrElement a) {
+    if (isLongMontgomery(a)) {
+        FrRawElement v;
+        rawNormal(v, a);
+        setLong(r, v, Fr_LONG);
+    } else {
+        *r = *a;
+    }
+}
+
+void Fr_toLongNormal(PFrElement r, PFrElement a) {
+    FrRawElement v;
+    rawNormal(v, a);
+    setLong(r, v, Fr_LONG);
+}
+
+void Fr_toMontgomery(PFrElement r, PFrElement a) {
+    if (a->type & Fr_MONTGOMERY) {
+        *r = *a;
+    } else if (!isLong(a)) {
+        FrRawElement v;
+        rawMontgomery(v, a);
+        int32_t shortVal = a->shortVal;
+        setLong(r, v, Fr_MONTGOMERY);
+        r->shortVal = shortVal;
+    } else {
+        FrRawElement v;
+        rawMontgomery(v, a);
+        setLong(r, v, Fr_LONGMONTGOMERY);
+    }
+}
+
+static inline bool rawBelow(const FrRawElement v, uint64_t bound) {
+    for (int i = 1; i < Fr_N64; i++) {
+        if (v[i] != 0) return false;
+    }
+    return v[0] < bound;
+}
+
+// returns the element as an integer in [-2^31, 2^31), fails otherwise
+int Fr_toInt(PFrElement pE) {
+    if (!isLong(pE)) return pE->shortVal;
+    FrRawElement v;
+    rawNormal(v, pE);
+    if (rawBelow(v, (uint64_t)1 << 31)) return (int)v[0];
+    Fr_rawNeg(v, v);
+    if (!rawBelow(v, ((uint64_t)1 << 31) + 1)) {
+        Fr_fail();
+        return 0;
+    }
+    return (int)(-(int64_t)v[0]);
+}
+
+int Fr_isTrue(PFrElement pE) {
+    if (!isLong(pE)) return pE->shortVal != 0;
+    FrRawElement v;
+    rawLoad(v, pE);
+    return !Fr_rawIsZero(v);
+}
+
+/*****************************************************************************/
+/* Arithmetic                                                                */
+/*****************************************************************************/
+
+void Fr_add(PFrElement r, PFrElement a, PFrElement b) {
+    if (!isLong(a) && !isLong(b)) {
+        setInt(r, (int64_t)a->shortVal + (int64_t)b->shortVal);
+        return;
+    }
+    FrRawElement va, vb;
+    if (isLongMontgomery(a) || isLongMontgomery(b)) {
+        rawMontgomery(va, a);
+        rawMontgomery(vb, b);
+        Fr_rawAdd(va, va, vb);
+        setLong(r, va, Fr_LONGMONTGOMERY);
+    } else {
+        rawNormal(va, a);
+        rawNormal(vb, b);
+        Fr_rawAdd(va, va, vb);
+        setLong(r, va, Fr_LONG);
+    }
+}
+
+void Fr_sub(PFrElement r, PFrElement a, PFrElement b) {
+    if (!isLong(a) && !isLong(b)) {
+        setInt(r, (int64_t)a->shortVal - (int64_t)b->shortVal);
+        return;
+    }
+    FrRawElement va, vb;
+    if (isLongMontgomery(a) || isLongMontgomery(b)) {
+        rawMontgomery(va, a);
+        rawMontgomery(vb, b);
+        Fr_rawSub(va, va, vb);
+        setLong(r, va, Fr_LONGMONTGOMERY);
+    } else {
+        rawNormal(va, a);
+        rawNormal(vb, b);
+        Fr_rawSub(va, va, vb);
+        setLong(r, va, Fr_LONG);
+    }
+}
+
+void Fr_neg(PFrElement r, PFrElement a) {
+    if (!isLong(a)) {
+        setInt(r, -(int64_t)a->shortVal);
+        return;
+    }
+    FrRawElement v;
+    rawLoad(v, a);
+    Fr_rawNeg(v, v);
+    setLong(r, v, a->type);
+}
+
+void Fr_mul(PFrElement r, PFrElement a, PFrElement b) {
+    if (!isLong(a) && !isLong(b)) {
+        setInt(r, (int64_t)a->shortVal * (int64_t)b->shortVal);
+        return;
+    }
+    FrRawElement va, vb;
+    bool ma = isLongMontgomery(a);
+    bool mb = isLongMontgomery(b);
+    if (ma && mb) {
+        rawLoad(va, a);
+        rawLoad(vb, b);
+        Fr_rawMMul(va, va, vb);
+        setLong(r, va, Fr_LONGMONTGOMERY);
+    } else if (ma || mb) {
+        // montgomery * normal gives the normal form of the product
+        rawLoad(va, ma ? a : b);
+        rawNormal(vb, ma ? b : a);
+        Fr_rawMMul(va, va, vb);
+        setLong(r, va, Fr_LONG);
+    } else {
+        rawNormal(va, a);
+        rawNormal(vb, b);
+        Fr_rawMMul(va, va, vb);
+        Fr_rawMMul(va, va, Fr_rawR3);
+        setLong(r, va, Fr_LONGMONTGOMERY);
+    }
+}
+
+void Fr_square(PFrElement r, PFrElement a) {
+    Fr_mul(r, a, a);
+}
+
+/*****************************************************************************/
+/* Bitwise operations                                                        */
+/*****************************************************************************/
+
+static inline void rawMaskAndReduce(FrRawElement r) {
+    r[Fr_N64 - 1] &= (uint64_t)Fr_LBO_MASK;
+    rawReduce(r);
+}
+
+void Fr_band(PFrElement r, PFrElement a, PFrElement b) {
+    if (!isLong(a) && !isLong(b) && a->shortVal >= 0 && b->shortVal >= 0) {
+        setInt(r, a->shortVal & b->shortVal);
+        return;
+    }
+    FrRawElement va, vb;
+    rawNormal(va, a);
+    rawNormal(vb, b);
+    for (int i = 0; i < Fr_N64; i++) va[i] &= vb[i];
+    rawMaskAndReduce(va);
+    setLong(r, va, Fr_LONG);
+}
+
+void Fr_bor(PFrElement r, PFrElement a, PFrElement b) {
+    if (!isLong(a) && !isLong(b) && a->shortVal >= 0 && b->shortVal >= 0) {
+        setInt(r, a->shortVal | b->shortVal);
+        return;
+    }
+    FrRawElement va, vb;
+    rawNormal(va, a);
+    rawNormal(vb, b);
+    for (int i = 0; i < Fr_N64; i++) va[i] |= vb[i];
+    rawMaskAndReduce(va);
+    setLong(r, va, Fr_LONG);
+}
+
+void Fr_bxor(PFrElement r, PFrElement a, PFrElement b) {
+    if (!isLong(a) && !isLong(b) && a->shortVal >= 0 && b->shortVal >= 0) {
+        setInt(r, a->shortVal ^ b->shortVal);
+        return;
+    }
+    FrRawElement va, vb;
+    rawNormal(va, a);
+    rawNormal(vb, b);
+    for (int i = 0; i < Fr_N64; i++) va[i] ^= vb[i];
+    rawMaskAndReduce(va);
+    setLong(r, va, Fr_LONG);
+}
+
+void Fr_bnot(PFrElement r, PFrElement a) {
+    FrRawElement v;
+    rawNormal(v, a);
+    for (int i = 0; i < Fr_N64; i++) v[i] = ~v[i];
+    rawMaskAndReduce(v);
+    setLong(r, v, Fr_LONG);
+}
+
+static void rawShr(FrRawElement r, const FrRawElement a, unsigned int n) {
+    unsigned int words = n / 64, bits = n % 64;
+    for (int i = 0; i < Fr_N64; i++) {
+        int k = i + (int)words;
+        uint64_t lo = k < Fr_N64 ? a[k] : 0;
+        uint64_t hi = k + 1 < Fr_N64 ? a[k + 1] : 0;
+        r[i] = bits ? (lo >> bits) | (hi << (64 - bits)) : lo;
+    }
+}
+
+static void rawShl(FrRawElement r, const FrRawElement a, unsigned int n) {
+    unsigned int words = n / 64, bits = n % 64;
+    for (int i = Fr_N64 - 1; i >= 0; i--) {
+        int k = i - (int)words;
+        uint64_t hi = k >= 0 ? a[k] : 0;
+        uint64_t lo = k - 1 >= 0 ? a[k - 1] : 0;
+        r[i] = bits ? (hi << bits) | (lo >> (64 - bits)) : hi;
+    }
+    rawMaskAndReduce(r);
+}
+
+static void doShr(PFrElement r, PFrElement a, unsigned int n) {
+    if (!isLong(a) && a->shortVal >= 0) {
+        setInt(r, n < 31 ? a->shortVal >> n : 0);
+        return;
+    }
+    FrRawElement v;
+    rawNormal(v, a);
+    rawShr(v, v, n);
+    setLong(r, v, Fr_LONG);
+}
+
+static void doShl(PFrElement r, PFrElement a, unsigned int n) {
+    if (!isLong(a) && a->shortVal >= 0 && n < 31) {
+        int64_t v = (int64_t)a->shortVal << n;
+        if (v <= INT32_MAX) {
+            setInt(r, v);
+            return;
+        }
+    }
+    FrRawElement v;
+    rawNormal(v, a);
+    rawShl(v, v, n);
+    setLong(r, v, Fr_LONG);
+}
+
+// Decodes the shift amount b: a value below Fr_NBITS shifts in the
+// requested direction, a negative value (q - b below Fr_NBITS) shifts in
+// the opposite one. Returns false if the result of the shift is zero.
+static bool shiftAmount(PFrElement b, unsigned int &n, bool &reverse) {
+    FrRawElement v;
+    rawNormal(v, b);
+    for (int k = 0; k < 2; k++) {
+        if (rawBelow(v, Fr_NBITS)) {
+            n = (unsigned int)v[0];
+            reverse = k == 1;
+            return true;
+        }
+        Fr_rawNeg(v, v);
+    }
+    return false;
+}
+
+void Fr_shr(PFrElement r, PFrElement a, PFrElement b) {
+    unsigned int n;
+    bool reverse;
+    if (!shiftAmount(b, n, reverse)) {
+        setInt(r, 0);
+    } else if (reverse) {
+        doShl(r, a, n);
+    } else {
+        doShr(r, a, n);
+    }
+}
+
+void Fr_shl(PFrElement r, PFrElement a, PFrElement b) {
+    unsigned int n;
+    bool reverse;
+    if (!shiftAmount(b, n, reverse)) {
+        setInt(r, 0);
+    } else if (reverse) {
+        doShr(r, a, n);
+    } else {
+        doShl(r, a, n);
+    }
+}
+
+/*****************************************************************************/
+/* Comparisons and logical operations                                        */
+/*****************************************************************************/
+
+// compares a and b as integers in (-q/2, q/2]
+static int cmp(PFrElement a, PFrElement b) {
+    if (!isLong(a) && !isLong(b)) {
+        return a->shortVal < b->shortVal ? -1 : (a->shortVal > b->shortVal ? 1 : 0);
+    }
+    FrRawElement va, vb;
+    rawNormal(va, a);
+    rawNormal(vb, b);
+    bool na = rawCmp(va, Fr_rawHalf) > 0;
+    bool nb = rawCmp(vb, Fr_rawHalf) > 0;
+    if (na != nb) return na ? -1 : 1;
+    return rawCmp(va, vb);
+}
+
+void Fr_eq(PFrElement r, PFrElement a, PFrElement b) {
+    setBool(r, cmp(a, b) == 0);
+}
+
+void Fr_neq(PFrElement r, PFrElement a, PFrElement b) {
+    setBool(r, cmp(a, b) != 0);
+}
+
+void Fr_lt(PFrElement r, PFrElement a, PFrElement b) {
+    setBool(r, cmp(a, b) < 0);
+}
+
+void Fr_gt(PFrElement r, PFrElement a, PFrElement b) {
+    setBool(r, cmp(a, b) > 0);
+}
+
+void Fr_leq(PFrElement r, PFrElement a, PFrElement b) {
+    setBool(r, cmp(a, b) <= 0);
+}
+
+void Fr_geq(PFrElement r, PFrElement a, PFrElement b) {
+    setBool(r, cmp(a, b) >= 0);
+}
+
+void Fr_land(PFrElement r, PFrElement a, PFrElement b) {
+    setBool(r, Fr_isTrue(a) && Fr_isTrue(b));
+}
+
+void Fr_lor(PFrElement r, PFrElement a, PFrElement b) {
+    setBool(r, Fr_isTrue(a) || Fr_isTrue(b));
+}
+
+void Fr_lnot(PFrElement r, PFrElement a) {
+    setBool(r, !Fr_isTrue(a));
+}
@@ -175,26 +175,22 @@ void loadJson(Circom_CalcWit *ctx, std::string filename) {
     u64 h = fnv1a(it.key());
     std::vector<FrElement> v;
     json2FrElements(it.value(),v);
-    uint signalSize = ctx->getInputSignalSize(h);
+    uint signalSize;
+    try {
+      signalSize = ctx->getInputSignalSize(h);
+    } catch (Circom_RuntimeError &e) {
+	// the witness calculator only knows the hash of the name
+	throw ctx->inputSignalError(e.message, it.key());
+    }
     if (v.size() < signalSize) {
-	std::ostringstream errStrStream;
-	errStrStream << "Error loading signal " << it.key() << ": Not enough values\n";
-	throw std::runtime_error(errStrStream.str() );
+	throw ctx->inputSignalError("Not enough values", it.key());
     }
     if (v.size() > signalSize) {
-	std::ostringstream errStrStream;
-	errStrStream << "Error loading signal " << it.key() << ": Too many values\n";
-	throw std::runtime_error(errStrStream.str() );
+	throw ctx->inputSignalError("Too many values", it.key());
     }
     for (uint i = 0; i<v.size(); i++){
-      try {
-	// std::cout << it.key() << "," << i << " => " << Fr_element2str(&(v[i])) << '\n';
-	ctx->setInputSignal(h,i,v[i]);
-      } catch (std::runtime_error e) {
-	std::ostringstream errStrStream;
-	errStrStream << "Error setting signal: " << it.key() << "\n" << e.what();
-	throw std::runtime_error(errStrStream.str() );
-      }
+      // std::cout << it.key() << "," << i << " => " << Fr_element2str(&(v[i])) << '\n';
+      ctx->setInputSignal(h,i,v[i]);
     }
   }
 }
@@ -249,8 +245,8 @@ void writeBinWitness(Circom_CalcWit *ctx, std::string wtnsFileName) {
 
 int main (int argc, char *argv[]) {
   std::string cl(argv[0]);
-  if (argc!=3) {
-        std::cout << "Usage: " << cl << " <input.json> <output.wtns>\n";
+  if (argc!=3 && argc!=4) {
+        std::cout << "Usage: " << cl << " <input.json> <output.wtns> [<trace.txt>]\n";
   } else {
     std::string datfile = cl + ".dat";
     std::string jsonfile(argv[1]);
@@ -261,11 +257,24 @@ int main (int argc, char *argv[]) {
    Circom_Circuit *circuit = loadCircuit(datfile);
 
    Circom_CalcWit *ctx = new Circom_CalcWit(circuit);
+
+   // only used by circuits compiled with --trace_signals
+   std::ofstream traceFile;
+   if (argc == 4) {
+     traceFile.open(argv[3]);
+     ctx->traceStream = &traceFile;
+   }
   
-   loadJson(ctx, jsonfile);
-   if (ctx->getRemaingInputsToBeSet()!=0) {
-     std::cerr << "Not all inputs have been set. Only " << get_main_input_signal_no()-ctx->getRemaingInputsToBeSet() << " out of " << get_main_input_signal_no() << std::endl;
-     assert(false);
+   try {
+     loadJson(ctx, jsonfile);
+     if (ctx->getRemaingInputsToBeSet()!=0) {
+       std::ostringstream errStrStream;
+       errStrStream << "Not all inputs have been set. Only " << get_main_input_signal_no()-ctx->getRemaingInputsToBeSet() << " out of " << get_main_input_signal_no();
+       throw ctx->inputSignalError(errStrStream.str(), "");
+     }
+   } catch (std::runtime_error &e) {
+     std::cerr << e.what() << std::endl;
+     return EXIT_FAILURE;
    }
    /*
      for (uint i = 0; i<get_size_of_witness(); i++){
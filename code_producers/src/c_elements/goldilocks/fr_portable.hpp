@@ -0,0 +1,16 @@
+#ifndef __FR_PORTABLE_H
+#define __FR_PORTABLE_H
+
+// Constants of the goldilocks prime field used by the portable implementation
+// of the field arithmetic (fr_portable.cpp). The raw values are stored in
+// little endian 64 bit limbs and R = 2^64 is the Montgomery factor.
+
+#define Fr_NBITS 64
+#define Fr_RAW_Q { 0xffffffff00000001 }
+#define Fr_RAW_HALF { 0x7fffffff80000000 }
+#define Fr_RAW_R2 { 0xfffffffe00000001 }
+#define Fr_RAW_R3 { 0x0000000000000001 }
+#define Fr_LBO_MASK 0xffffffffffffffff
+#define Fr_NP 0xfffffffeffffffff
+
+#endif // __FR_PORTABLE_H
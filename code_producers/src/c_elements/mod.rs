@@ -20,10 +20,17 @@ pub struct CProducer {
     pub prime: String,
     pub prime_str: String,
     pub main_input_list: InputList,
+    pub main_template_id: usize,
     pub witness_to_signal_list: SignalList,
     pub io_map: TemplateInstanceIOMap,
     pub template_instance_list: TemplateListParallel,
     pub message_list: MessageList,
+    pub message_file_list: MessageList,
+    pub template_debug_list: TemplateDebugList,
+    pub trace_signals: bool,
+    // size in KB of the translation units of the templates and functions,
+    // all the code is written in a single file if it is None
+    pub c_unit_size: Option<usize>,
     pub field_tracking: Vec<String>,
     pub major_version: usize,
     pub minor_version: usize,
@@ -69,6 +76,7 @@ impl Default for CProducer {
             number_of_main_outputs: 1,
             number_of_main_inputs: 2,
             main_input_list: [("in1".to_string(), 2, 1), ("in2".to_string(), 3, 1)].to_vec(), //[].to_vec(),
+            main_template_id: 0,
             signals_in_witness: 20,
             witness_to_signal_list: [
                 0, 1, 2, 3, 4, 5, 6, 12, 16, 19, 24, 27, 33, 42, 46, 50, 51, 65, 78, 79,
@@ -76,6 +84,10 @@ impl Default for CProducer {
             .to_vec(), //[].to_vec(),
             message_list: ["Main".to_string(), "Hola Herme".to_string(), "Hola Albert".to_string()]
                 .to_vec(), //[].to_vec(),
+            message_file_list: Vec::new(),
+            template_debug_list: Vec::new(),
+            trace_signals: false,
+            c_unit_size: None,
             field_tracking: [
                 "1884242871839275222246405745257275088548364400416034343698204186575808495617"
                     .to_string(),
@@ -158,6 +170,21 @@ impl CProducer {
     pub fn get_message_list(&self) -> &MessageList {
         &self.message_list
     }
+    pub fn get_message_file_list(&self) -> &MessageList {
+        &self.message_file_list
+    }
+    pub fn get_main_template_id(&self) -> usize {
+        self.main_template_id
+    }
+    pub fn get_template_debug_list(&self) -> &TemplateDebugList {
+        &self.template_debug_list
+    }
+    pub fn needs_signal_trace(&self) -> bool {
+        self.trace_signals
+    }
+    pub fn get_c_unit_size(&self) -> Option<usize> {
+        self.c_unit_size
+    }
     pub fn get_field_constant_list(&self) -> &Vec<String> {
         &self.field_tracking
     }
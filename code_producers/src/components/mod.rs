@@ -21,6 +21,33 @@ pub type InputOutputList = Vec<IODef>;
 pub type TemplateInstanceIOMap = BTreeMap<usize, InputOutputList>;
 pub type MessageList = Vec<String>;
 
+// A component declared in a template: its name, the position of its first
+// element among the subcomponents of the template and its dimensions
+#[derive(Clone)]
+pub struct ComponentDecl {
+    pub name: String,
+    pub start: usize,
+    pub lengths: Vec<usize>,
+}
+// A signal declared in a template: its name, its offset among the signals
+// of the component and its dimensions
+#[derive(Clone)]
+pub struct SignalDecl {
+    pub name: String,
+    pub offset: usize,
+    pub lengths: Vec<usize>,
+}
+// For each template instance, its name and the components and signals it
+// declares. Used to rebuild the instance path of a failing component and
+// to name the signals in the trace of assignments
+#[derive(Clone)]
+pub struct TemplateDebugInfo {
+    pub name: String,
+    pub components: Vec<ComponentDecl>,
+    pub signals: Vec<SignalDecl>,
+}
+pub type TemplateDebugList = Vec<TemplateDebugInfo>;
+
 pub fn hasher(value: &str) -> u64 {
     use lz_fnv::FnvHasher;
     let mut fnv_hasher: Fnv1a<u64> = Fnv1a::with_key(14695981039346656037);
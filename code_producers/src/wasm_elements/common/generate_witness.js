@@ -1,13 +1,15 @@
 const wc  = require("./witness_calculator.js");
 const { readFileSync, writeFile } = require("fs");
 
-if (process.argv.length != 5) {
-    console.log("Usage: node generate_witness.js <file.wasm> <input.json> <output.wtns>");
+if (process.argv.length != 5 && process.argv.length != 6) {
+    console.log("Usage: node generate_witness.js <file.wasm> <input.json> <output.wtns> [<trace.txt>]");
 } else {
     const input = JSON.parse(readFileSync(process.argv[3], "utf8"));
     
     const buffer = readFileSync(process.argv[2]);
-    wc(buffer).then(async witnessCalculator => {
+    // the trace file is only written by circuits compiled with --trace_signals
+    const options = process.argv.length == 6 ? { traceFile: process.argv[5] } : {};
+    wc(buffer, options).then(async witnessCalculator => {
 	//    const w= await witnessCalculator.calculateWitness(input,0);
 	//    for (let i=0; i< w.length; i++){
 	//	console.log(w[i]);
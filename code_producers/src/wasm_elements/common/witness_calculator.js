@@ -11,54 +11,107 @@ module.exports = async function builder(code, options) {
 	throw new Error(err);
     }
 
+    // Information used to report where runtime errors happen. It is
+    // stored by the compiler in a custom section of the module
+    let debugInfo;
+    const debugSections = WebAssembly.Module.customSections(wasmModule, "circom_debug");
+    if (debugSections.length > 0) {
+	debugInfo = JSON.parse(new TextDecoder().decode(debugSections[0]));
+    }
+
     let wc;
 
-    let errStr = "";
-    let msgStr = "";
-    
+    // State of the current call. It is reset by the witness calculator
+    // before every witness computation, so errors and partial log lines
+    // never leak from one call into the next one.
+    const state = {
+	errors: [],
+	msgStr: "",
+	fmtStr: "",
+	logger: options.logger || console.log,
+	componentPaths: new Map(),
+    };
+
+    // Receives the signal assignments of the circuits compiled with
+    // --trace_signals: options.traceSignal is called with every assignment,
+    // otherwise they are written to options.traceFile or to the logger
+    let traceSignal = options.traceSignal;
+    if (traceSignal === undefined) {
+	let writeLine = state.logger;
+	if (options.traceFile !== undefined) {
+	    const fs = require("fs");
+	    const fd = fs.openSync(options.traceFile, "w");
+	    writeLine = (line) => fs.writeSync(fd, line + "\n");
+	}
+	traceSignal = (t) => writeLine(`${t.path}.${t.signal} = ${t.value} (${t.template} at ${t.file}:${t.line})`);
+    }
+
     const instance = await WebAssembly.instantiate(wasmModule, {
         runtime: {
             exceptionHandler : function(code) {
-		let err;
-                if (code == 1) {
-                    err = "Signal not found.\n";
-                } else if (code == 2) {
-                    err = "Too many signals set.\n";
-                } else if (code == 3) {
-                    err = "Signal already set.\n";
-		} else if (code == 4) {
-                    err = "Assert Failed.\n";
-		} else if (code == 5) {
-                    err = "Not enough memory.\n";
-		} else if (code == 6) {
-                    err = "Input signal array access exceeds the size.\n";
-		} else {
-		    err = "Unknown error.\n";
-                }
-                throw new Error(err + errStr);
+		const info = getErrorSource();
+		info.details = state.errors.slice();
+		throw new WitnessCalculatorError(code, info);
             },
 	    printErrorMessage : function() {
-		errStr += getMessage() + "\n";
-                // console.error(getMessage());
+		state.errors.push(getMessage());
 	    },
 	    writeBufferMessage : function() {
 			const msg = getMessage();
 			// Any calls to `log()` will always end with a `\n`, so that's when we print and reset
 			if (msg === "\n") {
-				console.log(msgStr);
-				msgStr = "";
+				state.logger(state.msgStr);
+				state.msgStr = "";
 			} else {
 				// If we've buffered other content, put a space in between the items
-				if (msgStr !== "") {
-					msgStr += " "
+				if (state.msgStr !== "") {
+					state.msgStr += " "
 				}
 				// Then append the message to the message we are creating
-				msgStr += msg;
+				state.msgStr += msg;
 			}
 	    },
 	    showSharedRWMemory : function() {
 		printSharedRWMemory ();
-            }
+            },
+	    // Messages built from format strings: the pieces are appended
+	    // without separators and the message is ended as an item of the
+	    // current log (0) or as the message of a failed assert (1)
+	    writeFormatMessage : function() {
+		state.fmtStr += getMessage();
+	    },
+	    showFormatValue : function(hex) {
+		const value = readSharedRWMemory();
+		state.fmtStr += hex ? "0x" + value.toString(16) : value.toString();
+	    },
+	    endFormatMessage : function(isError) {
+		if (isError) {
+		    state.errors.push(state.fmtStr);
+		} else {
+		    if (state.msgStr !== "") {
+			state.msgStr += " "
+		    }
+		    state.msgStr += state.fmtStr;
+		}
+		state.fmtStr = "";
+	    },
+	    traceComponent : function(father, cmp) {
+		const mem = new Uint32Array(instance.exports.memory.buffer);
+		const name = getComponentName(mem, father, cmp);
+		state.componentPaths.set(cmp, getComponentPath(father) + "." + name);
+	    },
+	    traceSignal : function(cmp, index, messageId, line) {
+		const mem = new Uint32Array(instance.exports.memory.buffer);
+		const template = debugInfo.templates[mem[cmp / 4]];
+		traceSignal({
+		    path: getComponentPath(cmp),
+		    template: template.name,
+		    signal: getSignalName(template, index),
+		    value: readSharedRWMemory(),
+		    file: debugInfo.files[messageId],
+		    line: line,
+		});
+	    }
 
         }
     });
@@ -74,8 +127,8 @@ module.exports = async function builder(code, options) {
 //            options.logFinishComponent
 //        );
 
-    
-    wc = new WitnessCalculator(instance, sanityCheck);
+
+    wc = new WitnessCalculator(instance, sanityCheck, state);
     return wc;
 
     function getMessage() {
@@ -87,7 +140,110 @@ module.exports = async function builder(code, options) {
 	}
         return message;
     }
-	
+
+    // Reads the source of the last failure stored by the circuit: the
+    // message id and line of the failing instruction, the signal involved
+    // and the (father, subcomponent) pairs crossed by the error
+    function getErrorSource() {
+	if (debugInfo === undefined || !instance.exports.getErrorInfoStart) {
+	    return {};
+	}
+	const mem = new Uint32Array(instance.exports.memory.buffer);
+	const start = instance.exports.getErrorInfoStart() / 4;
+	const messageId = mem[start] | 0;
+	if (messageId < 0) {
+	    return {};
+	}
+	const info = {
+	    file: debugInfo.files[messageId],
+	    line: mem[start + 1],
+	};
+	const signalPos = mem[start + 2];
+	if (signalPos != 0) {
+	    info.signal = readString(signalPos);
+	}
+	const traceLength = mem[start + 3];
+	const path = [];
+	let templateId = debugInfo.main;
+	for (let i = 0; i < traceLength; i++) {
+	    const father = mem[start + 4 + 2 * i];
+	    const cmp = mem[start + 5 + 2 * i];
+	    if (i == 0) {
+		templateId = mem[cmp / 4];
+	    }
+	    path.unshift(getComponentName(mem, father, cmp));
+	}
+	path.unshift(traceLength < debugInfo.max_trace ? "main" : "...");
+	info.template = debugInfo.templates[templateId].name;
+	info.path = path.join(".");
+	return info;
+    }
+
+    // Name, with its indexes, of the subcomponent cmp of the component father
+    function getComponentName(mem, father, cmp) {
+	const template = debugInfo.templates[mem[father / 4]];
+	// template id, signal start and input counter precede the subcomponents
+	const subcomponents = father / 4 + 3;
+	for (const c of template.components) {
+	    const size = c.lengths.reduce((a, b) => a * b, 1);
+	    for (let i = 0; i < size; i++) {
+		if (mem[subcomponents + c.start + i] == cmp) {
+		    let name = "";
+		    let rest = i;
+		    for (let d = c.lengths.length - 1; d >= 0; d--) {
+			name = `[${rest % c.lengths[d]}]` + name;
+			rest = Math.floor(rest / c.lengths[d]);
+		    }
+		    return c.name + name;
+		}
+	    }
+	}
+	return "?";
+    }
+
+    // The main component is the only one whose creation is not reported
+    function getComponentPath(cmp) {
+	const path = state.componentPaths.get(cmp);
+	return path === undefined ? "main" : path;
+    }
+
+    // Name, with its indexes, of the signal at position index among the
+    // signals of a component of the given template
+    function getSignalName(template, index) {
+	for (const s of template.signals) {
+	    const size = s.lengths.reduce((a, b) => a * b, 1);
+	    if (s.offset <= index && index < s.offset + size) {
+		let name = "";
+		let rest = index - s.offset;
+		for (let d = s.lengths.length - 1; d >= 0; d--) {
+		    name = `[${rest % s.lengths[d]}]` + name;
+		    rest = Math.floor(rest / s.lengths[d]);
+		}
+		return s.name + name;
+	    }
+	}
+	return "?";
+    }
+
+    function readSharedRWMemory() {
+	const shared_rw_memory_size = instance.exports.getFieldNumLen32();
+	const arr = new Uint32Array(shared_rw_memory_size);
+	for (let j=0; j<shared_rw_memory_size; j++) {
+	    arr[shared_rw_memory_size-1-j] = instance.exports.readSharedRWMemory(j);
+	}
+	return fromArray32(arr);
+    }
+
+    function readString(pos) {
+	const bytes = new Uint8Array(instance.exports.memory.buffer);
+	let str = "";
+	while (bytes[pos] != 0) {
+	    str += String.fromCharCode(bytes[pos]);
+	    pos++;
+	}
+	return str;
+    }
+
     function printSharedRWMemory () {
 	const shared_rw_memory_size = instance.exports.getFieldNumLen32();
 	const arr = new Uint32Array(shared_rw_memory_size);
@@ -96,21 +252,81 @@ module.exports = async function builder(code, options) {
 	}
 
 	// If we've buffered other content, put a space in between the items
-	if (msgStr !== "") {
-		msgStr += " "
+	if (state.msgStr !== "") {
+		state.msgStr += " "
 	}
 	// Then append the value to the message we are creating
-	msgStr += (fromArray32(arr).toString());
+	state.msgStr += (fromArray32(arr).toString());
 	}
 
 };
 
+// Error codes raised by the wasm module through exceptionHandler (1-6)
+// and by the input loading performed in JavaScript (7-9).
+const ERROR_SIGNAL_NOT_FOUND = 1;
+const ERROR_TOO_MANY_SIGNALS_SET = 2;
+const ERROR_SIGNAL_ALREADY_SET = 3;
+const ERROR_ASSERT_FAILED = 4;
+const ERROR_NOT_ENOUGH_MEMORY = 5;
+const ERROR_INPUT_ARRAY_ACCESS = 6;
+const ERROR_NOT_ENOUGH_VALUES = 7;
+const ERROR_TOO_MANY_VALUES = 8;
+const ERROR_MISSING_INPUTS = 9;
+
+const ERROR_MESSAGES = {
+    [ERROR_SIGNAL_NOT_FOUND]: "Signal not found.",
+    [ERROR_TOO_MANY_SIGNALS_SET]: "Too many signals set.",
+    [ERROR_SIGNAL_ALREADY_SET]: "Signal already set.",
+    [ERROR_ASSERT_FAILED]: "Assert Failed.",
+    [ERROR_NOT_ENOUGH_MEMORY]: "Not enough memory.",
+    [ERROR_INPUT_ARRAY_ACCESS]: "Input signal array access exceeds the size.",
+    [ERROR_NOT_ENOUGH_VALUES]: "Not enough values for input signal.",
+    [ERROR_TOO_MANY_VALUES]: "Too many values for input signal.",
+    [ERROR_MISSING_INPUTS]: "Not all inputs have been set.",
+};
+
+class WitnessCalculatorError extends Error {
+    // info.signal: name of the signal involved, if any
+    // info.template: template of the failing component
+    // info.path: instance path of the failing component, e.g. main.hasher.mix[3]
+    // info.file, info.line: source location of the failing instruction
+    // info.details: messages printed by the circuit before failing
+    constructor(code, info) {
+	info = info || {};
+	const details = info.details || [];
+	let message = (ERROR_MESSAGES[code] || "Unknown error.") + "\n";
+	if (info.path !== undefined) {
+	    message += `Component: ${info.path} (template ${info.template})\n`;
+	}
+	if (info.signal !== undefined) {
+	    message += `Signal: ${info.signal}\n`;
+	}
+	if (info.file !== undefined) {
+	    message += `Location: ${info.file}:${info.line}\n`;
+	}
+	for (const d of details) {
+	    message += d + "\n";
+	}
+	super(message);
+	this.name = "WitnessCalculatorError";
+	this.code = code;
+	this.signal = info.signal;
+	this.template = info.template;
+	this.path = info.path;
+	this.file = info.file;
+	this.line = info.line;
+	this.details = details;
+    }
+}
+
 class WitnessCalculator {
-    constructor(instance, sanityCheck) {
+    constructor(instance, sanityCheck, state) {
         this.instance = instance;
+	this.state = state;
 
 	this.version = this.instance.exports.getVersion();
         this.n32 = this.instance.exports.getFieldNumLen32();
+	this.sharedRWMemoryStart = this.instance.exports.getSharedRWMemoryStart();
 
         this.instance.exports.getRawPrime();
         const arr = new Uint32Array(this.n32);
@@ -120,72 +336,124 @@ class WitnessCalculator {
         this.prime = fromArray32(arr);
 
         this.witnessSize = this.instance.exports.getWitnessSize();
+	this.inputSize = this.instance.exports.getInputSize();
 
         this.sanityCheck = sanityCheck;
+
+	// The header of a .wtns file only depends on the circuit, so it is
+	// built once and copied in front of every computed witness.
+	this.wtnsHeader = this._buildWTNSHeader();
+	this.inputHashes = new Map();
     }
-    
+
     circom_version() {
 	return this.instance.exports.getVersion();
     }
 
+    // Clears the state left by a previous call (buffered log output, error
+    // messages and traced components). The wasm state is reset by init at
+    // every call.
+    reset() {
+	this.state.errors = [];
+	this.state.msgStr = "";
+	this.state.fmtStr = "";
+	this.state.componentPaths.clear();
+    }
+
+    // The memory buffer is detached when the module grows its memory,
+    // so the view over the shared memory is created at every use.
+    _sharedRWMemory() {
+	return new Uint32Array(this.instance.exports.memory.buffer, this.sharedRWMemoryStart, this.n32);
+    }
+
+    _inputHash(k) {
+	let hs = this.inputHashes.get(k);
+	if (hs === undefined) {
+            const h = fnvHash(k);
+            hs = [parseInt(h.slice(0,8), 16), parseInt(h.slice(8,16), 16)];
+	    this.inputHashes.set(k, hs);
+	}
+	return hs;
+    }
+
     async _doCalculateWitness(input, sanityCheck) {
 	//input is assumed to be a map from signals to arrays of bigints
+	this.reset();
         this.instance.exports.init((this.sanityCheck || sanityCheck) ? 1 : 0);
         const keys = Object.keys(input);
 	var input_counter = 0;
         keys.forEach( (k) => {
-            const h = fnvHash(k);
-            const hMSB = parseInt(h.slice(0,8), 16);
-            const hLSB = parseInt(h.slice(8,16), 16);
+            const [hMSB, hLSB] = this._inputHash(k);
             const fArr = flatArray(input[k]);
 	    let signalSize = this.instance.exports.getInputSignalSize(hMSB, hLSB);
 	    if (signalSize < 0){
-		throw new Error(`Signal ${k} not found\n`);
+		throw new WitnessCalculatorError(ERROR_SIGNAL_NOT_FOUND, { signal: k });
 	    }
 	    if (fArr.length < signalSize) {
-		throw new Error(`Not enough values for input signal ${k}\n`);
+		throw new WitnessCalculatorError(ERROR_NOT_ENOUGH_VALUES, { signal: k });
 	    }
 	    if (fArr.length > signalSize) {
-		throw new Error(`Too many values for input signal ${k}\n`);
+		throw new WitnessCalculatorError(ERROR_TOO_MANY_VALUES, { signal: k });
 	    }
             for (let i=0; i<fArr.length; i++) {
                 const arrFr = toArray32(normalize(fArr[i],this.prime),this.n32)
+		const shared = this._sharedRWMemory();
                 for (let j=0; j<this.n32; j++) {
-		    this.instance.exports.writeSharedRWMemory(j,arrFr[this.n32-1-j]);
+		    shared[j] = arrFr[this.n32-1-j];
 		}
 		try {
                     this.instance.exports.setInputSignal(hMSB, hLSB,i);
 		    input_counter++;
 		} catch (err) {
-		    // console.log(`After adding signal ${i} of ${k}`)
+		    if (err instanceof WitnessCalculatorError) {
+			if (err.code == ERROR_ASSERT_FAILED || err.code == ERROR_NOT_ENOUGH_MEMORY) {
+			    throw err;
+			}
+			const signal = err.code == ERROR_INPUT_ARRAY_ACCESS ? k : elementName(k, input[k], i);
+			throw new WitnessCalculatorError(err.code, { signal: signal, details: err.details });
+		    }
                     throw new Error(err);
 		}
             }
 
         });
-	if (input_counter < this.instance.exports.getInputSize()) {
-	    throw new Error(`Not all inputs have been set. Only ${input_counter} out of ${this.instance.exports.getInputSize()}`);
+	if (input_counter < this.inputSize) {
+	    throw new WitnessCalculatorError(ERROR_MISSING_INPUTS, {
+		details: [`Only ${input_counter} out of ${this.inputSize}`]
+	    });
 	}
     }
 
+    // Copies the witness into buff32 starting at pos, one field element
+    // (this.n32 words, little endian) per witness signal.
+    _copyWitness(buff32, pos) {
+	const shared = this._sharedRWMemory();
+        for (let i=0; i<this.witnessSize; i++) {
+            this.instance.exports.getWitness(i);
+	    buff32.set(shared, pos);
+	    pos += this.n32;
+        }
+    }
+
     async calculateWitness(input, sanityCheck) {
 
         const w = [];
 
         await this._doCalculateWitness(input, sanityCheck);
 
+	const shared = this._sharedRWMemory();
         for (let i=0; i<this.witnessSize; i++) {
             this.instance.exports.getWitness(i);
 	    const arr = new Uint32Array(this.n32);
             for (let j=0; j<this.n32; j++) {
-            arr[this.n32-1-j] = this.instance.exports.readSharedRWMemory(j);
+		arr[this.n32-1-j] = shared[j];
             }
             w.push(fromArray32(arr));
         }
 
         return w;
     }
-    
+
 
     async calculateBinWitness(input, sanityCheck) {
 
@@ -193,86 +461,112 @@ class WitnessCalculator {
 	const buff = new  Uint8Array( buff32.buffer);
         await this._doCalculateWitness(input, sanityCheck);
 
-        for (let i=0; i<this.witnessSize; i++) {
-            this.instance.exports.getWitness(i);
-	    const pos = i*this.n32;
-            for (let j=0; j<this.n32; j++) {
-		buff32[pos+j] = this.instance.exports.readSharedRWMemory(j);
-            }
-        }
+	this._copyWitness(buff32, 0);
 
 	return buff;
     }
-    
+
 
     async calculateWTNSBin(input, sanityCheck) {
 
         const buff32 = new Uint32Array(this.witnessSize*this.n32+this.n32+11);
 	const buff = new  Uint8Array( buff32.buffer);
         await this._doCalculateWitness(input, sanityCheck);
-  
+
+	buff32.set(this.wtnsHeader, 0);
+	this._copyWitness(buff32, this.wtnsHeader.length);
+
+	return buff;
+    }
+
+    // Computes a .wtns buffer for every input of `inputs` (an iterable or
+    // async iterable), reusing this instance. Yields { index, wtns } for
+    // every successful computation and { index, error } when the circuit
+    // rejects an input, so a failing input does not stop the stream.
+    async *calculateWTNSBinStream(inputs, sanityCheck) {
+	let index = 0;
+	for await (const input of inputs) {
+	    try {
+		const wtns = await this.calculateWTNSBin(input, sanityCheck);
+		yield { index, wtns };
+	    } catch (err) {
+		if (!(err instanceof WitnessCalculatorError)) {
+		    throw err;
+		}
+		yield { index, error: err };
+	    }
+	    index++;
+	}
+    }
+
+    // Same as calculateWTNSBinStream, collecting all the results.
+    async calculateWTNSBinBatch(inputs, sanityCheck) {
+	const results = [];
+	for await (const result of this.calculateWTNSBinStream(inputs, sanityCheck)) {
+	    results.push(result);
+	}
+	return results;
+    }
+
+    _buildWTNSHeader() {
+	const header32 = new Uint32Array(this.n32+11);
+	const header = new Uint8Array(header32.buffer);
+
 	//"wtns"
-	buff[0] = "w".charCodeAt(0)
-	buff[1] = "t".charCodeAt(0)
-	buff[2] = "n".charCodeAt(0)
-	buff[3] = "s".charCodeAt(0)
+	header[0] = "w".charCodeAt(0)
+	header[1] = "t".charCodeAt(0)
+	header[2] = "n".charCodeAt(0)
+	header[3] = "s".charCodeAt(0)
 
 	//version 2
-	buff32[1] = 2;
+	header32[1] = 2;
 
 	//number of sections: 2
-	buff32[2] = 2;
+	header32[2] = 2;
 
 	//id section 1
-	buff32[3] = 1;
+	header32[3] = 1;
 
 	const n8 = this.n32*4;
 	//id section 1 length in 64bytes
 	const idSection1length = 8 + n8;
 	const idSection1lengthHex = idSection1length.toString(16);
-        buff32[4] = parseInt(idSection1lengthHex.slice(0,8), 16);
-        buff32[5] = parseInt(idSection1lengthHex.slice(8,16), 16);
+        header32[4] = parseInt(idSection1lengthHex.slice(0,8), 16);
+        header32[5] = parseInt(idSection1lengthHex.slice(8,16), 16);
 
 	//this.n32
-	buff32[6] = n8;
+	header32[6] = n8;
 
 	//prime number
 	this.instance.exports.getRawPrime();
 
 	var pos = 7;
         for (let j=0; j<this.n32; j++) {
-	    buff32[pos+j] = this.instance.exports.readSharedRWMemory(j);
+	    header32[pos+j] = this.instance.exports.readSharedRWMemory(j);
         }
 	pos += this.n32;
 
 	// witness size
-	buff32[pos] = this.witnessSize;
+	header32[pos] = this.witnessSize;
 	pos++;
 
 	//id section 2
-	buff32[pos] = 2;
+	header32[pos] = 2;
 	pos++;
 
 	// section 2 length
 	const idSection2length = n8*this.witnessSize;
 	const idSection2lengthHex = idSection2length.toString(16);
-        buff32[pos] = parseInt(idSection2lengthHex.slice(0,8), 16);
-        buff32[pos+1] = parseInt(idSection2lengthHex.slice(8,16), 16);
+        header32[pos] = parseInt(idSection2lengthHex.slice(0,8), 16);
+        header32[pos+1] = parseInt(idSection2lengthHex.slice(8,16), 16);
 
-	pos += 2;
-        for (let i=0; i<this.witnessSize; i++) {
-            this.instance.exports.getWitness(i);
-            for (let j=0; j<this.n32; j++) {
-		buff32[pos+j] = this.instance.exports.readSharedRWMemory(j);
-            }
-	    pos += this.n32;
-        }
-
-	return buff;
+	return header32;
     }
 
 }
 
+module.exports.WitnessCalculatorError = WitnessCalculatorError;
+
 
 function toArray32(rem,size) {
     const res = []; //new Uint32Array(size); //has no unshift
@@ -316,6 +610,22 @@ function flatArray(a) {
     }
 }
 
+// name of the element i of the flattened value of the input signal k, with
+// the position taken from the nesting of the arrays, e.g. a[1][2]
+function elementName(k, value, i) {
+    const lengths = [];
+    while (Array.isArray(value)) {
+	lengths.push(value.length);
+	value = value[0];
+    }
+    let position = "";
+    for (let d = lengths.length - 1; d >= 0; d--) {
+	position = `[${i % lengths[d]}]` + position;
+	i = Math.floor(i / lengths[d]);
+    }
+    return k + position;
+}
+
 function normalize(n, prime) {
     let res = BigInt(n) % prime
     if (res < 0) res += prime
@@ -23,8 +23,14 @@ pub struct WASMProducer {
     pub io_map: TemplateInstanceIOMap,
     pub template_instance_list: TemplateList,
     pub message_list: MessageList,
+    pub message_file_list: MessageList,
+    pub template_debug_list: TemplateDebugList,
+    pub main_template_id: usize,
     pub field_tracking: Vec<String>,
     pub wat_flag: bool,
+    pub trace_signals: bool,
+    // some log or assert message uses format strings
+    pub format_messages: bool,
     pub major_version: usize,
     pub minor_version: usize,
     pub patch_version: usize,
@@ -32,6 +38,7 @@ pub struct WASMProducer {
     local_info_size_u32: usize,
     size_of_message_buffer_in_bytes: usize,
     size_of_message_in_bytes: usize,
+    error_trace_max_length: usize,
     offset_tag: String,
     signal_offset_tag: String,
     signal_start_tag: String,
@@ -52,6 +59,7 @@ pub struct WASMProducer {
     store_aux_1_tag: String,
     store_aux_2_tag: String,
     copy_counter_tag: String,
+    store_trace_tag: String,
     create_loop_sub_cmp_tag: String,
     create_loop_offset_tag: String,
     create_loop_counter_tag: String,
@@ -80,6 +88,9 @@ impl Default for WASMProducer {
             signals_in_witness: 0,                                                      //20,
             witness_to_signal_list: [].to_vec(), //[0,1,2,3,4,5,6,12,16,19,24,27,33,42,46,50,51,65,78,79].to_vec(),
             message_list: [].to_vec(), //["Main".to_string(),"Hola Herme".to_string(),"Hola Albert".to_string()].to_vec(),
+            message_file_list: [].to_vec(),
+            template_debug_list: Vec::new(),
+            main_template_id: 0,
             total_number_of_signals: 0, //80,
             number_of_components: 1,   //3,
             size_of_component_tree: 3, //10,
@@ -87,6 +98,8 @@ impl Default for WASMProducer {
             template_instance_list: [].to_vec(),
             field_tracking: [].to_vec(),
             wat_flag: true,
+            trace_signals: false,
+            format_messages: false,
             major_version: 0,
             minor_version: 0,
             patch_version: 0,
@@ -94,6 +107,7 @@ impl Default for WASMProducer {
             local_info_size_u32: 0, // in the future we can add some info like pointer to run father or text father
             size_of_message_buffer_in_bytes: 256,
             size_of_message_in_bytes: 240,
+            error_trace_max_length: 64,
             offset_tag: "$offset".to_string(),
             signal_offset_tag: "$signaloffset".to_string(),
             signal_start_tag: "$signalstart".to_string(),
@@ -114,6 +128,7 @@ impl Default for WASMProducer {
             store_aux_1_tag: "$storeaux1".to_string(),
             store_aux_2_tag: "$storeaux2".to_string(),
             copy_counter_tag: "$copycounter".to_string(),
+            store_trace_tag: "$storetrace".to_string(),
             create_loop_sub_cmp_tag: "$createloopsubcmp".to_string(),
             create_loop_offset_tag: "$createloopoffset".to_string(),
             create_loop_counter_tag: "$createloopcounter".to_string(),
@@ -248,6 +263,18 @@ impl WASMProducer {
     pub fn get_message_list(&self) -> &MessageList {
         &self.message_list
     }
+    pub fn get_message_file_list(&self) -> &MessageList {
+        &self.message_file_list
+    }
+    pub fn get_template_debug_list(&self) -> &TemplateDebugList {
+        &self.template_debug_list
+    }
+    pub fn get_main_template_id(&self) -> usize {
+        self.main_template_id
+    }
+    pub fn get_error_trace_max_length(&self) -> usize {
+        self.error_trace_max_length
+    }
     pub fn get_field_constant_list(&self) -> &Vec<String> {
         &self.field_tracking
     }
@@ -321,9 +348,16 @@ impl WASMProducer {
         self.get_message_list_start() + self.size_of_message_in_bytes * self.message_list.len()
     }
 
-    pub fn get_constant_numbers_start(&self) -> usize {
+    pub fn get_error_info_start(&self) -> usize {
         self.get_string_list_start() + self.size_of_message_in_bytes * self.string_table.len()
     }
+    // message id, line, signal name, trace length and a (father, component) pair per trace entry
+    pub fn get_error_info_size(&self) -> usize {
+        4 * (4 + 2 * self.error_trace_max_length)
+    }
+    pub fn get_constant_numbers_start(&self) -> usize {
+        self.get_error_info_start() + self.get_error_info_size()
+    }
     
     pub fn get_var_stack_memory_start(&self) -> usize {
         self.get_constant_numbers_start() + (self.size_32_bit + 2) * 4 * self.field_tracking.len()
@@ -391,6 +425,9 @@ impl WASMProducer {
     pub fn get_copy_counter_tag(&self) -> &str {
         &self.copy_counter_tag
     }
+    pub fn get_store_trace_tag(&self) -> &str {
+        &self.store_trace_tag
+    }
     pub fn get_create_loop_sub_cmp_tag(&self) -> &str {
         &self.create_loop_sub_cmp_tag
     }
@@ -406,6 +443,12 @@ impl WASMProducer {
     pub fn needs_comments(&self) -> bool{
         self.wat_flag
     }
+    pub fn needs_signal_trace(&self) -> bool {
+        self.trace_signals
+    }
+    pub fn needs_message_format(&self) -> bool {
+        self.format_messages
+    }
 
     pub fn get_string_table(&self) -> &Vec<String> {
         &self.string_table
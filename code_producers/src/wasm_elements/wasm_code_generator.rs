@@ -509,6 +509,45 @@ pub fn generate_imports_list() -> Vec<WasmInstruction> {
     imports
 }
 
+// functions of the runtime that receive the trace of signal assignments.
+// Only imported by the circuits compiled with --trace_signals
+pub fn generate_trace_imports_list(producer: &WASMProducer) -> Vec<WasmInstruction> {
+    let mut imports = vec![];
+    if producer.needs_signal_trace() {
+        imports.push(
+            "(import \"runtime\" \"traceComponent\" (func $traceComponent (type $_t_i32i32)))"
+                .to_string(),
+        );
+        imports.push(
+            "(import \"runtime\" \"traceSignal\" (func $traceSignal (param i32 i32 i32 i32)))"
+                .to_string(),
+        );
+    }
+    imports
+}
+
+// functions of the runtime that build the messages with format strings.
+// Only imported by the circuits that use them, so the other circuits keep
+// working with the runtimes that do not provide them
+pub fn generate_format_imports_list(producer: &WASMProducer) -> Vec<WasmInstruction> {
+    let mut imports = vec![];
+    if producer.needs_message_format() {
+        imports.push(
+            "(import \"runtime\" \"writeFormatMessage\" (func $writeFormatMessage (type $_t_void)))"
+                .to_string(),
+        );
+        imports.push(
+            "(import \"runtime\" \"showFormatValue\" (func $showFormatValue (type $_t_i32)))"
+                .to_string(),
+        );
+        imports.push(
+            "(import \"runtime\" \"endFormatMessage\" (func $endFormatMessage (type $_t_i32)))"
+                .to_string(),
+        );
+    }
+    imports
+}
+
 pub fn generate_memory_def_list(producer: &WASMProducer) -> Vec<WasmInstruction> {
     let mut wmemory = vec![];
     wmemory.push(format!("(memory {})", get_initial_size_of_memory(&producer)));
@@ -546,6 +585,7 @@ pub fn generate_exports_list() -> Vec<WasmInstruction> {
     exports.push("(export \"getInputSize\" (func $getInputSize))".to_string());
     exports.push("(export \"getWitness\" (func $getWitness))".to_string());
     exports.push("(export \"getMessageChar\" (func $getMessageChar))".to_string());
+    exports.push("(export \"getErrorInfoStart\" (func $getErrorInfoStart))".to_string());
     exports
 }
 
@@ -793,6 +833,19 @@ pub fn init_generator(producer: &WASMProducer) -> Vec<WasmInstruction> {
     instructions.push(header);
     instructions.push(" (param $t i32)".to_string());
     instructions.push(" (local $i i32)".to_string());
+    // reset the stack pointer, a previous run may have been aborted by an exception
+    instructions.push(set_constant("0"));
+    instructions.push(set_constant(&producer.get_var_stack_memory_start().to_string()));
+    instructions.push(store32(None));
+    // reset the message buffer
+    instructions.push(set_constant(&producer.get_message_buffer_counter_position().to_string()));
+    instructions.push(set_constant("0"));
+    instructions.push(store32(None));
+    // reset the error information: no source and empty trace
+    instructions.push(set_constant("-1"));
+    instructions.push(set_constant("0"));
+    instructions.push(set_constant("0"));
+    instructions.push(call("$setErrorSource"));
     // initialize set counter
     instructions.push(set_constant(&producer.get_remaining_input_signal_counter().to_string()));
     instructions.push(";; Number of Main inputs".to_string());
@@ -1047,6 +1100,7 @@ pub fn get_input_signal_size_generator(_producer: &WASMProducer) -> Vec<WasmInst
     instructions.push(" (param $hmsb i32)".to_string());
     instructions.push(" (param $hlsb i32)".to_string());
     instructions.push("(result i32)".to_string());
+    instructions.push(" (local $mp i32) ;; map position".to_string());
     instructions.push(get_local("$hmsb"));
     instructions.push(extend_i32_u64());
     instructions.push(set_constant_64("32"));
@@ -1055,6 +1109,13 @@ pub fn get_input_signal_size_generator(_producer: &WASMProducer) -> Vec<WasmInst
     instructions.push(extend_i32_u64());
     instructions.push(or64());
     instructions.push(call("$getInputSignalMapPosition"));
+    instructions.push(tee_local("$mp"));
+    instructions.push(eqz32());
+    instructions.push(add_if());
+    instructions.push(set_constant("-1")); // signal not found
+    instructions.push(add_return());
+    instructions.push(add_end());
+    instructions.push(get_local("$mp"));
     instructions.push(load32(Some("12")));
     instructions.push(")".to_string());
     instructions
@@ -1436,6 +1497,192 @@ pub fn build_buffer_message_generator(producer: &WASMProducer) -> Vec<WasmInstru
     instructions
 }
 
+pub fn get_error_info_start_generator(producer: &WASMProducer) -> Vec<WasmInstruction> {
+    let mut instructions = vec![];
+    let header = "(func $getErrorInfoStart (type $_t_ri32)".to_string();
+    instructions.push(header);
+    instructions.push("(result i32)".to_string());
+    instructions.push(set_constant(&producer.get_error_info_start().to_string()));
+    instructions.push(")".to_string());
+    instructions
+}
+
+// stores where a failure happened: message id, line and address of the
+// name of the signal involved (0 if none). The trace of components is cleared
+pub fn set_error_source_generator(producer: &WASMProducer) -> Vec<WasmInstruction> {
+    let mut instructions = vec![];
+    let header = "(func $setErrorSource (type $_t_i32i32i32)".to_string();
+    instructions.push(header);
+    instructions.push(" (param $m i32)".to_string()); //message id
+    instructions.push(" (param $l i32)".to_string()); //line
+    instructions.push(" (param $s i32)".to_string()); //signal name position
+    let start = producer.get_error_info_start();
+    instructions.push(set_constant(&start.to_string()));
+    instructions.push(get_local("$m"));
+    instructions.push(store32(None));
+    instructions.push(set_constant(&(start + 4).to_string()));
+    instructions.push(get_local("$l"));
+    instructions.push(store32(None));
+    instructions.push(set_constant(&(start + 8).to_string()));
+    instructions.push(get_local("$s"));
+    instructions.push(store32(None));
+    instructions.push(set_constant(&(start + 12).to_string()));
+    instructions.push(set_constant("0"));
+    instructions.push(store32(None));
+    instructions.push(")".to_string());
+    instructions
+}
+
+// adds to the trace the pair (father, subcomponent) for a subcomponent
+// whose execution failed. Entries beyond the maximum length are dropped
+pub fn add_error_trace_generator(producer: &WASMProducer) -> Vec<WasmInstruction> {
+    let mut instructions = vec![];
+    let header = "(func $addErrorTrace (type $_t_i32i32)".to_string();
+    instructions.push(header);
+    instructions.push(" (param $f i32)".to_string()); //father component
+    instructions.push(" (param $c i32)".to_string()); //failing subcomponent
+    instructions.push(" (local $n i32)".to_string()); //length of the trace
+    instructions.push(" (local $p i32)".to_string()); //position of the new entry
+    let length_pos = producer.get_error_info_start() + 12;
+    instructions.push(set_constant(&length_pos.to_string()));
+    instructions.push(load32(None));
+    instructions.push(tee_local("$n"));
+    instructions.push(set_constant(&producer.get_error_trace_max_length().to_string()));
+    instructions.push(ge32_u());
+    instructions.push(add_if());
+    instructions.push(add_return());
+    instructions.push(add_end());
+    instructions.push(get_local("$n"));
+    instructions.push(set_constant("8"));
+    instructions.push(mul32());
+    instructions.push(set_constant(&(length_pos + 4).to_string()));
+    instructions.push(add32());
+    instructions.push(tee_local("$p"));
+    instructions.push(get_local("$f"));
+    instructions.push(store32(None));
+    instructions.push(get_local("$p"));
+    instructions.push(get_local("$c"));
+    instructions.push(store32(Some("4")));
+    instructions.push(set_constant(&length_pos.to_string()));
+    instructions.push(get_local("$n"));
+    instructions.push(set_constant("1"));
+    instructions.push(add32());
+    instructions.push(store32(None));
+    instructions.push(")".to_string());
+    instructions
+}
+
+// instructions that add the running component and the subcomponent stored
+// in sub_cmp to the trace of components of the current error
+pub fn add_error_trace(producer: &WASMProducer, sub_cmp: &str) -> Vec<WasmInstruction> {
+    vec![
+        get_local(producer.get_offset_tag()),
+        get_local(sub_cmp),
+        call("$addErrorTrace"),
+    ]
+}
+
+pub fn trace_signal_store_generator(producer: &WASMProducer) -> Vec<WasmInstruction> {
+    let mut instructions = vec![];
+    if !producer.needs_signal_trace() {
+        return instructions;
+    }
+    let header = "(func $traceSignalStore".to_string();
+    instructions.push(header);
+    instructions.push(" (param $c i32)".to_string()); //component
+    instructions.push(" (param $a i32)".to_string()); //address of the first assigned signal
+    instructions.push(" (param $n i32)".to_string()); //number of assigned signals
+    instructions.push(" (param $m i32)".to_string()); //message id
+    instructions.push(" (param $l i32)".to_string()); //line
+    let size = producer.get_size_32_bits_in_memory() * 4;
+    instructions.push(add_block());
+    instructions.push(add_loop());
+    instructions.push(get_local("$n"));
+    instructions.push(eqz32());
+    instructions.push(br_if("1"));
+    instructions.push(get_local("$a"));
+    instructions.push(call("$copyFr2SharedRWMemory"));
+    instructions.push(get_local("$c"));
+    // position of the signal among the signals of the component
+    instructions.push(get_local("$a"));
+    instructions.push(get_local("$c"));
+    instructions.push(load32(Some(&producer.get_signal_start_address_in_component().to_string())));
+    instructions.push(sub32());
+    instructions.push(set_constant(&size.to_string()));
+    instructions.push(div32_u());
+    instructions.push(get_local("$m"));
+    instructions.push(get_local("$l"));
+    instructions.push(call("$traceSignal"));
+    instructions.push(get_local("$a"));
+    instructions.push(set_constant(&size.to_string()));
+    instructions.push(add32());
+    instructions.push(set_local("$a"));
+    instructions.push(get_local("$n"));
+    instructions.push(set_constant("1"));
+    instructions.push(sub32());
+    instructions.push(set_local("$n"));
+    instructions.push(br("0"));
+    instructions.push(add_end());
+    instructions.push(add_end());
+    instructions.push(")".to_string());
+    instructions
+}
+
+// instructions that report the creation of the subcomponent whose address
+// is stored in the position sub_cmp_address of the running component, so
+// the runtime can name the components in the trace of signals
+pub fn trace_created_component(producer: &WASMProducer, sub_cmp_address: &str) -> Vec<WasmInstruction> {
+    vec![
+        get_local(producer.get_offset_tag()),
+        get_local(sub_cmp_address),
+        load32(None),
+        call("$traceComponent"),
+    ]
+}
+
+// custom section with the information needed to report runtime errors:
+// the file of every message, and the name and declared components of every
+// template instance, so the path of a failing component can be rebuilt.
+// The declared signals are used to name the signals in the trace
+pub fn generate_debug_section(producer: &WASMProducer) -> Vec<WasmInstruction> {
+    use serde_json::json;
+    let mut templates = vec![];
+    for template in producer.get_template_debug_list() {
+        let mut components = vec![];
+        for component in &template.components {
+            components.push(json!({
+                "name": component.name,
+                "start": component.start,
+                "lengths": component.lengths,
+            }));
+        }
+        let mut signals = vec![];
+        for signal in &template.signals {
+            signals.push(json!({
+                "name": signal.name,
+                "offset": signal.offset,
+                "lengths": signal.lengths,
+            }));
+        }
+        templates.push(json!({ "name": template.name, "components": components, "signals": signals }));
+    }
+    let info = json!({
+        "main": producer.get_main_template_id(),
+        "max_trace": producer.get_error_trace_max_length(),
+        "files": producer.get_message_file_list(),
+        "templates": templates,
+    });
+    let mut data = String::new();
+    for b in info.to_string().bytes() {
+        if b.is_ascii_alphanumeric() || b" _-.,:[]{}/".contains(&b) {
+            data.push(b as char);
+        } else {
+            data.push_str(&format!("\\{:02x}", b));
+        }
+    }
+    vec![format!("(@custom \"circom_debug\" \"{}\")", data)]
+}
+
 pub fn generate_table_of_template_runs(producer: &WASMProducer) -> Vec<WasmInstruction> {
     let mut instructions = vec![];
     //    if !producer.get_io_map().is_empty() {
@@ -3,9 +3,10 @@ use crate::circuit_design::function::FunctionCodeInfo;
 use crate::circuit_design::template::TemplateCodeInfo;
 use crate::hir::very_concrete_program::*;
 use crate::intermediate_representation::translate;
-use crate::intermediate_representation::translate::{CodeInfo, FieldTracker, TemplateDB, ParallelClusters};
+use crate::intermediate_representation::translate::{CodeInfo, FieldTracker, TemplateDB, ParallelClusters, TagMeaning};
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
+use program_structure::ast::Statement;
 use program_structure::file_definition::FileLibrary;
 use std::collections::{BTreeMap, HashMap};
 
@@ -20,6 +21,38 @@ fn matching_lengths_and_offsets(list: &InputOutputList) {
     }
 }
 
+// name of the file where the given code was written, used to report errors.
+// The file library keeps the quoted paths used by the diagnostics
+fn file_of_code(files: &FileLibrary, code: &Statement) -> String {
+    match code.get_meta().file_id.and_then(|file_id| files.get_filename(file_id)) {
+        Some(name) => name.trim_matches('"').to_string(),
+        None => String::new(),
+    }
+}
+
+fn build_template_debug_info(template: &TemplateInstance) -> TemplateDebugInfo {
+    // components are placed among the subcomponents in order of declaration
+    let mut components = Vec::new();
+    let mut start = 0;
+    for component in &template.components {
+        components.push(ComponentDecl {
+            name: component.name.clone(),
+            start,
+            lengths: component.lengths.clone(),
+        });
+        start += component.size();
+    }
+    let mut signals = Vec::new();
+    for signal in &template.signals {
+        signals.push(SignalDecl {
+            name: signal.name.clone(),
+            offset: signal.local_id,
+            lengths: signal.lengths.clone(),
+        });
+    }
+    TemplateDebugInfo { name: template.template_name.clone(), components, signals }
+}
+
 fn build_template_instances(
     circuit: &mut Circuit,
     c_info: &CircuitInfo,
@@ -43,6 +76,10 @@ fn build_template_instances(
     let mut tmp_id = 0;
     let mut string_table = HashMap::new();
     for template in ti {
+        let file = file_of_code(&c_info.file_library, &template.code);
+        let debug_info = build_template_debug_info(&template);
+        let trace_signals = c_info.trace_signals
+            && (c_info.trace_templates.is_empty() || c_info.trace_templates.contains(&template.template_name));
         let header = template.template_header;
         let name = template.template_name;
         let instance_values = template.header;
@@ -55,6 +92,10 @@ fn build_template_instances(
             cmp_to_type.insert(name, xtype);
         }
         circuit.wasm_producer.message_list.push(msg);
+        circuit.wasm_producer.message_file_list.push(file.clone());
+        circuit.c_producer.message_file_list.push(file);
+        circuit.wasm_producer.template_debug_list.push(debug_info.clone());
+        circuit.c_producer.template_debug_list.push(debug_info);
         circuit.c_producer.has_parallelism |= template.is_parallel || template.is_parallel_component;
 
         let mut component_to_parallel: HashMap<String, ParallelClusters> = HashMap::new();
@@ -98,6 +139,8 @@ fn build_template_instances(
             template_database: &c_info.template_database,
             string_table : string_table,
             signals_to_tags: template.signals_to_tags,
+            trace_signals,
+            check_tags: &c_info.check_tags,
         };
         let mut template_info = TemplateCodeInfo {
             name,
@@ -121,6 +164,7 @@ fn build_template_instances(
         template_info.var_stack_depth = out.stack_depth;
         template_info.signal_stack_depth = out.signal_depth;
         string_table = out.string_table;
+        circuit.wasm_producer.format_messages |= out.format_messages;
         cmp_id = out.next_cmp_id;
         circuit.add_template_code(template_info);
         tmp_id += 1;
@@ -143,7 +187,10 @@ fn build_function_instances(
         let params = instance.params_types;
         let returns = instance.return_type;
         let id = circuit.wasm_producer.message_list.len();
+        let file = file_of_code(&c_info.file_library, &instance.body);
         circuit.wasm_producer.message_list.push(msg);
+        circuit.wasm_producer.message_file_list.push(file.clone());
+        circuit.c_producer.message_file_list.push(file);
         let code_info = CodeInfo {
             field_tracker,
             header: header.clone(),
@@ -162,6 +209,8 @@ fn build_function_instances(
             template_database: &c_info.template_database,
             string_table : string_table,
             signals_to_tags: BTreeMap::new(),
+            trace_signals: false,
+            check_tags: &c_info.check_tags,
         };
         let mut function_info = FunctionCodeInfo {
             name,
@@ -173,6 +222,7 @@ fn build_function_instances(
         let code = instance.body;
         let out = translate::translate_code(code, code_info);
         string_table = out.string_table;
+        circuit.wasm_producer.format_messages |= out.format_messages;
         field_tracker = out.constant_tracker;
         function_info.body = out.code;
         function_info.max_number_of_ops_in_expression = out.expression_depth;
@@ -220,6 +270,7 @@ fn initialize_wasm_producer(vcp: &VCP, database: &TemplateDB, wat_flag:bool, ver
     producer.main_input_list = main_input_list(&vcp.templates[initial_node]);
     producer.io_map = build_io_map(vcp, database);
     producer.template_instance_list = build_template_list(vcp);
+    producer.main_template_id = initial_node;
     producer.field_tracking.clear();
     producer.wat_flag = wat_flag;
     (producer.major_version, producer.minor_version, producer.patch_version) = get_number_version(version);
@@ -254,6 +305,7 @@ fn initialize_c_producer(vcp: &VCP, database: &TemplateDB, version: &str) -> CPr
     producer.main_input_list = main_input_list(&vcp.templates[initial_node]);   
     producer.io_map = build_io_map(vcp, database);
     producer.template_instance_list = build_template_list_parallel(vcp);
+    producer.main_template_id = initial_node;
     producer.field_tracking.clear();
     (producer.major_version, producer.minor_version, producer.patch_version) = get_number_version(version);
     producer
@@ -353,6 +405,9 @@ struct CircuitInfo {
     file_library: FileLibrary,
     functions: HashMap<String, Vec<usize>>,
     template_database: TemplateDB,
+    trace_signals: bool,
+    trace_templates: Vec<String>,
+    check_tags: BTreeMap<String, TagMeaning>,
 }
 
 pub fn build_circuit(vcp: VCP, flag: CompilationFlags, version: &str) -> Circuit {
@@ -364,12 +419,18 @@ pub fn build_circuit(vcp: VCP, flag: CompilationFlags, version: &str) -> Circuit
     let mut circuit = Circuit::default();
     circuit.wasm_producer = initialize_wasm_producer(&vcp, &template_database, flag.wat_flag, version);
     circuit.c_producer = initialize_c_producer(&vcp, &template_database, version);
+    circuit.wasm_producer.trace_signals = flag.trace_signals;
+    circuit.c_producer.trace_signals = flag.trace_signals;
+    circuit.c_producer.c_unit_size = flag.c_unit_size;
 
     let field_tracker = FieldTracker::new();
     let circuit_info = CircuitInfo {
         template_database,
         file_library: vcp.file_library,
         functions: vcp.quick_knowledge,
+        trace_signals: flag.trace_signals,
+        trace_templates: flag.trace_templates,
+        check_tags: flag.check_tags,
     };
 
     let (field_tracker, string_table) =
@@ -2,14 +2,25 @@ use super::function::{FunctionCode, FunctionCodeInfo};
 use super::template::{TemplateCode, TemplateCodeInfo};
 use super::types::*;
 use crate::hir::very_concrete_program::VCP;
+use crate::intermediate_representation::translate::TagMeaning;
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
+use std::collections::BTreeMap;
 use std::io::Write;
 
 pub struct CompilationFlags {
     pub main_inputs_log: bool,
     pub wat_flag:bool,
+    // instrument the stores of signals to produce a trace of the assignments,
+    // only in the given templates if the list is not empty
+    pub trace_signals: bool,
+    pub trace_templates: Vec<String>,
+    // split the C++ code in translation units of about this size in KB
+    pub c_unit_size: Option<usize>,
+    // tags whose meaning is asserted in every assignment of the signals
+    // declared with them
+    pub check_tags: BTreeMap<String, TagMeaning>,
 }
 
 pub struct Circuit {
@@ -37,6 +48,10 @@ impl WriteWasm for Circuit {
         code.push("(module".to_string());
         let mut code_aux = generate_imports_list();
         code.append(&mut code_aux);
+        code_aux = generate_trace_imports_list(producer);
+        code.append(&mut code_aux);
+        code_aux = generate_format_imports_list(producer);
+        code.append(&mut code_aux);
         code_aux = generate_memory_def_list(&producer);
         code.append(&mut code_aux);
 
@@ -108,6 +123,18 @@ impl WriteWasm for Circuit {
         code_aux = build_log_message_generator(&producer);
         code.append(&mut code_aux);
 
+        code_aux = get_error_info_start_generator(producer);
+        code.append(&mut code_aux);
+
+        code_aux = set_error_source_generator(producer);
+        code.append(&mut code_aux);
+
+        code_aux = add_error_trace_generator(producer);
+        code.append(&mut code_aux);
+
+        code_aux = trace_signal_store_generator(producer);
+        code.append(&mut code_aux);
+
         // Actual code from the program
 
         for f in &self.functions {
@@ -127,6 +154,9 @@ impl WriteWasm for Circuit {
         code_aux = generate_data_list(&producer);
         code.append(&mut code_aux);
 
+        code_aux = generate_debug_section(producer);
+        code.append(&mut code_aux);
+
         code.push(")".to_string());
         code
     }
@@ -141,6 +171,16 @@ impl WriteWasm for Circuit {
         writer.write_all(code.as_bytes()).map_err(|_| {})?;
         writer.flush().map_err(|_| {})?;
 
+        code_aux = generate_trace_imports_list(producer);
+        code = merge_code(code_aux);
+        writer.write_all(code.as_bytes()).map_err(|_| {})?;
+        writer.flush().map_err(|_| {})?;
+
+        code_aux = generate_format_imports_list(producer);
+        code = merge_code(code_aux);
+        writer.write_all(code.as_bytes()).map_err(|_| {})?;
+        writer.flush().map_err(|_| {})?;
+
         code_aux = generate_memory_def_list(&producer);
         code = merge_code(code_aux);
         writer.write_all(code.as_bytes()).map_err(|_| {})?;
@@ -261,6 +301,26 @@ impl WriteWasm for Circuit {
         writer.write_all(code.as_bytes()).map_err(|_| {})?;
         writer.flush().map_err(|_| {})?;
 
+        code_aux = get_error_info_start_generator(producer);
+        code = merge_code(code_aux);
+        writer.write_all(code.as_bytes()).map_err(|_| {})?;
+        writer.flush().map_err(|_| {})?;
+
+        code_aux = set_error_source_generator(producer);
+        code = merge_code(code_aux);
+        writer.write_all(code.as_bytes()).map_err(|_| {})?;
+        writer.flush().map_err(|_| {})?;
+
+        code_aux = add_error_trace_generator(producer);
+        code = merge_code(code_aux);
+        writer.write_all(code.as_bytes()).map_err(|_| {})?;
+        writer.flush().map_err(|_| {})?;
+
+        code_aux = trace_signal_store_generator(producer);
+        code = merge_code(code_aux);
+        writer.write_all(code.as_bytes()).map_err(|_| {})?;
+        writer.flush().map_err(|_| {})?;
+
         // Actual code from the program
 
         for f in &self.functions {
@@ -288,6 +348,11 @@ impl WriteWasm for Circuit {
         writer.write_all(code.as_bytes()).map_err(|_| {})?;
         writer.flush().map_err(|_| {})?;
 
+        code_aux = generate_debug_section(producer);
+        code = merge_code(code_aux);
+        writer.write_all(code.as_bytes()).map_err(|_| {})?;
+        writer.flush().map_err(|_| {})?;
+
         writer.write_all(")".as_bytes()).map_err(|_| {})?;
         writer.flush().map_err(|_| {})
     }
@@ -296,24 +361,8 @@ impl WriteWasm for Circuit {
 impl WriteC for Circuit {
     fn produce_c(&self, producer: &CProducer, _parallel: Option<bool>) -> (Vec<String>, String) {
         use c_code_generator::*;
-        let mut code = vec![];
         // Prologue
-        code.push("#include <stdio.h>".to_string());
-        code.push("#include <iostream>".to_string());
-        code.push("#include <assert.h>".to_string());
-        code.push("#include \"circom.hpp\"".to_string());
-        code.push("#include \"calcwit.hpp\"".to_string());
-
-        let mut template_headers = collect_template_headers(producer.get_template_instance_list());
-        let function_headers: Vec<_> = self.functions
-            .iter()
-            .map(|f| f.header.clone())
-            .collect();
-        let mut function_headers = collect_function_headers(function_headers);
-        code.append(&mut template_headers);
-        code.append(&mut function_headers);
-        std::mem::drop(template_headers);
-        std::mem::drop(function_headers);
+        let mut code = self.produce_c_declarations(producer);
 
         let (func_list_no_parallel, func_list_parallel) = generate_function_list(
             producer, 
@@ -359,21 +408,25 @@ impl WriteC for Circuit {
             producer.get_io_map().len()
         ));
         //code.append(&mut generate_message_list_def(producer, producer.get_message_list()));
+        code.append(&mut generate_trace_signal_table(producer));
+        code.append(&mut generate_main_input_table(producer));
         
         // Functions to release the memory
         let mut release_component_code = generate_function_release_memory_component();
         code.append(&mut release_component_code);
 
-        // Actual code of the circuit
-        code.push("// function declarations".to_string());
-        for f in &self.functions {
-            let (mut f_code, _) = f.produce_c(producer, None);
-            code.append(&mut f_code);
-        }
-        code.push("// template declarations".to_string());
-        for t in &self.templates {
-            let (mut t_code, _) = t.produce_c(producer, None);
-            code.append(&mut t_code);
+        // Actual code of the circuit, unless it is split in translation units
+        if producer.get_c_unit_size().is_none() {
+            code.push("// function declarations".to_string());
+            for f in &self.functions {
+                let (mut f_code, _) = f.produce_c(producer, None);
+                code.append(&mut f_code);
+            }
+            code.push("// template declarations".to_string());
+            for t in &self.templates {
+                let (mut t_code, _) = t.produce_c(producer, None);
+                code.append(&mut t_code);
+            }
         }
 
 
@@ -440,7 +493,81 @@ impl Circuit {
     pub fn produce_ir_string_for_function(&self, id: ID) -> String {
         self.functions[id].to_string()
     }
-    pub fn produce_c<W: Write>(&self, c_folder: &str, run_name: &str, c_circuit: &mut W, c_dat: &mut W) -> Result<(), ()> {
+    // includes and declarations of the templates and functions, shared by
+    // the main file and the translation units
+    fn produce_c_declarations(&self, producer: &CProducer) -> Vec<String> {
+        use c_code_generator::*;
+        let mut code = vec![];
+        code.push("#include <stdio.h>".to_string());
+        code.push("#include <iostream>".to_string());
+        code.push("#include <assert.h>".to_string());
+        code.push("#include \"circom.hpp\"".to_string());
+        code.push("#include \"calcwit.hpp\"".to_string());
+
+        let mut template_headers = collect_template_headers(producer.get_template_instance_list());
+        let function_headers: Vec<_> = self.functions
+            .iter()
+            .map(|f| f.header.clone())
+            .collect();
+        let mut function_headers = collect_function_headers(function_headers);
+        code.append(&mut template_headers);
+        code.append(&mut function_headers);
+        code
+    }
+
+    // writes the code of the functions and templates in the translation
+    // units <run_name>_<i>.cpp, starting a new unit when the current one
+    // exceeds the size given in the producer. Returns the number of units
+    fn produce_c_units(&self, c_folder: &str, run_name: &str) -> Result<usize, ()> {
+        use c_code_generator::*;
+        use std::path::Path;
+        let producer = &self.c_producer;
+        let unit_size = match producer.get_c_unit_size() {
+            Some(size) => size * 1024,
+            None => return Ok(0),
+        };
+        let mut prologue = self.produce_c_declarations(producer);
+        prologue.push(format!("extern Circom_TemplateFunction {}[{}];",
+            function_table(), producer.get_number_of_template_instances()));
+        prologue.push(format!("extern Circom_TemplateFunction {}[{}];",
+            function_table_parallel(), producer.get_number_of_template_instances()));
+        prologue.push("void release_memory_component(Circom_CalcWit* ctx, uint pos);".to_string());
+        let prologue = merge_code(prologue);
+
+        let write_unit = |id: usize, code: Vec<String>| -> Result<(), ()> {
+            let mut file_path = Path::new(c_folder).to_path_buf();
+            file_path.push(format!("{}_{}.cpp", run_name, id));
+            let file = std::fs::File::create(file_path).map_err(|_err| {})?;
+            let mut writer = std::io::BufWriter::new(file);
+            writer.write_all(prologue.as_bytes()).map_err(|_err| {})?;
+            writer.write_all(merge_code(code).as_bytes()).map_err(|_err| {})?;
+            writer.flush().map_err(|_err| {})
+        };
+
+        let functions = self.functions.iter().map(|f| f.produce_c(producer, None).0);
+        let templates = self.templates.iter().map(|t| t.produce_c(producer, None).0);
+        let mut units = 0;
+        let mut unit = vec![];
+        let mut size = 0;
+        for code in functions.chain(templates) {
+            let code_size: usize = code.iter().map(|line| line.len()).sum();
+            if !unit.is_empty() && size + code_size > unit_size {
+                write_unit(units, std::mem::take(&mut unit))?;
+                units += 1;
+                size = 0;
+            }
+            size += code_size;
+            unit.extend(code);
+        }
+        if !unit.is_empty() {
+            write_unit(units, unit)?;
+            units += 1;
+        }
+        Ok(units)
+    }
+
+    // returns the number of translation units written besides c_circuit
+    pub fn produce_c<W: Write>(&self, c_folder: &str, run_name: &str, c_circuit: &mut W, c_dat: &mut W) -> Result<usize, ()> {
 	use std::path::Path;
 	let c_folder_path = Path::new(c_folder.clone()).to_path_buf();
         c_code_generator::generate_main_cpp_file(&c_folder_path).map_err(|_err| {})?;
@@ -450,9 +577,13 @@ impl Circuit {
         c_code_generator::generate_fr_cpp_file(&c_folder_path, &self.c_producer.prime_str).map_err(|_err| {})?;
         c_code_generator::generate_calcwit_cpp_file(&c_folder_path).map_err(|_err| {})?;
         c_code_generator::generate_fr_asm_file(&c_folder_path, &self.c_producer.prime_str).map_err(|_err| {})?;
-        c_code_generator::generate_make_file(&c_folder_path,run_name,&self.c_producer).map_err(|_err| {})?;
+        c_code_generator::generate_fr_portable_hpp_file(&c_folder_path, &self.c_producer.prime_str).map_err(|_err| {})?;
+        c_code_generator::generate_fr_portable_cpp_file(&c_folder_path).map_err(|_err| {})?;
         c_code_generator::generate_dat_file(c_dat, &self.c_producer).map_err(|_err| {})?;
-        self.write_c(c_circuit, &self.c_producer)
+        let units = self.produce_c_units(c_folder, run_name)?;
+        c_code_generator::generate_make_file(&c_folder_path,run_name,&self.c_producer,units).map_err(|_err| {})?;
+        self.write_c(c_circuit, &self.c_producer)?;
+        Ok(units)
     }
     pub fn produce_wasm<W: Write>(&self, js_folder: &str, _wasm_name: &str, writer: &mut W) -> Result<(), ()> {
 	use std::path::Path;
@@ -98,6 +98,9 @@ impl WriteWasm for TemplateCodeInfo {
         instructions.push(format!(" (local {} i32)", producer.get_create_loop_sub_cmp_tag()));
         instructions.push(format!(" (local {} i32)", producer.get_create_loop_offset_tag()));
         instructions.push(format!(" (local {} i32)", producer.get_create_loop_counter_tag()));
+        if producer.needs_signal_trace() {
+            instructions.push(format!(" (local {} i32)", producer.get_store_trace_tag()));
+        }
         instructions.push(format!(" (local {} i32)", producer.get_merror_tag()));
         let local_info_size_u32 = producer.get_local_info_size_u32(); // in the future we can add some info like pointer to run father or text father
                                                                       //set lvar (start of auxiliar memory for vars)
@@ -289,10 +292,26 @@ impl TemplateCodeInfo {
         run_body.push(format!("{};", declare_lvar(self.var_stack_depth)));
         run_body.push(format!("{};", declare_sub_component_aux()));
         run_body.push(format!("{};", declare_index_multiple_eq()));
+	// errors in parallel components are kept by ctx, since they may run in their own thread
+	if parallel {
+	    run_body.push("try {".to_string());
+	}
         for t in &self.body {
             let (mut instructions_body, _) = t.produce_c(producer, Some(parallel));
             run_body.append(&mut instructions_body);
         }
+	if parallel {
+	    run_body.push("} catch (...) {".to_string());
+	    run_body.push("ctx->setRuntimeError(std::current_exception());".to_string());
+	    // release the components waiting for the outputs of this one
+	    run_body.push(format!("for (uint i = 0; i < {}; i++) {{", self.number_of_outputs));
+	    run_body.push("ctx->componentMemory[ctx_index].mutexes[i].lock();".to_string());
+	    run_body.push("ctx->componentMemory[ctx_index].outputIsSet[i]=true;".to_string());
+	    run_body.push("ctx->componentMemory[ctx_index].mutexes[i].unlock();".to_string());
+	    run_body.push("ctx->componentMemory[ctx_index].cvs[i].notify_all();".to_string());
+	    run_body.push("}".to_string());
+	    run_body.push("}".to_string());
+	}
 	// parallelism (join at the end of the function)
 	if self.number_of_components > 0 && self.has_parallel_sub_cmp {
             run_body.push(format!("{{"));
@@ -1,5 +1,7 @@
 pub use crate::circuit_design::circuit::{Circuit, CompilationFlags};
 pub use crate::hir::very_concrete_program::VCP;
+pub use crate::intermediate_representation::translate::TagMeaning;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::BufWriter;
 
@@ -7,10 +9,21 @@ pub struct Config {
     pub debug_output: bool,
     pub produce_input_log: bool,
     pub wat_flag: bool,
+    pub trace_signals: bool,
+    pub trace_templates: Vec<String>,
+    pub c_unit_size: Option<usize>,
+    pub check_tags: BTreeMap<String, TagMeaning>,
 }
 
 pub fn run_compiler(vcp: VCP, config: Config, version: &str) -> Result<Circuit, ()> {
-    let flags = CompilationFlags { main_inputs_log: config.produce_input_log, wat_flag: config.wat_flag };
+    let flags = CompilationFlags {
+        main_inputs_log: config.produce_input_log,
+        wat_flag: config.wat_flag,
+        trace_signals: config.trace_signals,
+        trace_templates: config.trace_templates,
+        c_unit_size: config.c_unit_size,
+        check_tags: config.check_tags,
+    };
     let circuit = Circuit::build(vcp, flags, version);
     if config.debug_output {
         produce_debug_output(&circuit)?;
@@ -29,7 +42,8 @@ pub fn write_wasm(circuit: &Circuit, js_folder: &str, wasm_name: &str, file: &st
     circuit.produce_wasm(js_folder, wasm_name, &mut writer)
 }
 
-pub fn write_c(circuit: &Circuit, c_folder: &str, c_run_name: &str, c_file: &str, dat_file: &str) -> Result<(), ()> {
+// returns the number of translation units written besides c_file
+pub fn write_c(circuit: &Circuit, c_folder: &str, c_run_name: &str, c_file: &str, dat_file: &str) -> Result<usize, ()> {
     use std::path::Path;
     if Path::new(c_folder).is_dir() {
         std::fs::remove_dir_all(c_folder).map_err(|_err| {})?;
@@ -60,7 +60,8 @@ fn add_instance(name: &str, args: Vec<Param>, state: &mut State) {
         let body = state.generic_functions.get(name).unwrap().body.clone();
         let new_vcf = VCF {
             name: name.to_string(),
-            header: format!("{}_{}", name, state.vcf_collector.len()),
+            // functions of included namespaces are named prefix.name
+            header: format!("{}_{}", name.replace('.', "_"), state.vcf_collector.len()),
             params_types: args.to_vec(),
             return_type: inferred,
             body,
@@ -111,6 +112,8 @@ fn produce_vcf_stmt(stmt: &Statement, state: &mut State, environment: &mut E) {
         produce_vcf_conditional(stmt, state, environment);
     } else if stmt.is_underscore_substitution() {
         //No code should be produced for an instruction of the form _ <== exp;
+    } else if stmt.is_break() || stmt.is_continue() {
+        //Nothing to do for break and continue
     } else {
         unreachable!();
     }
@@ -149,8 +152,13 @@ fn produce_vcf_return(stmt: &Statement, state: &mut State, environment: &E) {
 
 fn produce_vcf_assert(stmt: &Statement, state: &mut State, environment: &E) {
     use Statement::Assert;
-    if let Assert { arg, .. } = stmt {
+    if let Assert { arg, message, .. } = stmt {
         produce_vcf_expr(arg, state, environment);
+        for arglog in message {
+            for arg in arglog.expressions() {
+                produce_vcf_expr(arg, state, environment);
+            }
+        }
     } else {
         unreachable!();
     }
@@ -160,10 +168,9 @@ fn produce_vcf_log_call(stmt: &Statement, state: &mut State, environment: &E) {
     use Statement::LogCall;
     if let LogCall { args, .. } = stmt {
         for arglog in args {
-            if let LogArgument::LogExp(arg) = arglog {
+            for arg in arglog.expressions() {
                 produce_vcf_expr(arg, state, environment);
             }
-            else {}// unimplemented!(); }
         }
     } else {
         unreachable!();
@@ -363,6 +370,8 @@ fn link_stmt(stmt: &mut Statement, state: &State, env: &mut E) {
         link_substitution(stmt, state, env);
     } else if stmt.is_underscore_substitution() {
         //No code should be produced for an instruction of the form _ <== exp;
+    } else if stmt.is_break() || stmt.is_continue() {
+        //Nothing to do for break and continue
     } else {
         unreachable!();
     }
@@ -419,7 +428,7 @@ fn link_log_call(stmt: &mut Statement, state: &State, env: &mut E) {
     use Statement::LogCall;
     if let LogCall { args, .. } = stmt {
         for arglog in args {
-            if let LogArgument::LogExp(arg) = arglog{
+            for arg in arglog.expressions_mut() {
                 link_expression(arg, state, env);
             }
         }   
@@ -430,8 +439,13 @@ fn link_log_call(stmt: &mut Statement, state: &State, env: &mut E) {
 
 fn link_assert(stmt: &mut Statement, state: &State, env: &mut E) {
     use Statement::Assert;
-    if let Assert { arg, .. } = stmt {
+    if let Assert { arg, message, .. } = stmt {
         link_expression(arg, state, env);
+        for arglog in message {
+            for arg in arglog.expressions_mut() {
+                link_expression(arg, state, env);
+            }
+        }
     } else {
         unreachable!();
     }
@@ -62,6 +62,8 @@ fn extend_statement(stmt: &mut Statement, state: &mut State, context: &Context)
         extend_log_call(stmt, state, context)
     } else if stmt.is_assert() {
         extend_assert(stmt, state, context)
+    } else if stmt.is_break() || stmt.is_continue() {
+        vec![]
     } else{
         unreachable!()
     }
@@ -171,7 +173,7 @@ fn extend_log_call(stmt: &mut Statement, state: &mut State, context: &Context) -
     if let LogCall { args, .. } = stmt {
         let mut initializations = Vec::new();
         for arglog in args {
-            if let LogArgument::LogExp(arg) = arglog {
+            for arg in arglog.expressions_mut() {
                 let mut exp = extend_expression(arg, state, context);
                 initializations.append(&mut exp.initializations);
             }
@@ -184,8 +186,15 @@ fn extend_log_call(stmt: &mut Statement, state: &mut State, context: &Context) -
 
 fn extend_assert(stmt: &mut Statement, state: &mut State, context: &Context) -> Vec<Statement> {
     use Statement::Assert;
-    if let Assert { arg, .. } = stmt {
-        extend_expression(arg, state, context).initializations
+    if let Assert { arg, message, .. } = stmt {
+        let mut initializations = extend_expression(arg, state, context).initializations;
+        for arglog in message {
+            for arg in arglog.expressions_mut() {
+                let mut exp = extend_expression(arg, state, context);
+                initializations.append(&mut exp.initializations);
+            }
+        }
+        initializations
     } else {
         unreachable!()
     }
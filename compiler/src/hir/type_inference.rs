@@ -38,7 +38,7 @@ fn infer_type_stmt(stmt: &Statement, state: &State, context: &mut SearchInfo) ->
         Option::None
     } else if stmt.is_log_call() {
         Option::None
-    } else if stmt.is_assert() {
+    } else if stmt.is_assert() || stmt.is_break() || stmt.is_continue() {
         Option::None
     } else{
         unreachable!()
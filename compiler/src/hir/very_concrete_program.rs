@@ -31,6 +31,8 @@ pub struct Signal {
     pub xtype: SignalType,
     pub local_id: usize,
     pub dag_local_id: usize,
+    // tags given to the signal in its declaration
+    pub tags: Vec<String>,
 }
 
 impl Signal {
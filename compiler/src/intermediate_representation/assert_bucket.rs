@@ -1,4 +1,5 @@
 use super::ir_interface::*;
+use super::log_bucket::{FormatPiece, produce_c_format, produce_wasm_format};
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
@@ -7,6 +8,10 @@ use code_producers::wasm_elements::*;
 pub struct AssertBucket {
     pub line: usize,
     pub message_id: usize,
+    // position in the string table of the name of the constrained signal, if any
+    pub signal: Option<usize>,
+    // message describing the failure, empty if there is none
+    pub message: Vec<FormatPiece>,
     pub evaluate: InstructionPointer,
 }
 
@@ -54,8 +59,33 @@ impl WriteWasm for AssertBucket {
         instructions.push(add_if());
         instructions.push(set_constant(&self.message_id.to_string()));
         instructions.push(set_constant(&self.line.to_string()));
+        let signal_pos = match self.signal {
+            Some(string_id) => {
+                producer.get_string_list_start() + string_id * producer.get_size_of_message_in_bytes()
+            }
+            None => 0,
+        };
+        instructions.push(set_constant(&signal_pos.to_string()));
+        instructions.push(call("$setErrorSource"));
+        instructions.push(set_constant(&self.message_id.to_string()));
+        instructions.push(set_constant(&self.line.to_string()));
         instructions.push(call("$buildBufferMessage"));
         instructions.push(call("$printErrorMessage"));
+        match self.message.as_slice() {
+            [] => {}
+            [FormatPiece::Str(string_id)] => {
+                let pos = producer.get_string_list_start() + string_id * producer.get_size_of_message_in_bytes();
+                instructions.push(set_constant(&pos.to_string()));
+                instructions.push(call("$buildLogMessage"));
+                instructions.push(call("$printErrorMessage"));
+            }
+            pieces => {
+                let mut instructions_message = produce_wasm_format(pieces, producer);
+                instructions.append(&mut instructions_message);
+                instructions.push(set_constant("1"));
+                instructions.push(call("$endFormatMessage"));
+            }
+        }
         instructions.push(set_constant(&exception_code_assert_fail().to_string()));
         instructions.push(add_return());
         instructions.push(add_end());
@@ -71,11 +101,33 @@ impl WriteC for AssertBucket {
         use c_code_generator::*;
         let (prologue, value) = self.evaluate.produce_c(producer, parallel);
         let is_true = build_call("Fr_isTrue".to_string(), vec![value]);
-        let if_condition = format!("if (!{}) {};", is_true, build_failed_assert_message(self.line));    
-        let assertion = format!("{};", build_call("assert".to_string(), vec![is_true]));
+        let signal = match self.signal {
+            Some(string_id) => producer.get_string_table()[string_id].clone(),
+            None => String::new(),
+        };
+        let file = &producer.get_message_file_list()[self.message_id];
         let mut assert_c = prologue;
-        assert_c.push(if_condition);
-        assert_c.push(assertion);
+        if self.message.iter().all(|piece| matches!(piece, FormatPiece::Str(_))) {
+            let mut message = String::new();
+            for piece in &self.message {
+                if let FormatPiece::Str(string_id) = piece {
+                    message.push_str(&producer.get_string_table()[*string_id]);
+                }
+            }
+            if message.is_empty() {
+                message = "Assert Failed".to_string();
+            }
+            let failure = build_failed_assert_error(&build_c_string(&message), &signal, file, self.line);
+            assert_c.push(format!("if (!{}) {};", is_true, failure));
+        } else {
+            // the values of the message are only computed when the assert fails
+            let failure = build_failed_assert_error("message", &signal, file, self.line);
+            assert_c.push(format!("if (!{}) {{", is_true));
+            assert_c.push("std::string message;".to_string());
+            assert_c.append(&mut produce_c_format(&self.message, producer, parallel, &build_c_string));
+            assert_c.push(format!("{};", failure));
+            assert_c.push("}".to_string());
+        }
         (assert_c, "".to_string())
     }
 }
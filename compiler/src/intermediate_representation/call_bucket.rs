@@ -312,6 +312,7 @@ impl WriteWasm for CallBucket {
                                     instructions.push(call(&format!("${}_run", name)));
                                     instructions.push(tee_local(producer.get_merror_tag()));
                                     instructions.push(add_if());
+                                    instructions.append(&mut add_error_trace(producer, producer.get_sub_cmp_tag()));
                                     instructions.push(set_constant(&self.message_id.to_string()));
                                     instructions.push(set_constant(&self.line.to_string()));
                                     instructions.push(call("$buildBufferMessage"));
@@ -332,6 +333,7 @@ impl WriteWasm for CallBucket {
                                 ));
                                 instructions.push(tee_local(producer.get_merror_tag()));
                                 instructions.push(add_if());
+                                instructions.append(&mut add_error_trace(producer, producer.get_sub_cmp_tag()));
                                 instructions.push(set_constant(&self.message_id.to_string()));
                                 instructions.push(set_constant(&self.line.to_string()));
                                 instructions.push(call("$buildBufferMessage"));
@@ -83,6 +83,8 @@ impl WriteWasm for CreateCmpBucket {
         instructions.push(add32()); // address of the subcomponent in the component
         if self.number_of_cmp > 1 {
             instructions.push(set_local(producer.get_create_loop_sub_cmp_tag()));
+        } else if producer.needs_signal_trace() {
+            instructions.push(tee_local(producer.get_create_loop_sub_cmp_tag()));
         }
         let signal_full_offset = self.signal_offset * producer.get_size_32_bits_in_memory() * 4;
         instructions.push(set_constant(&signal_full_offset.to_string())); //offset of the signals in the subcomponent
@@ -93,10 +95,14 @@ impl WriteWasm for CreateCmpBucket {
             if !self.has_inputs {
                 instructions.push(tee_local(producer.get_temp_tag())); //here we use $temp to keep the offset created subcomponet
                 instructions.push(store32(None)); //store the offset given by create in the subcomponent address
+                if producer.needs_signal_trace() {
+                    instructions.append(&mut trace_created_component(producer, producer.get_create_loop_sub_cmp_tag()));
+                }
                 instructions.push(get_local(producer.get_temp_tag()));
                 instructions.push(call(&format!("${}_run", self.symbol)));
                 instructions.push(tee_local(producer.get_merror_tag()));
                 instructions.push(add_if());
+                instructions.append(&mut add_error_trace(producer, producer.get_temp_tag()));
                 instructions.push(set_constant(&self.message_id.to_string()));
                 instructions.push(set_constant(&self.line.to_string()));
                 instructions.push(call("$buildBufferMessage"));
@@ -107,6 +113,9 @@ impl WriteWasm for CreateCmpBucket {
             }
             else {
                 instructions.push(store32(None)); //store the offset given by create in the subcomponent address
+                if producer.needs_signal_trace() {
+                    instructions.append(&mut trace_created_component(producer, producer.get_create_loop_sub_cmp_tag()));
+                }
             }
 	} else {
             instructions.push(set_local(producer.get_create_loop_offset_tag()));
@@ -122,10 +131,14 @@ impl WriteWasm for CreateCmpBucket {
                 if !self.has_inputs {
                     instructions.push(tee_local(producer.get_temp_tag())); //here we use $temp to keep the offset created subcomponet
                     instructions.push(store32(None)); //store the offset given by create in the subcomponent address
+                    if producer.needs_signal_trace() {
+                        instructions.append(&mut trace_created_component(producer, producer.get_create_loop_sub_cmp_tag()));
+                    }
                     instructions.push(get_local(producer.get_temp_tag()));
                     instructions.push(call(&format!("${}_run", self.symbol)));
                     instructions.push(tee_local(producer.get_merror_tag()));
                     instructions.push(add_if());
+                    instructions.append(&mut add_error_trace(producer, producer.get_temp_tag()));
                     instructions.push(set_constant(&self.message_id.to_string()));
                     instructions.push(set_constant(&self.line.to_string()));
                     instructions.push(call("$buildBufferMessage"));
@@ -136,6 +149,9 @@ impl WriteWasm for CreateCmpBucket {
 		}
                 else {
                     instructions.push(store32(None)); //store the offset given by create in the subcomponent address
+                    if producer.needs_signal_trace() {
+                        instructions.append(&mut trace_created_component(producer, producer.get_create_loop_sub_cmp_tag()));
+                    }
 		}
                 instructions.push(get_local(producer.get_create_loop_counter_tag()));
                 instructions.push(set_constant("1"));
@@ -172,6 +188,9 @@ impl WriteWasm for CreateCmpBucket {
                 //sub_component signal address start
                 instructions.push(call(&format!("${}_create", self.symbol)));
                 instructions.push(store32(None)); //store the offset given by create in the subcomponent address
+                if producer.needs_signal_trace() {
+                    instructions.append(&mut trace_created_component(producer, producer.get_create_loop_sub_cmp_tag()));
+                }
                 //producer.get_create_loop_sub_cmp_tag() contains the position of last created subcomponent
                 //producer.get_create_loop_offset_tag() contains the offset of last created subcomponent
                 for i in 1..self.defined_positions.len() {
@@ -191,6 +210,9 @@ impl WriteWasm for CreateCmpBucket {
                     //sub_component signal address start
                     instructions.push(call(&format!("${}_create", self.symbol)));
                     instructions.push(store32(None)); //store the offset given by create in the subcomponent addre
+                    if producer.needs_signal_trace() {
+                        instructions.append(&mut trace_created_component(producer, producer.get_create_loop_sub_cmp_tag()));
+                    }
                 }
             }
         }
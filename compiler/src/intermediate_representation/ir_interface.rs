@@ -8,9 +8,10 @@ pub use super::load_bucket::LoadBucket;
 pub use super::location_rule::LocationRule;
 pub use super::log_bucket::LogBucket;
 pub use super::loop_bucket::LoopBucket;
+pub use super::loop_control_bucket::{LoopControlBucket, LoopControlType};
 pub use super::return_bucket::ReturnBucket;
 pub use super::store_bucket::StoreBucket;
-pub use super::log_bucket::LogBucketArg;
+pub use super::log_bucket::{FormatPiece, LogBucketArg, NumberFormat};
 pub use super::types::{InstrContext, ValueType};
 pub use super::value_bucket::ValueBucket;
 
@@ -49,6 +50,7 @@ pub enum Instruction {
     Assert(AssertBucket),
     Log(LogBucket),
     Loop(LoopBucket),
+    LoopControl(LoopControlBucket),
     CreateCmp(CreateCmpBucket),
 }
 
@@ -70,6 +72,7 @@ impl ObtainMeta for Instruction {
             Branch(v) => v.get_line(),
             Return(v) => v.get_line(),
             Loop(v) => v.get_line(),
+            LoopControl(v) => v.get_line(),
             Assert(v) => v.get_line(),
             CreateCmp(v) => v.get_line(),
             Log(v) => v.get_line(),
@@ -87,6 +90,7 @@ impl ObtainMeta for Instruction {
             Branch(v) => v.get_message_id(),
             Return(v) => v.get_message_id(),
             Loop(v) => v.get_message_id(),
+            LoopControl(v) => v.get_message_id(),
             Assert(v) => v.get_message_id(),
             CreateCmp(v) => v.get_message_id(),
             Log(v) => v.get_message_id(),
@@ -118,6 +122,7 @@ impl WriteWasm for Instruction {
             Branch(v) => v.produce_wasm(producer),
             Return(v) => v.produce_wasm(producer),
             Loop(v) => v.produce_wasm(producer),
+            LoopControl(v) => v.produce_wasm(producer),
             Assert(v) => v.produce_wasm(producer),
             CreateCmp(v) => v.produce_wasm(producer),
             Log(v) => v.produce_wasm(producer),
@@ -138,6 +143,7 @@ impl WriteC for Instruction {
             Branch(v) => v.produce_c(producer, parallel),
             Return(v) => v.produce_c(producer, parallel),
             Loop(v) => v.produce_c(producer, parallel),
+            LoopControl(v) => v.produce_c(producer, parallel),
             Assert(v) => v.produce_c(producer, parallel),
             CreateCmp(v) => v.produce_c(producer, parallel),
             Log(v) => v.produce_c(producer, parallel),
@@ -157,6 +163,7 @@ impl ToString for Instruction {
             Branch(v) => v.to_string(),
             Return(v) => v.to_string(),
             Loop(v) => v.to_string(),
+            LoopControl(v) => v.to_string(),
             Assert(v) => v.to_string(),
             CreateCmp(v) => v.to_string(),
             Log(v) => v.to_string(),
@@ -2,22 +2,115 @@ use super::ir_interface::*;
 use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
+pub use program_structure::ast::NumberFormat;
 
 
 #[derive(Clone)]
 pub enum LogBucketArg {
     LogExp(InstructionPointer),
-    LogStr(usize)
+    LogStr(usize),
+    Format(Vec<FormatPiece>),
 }
 impl LogBucketArg{
     pub fn get_mut_arg_logexp(&mut self)-> &mut InstructionPointer{
         match self{
             LogBucketArg::LogExp(arg) => arg,
-            LogBucketArg::LogStr(_) => unreachable!(),
+            LogBucketArg::LogStr(_) | LogBucketArg::Format(_) => unreachable!(),
         }
     }
 }
 
+// Piece of a formatted message: a string of the string table, printed without
+// separators, or a value printed in the given base
+#[derive(Clone)]
+pub enum FormatPiece {
+    Str(usize),
+    Value(InstructionPointer, NumberFormat),
+}
+
+impl FormatPiece {
+    pub fn get_mut_value(&mut self) -> Option<&mut InstructionPointer> {
+        match self {
+            FormatPiece::Value(value, _) => Some(value),
+            FormatPiece::Str(_) => None,
+        }
+    }
+}
+
+pub fn format_pieces_to_string(pieces: &[FormatPiece]) -> String {
+    let mut ret = String::new();
+    for piece in pieces {
+        if let FormatPiece::Value(exp, _) = piece {
+            ret = ret + &exp.to_string();
+        }
+    }
+    ret
+}
+
+fn format_code(format: NumberFormat) -> &'static str {
+    match format {
+        NumberFormat::Decimal => "0",
+        NumberFormat::Hexadecimal => "1",
+    }
+}
+
+// Appends the pieces to the message under construction in the runtime.
+// The message is finished with $endFormatMessage
+pub fn produce_wasm_format(pieces: &[FormatPiece], producer: &WASMProducer) -> Vec<String> {
+    use code_producers::wasm_elements::wasm_code_generator::*;
+    let mut instructions = vec![];
+    for piece in pieces {
+        match piece {
+            FormatPiece::Str(string_id) => {
+                let pos = producer.get_string_list_start() +
+                          string_id * producer.get_size_of_message_in_bytes();
+                instructions.push(set_constant(&pos.to_string()));
+                instructions.push(call("$buildLogMessage"));
+                instructions.push(call("$writeFormatMessage"));
+            }
+            FormatPiece::Value(exp, format) => {
+                let mut instructions_value = exp.produce_wasm(producer);
+                instructions.append(&mut instructions_value);
+                instructions.push(call("$copyFr2SharedRWMemory"));
+                instructions.push(set_constant(format_code(*format)));
+                instructions.push(call("$showFormatValue"));
+            }
+        }
+    }
+    instructions
+}
+
+// Appends the pieces to the std::string called message. The texts are
+// transformed by the given function into C string literals
+pub fn produce_c_format(
+    pieces: &[FormatPiece],
+    producer: &CProducer,
+    parallel: Option<bool>,
+    c_string: &dyn Fn(&str) -> String,
+) -> Vec<String> {
+    use c_code_generator::*;
+    let mut format_c = Vec::new();
+    for piece in pieces {
+        match piece {
+            FormatPiece::Str(string_id) => {
+                let string_value = &producer.get_string_table()[*string_id];
+                format_c.push(format!("message += {};", c_string(string_value)));
+            }
+            FormatPiece::Value(exp, format) => {
+                let (mut argument_code, argument_result) = exp.produce_c(producer, parallel);
+                let hex = match format {
+                    NumberFormat::Decimal => "false",
+                    NumberFormat::Hexadecimal => "true",
+                };
+                let to_string_call =
+                    build_call("Fr_element2string".to_string(), vec![argument_result, hex.to_string()]);
+                format_c.append(&mut argument_code);
+                format_c.push(format!("message += {};", to_string_call));
+            }
+        }
+    }
+    format_c
+}
 
 #[derive(Clone)]
 pub struct LogBucket {
@@ -53,11 +146,13 @@ impl ToString for LogBucket {
         let template_id = self.message_id.to_string();
         let mut ret = String::new();
         for print in self.argsprint.clone() {
-            if let LogBucketArg::LogExp(exp) = print {
-                let print = exp.to_string();
-                let log = format!("LOG(line: {},template_id: {},evaluate: {})", line, template_id, print);
-                ret = ret + &log;
-            }
+            let print = match print {
+                LogBucketArg::LogExp(exp) => exp.to_string(),
+                LogBucketArg::Format(pieces) => format_pieces_to_string(&pieces),
+                LogBucketArg::LogStr(_) => continue,
+            };
+            let log = format!("LOG(line: {},template_id: {},evaluate: {})", line, template_id, print);
+            ret = ret + &log;
         }
         ret
     }
@@ -85,6 +180,12 @@ impl WriteWasm for LogBucket {
                     instructions.push(call("$buildLogMessage"));
                     instructions.push(call("$writeBufferMessage"));                    
 	        }
+		LogBucketArg::Format(pieces) => {
+                    let mut instructions_format = produce_wasm_format(pieces, producer);
+                    instructions.append(&mut instructions_format);
+                    instructions.push(set_constant("0"));
+                    instructions.push(call("$endFormatMessage"));
+	        }
             }
 	}
 	// add nl
@@ -135,8 +236,13 @@ impl WriteC for LogBucket {
                 log_c.push(format!("{};", print_c));
                 log_c.push("}".to_string());
             }
-            else{
-                unreachable!();
+            else if let LogBucketArg::Format(pieces) = logarg {
+                let c_string = |string_value: &str| format!("\"{}\"", string_value);
+                log_c.push("{".to_string());
+                log_c.push("std::string message;".to_string());
+                log_c.append(&mut produce_c_format(pieces, producer, parallel, &c_string));
+                log_c.push("printf(\"%s\", message.c_str());".to_string());
+                log_c.push("}".to_string());
             }
             if index != self.argsprint.len() - 1 { 
                 let print_c =
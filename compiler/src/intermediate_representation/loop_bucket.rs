@@ -3,6 +3,11 @@ use crate::translating_traits::*;
 use code_producers::c_elements::*;
 use code_producers::wasm_elements::*;
 
+// labels of the wasm block and loop of a loop bucket, the targets of break
+// and continue, which always refer to the innermost loop
+pub const LOOP_EXIT_LABEL: &str = "$loop_exit";
+pub const LOOP_BODY_LABEL: &str = "$loop_body";
+
 #[derive(Clone)]
 pub struct LoopBucket {
     pub line: usize,
@@ -52,18 +57,18 @@ impl WriteWasm for LoopBucket {
         if producer.needs_comments() {
             instructions.push(format!(";; loop bucket. Line {}", self.line)); //.to_string()
 	}
-        instructions.push(add_block());
-        instructions.push(add_loop());
+        instructions.push(format!("{} {}", add_block(), LOOP_EXIT_LABEL));
+        instructions.push(format!("{} {}", add_loop(), LOOP_BODY_LABEL));
         let mut instructions_continue = self.continue_condition.produce_wasm(producer);
         instructions.append(&mut instructions_continue);
         instructions.push(call("$Fr_isTrue"));
         instructions.push(eqz32());
-        instructions.push(br_if("1"));
+        instructions.push(br_if(LOOP_EXIT_LABEL));
         for ins in &self.body {
             let mut instructions_loop = ins.produce_wasm(producer);
             instructions.append(&mut instructions_loop);
         }
-        instructions.push(br("0"));
+        instructions.push(br(LOOP_BODY_LABEL));
         instructions.push(add_end());
         instructions.push(add_end());
         if producer.needs_comments() {
@@ -77,16 +82,15 @@ impl WriteC for LoopBucket {
     fn produce_c(&self, producer: &CProducer, parallel: Option<bool>) -> (Vec<String>, String) {
         use c_code_generator::merge_code;
         let (continue_code, continue_result) = self.continue_condition.produce_c(producer, parallel);
-        let continue_result = format!("Fr_isTrue({})", continue_result);
-        let mut body = vec![];
+        // the condition is computed at the start of the body, so continue
+        // computes it again before the next iteration
+        let mut body = continue_code;
+        body.push(format!("if(!Fr_isTrue({})) break;", continue_result));
         for instr in &self.body {
             let (mut instr_code, _) = instr.produce_c(producer, parallel);
             body.append(&mut instr_code);
         }
-        body.append(&mut continue_code.clone());
-        let while_loop = format!("while({}){{\n{}}}", continue_result, merge_code(body));
-        let mut loop_c = continue_code;
-        loop_c.push(while_loop);
+        let loop_c = vec![format!("for(;;){{\n{}}}", merge_code(body))];
         (loop_c, "".to_string())
     }
 }
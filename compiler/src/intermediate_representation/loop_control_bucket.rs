@@ -0,0 +1,75 @@
+use super::ir_interface::*;
+use super::loop_bucket::{LOOP_BODY_LABEL, LOOP_EXIT_LABEL};
+use crate::translating_traits::*;
+use code_producers::c_elements::*;
+use code_producers::wasm_elements::*;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LoopControlType {
+    Break,
+    Continue,
+}
+
+// Leaves the innermost loop or goes to its next iteration
+#[derive(Clone)]
+pub struct LoopControlBucket {
+    pub line: usize,
+    pub message_id: usize,
+    pub control: LoopControlType,
+}
+
+impl IntoInstruction for LoopControlBucket {
+    fn into_instruction(self) -> Instruction {
+        Instruction::LoopControl(self)
+    }
+}
+
+impl Allocate for LoopControlBucket {
+    fn allocate(self) -> InstructionPointer {
+        InstructionPointer::new(self.into_instruction())
+    }
+}
+
+impl ObtainMeta for LoopControlBucket {
+    fn get_line(&self) -> usize {
+        self.line
+    }
+    fn get_message_id(&self) -> usize {
+        self.message_id
+    }
+}
+
+impl std::fmt::Display for LoopControlBucket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let control = match self.control {
+            LoopControlType::Break => "BREAK",
+            LoopControlType::Continue => "CONTINUE",
+        };
+        write!(f, "{}(line:{},template_id:{})", control, self.line, self.message_id)
+    }
+}
+
+impl WriteWasm for LoopControlBucket {
+    fn produce_wasm(&self, producer: &WASMProducer) -> Vec<String> {
+        use code_producers::wasm_elements::wasm_code_generator::*;
+        let mut instructions = vec![];
+        if producer.needs_comments() {
+            instructions.push(format!(";; loop control bucket. Line {}", self.line));
+        }
+        match self.control {
+            LoopControlType::Break => instructions.push(br(LOOP_EXIT_LABEL)),
+            LoopControlType::Continue => instructions.push(br(LOOP_BODY_LABEL)),
+        }
+        instructions
+    }
+}
+
+impl WriteC for LoopControlBucket {
+    fn produce_c(&self, _producer: &CProducer, _parallel: Option<bool>) -> (Vec<String>, String) {
+        let instruction = match self.control {
+            LoopControlType::Break => "break;",
+            LoopControlType::Continue => "continue;",
+        };
+        (vec![instruction.to_string()], "".to_string())
+    }
+}
@@ -8,6 +8,7 @@ mod load_bucket;
 mod location_rule;
 mod log_bucket;
 mod loop_bucket;
+mod loop_control_bucket;
 mod return_bucket;
 mod store_bucket;
 mod types;
@@ -12,6 +12,8 @@ pub struct StoreBucket {
     pub dest_address_type: AddressType,
     pub dest: LocationRule,
     pub src: InstructionPointer,
+    // the assignment is reported in the trace of signals
+    pub trace: bool,
 }
 
 impl IntoInstruction for StoreBucket {
@@ -175,6 +177,9 @@ impl WriteWasm for StoreBucket {
                 }
             }
         }
+        if self.trace {
+            instructions.push(tee_local(producer.get_store_trace_tag()));
+        }
         if producer.needs_comments() {
             instructions.push(";; getting src".to_string());
 	}
@@ -214,6 +219,18 @@ impl WriteWasm for StoreBucket {
             instructions.push(add_end());
             instructions.push(add_end());
         }
+        if self.trace {
+            if let AddressType::SubcmpSignal { .. } = &self.dest_address_type {
+                instructions.push(get_local(producer.get_sub_cmp_tag()));
+            } else {
+                instructions.push(get_local(producer.get_offset_tag()));
+            }
+            instructions.push(get_local(producer.get_store_trace_tag()));
+            instructions.push(set_constant(&self.context.size.to_string()));
+            instructions.push(set_constant(&self.message_id.to_string()));
+            instructions.push(set_constant(&self.line.to_string()));
+            instructions.push(call("$traceSignalStore"));
+        }
         match &self.dest_address_type {
             AddressType::SubcmpSignal { .. } => {
                 // if subcomponent input check if run needed
@@ -249,6 +266,7 @@ impl WriteWasm for StoreBucket {
                             instructions.push(call(&format!("${}_run", name)));
                             instructions.push(tee_local(producer.get_merror_tag()));
                             instructions.push(add_if());
+                            instructions.append(&mut add_error_trace(producer, producer.get_sub_cmp_tag()));
                             instructions.push(set_constant(&self.message_id.to_string()));
                             instructions.push(set_constant(&self.line.to_string()));
                             instructions.push(call("$buildBufferMessage"));
@@ -269,6 +287,7 @@ impl WriteWasm for StoreBucket {
                         ));
                         instructions.push(tee_local(producer.get_merror_tag()));
                         instructions.push(add_if());
+                        instructions.append(&mut add_error_trace(producer, producer.get_sub_cmp_tag()));
                         instructions.push(set_constant(&self.message_id.to_string()));
                         instructions.push(set_constant(&self.line.to_string()));
                         instructions.push(call("$buildBufferMessage"));
@@ -372,9 +391,20 @@ impl WriteC for StoreBucket {
     prologue.append(&mut src_prologue);
 	prologue.push(format!("// end load src"));	
         std::mem::drop(src_prologue);
+        let trace = if self.trace {
+            let cmp = match &self.dest_address_type {
+                AddressType::SubcmpSignal { .. } => format!("{}[{}]", MY_SUBCOMPONENTS, cmp_index_ref),
+                _ => CTX_INDEX.to_string(),
+            };
+            let file = &producer.get_message_file_list()[self.message_id];
+            Some(build_signal_trace(&cmp, &aux_dest, self.context.size, file, self.line))
+        } else {
+            None
+        };
         if self.context.size > 1 {
             let copy_arguments = vec![aux_dest, src, self.context.size.to_string()];
             prologue.push(format!("{};", build_call("Fr_copyn".to_string(), copy_arguments)));
+            prologue.extend(trace);
 	    if let AddressType::Signal = &self.dest_address_type {
         if parallel.unwrap() && self.dest_is_output {
 		    prologue.push(format!("{{"));
@@ -391,6 +421,7 @@ impl WriteC for StoreBucket {
         } else {
             let copy_arguments = vec![aux_dest, src];
             prologue.push(format!("{};", build_call("Fr_copy".to_string(), copy_arguments)));
+            prologue.extend(trace);
 	    if let AddressType::Signal = &self.dest_address_type {
 		if parallel.unwrap() && self.dest_is_output {
 		    prologue.push(format!("{}->componentMemory[{}].mutexes[{}].lock();",CIRCOM_CALC_WIT,CTX_INDEX,aux_dest_index.clone()));
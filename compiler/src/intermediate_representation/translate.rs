@@ -1,8 +1,9 @@
 use super::ir_interface::*;
 use crate::hir::very_concrete_program::*;
-use crate::intermediate_representation::log_bucket::LogBucketArg;
+use crate::intermediate_representation::log_bucket::{FormatPiece, LogBucketArg};
 use constant_tracking::ConstantTracker;
 use num_bigint_dig::BigInt;
+use program_structure::ast;
 use program_structure::ast::*;
 use program_structure::file_definition::FileLibrary;
 use program_structure::utils::environment::VarEnvironment;
@@ -24,6 +25,37 @@ pub struct SignalInfo{
     lengths: Vec<usize>,
 }
 
+// meaning given to a tag by --check_tags, asserted at runtime every time a
+// signal declared with the tag is assigned
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TagMeaning {
+    // the value is 0 or 1
+    Binary,
+    // the value is smaller than 2^n, n being the value of the tag
+    MaxBit,
+    // the value is at most n, n being the value of the tag
+    Max,
+}
+
+impl TagMeaning {
+    pub fn from_name(name: &str) -> Option<TagMeaning> {
+        match name {
+            "binary" => Some(TagMeaning::Binary),
+            "maxbit" => Some(TagMeaning::MaxBit),
+            "max" => Some(TagMeaning::Max),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct TagCheck {
+    meaning: TagMeaning,
+    value: Option<BigInt>,
+    // position in the string table of the message reported on failure
+    message: usize,
+}
+
 #[derive(Clone)]
 pub struct TemplateDB {
     // one per template instance
@@ -102,6 +134,12 @@ struct State {
     code: InstructionList,
     // string_table
     string_table: HashMap<String, usize>,
+    // the stores of signals are instrumented to trace the assignments
+    trace_signals: bool,
+    // some log or assert message uses format strings
+    format_messages: bool,
+    // runtime checks of the tags of each signal
+    tag_checks: HashMap<String, Vec<TagCheck>>,
 }
 
 impl State {
@@ -127,6 +165,9 @@ impl State {
             max_stack_depth: 0,
             code: vec![],
             string_table : HashMap::new(),
+            trace_signals: false,
+            format_messages: false,
+            tag_checks: HashMap::new(),
         }
     }
     fn reserve(fresh: &mut usize, size: usize) -> usize {
@@ -230,6 +271,7 @@ fn initialize_constants(state: &mut State, constants: Vec<Argument>) {
                 dest: LocationRule::Indexed { location: full_address, template_header: None },
                 context: InstrContext { size: 1 },
                 src: content,
+                trace: false,
             }
             .allocate();
             state.code.push(store_instruction);
@@ -442,6 +484,8 @@ fn translate_statement(stmt: Statement, state: &mut State, context: &Context) {
         translate_return(stmt, state, context);
     } else if stmt.is_log_call() {
         translate_log(stmt, state, context);
+    } else if stmt.is_break() || stmt.is_continue() {
+        translate_loop_control(stmt, state, context);
     } else if stmt.is_initialization_block() {
         unreachable!("This statement is syntactic sugar");
     } else {
@@ -492,11 +536,22 @@ fn translate_while(stmt: Statement, state: &mut State, context: &Context) {
     }
 }
 
+fn translate_loop_control(stmt: Statement, state: &mut State, context: &Context) {
+    let meta = stmt.get_meta();
+    let line = context.files.get_line(meta.start, meta.get_file_id()).unwrap();
+    let control = if stmt.is_break() { LoopControlType::Break } else { LoopControlType::Continue };
+    let instruction = LoopControlBucket { line, message_id: state.message_id, control }.allocate();
+    state.code.push(instruction);
+}
+
 fn translate_substitution(stmt: Statement, state: &mut State, context: &Context) {
     use Statement::Substitution;
     if let Substitution { meta, var, access, rhe, .. } = stmt {
         debug_assert!(!meta.get_type_knowledge().is_component());
+        let checked = state.tag_checks.contains_key(&var)
+            && access.iter().all(|acc| matches!(acc, Access::ArrayAccess(_)));
         let def = SymbolDef { meta: meta.clone(), symbol: var, acc: access };
+        let check_def = if checked { Some(def.clone()) } else { None };
         let str_info =
             StoreInfo { prc_symbol: ProcessedSymbol::new(def, state, context), src: rhe };
         let store_instruction = if str_info.src.is_call() {
@@ -505,6 +560,14 @@ fn translate_substitution(stmt: Statement, state: &mut State, context: &Context)
             translate_standard_case(str_info, state, context)
         };
         state.code.push(store_instruction);
+        if let Some(def) = check_def {
+            let name = def.symbol.clone();
+            let stored = ProcessedSymbol::new(def, state, context);
+            let line = stored.line;
+            let length = stored.length;
+            let address = compute_full_address(state, stored.symbol, stored.before_signal);
+            check_signal_tags(state, &name, address, length, line);
+        }
     } else {
         unreachable!();
     }
@@ -588,6 +651,9 @@ fn translate_constraint_equality(stmt: Statement, state: &mut State, context: &C
             ProcessedSymbol::new(def, state, context).length
         } else {1};
         
+        let signal = constrained_signal_name(&lhe, state)
+            .or_else(|| constrained_signal_name(&rhe, state))
+            .map(|name| string_table_id(state, name));
         let lhe_pointer = translate_expression(lhe, state, context);
         let rhe_pointer = translate_expression(rhe, state, context);
         let stack = vec![lhe_pointer, rhe_pointer];
@@ -600,7 +666,7 @@ fn translate_constraint_equality(stmt: Statement, state: &mut State, context: &C
         }
         .allocate();
         let assert_instruction =
-            AssertBucket { line: starts_at, message_id: state.message_id, evaluate: equality }
+            AssertBucket { line: starts_at, message_id: state.message_id, signal, message: Vec::new(), evaluate: equality }
                 .allocate();
         state.code.push(assert_instruction);
     } else {
@@ -608,16 +674,201 @@ fn translate_constraint_equality(stmt: Statement, state: &mut State, context: &C
     }
 }
 
+// name of the signal used as one side of a constraint, without its indexes
+fn constrained_signal_name(expr: &Expression, state: &State) -> Option<String> {
+    use Expression::Variable;
+    if let Variable { name, access, .. } = expr {
+        let mut signal = name.clone();
+        let mut is_signal = state.signal_to_type.contains_key(name);
+        for acc in access {
+            if let Access::ComponentAccess(field) = acc {
+                signal = format!("{}.{}", signal, field);
+                is_signal = true;
+            }
+        }
+        if is_signal {
+            return Some(signal);
+        }
+    }
+    None
+}
+
+// asserts the meaning of the checked tags of the signal name in the length
+// positions that start at address
+fn check_signal_tags(state: &mut State, name: &str, address: InstructionPointer, length: usize, line: usize) {
+    let checks = state.tag_checks.get(name).cloned().unwrap_or_default();
+    let signal = string_table_id(state, name.to_string());
+    for index in 0..length {
+        let location = if index == 0 {
+            address.clone()
+        } else {
+            let offset = ValueBucket {
+                line,
+                message_id: state.message_id,
+                parse_as: ValueType::U32,
+                value: index,
+                op_aux_no: 0,
+            }
+            .allocate();
+            ComputeBucket {
+                line,
+                message_id: state.message_id,
+                op: OperatorType::AddAddress,
+                stack: vec![address.clone(), offset],
+                op_aux_no: 0,
+            }
+            .allocate()
+        };
+        for check in &checks {
+            let load = LoadBucket {
+                line,
+                message_id: state.message_id,
+                address_type: AddressType::Signal,
+                src: LocationRule::Indexed { location: location.clone(), template_header: None },
+            }
+            .allocate();
+            let evaluate = build_tag_condition(state, check, load, line);
+            let assert = AssertBucket {
+                line,
+                message_id: state.message_id,
+                signal: Some(signal),
+                message: vec![FormatPiece::Str(check.message)],
+                evaluate,
+            }
+            .allocate();
+            state.code.push(assert);
+        }
+    }
+}
+
+// condition that holds when value respects the meaning of the tag: v*(v-1) == 0
+// for binary, v >> n == 0 for maxbit and v \ (n+1) == 0 for max
+fn build_tag_condition(state: &mut State, check: &TagCheck, value: InstructionPointer, line: usize) -> InstructionPointer {
+    let constant = |state: &mut State, value: &BigInt| {
+        ValueBucket {
+            line,
+            message_id: state.message_id,
+            parse_as: ValueType::BigInt,
+            value: bigint_to_cid(&mut state.field_tracker, value),
+            op_aux_no: 0,
+        }
+        .allocate()
+    };
+    let compute = |state: &State, op: OperatorType, stack: Vec<InstructionPointer>| {
+        ComputeBucket { line, message_id: state.message_id, op, stack, op_aux_no: 0 }.allocate()
+    };
+    let tag_value = check.value.clone().unwrap_or_default();
+    let reduced = match check.meaning {
+        TagMeaning::Binary => {
+            let one = constant(state, &BigInt::from(1));
+            let minus_one = compute(state, OperatorType::Sub, vec![value.clone(), one]);
+            compute(state, OperatorType::Mul, vec![value, minus_one])
+        }
+        TagMeaning::MaxBit => {
+            let bits = constant(state, &tag_value);
+            compute(state, OperatorType::ShiftR, vec![value, bits])
+        }
+        TagMeaning::Max => {
+            let bound = constant(state, &(tag_value + 1));
+            compute(state, OperatorType::IntDiv, vec![value, bound])
+        }
+    };
+    let zero = constant(state, &BigInt::from(0));
+    compute(state, OperatorType::Eq(1), vec![reduced, zero])
+}
+
+// runtime checks of the tags declared for the signals that have a meaning
+// in checked_tags, the tags without meaning or without the value the meaning
+// needs are not checked
+fn initialize_tag_checks(state: &mut State, signals: &[Signal], checked_tags: &BTreeMap<String, TagMeaning>) {
+    for signal in signals {
+        let mut checks = vec![];
+        for tag in &signal.tags {
+            let meaning = match checked_tags.get(tag) {
+                Some(meaning) => *meaning,
+                None => continue,
+            };
+            let value = state.signal_to_tags.get(&signal.name).and_then(|tags| tags.get(tag)).cloned().flatten();
+            let message = match (meaning, &value) {
+                (TagMeaning::Binary, _) => format!("Tag check failed: {}", tag),
+                (_, Some(value)) => format!("Tag check failed: {} = {}", tag, value),
+                (_, None) => continue,
+            };
+            let message = string_table_id(state, message);
+            checks.push(TagCheck { meaning, value, message });
+        }
+        if !checks.is_empty() {
+            state.tag_checks.insert(signal.name.clone(), checks);
+        }
+    }
+}
+
+// checks the tags of the inputs, assigned before the execution of the template
+fn check_input_tags(state: &mut State, signals: &[Signal], line: usize) {
+    for signal in signals {
+        if signal.xtype == SignalType::Input && state.tag_checks.contains_key(&signal.name) {
+            let address = state.environment.get_variable(&signal.name).unwrap().access_instruction.clone();
+            check_signal_tags(state, &signal.name, address, signal.size(), line);
+        }
+    }
+}
+
+fn string_table_id(state: &mut State, string: String) -> usize {
+    match state.string_table.get(&string) {
+        Some(idx) => *idx,
+        None => {
+            let idx = state.string_table.len();
+            state.string_table.insert(string, idx);
+            idx
+        }
+    }
+}
+
 fn translate_assert(stmt: Statement, state: &mut State, context: &Context) {
     use Statement::Assert;
-    if let Assert { meta, arg, .. } = stmt {
+    if let Assert { meta, arg, message } = stmt {
         let line = context.files.get_line(meta.start, meta.get_file_id()).unwrap();
         let code = translate_expression(arg, state, context);
-        let assert = AssertBucket { line, message_id: state.message_id, evaluate: code }.allocate();
+        // the arguments of the message are separated by spaces, like in a log
+        let mut pieces = Vec::new();
+        for arglog in message {
+            if !pieces.is_empty() {
+                pieces.push(FormatPiece::Str(string_table_id(state, " ".to_string())));
+            }
+            match arglog {
+                LogArgument::LogExp(arg) => {
+                    let code = translate_expression(arg, state, context);
+                    pieces.push(FormatPiece::Value(code, NumberFormat::Decimal));
+                }
+                LogArgument::LogStr(string) => {
+                    pieces.push(FormatPiece::Str(string_table_id(state, string)));
+                }
+                LogArgument::LogFormat(format) => {
+                    pieces.append(&mut translate_format(format, state, context));
+                }
+            }
+        }
+        state.format_messages |= pieces.len() > 1 || pieces.iter().any(|piece| matches!(piece, FormatPiece::Value(..)));
+        let assert =
+            AssertBucket { line, message_id: state.message_id, signal: None, message: pieces, evaluate: code }.allocate();
         state.code.push(assert);
     }
 }
 
+fn translate_format(format: Vec<ast::FormatPiece>, state: &mut State, context: &Context) -> Vec<FormatPiece> {
+    let mut pieces = Vec::new();
+    for piece in format {
+        match piece {
+            ast::FormatPiece::Text(text) => pieces.push(FormatPiece::Str(string_table_id(state, text))),
+            ast::FormatPiece::Value(arg, format) => {
+                let code = translate_expression(*arg, state, context);
+                pieces.push(FormatPiece::Value(code, format));
+            }
+        }
+    }
+    pieces
+}
+
 fn translate_log(stmt: Statement, state: &mut State, context: &Context) {
     use Statement::LogCall;
     if let LogCall { meta, args, .. } = stmt {
@@ -639,6 +890,11 @@ fn translate_log(stmt: Statement, state: &mut State, context: &Context) {
                     }
                     
                 }
+                LogArgument::LogFormat(format) => {
+                    let pieces = translate_format(format, state, context);
+                    logbucket_args.push(LogBucketArg::Format(pieces));
+                    state.format_messages = true;
+                }
             }
         }
         
@@ -889,6 +1145,7 @@ fn build_signal_location(
     }
 }
 
+#[derive(Clone)]
 struct SymbolDef {
     meta: Meta,
     symbol: String,
@@ -1050,6 +1307,7 @@ impl ProcessedSymbol {
                 context: InstrContext { size: self.length },
                 dest_is_output: false,
                 dest_address_type: dest_type,
+                trace: state.trace_signals,
             }
             .allocate()
         } else {
@@ -1058,8 +1316,10 @@ impl ProcessedSymbol {
                 TypeReduction::Variable => AddressType::Variable,
                 _ => AddressType::Signal,
             };
+            let trace = state.trace_signals && matches!(xtype, AddressType::Signal);
             StoreBucket {
                 src,
+                trace,
                 line: self.line,
                 dest_address_type: xtype,
                 message_id: self.message_id,
@@ -1251,6 +1511,8 @@ pub struct CodeInfo<'a> {
     pub component_to_parallel: HashMap<String, ParallelClusters>,
     pub string_table: HashMap<String, usize>,
     pub signals_to_tags: BTreeMap<String, TagInfo>,
+    pub trace_signals: bool,
+    pub check_tags: &'a BTreeMap<String, TagMeaning>,
 }
 
 pub struct CodeOutput {
@@ -1261,6 +1523,7 @@ pub struct CodeOutput {
     pub code: InstructionList,
     pub constant_tracker: FieldTracker,
     pub string_table: HashMap<String, usize>,
+    pub format_messages: bool,
 }
 
 pub fn translate_code(body: Statement, code_info: CodeInfo) -> CodeOutput {
@@ -1273,6 +1536,9 @@ pub fn translate_code(body: Statement, code_info: CodeInfo) -> CodeOutput {
         code_info.signals_to_tags,
     );
     state.string_table = code_info.string_table;
+    state.trace_signals = code_info.trace_signals;
+    initialize_tag_checks(&mut state, &code_info.signals, code_info.check_tags);
+    let signals = code_info.signals.clone();
     initialize_components(&mut state, code_info.components);
     initialize_signals(&mut state, code_info.signals);
     initialize_constants(&mut state, code_info.constants);
@@ -1287,6 +1553,8 @@ pub fn translate_code(body: Statement, code_info: CodeInfo) -> CodeOutput {
     };
 
     create_components(&mut state, &code_info.triggers, code_info.clusters);
+    let body_line = context.files.get_line(body.get_meta().start, body.get_meta().get_file_id()).unwrap();
+    check_input_tags(&mut state, &signals, body_line);
     translate_statement(body, &mut state, &context);
 
     ir_processing::build_inputs_info(&mut state.code);
@@ -1302,6 +1570,7 @@ pub fn translate_code(body: Statement, code_info: CodeInfo) -> CodeOutput {
         stack_depth: state.max_stack_depth,
         signal_depth: state.signal_stack,
         constant_tracker: state.field_tracker,
-        string_table : state.string_table
+        string_table : state.string_table,
+        format_messages: state.format_messages,
     }
 }
@@ -39,6 +39,7 @@ pub fn visit_instruction(
         Compute(b) => visit_compute(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
         Load(b) => visit_load(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
         Loop(b) => visit_loop(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
+        LoopControl(_) => found_unknown_address,
         Return(b) => visit_return(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
         Store(b) => visit_store(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
         Value(b) => visit_value(b, known_last_component, unknown_last_component, found_unknown_address, inside_loop),
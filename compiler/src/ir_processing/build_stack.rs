@@ -17,6 +17,7 @@ pub fn build_instruction(instruction: &mut Instruction, fresh: usize) -> usize {
         Compute(b) => build_compute(b, fresh),
         Load(b) => build_load(b, fresh),
         Loop(b) => build_loop(b, fresh),
+        LoopControl(_) => fresh,
         Return(b) => build_return(b, fresh),
         Store(b) => build_store(b, fresh),
         Value(b) => build_value(b, fresh),
@@ -96,13 +97,27 @@ pub fn build_log(bucket: &mut LogBucket, fresh: usize) -> usize {
                 in_log = std::cmp::max(in_log, new_log);
             }
             LogBucketArg::LogStr(..) => {}
+            LogBucketArg::Format(pieces) => {
+                in_log = std::cmp::max(in_log, build_format(pieces, fresh));
+            }
         }
     }
     in_log
 }
 
+fn build_format(pieces: &mut [FormatPiece], fresh: usize) -> usize {
+    let mut in_format = 0;
+    for piece in pieces {
+        if let Some(value) = piece.get_mut_value() {
+            in_format = std::cmp::max(in_format, build_instruction(value, fresh));
+        }
+    }
+    in_format
+}
+
 pub fn build_assert(bucket: &mut AssertBucket, fresh: usize) -> usize {
-    build_instruction(&mut bucket.evaluate, fresh)
+    let in_evaluate = build_instruction(&mut bucket.evaluate, fresh);
+    std::cmp::max(in_evaluate, build_format(&mut bucket.message, fresh))
 }
 
 pub fn build_store(bucket: &mut StoreBucket, fresh: usize) -> usize {
@@ -20,6 +20,7 @@ pub fn reduce_instruction(instr: Instruction) -> Instruction {
         Assert(b) => reduce_assert(b),
         Log(b) => reduce_log(b),
         Loop(b) => reduce_loop(b),
+        LoopControl(b) => IntoInstruction::into_instruction(b),
         CreateCmp(b) => reduce_crt_cmp(b),
         Compute(b) => reduce_compute(b),
     }
@@ -92,6 +93,9 @@ pub fn reduce_log(mut bucket: LogBucket) -> Instruction {
             LogBucketArg::LogStr(s) => {
                 new_args_prints.push(LogBucketArg::LogStr(s));
             },
+            LogBucketArg::Format(pieces) => {
+                new_args_prints.push(LogBucketArg::Format(reduce_format(pieces)));
+            },
         }
         
     }
@@ -100,8 +104,22 @@ pub fn reduce_log(mut bucket: LogBucket) -> Instruction {
     IntoInstruction::into_instruction(bucket)
 }
 
+fn reduce_format(pieces: Vec<FormatPiece>) -> Vec<FormatPiece> {
+    let mut new_pieces = Vec::new();
+    for piece in pieces {
+        match piece {
+            FormatPiece::Value(exp, format) => {
+                new_pieces.push(FormatPiece::Value(Allocate::allocate(reduce_instruction(*exp)), format));
+            }
+            FormatPiece::Str(s) => new_pieces.push(FormatPiece::Str(s)),
+        }
+    }
+    new_pieces
+}
+
 pub fn reduce_assert(mut bucket: AssertBucket) -> Instruction {
     bucket.evaluate = Allocate::allocate(reduce_instruction(*bucket.evaluate));
+    bucket.message = reduce_format(bucket.message);
     IntoInstruction::into_instruction(bucket)
 }
 
@@ -21,6 +21,7 @@ pub fn visit_instruction(
         Compute(b) => visit_compute(b, function_to_arena_size),
         Load(b) => visit_load(b, function_to_arena_size),
         Loop(b) => visit_loop(b, function_to_arena_size),
+        LoopControl(_) => {}
         Return(b) => visit_return(b, function_to_arena_size),
         Store(b) => visit_store(b, function_to_arena_size),
         Value(b) => visit_value(b, function_to_arena_size),
@@ -77,16 +78,27 @@ pub fn visit_return(bucket: &mut ReturnBucket, function_to_arena_size: &HashMap<
 }
 
 pub fn visit_log(bucket: &mut LogBucket, function_to_arena_size: &HashMap<String, usize>) {
-    for print in bucket.argsprint.clone() {
-        if let LogBucketArg::LogExp(mut exp) = print { 
-            visit_instruction(&mut exp, function_to_arena_size);
+    for print in &mut bucket.argsprint {
+        match print {
+            LogBucketArg::LogExp(exp) => visit_instruction(exp, function_to_arena_size),
+            LogBucketArg::Format(pieces) => visit_format(pieces, function_to_arena_size),
+            LogBucketArg::LogStr(_) => {}
         }
     }
     
 }
 
+fn visit_format(pieces: &mut [FormatPiece], function_to_arena_size: &HashMap<String, usize>) {
+    for piece in pieces {
+        if let Some(value) = piece.get_mut_value() {
+            visit_instruction(value, function_to_arena_size);
+        }
+    }
+}
+
 pub fn visit_assert(bucket: &mut AssertBucket, function_to_arena_size: &HashMap<String, usize>) {
     visit_instruction(&mut bucket.evaluate, function_to_arena_size);
+    visit_format(&mut bucket.message, function_to_arena_size);
 }
 
 pub fn visit_store(bucket: &mut StoreBucket, function_to_arena_size: &HashMap<String, usize>) {
@@ -30,8 +30,16 @@ enum BlockType {
     Unknown,
 }
 
+// break or continue executed in the body of the current loop
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum LoopControl {
+    Break,
+    Continue,
+}
+
 struct RuntimeInformation {
     pub block_type: BlockType,
+    pub loop_control: Option<LoopControl>,
     pub analysis: Analysis,
     pub public_inputs: Vec<String>,
     pub constants: UsefulConstants,
@@ -47,6 +55,7 @@ impl RuntimeInformation {
         RuntimeInformation {
             current_file,
             block_type: BlockType::Known,
+            loop_control: None,
             analysis: Analysis::new(id_max),
             public_inputs: vec![],
             constants: UsefulConstants::new(prime),
@@ -82,7 +91,7 @@ impl Default for FoldedValue {
 
 enum ExecutionError {
     NonQuadraticConstraint,
-    FalseAssert,
+    FalseAssert(Option<String>),
     ArraySizeTooBig
 }
 
@@ -102,7 +111,7 @@ pub fn constraint_execution(
     runtime_information.public_inputs = program_archive.get_public_inputs_main_component().clone();
     
     let folded_value_result = 
-        if let Call { id, args, .. } = &program_archive.get_main_expression() {
+        if let Call { id, args, meta } = &program_archive.get_main_expression() {
             let mut arg_values = Vec::new();
             for arg_expression in args.iter() {
                 let f_arg = execute_expression(arg_expression, program_archive, &mut runtime_information, flag_verbose);
@@ -113,6 +122,7 @@ pub fn constraint_execution(
                 id,
                 arg_values,
                 BTreeMap::new(),
+                meta,
                 program_archive,
                 &mut runtime_information,
                 flag_verbose,
@@ -230,11 +240,12 @@ fn execute_statement(
                             name,
                             &usable_dimensions,
                         ),
-                        VariableType::Signal(signal_type, tag_list) => execute_signal_declaration(
+                        VariableType::Signal(signal_type, tag_list, is_kept) => execute_signal_declaration(
                             name,
                             &usable_dimensions,
                             tag_list,
                             *signal_type,
+                            *is_kept,
                             &mut runtime.environment,
                             actual_node,
                         ),
@@ -384,33 +395,51 @@ fn execute_statement(
             )?;
             possible_return
         }
-        While { cond, stmt, .. } => loop {
-            let (returned, condition_result) = execute_conditional_statement(
-                cond,
-                stmt,
-                Option::None,
-                program_archive,
-                runtime,
-                actual_node,
-                flag_verbose
-            )?;
-            if returned.is_some() {
-                break returned;
-            } else if condition_result.is_none() {
-                let (returned, _) = execute_conditional_statement(
+        While { cond, stmt, .. } => {
+            // a break or continue under an unknown condition makes the
+            // number of iterations unknown from that iteration on
+            let previous_block_type = runtime.block_type;
+            let returned = loop {
+                let (returned, condition_result) = execute_conditional_statement(
                     cond,
                     stmt,
-                    None,
+                    Option::None,
                     program_archive,
                     runtime,
                     actual_node,
                     flag_verbose
                 )?;
-                break returned;
-            } else if !condition_result.unwrap() {
-                break returned;
-            }
-        },
+                let control = runtime.loop_control.take();
+                if returned.is_some() {
+                    break returned;
+                } else if condition_result.is_none() || runtime.block_type != previous_block_type {
+                    runtime.block_type = BlockType::Unknown;
+                    let (returned, _) = execute_conditional_statement(
+                        cond,
+                        stmt,
+                        None,
+                        program_archive,
+                        runtime,
+                        actual_node,
+                        flag_verbose
+                    )?;
+                    runtime.loop_control = None;
+                    break returned;
+                } else if !condition_result.unwrap() || control == Some(LoopControl::Break) {
+                    break returned;
+                }
+            };
+            runtime.block_type = previous_block_type;
+            returned
+        }
+        Break { .. } => {
+            runtime.loop_control = Some(LoopControl::Break);
+            Option::None
+        }
+        Continue { .. } => {
+            runtime.loop_control = Some(LoopControl::Continue);
+            Option::None
+        }
         Block { stmts, .. } => {
             ExecutionEnvironment::add_variable_block(&mut runtime.environment);
             let return_value =
@@ -420,36 +449,21 @@ fn execute_statement(
         }
         LogCall { args, .. } => {
             if flag_verbose{
-                let mut index = 0;
-                for arglog in args {
-                    if let LogArgument::LogExp(arg) = arglog{
-                        let f_result = execute_expression(arg, program_archive, runtime, flag_verbose)?;
-                        let arith = safe_unwrap_to_single_arithmetic_expression(f_result, line!());
-                        if AExpr::is_number(&arith){
-                            print!("{}", arith);
-                        }
-                        else{
-                            print!("Unknown")
-                        }
-                    }
-                    else if let LogArgument::LogStr(s) = arglog {
-                            print!("{}",s);
-                    }
-                    if index != args.len()-1{
-                        print!(" ");
-                    }
-                    index += 1;
-                }
-                println!("");
+                let message = execute_log_arguments(args, program_archive, runtime, flag_verbose)?;
+                println!("{}", message);
             }
             Option::None
         }
-        Assert { arg, meta, .. } => {
+        Assert { arg, meta, message } => {
             let f_result = execute_expression(arg, program_archive, runtime, flag_verbose)?;
             let arith = safe_unwrap_to_single_arithmetic_expression(f_result, line!());
             let possible_bool = AExpr::get_boolean_equivalence(&arith, runtime.constants.get_p());
             let result = match possible_bool {
-                Some(b) if !b => Err(ExecutionError::FalseAssert),
+                Some(b) if !b && message.is_empty() => Err(ExecutionError::FalseAssert(None)),
+                Some(b) if !b => {
+                    let message = execute_log_arguments(message, program_archive, runtime, flag_verbose)?;
+                    Err(ExecutionError::FalseAssert(Some(message)))
+                }
                 _ => Ok(None),
             };
             treat_result_with_execution_error(
@@ -487,6 +501,60 @@ fn execute_statement(
     Result::Ok(res)
 }
 
+// Builds the text printed by a log or by a failing assert: the arguments are
+// separated by spaces and the values that are not known are printed as Unknown
+fn execute_log_arguments(
+    args: &[LogArgument],
+    program_archive: &ProgramArchive,
+    runtime: &mut RuntimeInformation,
+    flag_verbose: bool,
+) -> Result<String, ()> {
+    let mut texts = Vec::new();
+    for arglog in args {
+        let text = match arglog {
+            LogArgument::LogStr(s) => s.clone(),
+            LogArgument::LogExp(arg) => {
+                execute_log_value(arg, NumberFormat::Decimal, program_archive, runtime, flag_verbose)?
+            }
+            LogArgument::LogFormat(pieces) => {
+                let mut text = String::new();
+                for piece in pieces {
+                    match piece {
+                        FormatPiece::Text(s) => text.push_str(s),
+                        FormatPiece::Value(arg, format) => text.push_str(&execute_log_value(
+                            arg,
+                            *format,
+                            program_archive,
+                            runtime,
+                            flag_verbose,
+                        )?),
+                    }
+                }
+                text
+            }
+        };
+        texts.push(text);
+    }
+    Result::Ok(texts.join(" "))
+}
+
+fn execute_log_value(
+    arg: &Expression,
+    format: NumberFormat,
+    program_archive: &ProgramArchive,
+    runtime: &mut RuntimeInformation,
+    flag_verbose: bool,
+) -> Result<String, ()> {
+    let f_result = execute_expression(arg, program_archive, runtime, flag_verbose)?;
+    let arith = safe_unwrap_to_single_arithmetic_expression(f_result, line!());
+    let text = match (arith, format) {
+        (AExpr::Number { value }, NumberFormat::Hexadecimal) => format!("0x{}", value.to_str_radix(16)),
+        (arith, _) if AExpr::is_number(&arith) => format!("{}", arith),
+        _ => "Unknown".to_string(),
+    };
+    Result::Ok(text)
+}
+
 fn execute_expression(
     expr: &Expression,
     program_archive: &ProgramArchive,
@@ -612,8 +680,8 @@ fn execute_expression(
                 FoldedValue { arithmetic_slice, ..FoldedValue::default() }
             }
         }
-        Call { id, args, .. } => {
-            execute_call(id, args, program_archive, runtime, flag_verbose)?
+        Call { meta, id, args } => {
+            execute_call(meta, id, args, program_archive, runtime, flag_verbose)?
         }
         ParallelOp{rhe, ..} => {
             let folded_value = execute_expression(rhe, program_archive, runtime, flag_verbose)?;
@@ -638,6 +706,7 @@ fn execute_expression(
 //************************************************* Statement execution support *************************************************
 
 fn execute_call(
+    meta: &Meta,
     id: &String,
     args: &Vec<Expression>,
     program_archive: &ProgramArchive,
@@ -668,7 +737,7 @@ fn execute_call(
         runtime.call_trace.pop();
         Ok(folded_result)
     } else { // in this case we preexecute and check if it needs tags
-        let folded_result = preexecute_template_call(id, &arg_values, program_archive, runtime)?;
+        let folded_result = preexecute_template_call(meta, id, &arg_values, program_archive, runtime)?;
         Ok(folded_result)
     }
 }
@@ -677,6 +746,7 @@ fn execute_template_call_complete(
     id: &String,
     arg_values: Vec<AExpressionSlice>,
     tags: BTreeMap<String, TagInfo>,
+    call_site: &Meta,
     program_archive: &ProgramArchive,
     runtime: &mut RuntimeInformation,
     flag_verbose: bool,
@@ -691,7 +761,7 @@ fn execute_template_call_complete(
         let previous_id = std::mem::replace(&mut runtime.current_file, new_file_id);
 
         runtime.call_trace.push(id.clone());
-        let folded_result = execute_template_call(id, arg_values, tags, program_archive, runtime, flag_verbose)?;
+        let folded_result = execute_template_call(id, arg_values, tags, call_site, program_archive, runtime, flag_verbose)?;
 
         runtime.environment = previous_environment;
         runtime.current_file = previous_id;
@@ -734,6 +804,7 @@ fn execute_signal_declaration(
     dimensions: &[SliceCapacity],
     list_tags: &Vec<String>,
     signal_type: SignalType,
+    is_kept: bool,
     environment: &mut ExecutionEnvironment,
     actual_node: &mut Option<ExecutedTemplate>,
 ) {
@@ -744,6 +815,10 @@ fn execute_signal_declaration(
     } 
     if let Option::Some(node) = actual_node {
         node.add_ordered_signal(signal_name, dimensions);
+        node.add_declared_tags(signal_name, list_tags);
+        if is_kept {
+            node.add_kept_signal(signal_name);
+        }
         match signal_type {
             Input => {
                 if let Some(tags_input) = node.tag_instances().get(signal_name){
@@ -1096,10 +1171,12 @@ fn perform_assign(
                 // calls to execute and initialize the component              
                 let pretemplate_info = runtime.exec_program.get_prenode_value(prenode_pointer).unwrap();
                 let inputs_tags = component.inputs_tags.clone();
+                let call_site = pretemplate_info.call_site().clone();
                 let result = execute_template_call_complete(
                     pretemplate_info.template_name(),
                     pretemplate_info.parameter_instances().clone(),
                     inputs_tags,
+                    &call_site,
                     program_archive,
                     runtime,
                     flag_verbose,
@@ -1185,11 +1262,13 @@ fn perform_assign(
                     component.node_pointer.unwrap()
                 ).unwrap();
                 let inputs_tags = component.inputs_tags.clone();
+                let call_site = pretemplate_info.call_site().clone();
 
                 let folded_result = execute_template_call_complete(
                     pretemplate_info.template_name(),
                     pretemplate_info.parameter_instances().clone(),
                     inputs_tags,
+                    &call_site,
                     program_archive,
                     runtime,
                     flag_verbose,
@@ -1302,13 +1381,16 @@ fn execute_conditional_statement(
         let previous_block_type = runtime.block_type;
         runtime.block_type = BlockType::Unknown;
         let mut ret_value = execute_statement(true_case, program_archive, runtime, actual_node, flag_verbose)?;
+        let mut loop_exit = runtime.loop_control.take().is_some();
         if let Option::Some(else_stmt) = false_case {
             let else_ret = execute_statement(else_stmt, program_archive, runtime, actual_node, flag_verbose)?;
+            loop_exit = runtime.loop_control.take().is_some() || loop_exit;
             if ret_value.is_none() {
                 ret_value = else_ret;
             }
         }
-        runtime.block_type = previous_block_type;
+        // the rest of the loop may not be executed, so it is unknown
+        runtime.block_type = if loop_exit { BlockType::Unknown } else { previous_block_type };
         return Result::Ok((ret_value, Option::None));
     }
 }
@@ -1326,6 +1408,9 @@ fn execute_sequence_of_statements(
         if f_value.is_some() {
             return Result::Ok(f_value);
         }
+        if runtime.loop_control.is_some() {
+            return Result::Ok(Option::None);
+        }
     }
     if is_complete_template{
         execute_delayed_declarations(program_archive, runtime, actual_node, flag_verbose)?;
@@ -1676,10 +1761,57 @@ fn execute_function_call(
     Result::Ok(return_value)
 }
 
+// The requirements are evaluated with the parameters of the instance, when
+// one does not hold the error points both to the instantiation and to it
+fn check_template_requirements(
+    id: &str,
+    instantiation_name: &str,
+    call_site: &Meta,
+    program_archive: &ProgramArchive,
+    runtime: &mut RuntimeInformation,
+    flag_verbose: bool,
+) -> Result<(), ()> {
+    let template_data = program_archive.get_template_data(id);
+    for requirement in template_data.get_requirements() {
+        let f_result = execute_expression(&requirement.condition, program_archive, runtime, flag_verbose)?;
+        let arith = safe_unwrap_to_single_arithmetic_expression(f_result, line!());
+        if AExpr::get_boolean_equivalence(&arith, runtime.constants.get_p()) != Some(false) {
+            continue;
+        }
+        let mut msg = format!("Requirement of template {} not satisfied", id);
+        if !requirement.message.is_empty() {
+            let message = execute_log_arguments(&requirement.message, program_archive, runtime, flag_verbose)?;
+            msg.push_str(&format!(": {}", message));
+        }
+        let mut report = Report::error(msg, ReportCode::UnsatisfiedRequirement);
+        report.add_primary(
+            call_site.location.clone(),
+            call_site.get_file_id(),
+            format!("instantiated here as {}", instantiation_name),
+        );
+        report.add_secondary(
+            requirement.meta.location.clone(),
+            template_data.get_file_id(),
+            Some("this requirement does not hold".to_string()),
+        );
+        let mut trace = "call trace:\n".to_string();
+        let mut spacing = "".to_string();
+        for call in runtime.call_trace.iter() {
+            trace.push_str(&format!("{}->{}\n", spacing, call));
+            spacing.push(' ');
+        }
+        report.add_note(trace);
+        runtime.runtime_errors.push(report);
+        return Err(());
+    }
+    Ok(())
+}
+
 fn execute_template_call(
     id: &str,
     parameter_values: Vec<AExpressionSlice>,
     tag_values: BTreeMap<String, TagInfo>,
+    call_site: &Meta,
     program_archive: &ProgramArchive,
     runtime: &mut RuntimeInformation,
     flag_verbose: bool
@@ -1720,6 +1852,7 @@ fn execute_template_call(
     let node_pointer = if let Option::Some(pointer) = existent_node {
         pointer
     } else {
+        check_template_requirements(id, &instantiation_name, call_site, program_archive, runtime, flag_verbose)?;
         let analysis =
             std::mem::replace(&mut runtime.analysis, Analysis::new(program_archive.id_max));
         let code = program_archive.get_template_data(id).get_body().clone();
@@ -1765,6 +1898,7 @@ fn execute_template_call(
 }
 
 fn preexecute_template_call(
+    call_site: &Meta,
     id: &str,
     parameter_values: &[AExpressionSlice],
     program_archive: &ProgramArchive,
@@ -1791,6 +1925,7 @@ fn preexecute_template_call(
         parameter_values.to_vec(),
         inputs_to_tags,
         outputs_to_tags,
+        call_site.clone(),
     ));
 
     let new_node = node_wrap.unwrap();
@@ -2249,9 +2384,12 @@ fn treat_result_with_execution_error<C>(
                     "Non quadratic constraints are not allowed!".to_string(),
                     ReportCode::RuntimeError,
                 ),
-                FalseAssert => {
+                FalseAssert(None) => {
                     Report::error("False assert reached".to_string(), ReportCode::RuntimeError)
                 }
+                FalseAssert(Some(message)) => {
+                    Report::error(format!("False assert reached: {}", message), ReportCode::RuntimeError)
+                }
                 ArraySizeTooBig => Report::error(
                     "The size of the array is expected to be a usize".to_string(),
                     ReportCode::RuntimeError,
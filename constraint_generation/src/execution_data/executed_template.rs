@@ -3,7 +3,7 @@ use circom_algebra::algebra::ArithmeticExpression;
 use compiler::hir::very_concrete_program::*;
 use dag::DAG;
 use num_bigint::BigInt;
-use program_structure::ast::{SignalType, Statement};
+use program_structure::ast::{Meta, SignalType, Statement};
 use std::collections::{HashMap, HashSet};
 use crate::execution_data::AExpressionSlice;
 use crate::execution_data::TagInfo;
@@ -24,6 +24,9 @@ pub struct PreExecutedTemplate {
     pub parameter_instances: Vec<AExpressionSlice>,
     pub inputs: HashMap<String, HashSet<String>>,
     pub outputs: HashMap<String, HashSet<String>>,
+    // call that created the component, used to report the requirements of
+    // the template that its parameters do not satisfy
+    pub call_site: Meta,
 } 
 
 impl PreExecutedTemplate {
@@ -32,12 +35,14 @@ impl PreExecutedTemplate {
         instance: Vec<AExpressionSlice>,
         inputs: HashMap<String, HashSet<String>>,
         outputs: HashMap<String, HashSet<String>>,
+        call_site: Meta,
     ) -> PreExecutedTemplate {
         PreExecutedTemplate {
             template_name: name,
             parameter_instances: instance,
             inputs,
             outputs,
+            call_site,
         }
     }
 
@@ -56,6 +61,10 @@ impl PreExecutedTemplate {
     pub fn outputs(&self) -> &HashMap<String, HashSet<String>> {
         &self.outputs
     }
+
+    pub fn call_site(&self) -> &Meta {
+        &self.call_site
+    }
 }
 
 
@@ -75,6 +84,8 @@ pub struct ExecutedTemplate {
     pub parameter_instances: ParameterContext,
     pub tag_instances: TagContext,
     pub signal_to_tags: TagContext,
+    pub declared_tags: HashMap<String, Vec<String>>,
+    pub kept_signals: HashSet<String>,
     pub is_parallel: bool,
     pub has_parallel_sub_cmp: bool,
     pub is_custom_gate: bool,
@@ -103,6 +114,8 @@ impl ExecutedTemplate {
             template_name: name,
             parameter_instances: instance,
             signal_to_tags: tag_instances.clone(),
+            declared_tags: HashMap::new(),
+            kept_signals: HashSet::new(),
             tag_instances,
             inputs: SignalCollector::new(),
             outputs: SignalCollector::new(),
@@ -171,6 +184,16 @@ impl ExecutedTemplate {
         }
     }
 
+    pub fn add_declared_tags(&mut self, signal_name: &str, tags: &[String]) {
+        if !tags.is_empty() {
+            self.declared_tags.insert(signal_name.to_string(), tags.to_vec());
+        }
+    }
+
+    pub fn add_kept_signal(&mut self, signal_name: &str) {
+        self.kept_signals.insert(signal_name.to_string());
+    }
+
     pub fn add_component(&mut self, component_name: &str, dimensions: &[usize]) {
         self.components.push((component_name.to_string(), dimensions.to_vec()));
         self.number_of_components += dimensions.iter().fold(1, |p, c| p * (*c));
@@ -231,26 +254,26 @@ impl ExecutedTemplate {
     fn build_signals(&self, dag: &mut DAG) {
         for (name, dim) in self.outputs() {
             let state = State { name: name.clone(), dim: 0 };
-            let config = SignalConfig { signal_type: 1, dimensions: dim, is_public: false };
+            let config = SignalConfig { signal_type: 1, dimensions: dim, is_public: false, is_kept: self.kept_signals.contains(name) };
             generate_symbols(dag, state, &config);
         }
         for (name, dim) in self.inputs() {
             if self.public_inputs.contains(name) {
                 let state = State { name: name.clone(), dim: 0 };
-                let config = SignalConfig { signal_type: 0, dimensions: dim, is_public: true };
+                let config = SignalConfig { signal_type: 0, dimensions: dim, is_public: true, is_kept: self.kept_signals.contains(name) };
                 generate_symbols(dag, state, &config);
             }
         }
         for (name, dim) in self.inputs() {
             if !self.public_inputs.contains(name) {
                 let state = State { name: name.clone(), dim: 0 };
-                let config = SignalConfig { signal_type: 0, dimensions: dim, is_public: false };
+                let config = SignalConfig { signal_type: 0, dimensions: dim, is_public: false, is_kept: self.kept_signals.contains(name) };
                 generate_symbols(dag, state, &config);
             }
         }
         for (name, dim) in self.intermediates() {
             let state = State { name: name.clone(), dim: 0 };
-            let config = SignalConfig { signal_type: 2, dimensions: dim, is_public: false };
+            let config = SignalConfig { signal_type: 2, dimensions: dim, is_public: false, is_kept: self.kept_signals.contains(name) };
             generate_symbols(dag, state, &config);
         }
     }
@@ -285,7 +308,7 @@ impl ExecutedTemplate {
             dag.add_constraint(cc);
         }
     }
-    pub fn export_to_circuit(self, instances: &mut [TemplateInstance]) -> TemplateInstance {
+    pub fn export_to_circuit(mut self, instances: &mut [TemplateInstance]) -> TemplateInstance {
         use SignalType::*;
         fn build_triggers(
             instances: &mut [TemplateInstance],
@@ -330,7 +353,8 @@ impl ExecutedTemplate {
             arguments
         }
 
-        let header = format!("{}_{}", self.template_name, instances.len());
+        // templates of included namespaces are named prefix.name
+        let header = format!("{}_{}", self.template_name.replace('.', "_"), instances.len());
         let clusters = build_clusters(&self, instances);
         let triggers = build_triggers(instances, self.connexions);
         let components = build_components(self.components);
@@ -364,25 +388,29 @@ impl ExecutedTemplate {
         let mut local_id = 0;
         let mut dag_local_id = 1;
         for (name, lengths) in self.outputs {
-            let signal = Signal { name, lengths, local_id, dag_local_id, xtype: Output};
+            let tags = self.declared_tags.remove(&name).unwrap_or_default();
+            let signal = Signal { name, lengths, local_id, dag_local_id, xtype: Output, tags };
             local_id += signal.size();
             dag_local_id += signal.size();
             instance.add_signal(signal);
         }
         for (name, lengths) in public {
-            let signal = Signal { name, lengths, local_id, dag_local_id, xtype: Input};
+            let tags = self.declared_tags.remove(&name).unwrap_or_default();
+            let signal = Signal { name, lengths, local_id, dag_local_id, xtype: Input, tags };
             local_id += signal.size();
             dag_local_id += signal.size();
             instance.add_signal(signal);
         }
         for (name, lengths) in not_public {
-            let signal = Signal { name, lengths, local_id, dag_local_id, xtype: Input};
+            let tags = self.declared_tags.remove(&name).unwrap_or_default();
+            let signal = Signal { name, lengths, local_id, dag_local_id, xtype: Input, tags };
             local_id += signal.size();
             dag_local_id += signal.size();
             instance.add_signal(signal);
         }
         for (name, lengths) in self.intermediates {
-            let signal = Signal { name, lengths, local_id, dag_local_id, xtype: Intermediate};
+            let tags = self.declared_tags.remove(&name).unwrap_or_default();
+            let signal = Signal { name, lengths, local_id, dag_local_id, xtype: Intermediate, tags };
             local_id += signal.size();
             dag_local_id += signal.size();
             instance.add_signal(signal);
@@ -394,6 +422,7 @@ impl ExecutedTemplate {
 
 struct SignalConfig<'a> {
     is_public: bool,
+    is_kept: bool,
     signal_type: usize,
     dimensions: &'a [usize],
 }
@@ -403,6 +432,7 @@ struct State {
 }
 fn generate_symbols(dag: &mut DAG, state: State, config: &SignalConfig) {
     if state.dim == config.dimensions.len() {
+        let name = state.name.clone();
         if config.signal_type == 0 {
             dag.add_input(state.name, config.is_public);
         } else if config.signal_type == 1 {
@@ -410,6 +440,9 @@ fn generate_symbols(dag: &mut DAG, state: State, config: &SignalConfig) {
         } else if config.signal_type == 2 {
             dag.add_intermediate(state.name);
         }
+        if config.is_kept {
+            dag.add_kept_signal(&name);
+        }
     } else {
         let mut index = 0;
         while index < config.dimensions[state.dim] {
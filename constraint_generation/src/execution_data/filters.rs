@@ -89,21 +89,18 @@ pub fn apply_computed(stmt: &mut Statement, analysis: &Analysis) {
             apply_computed_expr(rhe, analysis);
         }
         LogCall { args, .. } => {
-            for arglog in args {
-                if let LogArgument::LogExp(arg) = arglog{
-                    *arg = computed_or_original(analysis, arg);
-                    apply_computed_expr(arg, analysis);
-                }
-            }
+            apply_computed_log_arguments(args, analysis);
         }
-        Assert { arg, .. } => {
+        Assert { arg, message, .. } => {
             *arg = computed_or_original(analysis, arg);
             apply_computed_expr(arg, analysis);
+            apply_computed_log_arguments(message, analysis);
         }
         UnderscoreSubstitution {  rhe, .. } => {
             *rhe = computed_or_original(analysis, rhe);
             apply_computed_expr(rhe, analysis);
         },
+        Break { .. } | Continue { .. } => {}
     }
 }
 
@@ -148,6 +145,15 @@ fn computed_or_original(analysis: &Analysis, expr: &Expression) -> Expression {
     }
 }
 
+fn apply_computed_log_arguments(args: &mut [LogArgument], analysis: &Analysis) {
+    for arglog in args {
+        for arg in arglog.expressions_mut() {
+            *arg = computed_or_original(analysis, arg);
+            apply_computed_expr(arg, analysis);
+        }
+    }
+}
+
 fn apply_computed_expr(expr: &mut Expression, analysis: &Analysis) {
     use Expression::*;
     match expr {
@@ -9,21 +9,29 @@ mod execution_data;
 use ansi_term::Colour;
 use circom_algebra::algebra::{ArithmeticError, ArithmeticExpression};
 use compiler::hir::very_concrete_program::VCP;
+use constraint_list::statistics::SimplificationStatistics;
 use constraint_list::ConstraintList;
 use constraint_writers::ConstraintExporter;
 use dag::DAG;
 use execution_data::executed_program::ExportResult;
 use execution_data::ExecutedProgram;
 use program_structure::ast::{self};
+use program_structure::budget::Budgets;
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::{Report, ReportCollection};
-use program_structure::file_definition::FileID;
+use program_structure::file_definition::{FileID, FileLibrary};
 use program_structure::program_archive::ProgramArchive;
 use std::rc::Rc;
 
 pub struct BuildConfig {
     pub no_rounds: usize,
-    pub flag_json_sub: bool,
+    pub keep: Vec<String>,
+    pub memory_limit: Option<usize>,
+    // the budgets of the manifest
+    pub budgets: Budgets,
+    pub json_substitutions: Option<String>,
+    pub certificate: Option<String>,
+    pub simplification_stats: Option<String>,
     pub flag_s: bool,
     pub flag_f: bool,
     pub flag_p: bool,
@@ -37,6 +45,8 @@ pub type ConstraintWriter = Box<dyn ConstraintExporter>;
 type BuildResponse = Result<(ConstraintWriter, VCP), ()>;
 pub fn build_circuit(program: ProgramArchive, config: BuildConfig) -> BuildResponse {
     let files = program.file_library.clone();
+    let (budgets, warnings) = collect_budgets(&program, &config.budgets);
+    Report::print_reports(&warnings, &files);
     let (exe, warnings) = instantiation(&program, config.flag_verbose, &config.prime).map_err(|r| {
         Report::print_reports(&r, &files);
     })?;
@@ -47,15 +57,50 @@ pub fn build_circuit(program: ProgramArchive, config: BuildConfig) -> BuildRespo
     if config.inspect_constraints {
         Report::print_reports(&warnings, &files);
     }
+    check_budgets(dag.check_budgets(&budgets, config.flag_f), &files)?;
     if config.flag_f {
         sync_dag_and_vcp(&mut vcp, &mut dag);
         Result::Ok((Box::new(dag), vcp))
     } else {
-        let list = simplification_process(&mut vcp, dag, &config);
+        let list = simplification_process(&mut vcp, dag, &config).map_err(|r| {
+            Report::print_reports(&r, &files);
+        })?;
+        check_budgets(list.check_budgets(&budgets), &files)?;
         Result::Ok((Box::new(list), vcp))
     }
 }
 
+// The budgets given with #[budget(...)] in the templates, replaced by the
+// ones of the manifest
+fn collect_budgets(program: &ProgramArchive, manifest: &Budgets) -> (Budgets, ReportCollection) {
+    let mut budgets = Budgets { circuit: manifest.circuit, ..Budgets::default() };
+    let mut warnings = ReportCollection::new();
+    for (name, template) in program.get_templates() {
+        if !template.get_budget().is_empty() {
+            budgets.templates.insert(name.clone(), *template.get_budget());
+        }
+    }
+    for (name, budget) in &manifest.templates {
+        if !program.contains_template(name) {
+            let message = format!("The manifest gives a budget to {}, which is not a template of the circuit", name);
+            warnings.push(Report::warning(message, ReportCode::ManifestError));
+            continue;
+        }
+        let budget = budgets.templates.get(name).cloned().unwrap_or_default().overridden_by(budget);
+        budgets.templates.insert(name.clone(), budget);
+    }
+    (budgets, warnings)
+}
+
+fn check_budgets(reports: ReportCollection, files: &FileLibrary) -> Result<(), ()> {
+    Report::print_reports(&reports, files);
+    if reports.iter().any(|report| report.is_error()) {
+        Result::Err(())
+    } else {
+        Result::Ok(())
+    }
+}
+
 type InstantiationResponse = Result<(ExecutedProgram, ReportCollection), ReportCollection>;
 fn instantiation(program: &ProgramArchive, flag_verbose: bool, prime: &String) -> InstantiationResponse {
     let execution_result = execute::constraint_execution(&program, flag_verbose, prime);
@@ -81,17 +126,133 @@ fn sync_dag_and_vcp(vcp: &mut VCP, dag: &mut DAG) {
     VCP::add_witness_list(vcp, Rc::clone(&witness));
 }
 
-fn simplification_process(vcp: &mut VCP, dag: DAG, config: &BuildConfig) -> ConstraintList {
+fn simplification_process(vcp: &mut VCP, dag: DAG, config: &BuildConfig) -> Result<ConstraintList, ReportCollection> {
     use dag::SimplificationFlags;
     let flags = SimplificationFlags {
         flag_s: config.flag_s,
         parallel_flag: config.flag_p,
-        port_substitution: config.flag_json_sub,
+        substitution_log: config.json_substitutions.clone(),
+        certificate: config.certificate.clone(),
         no_rounds: config.no_rounds,
         flag_old_heuristics: config.flag_old_heuristics,
         prime : config.prime.clone(),
+        keep: config.keep.clone(),
+        memory_limit: config.memory_limit,
     };
     let list = DAG::map_to_list(dag, flags);
+    if let Some(report) = list.spill_error() {
+        return Err(vec![report]);
+    }
     VCP::add_witness_list(vcp, Rc::new(list.get_witness_as_vec()));
-    list
+    print_removed_duplicates(&list);
+    if config.flag_verbose {
+        print_statistics(&list.statistics);
+    }
+    if let Some(file) = &config.simplification_stats {
+        if list.statistics.write_json(file).is_err() {
+            eprintln!("{}", Colour::Red.paint("Could not write the simplification statistics"));
+        }
+    }
+    Ok(list)
+}
+
+fn print_statistics(statistics: &SimplificationStatistics) {
+    // the templates that lost more constraints
+    const SHOWN_TEMPLATES: usize = 10;
+    println!("{}", Colour::Green.paint("simplification:"));
+    for phase in &statistics.phases {
+        println!(
+            "    {}: {} signals and {} constraints removed in {} ms",
+            phase.phase, phase.removed_signals, phase.removed_constraints, phase.time
+        );
+    }
+    let reduced = statistics.most_reduced();
+    let reduced: Vec<_> = reduced.iter().filter(|(_, template)| template.removed() > 0).collect();
+    if !reduced.is_empty() {
+        println!("{}", Colour::Green.paint("templates with more removed constraints:"));
+    }
+    for (name, template) in reduced.iter().take(SHOWN_TEMPLATES) {
+        println!(
+            "    {}: {} of {} constraints removed in {} instances",
+            name, template.removed(), template.before, template.instances
+        );
+    }
+    if statistics.without_template > 0 {
+        println!("    {} linear constraints remain combining several templates", statistics.without_template);
+    }
+}
+
+fn print_removed_duplicates(list: &ConstraintList) {
+    let total: usize = list.removed_duplicates.values().sum();
+    if total == 0 {
+        return;
+    }
+    let removed = Colour::Green.paint("duplicated constraints removed");
+    println!("{}: {}", removed, total);
+    for (template, no_removed) in &list.removed_duplicates {
+        println!("    {}: {}", template, no_removed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::execute;
+    use program_structure::constants::UsefulConstants;
+    use type_analysis::check_types::check_types;
+
+    // Whether a constant with the value of const_expr is equal to the value
+    // of function_expr computed in a function
+    fn equal_in_function(const_expr: &str, function_expr: &str) -> bool {
+        let prime = "bn128".to_string();
+        let file = std::env::temp_dir().join(format!("constant_in_function_{}.circom", std::process::id()));
+        let source = format!(
+            "pragma circom 2.0.0;\nconst C = {};\nfunction f() {{ return {}; }}\n\
+             template T() {{ assert(C == f()); }}\ncomponent main = T();\n",
+            const_expr, function_expr
+        );
+        std::fs::write(&file, source).unwrap();
+        let field = UsefulConstants::new(&prime).get_p().clone();
+        let parsed = parser::run_parser(
+            file.display().to_string(),
+            "2.1.4",
+            Vec::new(),
+            Vec::new(),
+            &parser::Defines::new(),
+            &field,
+            Vec::new(),
+        );
+        std::fs::remove_file(&file).unwrap();
+        let mut program = match parsed {
+            Ok((program, _)) => program,
+            Err(_) => panic!("{} is not a valid constant", const_expr),
+        };
+        assert!(check_types(&mut program).is_ok());
+        execute::constraint_execution(&program, false, &prime).is_ok()
+    }
+
+    #[test]
+    fn constants_are_computed_like_in_functions() {
+        let expressions = [
+            "-1 \\ 2",
+            "-7 \\ 2",
+            "-5 \\ -2",
+            "1 / 2",
+            "-7 % 3",
+            "7 % -3",
+            "2**254 % 7",
+            "-2 ** 3",
+            "3 ** -1",
+            "~0 & 255",
+            "~(-1)",
+            "1 << 300",
+            "-8 >> 1",
+            "1 >> -2",
+            "-1 < 0",
+            "-3 | 1",
+        ];
+        for expression in expressions {
+            assert!(equal_in_function(expression, expression), "{} differs from the function", expression);
+        }
+        assert!(!equal_in_function("-1 \\ 2", "0"));
+    }
 }
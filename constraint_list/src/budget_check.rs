@@ -0,0 +1,94 @@
+use super::ConstraintList;
+use program_structure::budget::{Budgets, Overrun};
+use program_structure::error_definition::ReportCollection;
+use std::collections::BTreeMap;
+
+const STAGE: &str = "after the simplification";
+
+struct Walk<'a> {
+    list: &'a ConstraintList,
+    budgets: &'a Budgets,
+    // signals of the list, sorted by their id before the simplification
+    wires: Vec<usize>,
+    // signals of each node counting the ones of its subcomponents
+    no_signals: Vec<usize>,
+    overruns: BTreeMap<String, Overrun>,
+    instances: BTreeMap<String, usize>,
+}
+
+// The signals of an instance are the ones of the list with an id in
+// (offset, offset + signals of the node], and its constraints are the ones
+// generated in it or in its subcomponents that remain
+pub fn check(list: &ConstraintList, budgets: &Budgets) -> ReportCollection {
+    let mut reports = ReportCollection::new();
+    if budgets.is_empty() {
+        return reports;
+    }
+    let encoding = &list.dag_encoding;
+    let mut no_signals = vec![0; encoding.nodes.len()];
+    for (node_id, node) in encoding.nodes.iter().enumerate() {
+        let in_subcomponents: usize = encoding.adjacency[node_id].iter().map(|edge| no_signals[edge.goes_to]).sum();
+        no_signals[node_id] = node.signals.len() + in_subcomponents;
+    }
+    let mut wires: Vec<usize> = list.signal_map.keys().cloned().filter(|signal| *signal != 0).collect();
+    wires.sort_unstable();
+    let mut walk = Walk {
+        list,
+        budgets,
+        wires,
+        no_signals,
+        overruns: BTreeMap::new(),
+        instances: BTreeMap::new(),
+    };
+    if !budgets.templates.is_empty() {
+        visit(&mut walk, encoding.init, 0, "main");
+    }
+    for (template, mut overrun) in std::mem::take(&mut walk.overruns) {
+        overrun.instances = walk.instances[&template];
+        reports.push(budgets.templates[&template].report(&overrun, STAGE, true));
+    }
+    let constraints = list.constraints.get_ids().len();
+    let signals = walk.wires.len();
+    if budgets.circuit.is_exceeded(constraints, signals) {
+        let overrun = Overrun {
+            template: None,
+            path: "main".to_string(),
+            constraints,
+            signals,
+            instances_over: 1,
+            instances: 1,
+        };
+        reports.push(budgets.circuit.report(&overrun, STAGE, true));
+    }
+    reports
+}
+
+// Returns the constraints of the instance
+fn visit(walk: &mut Walk, node_id: usize, offset: usize, path: &str) -> usize {
+    let encoding = &walk.list.dag_encoding;
+    let mut constraints = walk.list.constraints_by_instance.get(&(node_id, offset)).cloned().unwrap_or(0);
+    for edge in &encoding.adjacency[node_id] {
+        let subpath = format!("{}.{}", path, edge.path);
+        constraints += visit(walk, edge.goes_to, offset + edge.offset, &subpath);
+    }
+    let (template, budget) = match walk.budgets.of_instance(&encoding.nodes[node_id].name) {
+        Some(budgeted) => budgeted,
+        None => return constraints,
+    };
+    *walk.instances.entry(template.clone()).or_insert(0) += 1;
+    let first = walk.wires.partition_point(|signal| *signal <= offset);
+    let last = walk.wires.partition_point(|signal| *signal <= offset + walk.no_signals[node_id]);
+    let signals = last - first;
+    if budget.is_exceeded(constraints, signals) {
+        let overrun = walk.overruns.entry(template.clone()).or_insert_with(|| Overrun {
+            template: Some(template.clone()),
+            path: path.to_string(),
+            constraints,
+            signals,
+            instances_over: 0,
+            instances: 0,
+        });
+        overrun.instances_over += 1;
+    }
+    constraints
+}
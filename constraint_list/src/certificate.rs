@@ -0,0 +1,558 @@
+use crate::non_linear_simplification::{add_to_monomial, normalize, reduce, Monomial, Polynomial};
+use circom_algebra::modular_arithmetic;
+use circom_algebra::num_bigint::{BigInt, Sign};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// A linear combination of signals, the constant is signal 0
+type Combination = HashMap<usize, BigInt>;
+type Basis = BTreeMap<Monomial, Polynomial>;
+
+struct R1CS {
+    field: BigInt,
+    wires: usize,
+    public_outputs: usize,
+    public_inputs: usize,
+    constraints: Vec<[Combination; 3]>,
+    // signal of each wire
+    labels: Vec<usize>,
+}
+
+// Checks the certificate written with --certificate. The original r1cs is the
+// one produced with --O0, whose wires are the signals of the circuit, and the
+// simplified one must come from the same compilation as the certificate. The
+// check succeeds when:
+//  - the public signals are the first wires of both r1cs,
+//  - the removed signals are not wires of the simplified r1cs and each one is
+//    removed once, without depending on itself through other substitutions,
+//  - every substitution is implied by the original constraints,
+//  - every original constraint, after applying the substitutions, is a linear
+//    combination of the simplified constraints, and every simplified
+//    constraint is a linear combination of the original ones after applying
+//    the substitutions.
+// Then an assignment of the wires of the simplified r1cs satisfies it if and
+// only if, extended with the values given by the substitutions, it satisfies
+// the original constraints. Returns a summary of what was checked.
+pub fn check_certificate(original: &str, simplified: &str, certificate: &str) -> Result<String, String> {
+    let original = read_r1cs(original)?;
+    let simplified = read_r1cs(simplified)?;
+    let (field, substitutions) = read_certificate(certificate)?;
+    if original.field != field || simplified.field != field {
+        return Err("the prime of the certificate is not the one of the r1cs files".to_string());
+    }
+    if original.labels.iter().enumerate().any(|(wire, label)| wire != *label) {
+        return Err("the original r1cs must be compiled with --O0".to_string());
+    }
+    let public = 1 + simplified.public_outputs + simplified.public_inputs;
+    if original.public_outputs != simplified.public_outputs
+        || original.public_inputs != simplified.public_inputs
+        || simplified.labels.iter().take(public).enumerate().any(|(wire, label)| wire != *label)
+    {
+        return Err("the public signals are not the same in both r1cs".to_string());
+    }
+
+    // every signal becomes a variable of the simplified r1cs: its wire when
+    // it has one, otherwise a fresh variable after the wires
+    let mut variables = HashMap::new();
+    for (wire, label) in simplified.labels.iter().enumerate() {
+        if *label >= original.wires || variables.insert(*label, wire).is_some() {
+            return Err(format!("the wire {} of the simplified r1cs is not a signal of the circuit", wire));
+        }
+    }
+    for (signal, _) in &substitutions {
+        if *signal == 0 || *signal >= original.wires {
+            return Err(format!("the signal {} of the substitutions is not a signal of the circuit", signal));
+        }
+        if variables.contains_key(signal) {
+            return Err(format!("the signal {} is substituted but it is a wire of the simplified r1cs", signal));
+        }
+    }
+    let variable = |signal: usize| variables.get(&signal).cloned().unwrap_or(simplified.wires + signal);
+    let order: Vec<_> = substitutions.iter().map(|(signal, _)| *signal).collect();
+    let resolved = resolve_substitutions(substitutions, &variable, &field)?;
+    check_substitutions(&original.constraints, &order, &resolved, &variable, &field)?;
+
+    let substituted: Vec<_> = original
+        .constraints
+        .iter()
+        .map(|[a, b, c]| {
+            let substitute = |combination| substitute(combination, &resolved, &variable, &field);
+            [substitute(a), substitute(b), substitute(c)]
+        })
+        .collect();
+
+    // the constraints that are the same in both systems do not need to be
+    // expanded, the rest are checked in the groups of constraints that share
+    // monomials with them
+    let original_keys: Vec<_> = substituted.iter().map(|constraint| constraint_key(constraint, &field)).collect();
+    let simplified_keys: Vec<_> =
+        simplified.constraints.iter().map(|constraint| constraint_key(constraint, &field)).collect();
+    let in_original: HashSet<_> = original_keys.iter().collect();
+    let in_simplified: HashSet<_> = simplified_keys.iter().collect();
+    let zero = (Vec::new(), Vec::new(), Vec::new());
+    let pending_original: Vec<_> = (0..substituted.len())
+        .filter(|index| original_keys[*index] != zero && !in_simplified.contains(&original_keys[*index]))
+        .collect();
+    let pending_simplified: Vec<_> = (0..simplified.constraints.len())
+        .filter(|index| simplified_keys[*index] != zero && !in_original.contains(&simplified_keys[*index]))
+        .collect();
+    if pending_original.is_empty() && pending_simplified.is_empty() {
+        return Ok(summary(&original, &simplified, resolved.len()));
+    }
+
+    let all: Vec<_> = substituted.iter().chain(simplified.constraints.iter()).collect();
+    let groups = build_groups(&all);
+    let pending_groups: HashSet<_> = pending_original
+        .iter()
+        .map(|index| groups[*index])
+        .chain(pending_simplified.iter().map(|index| groups[substituted.len() + *index]))
+        .collect();
+    let in_pending_group = |index: &usize| pending_groups.contains(&groups[*index]);
+    let expand_all = |indexes: &mut dyn Iterator<Item = usize>| -> HashMap<usize, Polynomial> {
+        indexes.map(|index| (index, expand(all[index], &field))).collect()
+    };
+    let original_polynomials = expand_all(&mut (0..substituted.len()).filter(in_pending_group));
+    let simplified_polynomials = expand_all(&mut (substituted.len()..all.len()).filter(in_pending_group));
+
+    let basis = build_basis(simplified_polynomials.values(), &field);
+    for index in pending_original {
+        if !reduce(original_polynomials[&index].clone(), &basis, &field).is_empty() {
+            return Err(format!(
+                "the original constraint {} is not implied by the simplified constraints and the substitutions",
+                index
+            ));
+        }
+    }
+    let basis = build_basis(original_polynomials.values(), &field);
+    for index in pending_simplified {
+        if !reduce(simplified_polynomials[&(substituted.len() + index)].clone(), &basis, &field).is_empty() {
+            return Err(format!("the simplified constraint {} is not implied by the original constraints", index));
+        }
+    }
+    Ok(summary(&original, &simplified, resolved.len()))
+}
+
+fn summary(original: &R1CS, simplified: &R1CS, no_substitutions: usize) -> String {
+    format!(
+        "{} original constraints, {} substitutions and {} simplified constraints",
+        original.constraints.len(),
+        no_substitutions,
+        simplified.constraints.len()
+    )
+}
+
+// A constraint A*B - C with A and B divided by their first coefficients
+// and C by the product of both, so two constraints with the same key have
+// the same expansion up to a factor. The linear constraints only have C,
+// divided by its first coefficient.
+type Key = (Vec<(usize, BigInt)>, Vec<(usize, BigInt)>, Vec<(usize, BigInt)>);
+
+fn constraint_key([a, b, c]: &[Combination; 3], field: &BigInt) -> Key {
+    let scaled = |combination: &Combination, factor: Option<BigInt>| {
+        let mut terms: Vec<_> = combination.iter().map(|(signal, value)| (*signal, value.clone())).collect();
+        terms.sort();
+        let factor = match factor.or_else(|| terms.first().map(|(_, value)| value.clone())) {
+            Some(factor) => factor,
+            None => return (terms, BigInt::from(1)),
+        };
+        let inverse = match modular_arithmetic::div(&BigInt::from(1), &factor, field) {
+            Ok(inverse) => inverse,
+            Err(_) => unreachable!(),
+        };
+        for (_, value) in &mut terms {
+            *value = modular_arithmetic::mul(value, &inverse, field);
+        }
+        (terms, factor)
+    };
+    if a.is_empty() || b.is_empty() {
+        return (Vec::new(), Vec::new(), scaled(c, None).0);
+    }
+    let (a, alpha) = scaled(a, None);
+    let (b, beta) = scaled(b, None);
+    let (c, _) = scaled(c, Some(modular_arithmetic::mul(&alpha, &beta, field)));
+    if a <= b {
+        (a, b, c)
+    } else {
+        (b, a, c)
+    }
+}
+
+// Assigns a group to each constraint, the constraints of different groups do
+// not share monomials so a polynomial is a combination of the others only if
+// it is a combination of the ones of its group
+fn build_groups(constraints: &[&[Combination; 3]]) -> Vec<usize> {
+    fn find(parent: &mut [usize], index: usize) -> usize {
+        let mut root = index;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        let mut current = index;
+        while parent[current] != root {
+            let next = parent[current];
+            parent[current] = root;
+            current = next;
+        }
+        root
+    }
+
+    let mut parent: Vec<usize> = (0..constraints.len()).collect();
+    let mut owner: HashMap<Monomial, usize> = HashMap::new();
+    for (index, [a, b, c]) in constraints.iter().enumerate() {
+        let products = a.keys().flat_map(|s_a| b.keys().map(move |s_b| (*s_a.min(s_b), *s_a.max(s_b))));
+        for monomial in products.chain(c.keys().map(|s_c| (0, *s_c))) {
+            match owner.get(&monomial) {
+                Some(other) => {
+                    let root_0 = find(&mut parent, *other);
+                    let root_1 = find(&mut parent, index);
+                    parent[root_1] = root_0;
+                }
+                None => {
+                    owner.insert(monomial, index);
+                }
+            }
+        }
+    }
+    (0..constraints.len()).map(|index| find(&mut parent, index)).collect()
+}
+
+// Expresses each removed signal in terms of the variables that are not
+// removed, following the substitutions that appear in its expression
+fn resolve_substitutions(
+    substitutions: Vec<(usize, Combination)>,
+    variable: &dyn Fn(usize) -> usize,
+    field: &BigInt,
+) -> Result<HashMap<usize, Combination>, String> {
+    let mut pending = HashMap::new();
+    for (signal, to) in substitutions {
+        if pending.insert(signal, to).is_some() {
+            return Err(format!("the signal {} is substituted more than once", signal));
+        }
+    }
+    let mut resolved: HashMap<usize, Combination> = HashMap::new();
+    let mut visiting = HashSet::new();
+    let signals: Vec<_> = pending.keys().cloned().collect();
+    for signal in signals {
+        let mut stack = vec![(signal, false)];
+        while let Some((current, expanded)) = stack.pop() {
+            if resolved.contains_key(&current) {
+                continue;
+            }
+            if expanded {
+                let result = substitute(&pending[&current], &resolved, variable, field);
+                resolved.insert(current, result);
+            } else {
+                if !visiting.insert(current) {
+                    return Err(format!("the substitution of the signal {} depends on itself", current));
+                }
+                stack.push((current, true));
+                for other in pending[&current].keys() {
+                    if pending.contains_key(other) && !resolved.contains_key(other) {
+                        stack.push((*other, false));
+                    }
+                }
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+// Every substitution must follow from the original constraints, otherwise
+// the simplified r1cs could accept wires whose extension does not satisfy
+// them. In the order they were applied, each substitution, with its signals
+// replaced by their final expressions, must be a linear combination of the
+// original constraints with the previous substitutions applied. The linear
+// ones are kept in a basis, where the quadratic constraints are added when
+// a substitution makes them linear. Only when a substitution is not in its
+// span are the quadratic constraints expanded, as their combinations may
+// cancel the quadratic monomials.
+fn check_substitutions(
+    constraints: &[[Combination; 3]],
+    order: &[usize],
+    resolved: &HashMap<usize, Combination>,
+    variable: &dyn Fn(usize) -> usize,
+    field: &BigInt,
+) -> Result<(), String> {
+    let no_substitutions = HashMap::new();
+    let mut quadratic = Vec::new();
+    let mut linear = Vec::new();
+    for [a, b, c] in constraints {
+        let substitute = |combination| substitute(combination, &no_substitutions, variable, field);
+        let constraint = [substitute(a), substitute(b), substitute(c)];
+        let polynomial = expand(&constraint, field);
+        if is_linear(&polynomial) {
+            linear.push(polynomial);
+        } else {
+            quadratic.push(constraint);
+        }
+    }
+    let mut basis = build_basis(linear.iter(), field);
+    let mut uses: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (index, [a, b, c]) in quadratic.iter().enumerate() {
+        let variables: HashSet<_> = a.keys().chain(b.keys()).chain(c.keys()).cloned().collect();
+        for variable in variables {
+            uses.entry(variable).or_default().push(index);
+        }
+    }
+
+    for signal in order {
+        let removed = variable(*signal);
+        let to = &resolved[signal];
+        let mut polynomial = Polynomial::new();
+        add_to_monomial(&mut polynomial, (0, removed), &BigInt::from(1), field);
+        for (other, value) in to {
+            add_to_monomial(&mut polynomial, (0, *other), &modular_arithmetic::prefix_sub(value, field), field);
+        }
+        if !reduce(polynomial.clone(), &basis, field).is_empty() {
+            let mut extended = basis.clone();
+            for constraint in &quadratic {
+                insert(&mut extended, expand(constraint, field), field);
+            }
+            if !reduce(polynomial, &extended, field).is_empty() {
+                return Err(format!(
+                    "the substitution of the signal {} is not implied by the original constraints",
+                    signal
+                ));
+            }
+            // the rows with a linear pivot are linear
+            basis = extended.into_iter().filter(|((s_0, _), _)| *s_0 == 0).collect();
+        }
+        let step = HashMap::from([(removed, to.clone())]);
+        for index in uses.remove(&removed).unwrap_or_default() {
+            let was_linear = is_linear(&expand(&quadratic[index], field));
+            for combination in &mut quadratic[index] {
+                *combination = substitute(combination, &step, &|variable| variable, field);
+            }
+            let polynomial = expand(&quadratic[index], field);
+            if !was_linear && is_linear(&polynomial) {
+                insert(&mut basis, polynomial, field);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_linear(polynomial: &Polynomial) -> bool {
+    polynomial.keys().next_back().is_none_or(|(s_0, _)| *s_0 == 0)
+}
+
+fn insert(basis: &mut Basis, polynomial: Polynomial, field: &BigInt) {
+    let reduced = reduce(polynomial, basis, field);
+    if let Some(pivot) = reduced.keys().next_back().cloned() {
+        basis.insert(pivot, normalize(reduced, field));
+    }
+}
+
+// Replaces the removed signals of a combination of signals by their
+// expressions, the rest of signals by their variables
+fn substitute(
+    combination: &Combination,
+    resolved: &HashMap<usize, Combination>,
+    variable: &dyn Fn(usize) -> usize,
+    field: &BigInt,
+) -> Combination {
+    let zero = BigInt::from(0);
+    let mut result = Combination::new();
+    let mut add = |variable: usize, value: BigInt| {
+        let current = result.remove(&variable).unwrap_or_else(|| zero.clone());
+        let sum = modular_arithmetic::add(&current, &value, field);
+        if sum != zero {
+            result.insert(variable, sum);
+        }
+    };
+    for (signal, value) in combination {
+        match resolved.get(signal) {
+            Some(to) => {
+                for (other, factor) in to {
+                    add(*other, modular_arithmetic::mul(value, factor, field));
+                }
+            }
+            None => add(variable(*signal), value.clone()),
+        }
+    }
+    result
+}
+
+// Expansion of A*B - C, the products are added up before taking the
+// remainder because the substituted constraints can be long
+fn expand([a, b, c]: &[Combination; 3], field: &BigInt) -> Polynomial {
+    let mut sums: HashMap<Monomial, BigInt> = HashMap::new();
+    for (s_a, v_a) in a {
+        for (s_b, v_b) in b {
+            let monomial = if s_a <= s_b { (*s_a, *s_b) } else { (*s_b, *s_a) };
+            *sums.entry(monomial).or_default() += v_a * v_b;
+        }
+    }
+    for (s_c, v_c) in c {
+        *sums.entry((0, *s_c)).or_default() -= v_c;
+    }
+    let mut polynomial = Polynomial::new();
+    for (monomial, sum) in sums {
+        add_to_monomial(&mut polynomial, monomial, &(sum % field), field);
+    }
+    polynomial
+}
+
+// The shortest polynomials are added first, so the long ones are reduced by
+// them instead of filling them with their monomials
+fn build_basis<'a>(polynomials: impl Iterator<Item = &'a Polynomial>, field: &BigInt) -> Basis {
+    let mut order: Vec<_> = polynomials.collect();
+    order.sort_by_key(|polynomial| polynomial.len());
+    let mut basis = Basis::new();
+    for polynomial in order {
+        insert(&mut basis, polynomial.clone(), field);
+    }
+    basis
+}
+
+fn read_certificate(file: &str) -> Result<(BigInt, Vec<(usize, Combination)>), String> {
+    let invalid = || format!("{} is not a valid certificate", file);
+    let contents = std::fs::read_to_string(file).map_err(|_| format!("the file {} can not be read", file))?;
+    let certificate = json::parse(&contents).map_err(|_| invalid())?;
+    let field = certificate["prime"].as_str().and_then(|prime| BigInt::parse_bytes(prime.as_bytes(), 10));
+    let field = field.ok_or_else(invalid)?;
+    let mut substitutions = Vec::new();
+    for substitution in certificate["substitutions"].members() {
+        let signal = substitution["signal"].as_usize().ok_or_else(invalid)?;
+        let mut to = Combination::new();
+        for (key, value) in substitution["to"].entries() {
+            let key = key.parse().map_err(|_| invalid())?;
+            let value = value.as_str().and_then(|value| BigInt::parse_bytes(value.as_bytes(), 10));
+            to.insert(key, value.ok_or_else(invalid)?);
+        }
+        substitutions.push((signal, to));
+    }
+    Ok((field, substitutions))
+}
+
+fn read_r1cs(file: &str) -> Result<R1CS, String> {
+    let invalid = || format!("{} is not a valid r1cs file", file);
+    let bytes = std::fs::read(file).map_err(|_| format!("the file {} can not be read", file))?;
+    let mut reader = Reader { bytes: &bytes, position: 0 };
+    if reader.take(4) != Some(b"r1cs".as_ref()) {
+        return Err(invalid());
+    }
+    reader.number(4).ok_or_else(invalid)?;
+    let no_sections = reader.number(4).ok_or_else(invalid)?;
+    let mut sections = HashMap::new();
+    for _ in 0..no_sections {
+        let kind = reader.number(4).ok_or_else(invalid)?;
+        let size = reader.number(8).ok_or_else(invalid)?;
+        sections.insert(kind, reader.take(size).ok_or_else(invalid)?);
+    }
+    let section = |kind: usize| sections.get(&kind).map(|bytes| Reader { bytes, position: 0 });
+
+    let mut header = section(1).ok_or_else(invalid)?;
+    let field_size = header.number(4).ok_or_else(invalid)?;
+    let field = BigInt::from_bytes_le(Sign::Plus, header.take(field_size).ok_or_else(invalid)?);
+    let wires = header.number(4).ok_or_else(invalid)?;
+    let public_outputs = header.number(4).ok_or_else(invalid)?;
+    let public_inputs = header.number(4).ok_or_else(invalid)?;
+    header.number(4).ok_or_else(invalid)?;
+    header.number(8).ok_or_else(invalid)?;
+    let no_constraints = header.number(4).ok_or_else(invalid)?;
+
+    let mut body = section(2).ok_or_else(invalid)?;
+    let mut constraints = Vec::with_capacity(no_constraints);
+    for _ in 0..no_constraints {
+        let mut read_combination = || {
+            let mut combination = Combination::new();
+            for _ in 0..body.number(4)? {
+                let wire = body.number(4)?;
+                let value = BigInt::from_bytes_le(Sign::Plus, body.take(field_size)?);
+                if value != BigInt::from(0) {
+                    combination.insert(wire, value);
+                }
+            }
+            Some(combination)
+        };
+        let a = read_combination().ok_or_else(invalid)?;
+        let b = read_combination().ok_or_else(invalid)?;
+        let c = read_combination().ok_or_else(invalid)?;
+        constraints.push([a, b, c]);
+    }
+
+    let mut map = section(3).ok_or_else(invalid)?;
+    let mut labels = Vec::with_capacity(wires);
+    for _ in 0..wires {
+        labels.push(map.number(8).ok_or_else(invalid)?);
+    }
+    Ok(R1CS { field, wires, public_outputs, public_inputs, constraints, labels })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, size: usize) -> Option<&'a [u8]> {
+        let taken = self.bytes.get(self.position..self.position + size)?;
+        self.position += size;
+        Some(taken)
+    }
+
+    // little endian unsigned number of the given number of bytes
+    fn number(&mut self, size: usize) -> Option<usize> {
+        let taken = self.take(size)?;
+        Some(taken.iter().rev().fold(0, |number, byte| (number << 8) | *byte as usize))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn combination(terms: &[(usize, i64)]) -> Combination {
+        terms.iter().map(|(signal, value)| (*signal, BigInt::from(*value))).collect()
+    }
+
+    #[test]
+    fn chained_substitutions_are_resolved() {
+        let field = BigInt::from(257);
+        // 3 = 2*4 + 1, 4 = 1 + 5, where 1 and 5 are wires 1 and 2
+        let substitutions =
+            vec![(3, combination(&[(4, 2), (0, 1)])), (4, combination(&[(1, 1), (5, 1)]))];
+        let variable = |signal: usize| if signal == 5 { 2 } else { signal };
+        let resolved = resolve_substitutions(substitutions, &variable, &field).unwrap();
+        assert_eq!(resolved[&3], combination(&[(1, 2), (2, 2), (0, 1)]));
+        assert_eq!(resolved[&4], combination(&[(1, 1), (2, 1)]));
+    }
+
+    #[test]
+    fn cyclic_substitutions_are_rejected() {
+        let field = BigInt::from(257);
+        let substitutions = vec![(3, combination(&[(4, 1)])), (4, combination(&[(3, 2), (1, 1)]))];
+        assert!(resolve_substitutions(substitutions, &|signal| signal, &field).is_err());
+    }
+
+    #[test]
+    fn substitutions_must_follow_from_the_constraints() {
+        let field = BigInt::from(257);
+        let resolve = |substitutions| resolve_substitutions(substitutions, &|signal| signal, &field).unwrap();
+        // x * y = z with x, y and z the signals 1, 2 and 3: removing z with
+        // z = 0 would allow the simplified r1cs to accept any x and y
+        let product = [combination(&[(1, 1)]), combination(&[(2, 1)]), combination(&[(3, 1)])];
+        let forged = vec![(3, combination(&[(0, 0)]))];
+        let forged = resolve(forged);
+        let result = check_substitutions(std::slice::from_ref(&product), &[3], &forged, &|signal| signal, &field);
+        assert!(result.is_err());
+        // with x = 5 the product becomes linear once x is removed, so z = 5 * y
+        // follows after it but not before it
+        let constant = [combination(&[]), combination(&[]), combination(&[(1, 1), (0, -5)])];
+        let constraints = [constant, product];
+        let substitutions = vec![(1, combination(&[(0, 5)])), (3, combination(&[(2, 5)]))];
+        let resolved = resolve(substitutions);
+        assert!(check_substitutions(&constraints, &[1, 3], &resolved, &|signal| signal, &field).is_ok());
+        assert!(check_substitutions(&constraints, &[3, 1], &resolved, &|signal| signal, &field).is_err());
+    }
+
+    #[test]
+    fn scaled_constraints_have_the_same_key() {
+        let field = BigInt::from(257);
+        // (x + 2) * y = z and (2x + 4) * (3y) = 6z
+        let c_0 = [combination(&[(1, 1), (0, 2)]), combination(&[(2, 1)]), combination(&[(3, 1)])];
+        let c_1 = [combination(&[(2, 3)]), combination(&[(1, 2), (0, 4)]), combination(&[(3, 6)])];
+        assert!(constraint_key(&c_0, &field) == constraint_key(&c_1, &field));
+        let c_2 = [combination(&[(2, 3)]), combination(&[(1, 2), (0, 4)]), combination(&[(3, 5)])];
+        assert!(constraint_key(&c_0, &field) != constraint_key(&c_2, &field));
+    }
+}
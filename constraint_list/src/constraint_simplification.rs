@@ -1,27 +1,67 @@
 // Uncomment lines 163, 165, 336 and 338 to print cluster information
-use super::{ConstraintStorage, EncodingIterator, SEncoded, Simplifier, A, C, S};
+use super::{ConstraintStorage, DAGEncoding, EncodingIterator, Instance, SEncoded, Simplifier, A, C, S};
+use crate::statistics::SimplificationStatistics;
 use crate::SignalMap;
 use circom_algebra::num_bigint::BigInt;
-use constraint_writers::json_writer::SubstitutionJSON;
-use std::collections::{HashMap, HashSet, LinkedList, BTreeSet};
+use constraint_writers::json_writer::{CertificateJSON, SubstitutionJSON};
+use std::collections::{HashMap, HashSet, LinkedList, BTreeMap, BTreeSet};
 use std::sync::Arc;
 
-const SUB_LOG: &str = "./log_substitution.json";
+// Where the substitutions are written: the json log (--jsons), with the
+// names of the signals, and the certificate (--certificate), when they are
+// requested
+#[derive(Default)]
+pub struct SubstitutionLog {
+    json: Option<SubstitutionJSON>,
+    names: Vec<String>,
+    certificate: Option<CertificateJSON>,
+}
+impl SubstitutionLog {
+    pub fn new(smp: &Simplifier) -> SubstitutionLog {
+        SubstitutionLog {
+            json: smp.substitution_log.as_ref().map(|file| SubstitutionJSON::new(file).unwrap()),
+            names: if smp.substitution_log.is_some() {
+                crate::json_porting::signal_names(&smp.dag_encoding, smp.max_signal)
+            } else {
+                Vec::new()
+            },
+            certificate: smp.certificate.as_ref().map(|file| {
+                CertificateJSON::new(file, &smp.field.to_str_radix(10)).unwrap()
+            }),
+        }
+    }
+
+    pub fn end(self) {
+        if let Some(w) = self.json {
+            w.end().unwrap();
+        }
+        if let Some(w) = self.certificate {
+            w.end().unwrap();
+        }
+    }
+}
 
-fn log_substitutions(substitutions: &LinkedList<S>, writer: &mut Option<SubstitutionJSON>) {
-    use super::json_porting::port_substitution;
-    if let Some(w) = writer {
-        for s in substitutions {
+pub fn log_substitutions<'a>(
+    substitutions: impl IntoIterator<Item = &'a S>,
+    phase: &str,
+    log: &mut SubstitutionLog,
+) {
+    use super::json_porting::{port_named_substitution, port_substitution};
+    for s in substitutions {
+        if let Some(w) = &mut log.json {
+            w.write_substitution(&port_named_substitution(s, phase, &log.names)).unwrap();
+        }
+        if let Some(w) = &mut log.certificate {
             let (from, to) = port_substitution(s);
-            w.write_substitution(&from, &to).unwrap();
+            w.write_substitution(&from, phase, &to).unwrap();
         }
     }
 }
 
 #[derive(Default, Clone)]
-struct Cluster {
-    constraints: LinkedList<C>,
-    num_signals: usize
+pub struct Cluster {
+    pub constraints: LinkedList<C>,
+    pub num_signals: usize
 }
 impl Cluster {
     pub fn new(constraint: C, num_signals: usize) -> Cluster {
@@ -100,19 +140,20 @@ fn build_clusters(linear: LinkedList<C>, no_vars: usize) -> Vec<Cluster> {
     clusters
 }
 
-fn rebuild_witness(
+// is_used tells if a signal appears in the constraints that remain
+pub fn rebuild_witness(
     max_signal: usize, 
-    deleted: HashSet<usize>, 
+    is_deleted: impl Fn(usize) -> bool, 
     forbidden: &HashSet<usize>, 
-    non_linear_map: SignalToConstraints, 
+    is_used: impl Fn(usize) -> bool, 
     remove_unused: bool,
 ) -> SignalMap {
     let mut map = SignalMap::with_capacity(max_signal);
     let mut free = LinkedList::new();
     for signal in 0..max_signal {
-        if deleted.contains(&signal) {
+        if is_deleted(signal) {
             free.push_back(signal);
-        } else if remove_unused && !forbidden.contains(&signal) && !non_linear_map.contains_key(&signal){
+        } else if remove_unused && !forbidden.contains(&signal) && !is_used(signal) {
             free.push_back(signal);
         } else if let Some(new_pos) = free.pop_front() {
             map.insert(signal, new_pos);
@@ -124,7 +165,7 @@ fn rebuild_witness(
     map
 }
 
-fn eq_cluster_simplification(
+pub fn eq_cluster_simplification(
     mut cluster: Cluster,
     forbidden: &HashSet<usize>,
     field: &BigInt,
@@ -157,7 +198,7 @@ fn eq_cluster_simplification(
         let mut cons = LinkedList::new();
         let mut subs = LinkedList::new();
         let (mut remains, mut min_remains) = (BTreeSet::new(), None);
-        let (mut remove, mut min_remove) = (HashSet::new(), None);
+        let (mut remove, mut min_remove) = (BTreeSet::new(), None);
         for c in cluster.constraints {
             for signal in C::take_cloned_signals_ordered(&c) {
                 if HashSet::contains(&forbidden, &signal) {
@@ -165,7 +206,7 @@ fn eq_cluster_simplification(
                     min_remains = Some(min_remains.map_or(signal, |s| std::cmp::min(s, signal)));
                 } else {
                     min_remove = Some(min_remove.map_or(signal, |s| std::cmp::min(s, signal)));
-                    HashSet::insert(&mut remove, signal);
+                    BTreeSet::insert(&mut remove, signal);
                 }
             }
         }
@@ -175,7 +216,7 @@ fn eq_cluster_simplification(
             signal
         } else {
             let signal = min_remove.unwrap();
-            HashSet::remove(&mut remove, &signal);
+            BTreeSet::remove(&mut remove, &signal);
             signal
         };
 
@@ -201,7 +242,7 @@ fn eq_simplification(
     forbidden: Arc<HashSet<usize>>,
     no_vars: usize,
     field: &BigInt,
-    substitution_log: &mut Option<SubstitutionJSON>,
+    substitution_log: &mut SubstitutionLog,
 ) -> (LinkedList<S>, LinkedList<C>) {
     use std::sync::mpsc;
     use threadpool::ThreadPool;
@@ -216,11 +257,12 @@ fn eq_simplification(
     let mut single_clusters = 0;
     let mut id = 0;
     let mut aux_constraints = vec![LinkedList::new(); clusters.len()];
+    let mut aux_substitutions = vec![LinkedList::new(); clusters.len()];
     for cluster in clusters {
         if Cluster::size(&cluster) == 1 {
-            let (mut subs, cons) = eq_cluster_simplification(cluster, &forbidden, &field);
+            let (subs, cons) = eq_cluster_simplification(cluster, &forbidden, &field);
             aux_constraints[id] = cons;
-            LinkedList::append(&mut substitutions, &mut subs);
+            aux_substitutions[id] = subs;
             single_clusters += 1;
         } else {
             let cluster_tx = cluster_tx.clone();
@@ -240,22 +282,24 @@ fn eq_simplification(
     // println!("{} clusters were of size 1", single_clusters);
     ThreadPool::join(&pool);
     for _ in 0..(no_clusters - single_clusters) {
-        let (id, (mut subs, cons)) = simplified_rx.recv().unwrap();
+        let (id, (subs, cons)) = simplified_rx.recv().unwrap();
         aux_constraints[id] = cons;
-        LinkedList::append(&mut substitutions, &mut subs);
+        aux_substitutions[id] = subs;
     }
+    // in the order of the clusters, not in the one they are finished
     for id in 0..no_clusters {
         LinkedList::append(&mut constraints, &mut aux_constraints[id]);
+        LinkedList::append(&mut substitutions, &mut aux_substitutions[id]);
     }
-    log_substitutions(&substitutions, substitution_log);
+    log_substitutions(&substitutions, "equality", substitution_log);
     (substitutions, constraints)
 }
 
-fn constant_eq_simplification(
+pub fn constant_eq_simplification(
     c_eq: LinkedList<C>,
     forbidden: &HashSet<usize>,
     field: &BigInt,
-    substitution_log: &mut Option<SubstitutionJSON>,
+    substitution_log: &mut SubstitutionLog,
 ) -> (LinkedList<S>, LinkedList<C>) {
     let mut cons = LinkedList::new();
     let mut subs = LinkedList::new();
@@ -269,12 +313,12 @@ fn constant_eq_simplification(
             LinkedList::push_back(&mut subs, sub);
         }
     }
-    log_substitutions(&subs, substitution_log);
+    log_substitutions(&subs, "constant", substitution_log);
     (subs, cons)
 }
 
 fn linear_simplification(
-    log: &mut Option<SubstitutionJSON>,
+    log: &mut SubstitutionLog,
     linear: LinkedList<C>,
     forbidden: Arc<HashSet<usize>>,
     no_labels: usize,
@@ -289,13 +333,14 @@ fn linear_simplification(
     // println!("Cluster simplification");
     let mut cons = LinkedList::new();
     let mut substitutions = LinkedList::new();
-    let clusters = build_clusters(linear, no_labels);
+    let mut clusters = build_clusters(linear, no_labels);
+    // the biggest clusters first, so that they do not delay the end
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(Cluster::size(cluster)));
     let (cluster_tx, simplified_rx) = mpsc::channel();
     let pool = ThreadPool::new(num_cpus::get());
     let no_clusters = Vec::len(&clusters);
     // println!("Clusters: {}", no_clusters);
-    let mut id = 0;
-    for cluster in clusters {
+    for (id, cluster) in clusters.into_iter().enumerate() {
         let cluster_tx = cluster_tx.clone();
         let config = Config {
             field: field.clone(),
@@ -308,17 +353,22 @@ fn linear_simplification(
             // println!("cluster: {}", id);
             let result = full_simplification(config);
             // println!("End of cluster: {}", id);
-            cluster_tx.send(result).unwrap();
+            cluster_tx.send((id, result)).unwrap();
         };
         ThreadPool::execute(&pool, job);
-        let _ = id;
-        id += 1;
     }
     ThreadPool::join(&pool);
 
+    // the results are taken in the order of the clusters, not in the one
+    // they are finished
+    let mut results: Vec<_> = (0..no_clusters).map(|_| None).collect();
     for _ in 0..no_clusters {
-        let mut result = simplified_rx.recv().unwrap();
-        log_substitutions(&result.substitutions, log);
+        let (id, result) = simplified_rx.recv().unwrap();
+        results[id] = Some(result);
+    }
+    for result in results {
+        let mut result = result.unwrap();
+        log_substitutions(&result.substitutions, "linear", log);
         LinkedList::append(&mut cons, &mut result.constraints);
         LinkedList::append(&mut substitutions, &mut result.substitutions);
     }
@@ -438,14 +488,46 @@ fn remove_not_relevant(substitutions: &mut SEncoded, relevant: &HashSet<usize>)
     }
 }
 
-pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
+pub fn count_constraints(storage: &ConstraintStorage) -> usize {
+    storage.get_ids().into_iter().filter(|c_id| !storage.read_constraint(*c_id).unwrap().is_empty()).count()
+}
+
+// origins[c_id] is the instance where the constraint c_id was generated,
+// when it is known. Returns the number of constraints of each instance.
+pub fn count_constraints_by_template(
+    storage: &ConstraintStorage,
+    origins: &[Instance],
+    encoding: &DAGEncoding,
+    statistics: &mut SimplificationStatistics,
+) -> HashMap<Instance, usize> {
+    let mut by_instance = HashMap::new();
+    for c_id in storage.get_ids() {
+        if storage.read_constraint(c_id).unwrap().is_empty() {
+            continue;
+        }
+        match origins.get(c_id) {
+            Some(instance) => {
+                let name = &encoding.nodes[instance.0].name;
+                statistics.templates.entry(name.clone()).or_default().after += 1;
+                *by_instance.entry(*instance).or_insert(0) += 1;
+            }
+            None => statistics.without_template += 1,
+        }
+    }
+    by_instance
+}
+
+pub type SimplificationResult = (ConstraintStorage, SignalMap, BTreeMap<String, usize>, HashMap<Instance, usize>);
+pub fn simplification(smp: &mut Simplifier) -> SimplificationResult {
     use super::non_linear_utils::obtain_and_simplify_non_linear;
     use circom_algebra::simplification_utils::build_encoded_fast_substitutions;
     use circom_algebra::simplification_utils::fast_encoded_constraint_substitution;
     use std::time::SystemTime;
 
-    let mut substitution_log =
-        if smp.port_substitution { Some(SubstitutionJSON::new(SUB_LOG).unwrap()) } else { None };
+    if let Some(input) = smp.spilled.take() {
+        return crate::spilled_simplification::simplification(smp, input);
+    }
+    let mut substitution_log = SubstitutionLog::new(smp);
     let apply_linear = !smp.flag_s;
     let use_old_heuristics = smp.flag_old_heuristics;
     let field = smp.field.clone();
@@ -456,6 +538,11 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
     let mut cons_equalities = std::mem::replace(&mut smp.cons_equalities, LinkedList::new());
     let mut linear = std::mem::replace(&mut smp.linear, LinkedList::new());
     let mut deleted = HashSet::new();
+    let local_substitutions = std::mem::take(&mut smp.local_substitutions);
+    log_substitutions(&local_substitutions, "local", &mut substitution_log);
+    for substitution in local_substitutions {
+        deleted.insert(*substitution.from());
+    }
     let mut lconst = LinkedList::new();
     let mut no_rounds = smp.no_rounds;
     let remove_unused = true;
@@ -476,6 +563,7 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
     let single_substitutions = {
         // println!("Start of single assignment simplification");
         let now = SystemTime::now();
+        let no_equalities = equalities.len();
         let (subs, mut cons) = eq_simplification(
             equalities,
             Arc::clone(&forbidden),
@@ -484,6 +572,7 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
             &mut substitution_log,
         );
 
+        smp.statistics.add_phase("equality", subs.len(), no_equalities - cons.len(), now);
         LinkedList::append(&mut lconst, &mut cons);
         let mut substitutions = build_encoded_fast_substitutions(subs);
         for constraint in &mut linear {
@@ -508,8 +597,10 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
     let cons_substitutions = {
         // println!("Start of constant assignment simplification");
         let now = SystemTime::now();
+        let no_equalities = cons_equalities.len();
         let (subs, mut cons) =
             constant_eq_simplification(cons_equalities, &forbidden, &field, &mut substitution_log);
+        smp.statistics.add_phase("constant", subs.len(), no_equalities - cons.len(), now);
         LinkedList::append(&mut lconst, &mut cons);
         let substitutions = build_encoded_fast_substitutions(subs);
         for constraint in &mut linear {
@@ -538,6 +629,7 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
 
     let linear_substitutions = if apply_linear {
         let now = SystemTime::now();
+        let no_linear = linear.len();
         let (subs, mut cons) = linear_simplification(
             &mut substitution_log,
             linear,
@@ -546,6 +638,7 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
             &field,
             use_old_heuristics,
         );
+        smp.statistics.add_phase("linear", subs.len(), no_linear - cons.len(), now);
         // println!("Building substitution map");
         let now0 = SystemTime::now();
         let mut only_relevant = LinkedList::new();
@@ -572,6 +665,7 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
         HashMap::with_capacity(0)
     };
 
+    let mut origins = Vec::new();
     let (with_linear, mut constraint_storage) = {
         // println!("Building constraint storage");
         let now = SystemTime::now();
@@ -581,7 +675,8 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
         LinkedList::push_back(&mut frames, linear_substitutions);
         let iter = EncodingIterator::new(&smp.dag_encoding);
         let mut storage = ConstraintStorage::new();
-        let with_linear = obtain_and_simplify_non_linear(iter, &mut storage, &frames, &field);
+        let with_linear =
+            obtain_and_simplify_non_linear(iter, &mut storage, &mut origins, &frames, &field);
         crate::state_utils::empty_encoding_constraints(&mut smp.dag_encoding);
         let _dur = now.elapsed().unwrap().as_millis();
         // println!("Storages built in {} ms", dur);
@@ -590,7 +685,6 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
     };
 
     let mut round_id = 0;
-    let _ = round_id;
     let mut linear = with_linear;
     let mut apply_round = apply_linear && no_rounds > 0 && !linear.is_empty();
     let mut non_linear_map = if apply_round || remove_unused {
@@ -606,6 +700,7 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
     while apply_round {
         let now = SystemTime::now();
         // println!("Number of linear constraints: {}", linear.len());
+        let no_linear = linear.len();
         let (substitutions, mut constants) = linear_simplification(
             &mut substitution_log,
             linear,
@@ -615,6 +710,13 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
             use_old_heuristics,
         );
 
+        round_id += 1;
+        smp.statistics.add_phase(
+            &format!("linear round {}", round_id),
+            substitutions.len(),
+            no_linear - constants.len(),
+            now,
+        );
         for sub in &substitutions {
             deleted.insert(*sub.from());
         }
@@ -631,7 +733,6 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
             &substitutions,
             &field,
         );
-        round_id += 1;
         no_rounds -= 1;
         apply_round = !linear.is_empty() && no_rounds > 0;
         let _dur = now.elapsed().unwrap().as_millis();
@@ -677,32 +778,53 @@ pub fn simplification(smp: &mut Simplifier) -> (ConstraintStorage, SignalMap) {
         }
     }
 
-    let erased = crate::non_linear_simplification::simplify(
+    let now = SystemTime::now();
+    let removed_duplicates = crate::deduplication::remove_duplicates(
         &mut constraint_storage,
-        &forbidden,
-        &field
+        &origins,
+        &smp.dag_encoding,
+        &field,
     );
+    smp.statistics.add_phase("duplicates", 0, removed_duplicates.values().sum(), now);
 
-    for signal in erased {
-        deleted.insert(signal);
+    if apply_linear {
+        let now = SystemTime::now();
+        let no_constraints = count_constraints(&constraint_storage);
+        let substitutions = crate::non_linear_simplification::simplify(
+            &mut constraint_storage,
+            &forbidden,
+            &field
+        );
+        let removed = no_constraints - count_constraints(&constraint_storage);
+        smp.statistics.add_phase("non_linear", substitutions.len(), removed, now);
+        log_substitutions(&substitutions, "non_linear", &mut substitution_log);
+        for substitution in substitutions {
+            deleted.insert(*substitution.from());
+        }
     }
 
+    let constraints_by_instance =
+        count_constraints_by_template(&constraint_storage, &origins, &smp.dag_encoding, &mut smp.statistics);
     let _trash = constraint_storage.extract_with(&|c| C::is_empty(c));
 
     let signal_map = {
         // println!("Rebuild witness");
         let now = SystemTime::now();
-        let signal_map = rebuild_witness(max_signal, deleted, &forbidden, non_linear_map, remove_unused);
+        let signal_map = rebuild_witness(
+            max_signal,
+            |signal| deleted.contains(&signal),
+            &forbidden,
+            |signal| non_linear_map.contains_key(&signal),
+            remove_unused,
+        );
         let _dur = now.elapsed().unwrap().as_millis();
         // println!("End of rebuild witness: {} ms", dur);
         signal_map
     };
 
-    if let Some(w) = substitution_log {
-        w.end().unwrap();
-    }
+    substitution_log.end();
     // println!("NO CONSTANTS: {}", constraint_storage.no_constants());
-    (constraint_storage, signal_map)
+    (constraint_storage, signal_map, removed_duplicates, constraints_by_instance)
 }
 
 
@@ -0,0 +1,54 @@
+use super::{DAGEncoding, Instance, C};
+use circom_algebra::algebra::canonical_form;
+use circom_algebra::constraint_storage::{ConstraintID, ConstraintStorage};
+use circom_algebra::num_bigint::BigInt;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+// Origin given to the constraints that do not come directly from a template,
+// the ones that remain after the linear simplification
+const NO_TEMPLATE: &str = "<linear simplification>";
+
+// Erases the constraints that are equal to, or a multiple of, a previous one.
+// origins[c_id] is the instance where the constraint c_id was generated,
+// when it is known. Returns the number of removed constraints of
+// each template.
+pub fn remove_duplicates(
+    storage: &mut ConstraintStorage,
+    origins: &[Instance],
+    encoding: &DAGEncoding,
+    field: &BigInt,
+) -> BTreeMap<String, usize> {
+    let mut removed = BTreeMap::new();
+    // only the hashes of the canonical forms are kept, the constraints with
+    // the same hash are read again to compare them
+    let mut seen: HashMap<u64, Vec<ConstraintID>> = HashMap::new();
+    for c_id in storage.get_ids() {
+        let constraint = storage.read_constraint(c_id).unwrap();
+        if constraint.is_empty() {
+            continue;
+        }
+        let canonical = canonical_form(&constraint, field);
+        let mut hasher = DefaultHasher::new();
+        canonical.hash(&mut hasher);
+        let candidates = seen.entry(hasher.finish()).or_default();
+        let is_duplicated = candidates
+            .iter()
+            .any(|other| canonical_form(&storage.read_constraint(*other).unwrap(), field) == canonical);
+        if !is_duplicated {
+            candidates.push(c_id);
+            continue;
+        }
+        storage.replace(c_id, C::empty());
+        *removed.entry(template_of(c_id, origins, encoding)).or_insert(0) += 1;
+    }
+    removed
+}
+
+fn template_of(c_id: ConstraintID, origins: &[Instance], encoding: &DAGEncoding) -> String {
+    match origins.get(c_id) {
+        Some((node, _)) => encoding.nodes[*node].name.clone(),
+        None => NO_TEMPLATE.to_string(),
+    }
+}
@@ -1,4 +1,4 @@
-use super::{ConstraintStorage, C, S};
+use super::{ConstraintStorage, DAGEncoding, EncodingIterator, C, S};
 use crate::SignalMap;
 use circom_algebra::num_bigint::BigInt;
 use constraint_writers::debug_writer::DebugWriter;
@@ -25,13 +25,52 @@ fn hashmap_as_json(values: &HashMap<usize, BigInt>) -> JsonValue {
     correspondence
 }
 
-#[allow(unused)]
 pub fn port_substitution(sub: &S) -> (String, String) {
     let to = hashmap_as_json(sub.to()).to_string();
     let from = sub.from().to_string();
     (from, to)
 }
 
+// A line of the substitution log: the removed signal, the phase of the
+// simplification that removed it and the signals of its expression, with
+// their names as in the sym file (the constant is the signal 0, "one")
+pub fn port_named_substitution(sub: &S, phase: &str, names: &[String]) -> String {
+    let mut order: Vec<&usize> = sub.to().keys().collect();
+    order.sort();
+    let mut to = JsonValue::new_array();
+    for signal in order {
+        let term = json::object! {
+            "signal": *signal,
+            "name": names[*signal].as_str(),
+            "value": sub.to()[signal].to_str_radix(10),
+        };
+        to.push(term).unwrap();
+    }
+    let line = json::object! {
+        "phase": phase,
+        "signal": *sub.from(),
+        "name": names[*sub.from()].as_str(),
+        "to": to,
+    };
+    line.dump()
+}
+
+pub fn signal_names(encoding: &DAGEncoding, no_signals: usize) -> Vec<String> {
+    fn add_names(mut iter: EncodingIterator, names: &mut [String]) {
+        let (signals, _) = EncodingIterator::take(&mut iter);
+        for signal in signals {
+            names[signal.id] = signal.name;
+        }
+        for edge in EncodingIterator::edges(&iter) {
+            add_names(EncodingIterator::next(&iter, edge), names);
+        }
+    }
+    let mut names = vec![String::new(); no_signals];
+    names[0] = "one".to_string();
+    add_names(EncodingIterator::new(encoding), &mut names);
+    names
+}
+
 pub fn port_constraints(
     storage: &ConstraintStorage,
     map: &SignalMap,
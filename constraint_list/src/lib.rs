@@ -1,17 +1,26 @@
-use std::collections::{HashMap, HashSet, LinkedList};
+use std::collections::{BTreeMap, HashMap, HashSet, LinkedList};
 
-use circom_algebra::constraint_storage::ConstraintStorage;
+use circom_algebra::constraint_storage::{ConstraintStorage, MemoryLimit, SubstitutionStorage};
 use circom_algebra::num_bigint::BigInt;
 use constraint_writers::debug_writer::DebugWriter;
 use constraint_writers::ConstraintExporter;
+use program_structure::budget::Budgets;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::FileLibrary;
 
+mod budget_check;
 mod constraint_simplification;
+mod deduplication;
 mod json_porting;
 mod non_linear_utils;
 mod r1cs_porting;
 mod state_utils;
 mod sym_porting;
 mod non_linear_simplification;
+mod spilled_simplification;
+pub mod certificate;
+pub mod statistics;
 
 type C = circom_algebra::algebra::Constraint<usize>;
 type S = circom_algebra::algebra::Substitution<usize>;
@@ -19,6 +28,9 @@ type A = circom_algebra::algebra::ArithmeticExpression<usize>;
 type SignalMap = HashMap<usize, usize>;
 type SEncoded = HashMap<usize, A>;
 type SFrames = LinkedList<SEncoded>;
+// An instance of a node of the encoding, given by the node and the offset of
+// its signals
+pub type Instance = (usize, usize);
 
 pub struct SignalInfo {
     pub name: String,
@@ -107,6 +119,30 @@ impl<'a> EncodingIterator<'a> {
     }
 }
 
+// The input of the simplification kept in storages that are spilled to disk
+pub struct SpilledInput {
+    pub memory: MemoryLimit,
+    pub cons_equalities: ConstraintStorage,
+    pub equalities: ConstraintStorage,
+    pub linear: ConstraintStorage,
+    pub local_substitutions: SubstitutionStorage,
+}
+
+impl SpilledInput {
+    // The storages may keep half of the limit in memory, the other half is
+    // for the clusters that are being simplified
+    pub fn new(limit: usize) -> SpilledInput {
+        let memory = MemoryLimit::new(limit / 2);
+        SpilledInput {
+            cons_equalities: ConstraintStorage::with_memory_limit(&memory),
+            equalities: ConstraintStorage::with_memory_limit(&memory),
+            linear: ConstraintStorage::with_memory_limit(&memory),
+            local_substitutions: SubstitutionStorage::new(&memory),
+            memory,
+        }
+    }
+}
+
 pub struct Simplifier {
     pub field: BigInt,
     pub dag_encoding: DAGEncoding,
@@ -117,6 +153,9 @@ pub struct Simplifier {
     pub cons_equalities: LinkedList<C>,
     pub equalities: LinkedList<C>,
     pub linear: LinkedList<C>,
+    // Substitutions of the intermediate signals removed in each template
+    // before the simplification, only given when they are logged
+    pub local_substitutions: LinkedList<S>,
     //  Signals in [witness_len, Vec::len(&signal_map)) are the ones deleted
     pub max_signal: usize,
     // Flags
@@ -124,11 +163,19 @@ pub struct Simplifier {
     pub parallel_flag: bool,
     pub flag_s: bool,
     pub flag_old_heuristics: bool,
-    pub port_substitution: bool,
+    // Given when the simplification has a memory limit, the constraints and
+    // the local substitutions are there instead of in the lists
+    pub spilled: Option<SpilledInput>,
+    pub substitution_log: Option<String>,
+    pub certificate: Option<String>,
+    // Filled by the phases of the simplification, it may come with the ones
+    // done before
+    pub statistics: statistics::SimplificationStatistics,
 }
 impl Simplifier {
     pub fn simplify_constraints(mut self) -> ConstraintList {
-        let (portable, map) = constraint_simplification::simplification(&mut self);
+        let (portable, map, removed_duplicates, constraints_by_instance) =
+            constraint_simplification::simplification(&mut self);
         ConstraintList {
             field: self.field,
             dag_encoding: self.dag_encoding,
@@ -138,6 +185,9 @@ impl Simplifier {
             no_labels: self.max_signal,
             constraints: portable,
             signal_map: map,
+            removed_duplicates,
+            constraints_by_instance,
+            statistics: self.statistics,
         }
     }
 
@@ -160,15 +210,23 @@ pub struct ConstraintList {
     pub no_labels: usize,
     //  Signals in [witness_len, Vec::len(&signal_map)) are the ones deleted
     pub signal_map: SignalMap,
+    // Number of duplicated constraints removed from each template
+    pub removed_duplicates: BTreeMap<String, usize>,
+    // Number of constraints generated in each instance that remain, the
+    // linear ones that remain after the simplification are not counted
+    pub constraints_by_instance: HashMap<Instance, usize>,
+    pub statistics: statistics::SimplificationStatistics,
 }
 
 impl ConstraintExporter for ConstraintList {
     fn r1cs(&self, out: &str, custom_gates: bool) -> Result<(), ()> {
-        r1cs_porting::port_r1cs(self, out, custom_gates)
+        r1cs_porting::port_r1cs(self, out, custom_gates)?;
+        self.check_spilled_constraints()
     }
 
     fn json_constraints(&self, writer: &DebugWriter) -> Result<(), ()> {
-        json_porting::port_constraints(&self.constraints, &self.signal_map, writer)
+        json_porting::port_constraints(&self.constraints, &self.signal_map, writer)?;
+        self.check_spilled_constraints()
     }
 
     fn sym(&self, out: &str) -> Result<(), ()> {
@@ -196,4 +254,26 @@ impl ConstraintList {
     pub fn no_wires(&self) -> usize {
         self.signal_map.len()
     }
+
+    pub fn check_budgets(&self, budgets: &Budgets) -> ReportCollection {
+        budget_check::check(self, budgets)
+    }
+
+    // The first error found when the constraints were spilled to disk or
+    // read from it with a memory limit, the constraints are not complete
+    pub fn spill_error(&self) -> Option<Report> {
+        let error = self.constraints.spill_error()?;
+        let message = format!("The simplification with a memory limit failed, {}", error);
+        Some(Report::error(message, ReportCode::FileOs))
+    }
+
+    fn check_spilled_constraints(&self) -> Result<(), ()> {
+        match self.spill_error() {
+            Some(report) => {
+                Report::print_reports(&[report], &FileLibrary::new());
+                Err(())
+            }
+            None => Ok(()),
+        }
+    }
 }
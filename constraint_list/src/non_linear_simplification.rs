@@ -1,12 +1,385 @@
+use super::{C, S};
+use circom_algebra::constraint_storage::{ConstraintID, ConstraintStorage};
+use circom_algebra::algebra::ArithmeticExpression;
+use circom_algebra::modular_arithmetic;
 use circom_algebra::num_bigint::BigInt;
-use circom_algebra::constraint_storage::ConstraintStorage;
-use std::collections::{HashSet, LinkedList};
+use std::collections::{BTreeMap, HashMap, HashSet, LinkedList};
 
+// A constraint A*B - C = 0 is compared with the others through its expanded
+// polynomial, whose monomials are pairs of signals (the constant is signal 0).
+// The monomials of the linear part, (0, s), come before the quadratic ones.
+pub(crate) type Monomial = (usize, usize);
+pub(crate) type Polynomial = BTreeMap<Monomial, BigInt>;
+type SignalToConstraints = HashMap<usize, Vec<ConstraintID>>;
 
+// Constraints whose expansion has more monomials are left as they are
+const MAX_MONOMIALS: usize = 1024;
+// Groups of constraints with more members are only checked for duplicates
+const MAX_GROUP: usize = 64;
+
+// Applied after the linear simplification: the constants that are still
+// present are propagated, the constraints implied by the others are erased
+// and the quadratic constraints that become linear, either by the constants
+// or by combining them with others, are used to remove one of their signals.
+// Forbidden signals are never removed. Returns the substitutions of the
+// removed signals.
 pub fn simplify(
-    _storage: &mut ConstraintStorage,
-    _forbidden: &HashSet<usize>,
-    _field: &BigInt
-) -> LinkedList<usize> {
-    LinkedList::new()
+    storage: &mut ConstraintStorage,
+    forbidden: &HashSet<usize>,
+    field: &BigInt
+) -> LinkedList<S> {
+    let mut substitutions = LinkedList::new();
+    propagate_linear(storage, forbidden, LinkedList::new(), &mut substitutions, field);
+    loop {
+        let became_linear = remove_implied(storage, field);
+        if became_linear.is_empty()
+            || propagate_linear(storage, forbidden, became_linear, &mut substitutions, field) == 0
+        {
+            break;
+        }
+    }
+    substitutions
+}
+
+// Removes a signal with each constant equality and each constraint in
+// became_linear, returns the number of removed signals
+fn propagate_linear(
+    storage: &mut ConstraintStorage,
+    forbidden: &HashSet<usize>,
+    mut pending: LinkedList<ConstraintID>,
+    substitutions: &mut LinkedList<S>,
+    field: &BigInt,
+) -> usize {
+    let mut map = SignalToConstraints::new();
+    for c_id in storage.get_ids() {
+        let constraint = storage.read_constraint(c_id).unwrap();
+        for signal in constraint.take_cloned_signals() {
+            map.entry(signal).or_default().push(c_id);
+        }
+        if constraint.is_constant_equality() {
+            pending.push_back(c_id);
+        }
+    }
+
+    let mut removed = 0;
+    while let Some(c_id) = pending.pop_front() {
+        let constraint = storage.read_constraint(c_id).unwrap();
+        if !C::is_linear(&constraint) {
+            continue;
+        }
+        let signal = match signal_to_remove(&constraint, forbidden, &map) {
+            Some(signal) => signal,
+            None => continue,
+        };
+        let substitution = C::clear_signal_from_linear(constraint, &signal, field);
+        storage.replace(c_id, C::empty());
+        for previous in substitutions.iter_mut() {
+            S::apply_substitution(previous, &substitution, field);
+            S::rmv_zero_coefficients(previous);
+        }
+
+        let new_signals: Vec<_> = substitution.take_cloned_signals().into_iter().collect();
+        for other in map.remove(&signal).unwrap_or_default() {
+            if other == c_id {
+                continue;
+            }
+            let mut constraint = storage.read_constraint(other).unwrap();
+            let was_linear = C::is_linear(&constraint);
+            C::apply_substitution(&mut constraint, &substitution, field);
+            C::fix_constraint(&mut constraint, field);
+            if (!was_linear && C::is_linear(&constraint)) || constraint.is_constant_equality() {
+                pending.push_back(other);
+            }
+            storage.replace(other, constraint);
+            for new_signal in &new_signals {
+                map.entry(*new_signal).or_default().push(other);
+            }
+        }
+        substitutions.push_back(substitution);
+        removed += 1;
+    }
+    removed
+}
+
+// The signal that appears in less constraints is the one removed
+fn signal_to_remove(
+    constraint: &C,
+    forbidden: &HashSet<usize>,
+    map: &SignalToConstraints,
+) -> Option<usize> {
+    let mut candidate: Option<(usize, usize)> = None;
+    for signal in constraint.take_cloned_signals_ordered() {
+        if forbidden.contains(&signal) {
+            continue;
+        }
+        let uses = map.get(&signal).map_or(0, |list| list.len());
+        if candidate.is_none_or(|(_, best)| uses <= best) {
+            candidate = Some((signal, uses));
+        }
+    }
+    candidate.map(|(signal, _)| signal)
+}
+
+// Returns the quadratic constraints that were replaced by a linear one. The
+// constraints of each group are read when the group is checked.
+fn remove_implied(storage: &mut ConstraintStorage, field: &BigInt) -> LinkedList<ConstraintID> {
+    let mut c_ids = Vec::new();
+    let mut keys = Vec::new();
+    for c_id in storage.get_ids() {
+        let constraint = storage.read_constraint(c_id).unwrap();
+        if !constraint.is_empty() && constraint.a().len() * constraint.b().len() <= MAX_MONOMIALS {
+            keys.push(group_keys(&constraint));
+            c_ids.push(c_id);
+        }
+    }
+
+    let mut became_linear = LinkedList::new();
+    for group in build_groups(&keys) {
+        if group.len() < 2 {
+            continue;
+        }
+        let polynomials: Vec<_> = group
+            .into_iter()
+            .map(|index| (c_ids[index], expand(&storage.read_constraint(c_ids[index]).unwrap(), field)))
+            .collect();
+        if polynomials.len() > MAX_GROUP {
+            // duplicates and scalar multiples have the same normalized expansion
+            let mut seen = HashSet::new();
+            for (c_id, polynomial) in polynomials {
+                if !seen.insert(normalize(polynomial, field)) {
+                    storage.replace(c_id, C::empty());
+                }
+            }
+            continue;
+        }
+        let mut basis = BTreeMap::new();
+        for (c_id, polynomial) in polynomials {
+            let reduced = reduce(polynomial.clone(), &basis, field);
+            let pivot = match reduced.keys().next_back() {
+                Some(pivot) => *pivot,
+                None => {
+                    storage.replace(c_id, C::empty());
+                    continue;
+                }
+            };
+            if is_linear(&reduced) && !is_linear(&polynomial) {
+                storage.replace(c_id, into_linear_constraint(&reduced, field));
+                became_linear.push_back(c_id);
+            }
+            basis.insert(pivot, normalize(reduced, field));
+        }
+    }
+    became_linear
+}
+
+// A constraint can only be implied by the ones that share quadratic
+// monomials with it, the linear ones are grouped by their signals
+fn group_keys(constraint: &C) -> Vec<Monomial> {
+    let constant = C::constant_coefficient();
+    let mut keys = Vec::new();
+    for s_a in constraint.a().keys().filter(|s| **s != constant) {
+        for s_b in constraint.b().keys().filter(|s| **s != constant) {
+            keys.push(if s_a <= s_b { (*s_a, *s_b) } else { (*s_b, *s_a) });
+        }
+    }
+    if keys.is_empty() {
+        keys = constraint.c().keys().filter(|s| **s != constant).map(|s| (0, *s)).collect();
+    }
+    keys
+}
+
+fn is_linear(polynomial: &Polynomial) -> bool {
+    polynomial.keys().next_back().is_none_or(|(s_0, _)| *s_0 == 0)
+}
+
+fn into_linear_constraint(polynomial: &Polynomial, field: &BigInt) -> C {
+    let mut coefficients = HashMap::new();
+    coefficients.insert(C::constant_coefficient(), BigInt::from(0));
+    for ((_, signal), value) in polynomial {
+        coefficients.insert(*signal, value.clone());
+    }
+    let expression = ArithmeticExpression::Linear { coefficients };
+    let mut constraint = ArithmeticExpression::transform_expression_to_constraint_form(expression, field).unwrap();
+    C::fix_constraint(&mut constraint, field);
+    constraint
+}
+
+fn build_groups(keys: &[Vec<Monomial>]) -> Vec<Vec<usize>> {
+    fn find(parent: &mut [usize], index: usize) -> usize {
+        let mut root = index;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        let mut current = index;
+        while parent[current] != root {
+            let next = parent[current];
+            parent[current] = root;
+            current = next;
+        }
+        root
+    }
+
+    let mut parent: Vec<usize> = (0..keys.len()).collect();
+    let mut owner: HashMap<Monomial, usize> = HashMap::new();
+    for (index, monomials) in keys.iter().enumerate() {
+        for monomial in monomials {
+            if let Some(other) = owner.get(monomial) {
+                let root_0 = find(&mut parent, *other);
+                let root_1 = find(&mut parent, index);
+                parent[root_1] = root_0;
+            } else {
+                owner.insert(*monomial, index);
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for index in 0..keys.len() {
+        let root = find(&mut parent, index);
+        groups.entry(root).or_default().push(index);
+    }
+    groups.into_values().collect()
+}
+
+// Expansion of A*B - C
+fn expand(constraint: &C, field: &BigInt) -> Polynomial {
+    let mut polynomial = Polynomial::new();
+    for (s_a, v_a) in constraint.a() {
+        for (s_b, v_b) in constraint.b() {
+            let monomial = if s_a <= s_b { (*s_a, *s_b) } else { (*s_b, *s_a) };
+            let value = modular_arithmetic::mul(v_a, v_b, field);
+            add_to_monomial(&mut polynomial, monomial, &value, field);
+        }
+    }
+    for (s_c, v_c) in constraint.c() {
+        let value = modular_arithmetic::prefix_sub(v_c, field);
+        add_to_monomial(&mut polynomial, (0, *s_c), &value, field);
+    }
+    polynomial
+}
+
+pub(crate) fn add_to_monomial(polynomial: &mut Polynomial, monomial: Monomial, value: &BigInt, field: &BigInt) {
+    let zero = BigInt::from(0);
+    let current = polynomial.remove(&monomial).unwrap_or_else(|| zero.clone());
+    let result = modular_arithmetic::add(&current, value, field);
+    if result != zero {
+        polynomial.insert(monomial, result);
+    }
+}
+
+// Divides by the last coefficient so it becomes 1
+pub(crate) fn normalize(mut polynomial: Polynomial, field: &BigInt) -> Polynomial {
+    let last = match polynomial.values().next_back() {
+        Some(value) => value.clone(),
+        None => return polynomial,
+    };
+    let inverse = match modular_arithmetic::div(&BigInt::from(1), &last, field) {
+        Ok(inverse) => inverse,
+        Err(_) => unreachable!(),
+    };
+    for value in polynomial.values_mut() {
+        *value = modular_arithmetic::mul(value, &inverse, field);
+    }
+    polynomial
+}
+
+// Every row of the basis ends with its pivot and has it with coefficient 1,
+// so removing a pivot only changes the monomials that come before it. The
+// quadratic monomials are removed first: when all of them cancel the result
+// is a linear consequence of the group.
+pub(crate) fn reduce(mut polynomial: Polynomial, basis: &BTreeMap<Monomial, Polynomial>, field: &BigInt) -> Polynomial {
+    let mut next = polynomial.keys().rev().find(|monomial| basis.contains_key(monomial)).cloned();
+    while let Some(pivot) = next {
+        let factor = polynomial.get(&pivot).unwrap().clone();
+        for (monomial, value) in &basis[&pivot] {
+            let value = modular_arithmetic::mul(value, &factor, field);
+            add_to_monomial(&mut polynomial, *monomial, &modular_arithmetic::prefix_sub(&value, field), field);
+        }
+        next = polynomial
+            .range(..pivot)
+            .rev()
+            .map(|(monomial, _)| *monomial)
+            .find(|monomial| basis.contains_key(monomial));
+    }
+    polynomial
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use circom_algebra::algebra::ArithmeticExpression;
+    const FIELD: &str = "257";
+    type A = ArithmeticExpression<usize>;
+
+    // a*b = c
+    fn constraint(a: &[(usize, i64)], b: &[(usize, i64)], c: &[(usize, i64)], field: &BigInt) -> C {
+        let raw = |values: &[(usize, i64)], sign: i64| {
+            let mut raw: HashMap<_, _> = values.iter().map(|(s, v)| (*s, BigInt::from(sign * *v))).collect();
+            raw.entry(0).or_insert_with(|| BigInt::from(0));
+            raw
+        };
+        let expr = A::Quadratic { a: raw(a, 1), b: raw(b, 1), c: raw(c, -1) };
+        let mut constraint = A::transform_expression_to_constraint_form(expr, field).unwrap();
+        C::fix_constraint(&mut constraint, field);
+        constraint
+    }
+
+    fn field() -> BigInt {
+        BigInt::parse_bytes(FIELD.as_bytes(), 10).unwrap()
+    }
+
+    #[test]
+    fn non_linear_removes_multiples_and_combinations() {
+        let field = field();
+        let mut storage = ConstraintStorage::new();
+        // x*y = z, (2x)*y = 2z, x*(y+1) = z + x and (x+1)*y = z + y
+        storage.add_constraint(constraint(&[(1, 1)], &[(2, 1)], &[(3, 1)], &field));
+        storage.add_constraint(constraint(&[(1, 2)], &[(2, 1)], &[(3, 2)], &field));
+        storage.add_constraint(constraint(&[(1, 1)], &[(2, 1), (0, 1)], &[(3, 1), (1, 1)], &field));
+        // x*x = w and x*x + x*y = w + z, implied by the first one
+        storage.add_constraint(constraint(&[(1, 1)], &[(1, 1)], &[(4, 1)], &field));
+        storage.add_constraint(constraint(&[(1, 1)], &[(1, 1), (2, 1)], &[(4, 1), (3, 1)], &field));
+        let forbidden: HashSet<_> = (1..5).collect();
+        let substitutions = simplify(&mut storage, &forbidden, &field);
+        assert!(substitutions.is_empty());
+        let removed = storage.extract_with(&|c| c.is_empty());
+        assert_eq!(removed.len(), 3);
+        assert_eq!(storage.get_ids().len(), 2);
+    }
+
+    #[test]
+    fn non_linear_removes_common_products() {
+        let field = field();
+        let mut storage = ConstraintStorage::new();
+        // x*y = z, x*y = w and (x+1)*y = v, with w and v not forbidden
+        storage.add_constraint(constraint(&[(1, 1)], &[(2, 1)], &[(3, 1)], &field));
+        storage.add_constraint(constraint(&[(1, 1)], &[(2, 1)], &[(4, 1)], &field));
+        storage.add_constraint(constraint(&[(1, 1), (0, 1)], &[(2, 1)], &[(5, 1)], &field));
+        let forbidden: HashSet<_> = (1..4).collect();
+        let substitutions = simplify(&mut storage, &forbidden, &field);
+        let removed: Vec<_> = substitutions.iter().map(|s| *s.from()).collect();
+        assert_eq!(removed, vec![4, 5]);
+        storage.extract_with(&|c| c.is_empty());
+        assert_eq!(storage.get_ids().len(), 1);
+    }
+
+    #[test]
+    fn non_linear_propagates_constants() {
+        let field = field();
+        let mut storage = ConstraintStorage::new();
+        // x = 1, x*y = z and y*y = w, with y not forbidden
+        storage.add_constraint(constraint(&[], &[], &[(1, 1), (0, -1)], &field));
+        storage.add_constraint(constraint(&[(1, 1)], &[(2, 1)], &[(3, 1)], &field));
+        storage.add_constraint(constraint(&[(2, 1)], &[(2, 1)], &[(4, 1)], &field));
+        let forbidden: HashSet<_> = vec![3, 4].into_iter().collect();
+        let substitutions = simplify(&mut storage, &forbidden, &field);
+        let removed: Vec<_> = substitutions.iter().map(|s| *s.from()).collect();
+        assert_eq!(removed, vec![1, 2]);
+        storage.extract_with(&|c| c.is_empty());
+        // only z*z = w remains
+        assert_eq!(storage.get_ids().len(), 1);
+        let remaining = storage.read_constraint(0).unwrap();
+        assert!(!C::is_linear(&remaining));
+        assert!(remaining.take_cloned_signals().contains(&3));
+    }
 }
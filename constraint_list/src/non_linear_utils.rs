@@ -1,4 +1,4 @@
-use super::{ConstraintStorage, EncodingIterator, SFrames, C};
+use super::{ConstraintStorage, EncodingIterator, Instance, SFrames, C};
 use circom_algebra::num_bigint::BigInt;
 use circom_algebra::simplification_utils::fast_encoded_constraint_substitution;
 use std::collections::LinkedList;
@@ -6,6 +6,7 @@ use std::collections::LinkedList;
 pub fn obtain_and_simplify_non_linear(
     mut iter: EncodingIterator,
     storage: &mut ConstraintStorage,
+    origins: &mut Vec<Instance>,
     frames: &SFrames,
     field: &BigInt,
 ) -> LinkedList<C> {
@@ -20,11 +21,12 @@ pub fn obtain_and_simplify_non_linear(
             linear.push_back(constraint);
         } else {
             storage.add_constraint(constraint);
+            origins.push((iter.node_id, iter.offset));
         }
     }
     for edge in EncodingIterator::edges(&iter) {
         let next = EncodingIterator::next(&iter, edge);
-        let mut linear_in_next = obtain_and_simplify_non_linear(next, storage, frames, field);
+        let mut linear_in_next = obtain_and_simplify_non_linear(next, storage, origins, frames, field);
         linear.append(&mut linear_in_next);
     }
     linear
@@ -0,0 +1,598 @@
+// Simplification with a memory limit. The constraints and the substitutions
+// are kept in storages that are spilled to disk, the clusters are built with
+// the ids of their constraints and read when they are simplified, in batches
+// that fit in the limit, and the sets of signals are bitsets. The phases are
+// the ones of constraint_simplification::simplification, only the order of
+// the constraints that become linear in the rounds may change, and each of
+// them is counted once in the statistics of the rounds.
+use super::constraint_simplification::{
+    constant_eq_simplification, count_constraints, count_constraints_by_template, eq_cluster_simplification,
+    log_substitutions, rebuild_witness, Cluster, SimplificationResult, SubstitutionLog,
+};
+use super::{EncodingIterator, Instance, Simplifier, SpilledInput, C, S};
+use circom_algebra::constraint_storage::{ConstraintID, ConstraintStorage, SubstitutionStorage};
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::simplification_utils::{full_simplification, Config};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+type Frame = SubstitutionStorage;
+
+// Estimation of the memory used by a term of a constraint that is being
+// simplified, with its coefficient and the structures of the simplification
+const TERM_BYTES: usize = 128;
+// Number of constant equalities simplified at once
+const CONSTANT_BATCH: usize = 1024;
+
+struct SignalSet {
+    bits: Vec<u64>,
+}
+
+impl SignalSet {
+    fn new(no_signals: usize) -> SignalSet {
+        SignalSet { bits: vec![0; no_signals / 64 + 1] }
+    }
+
+    fn insert(&mut self, signal: usize) {
+        if signal / 64 >= self.bits.len() {
+            self.bits.resize(signal / 64 + 1, 0);
+        }
+        self.bits[signal / 64] |= 1 << (signal % 64);
+    }
+
+    fn contains(&self, signal: usize) -> bool {
+        self.bits.get(signal / 64).is_some_and(|bits| bits & (1 << (signal % 64)) != 0)
+    }
+}
+
+// The clusters of the constraints that share signals, given by the ids of
+// their constraints in increasing order. As in build_clusters, they are
+// sorted by their last constraint and the empty constraints are left out.
+struct Clusters {
+    members: Vec<ConstraintID>,
+    // members[starts[i]..starts[i + 1]] are the constraints of the cluster i
+    starts: Vec<usize>,
+    num_signals: Vec<usize>,
+    terms: Vec<usize>,
+}
+
+impl Clusters {
+    fn build(storage: &ConstraintStorage, no_signals: usize) -> Clusters {
+        fn find(parent: &mut [usize], index: usize) -> usize {
+            let mut root = index;
+            while parent[root] != root {
+                root = parent[root];
+            }
+            let mut current = index;
+            while parent[current] != root {
+                let next = parent[current];
+                parent[current] = root;
+                current = next;
+            }
+            root
+        }
+
+        // the root of each cluster is its last constraint
+        let no_constraints = storage.get_ids().len();
+        let mut parent: Vec<_> = (0..no_constraints).collect();
+        let mut num_signals = vec![0; no_constraints];
+        let mut terms = vec![0; no_constraints];
+        let mut in_cluster = vec![false; no_constraints];
+        let mut signal_to_constraint = vec![usize::MAX; no_signals];
+        for c_id in storage.get_ids() {
+            let constraint = storage.read_constraint(c_id).unwrap();
+            if constraint.is_empty() {
+                continue;
+            }
+            let signals = C::take_cloned_signals(&constraint);
+            in_cluster[c_id] = true;
+            num_signals[c_id] = signals.len();
+            terms[c_id] = constraint.a().len() + constraint.b().len() + constraint.c().len();
+            for signal in signals {
+                let previous = std::mem::replace(&mut signal_to_constraint[signal], c_id);
+                if previous != usize::MAX {
+                    let root = find(&mut parent, previous);
+                    if root != c_id {
+                        parent[root] = c_id;
+                        num_signals[c_id] += num_signals[root];
+                        terms[c_id] += terms[root];
+                    }
+                    num_signals[c_id] -= 1;
+                }
+            }
+        }
+
+        // the constraints are placed by their root, position[root] is where
+        // the next constraint of its cluster goes
+        let mut position = vec![0; no_constraints];
+        for c_id in (0..no_constraints).filter(|c_id| in_cluster[*c_id]) {
+            let root = find(&mut parent, c_id);
+            position[root] += 1;
+        }
+        let mut clusters =
+            Clusters { members: Vec::new(), starts: Vec::new(), num_signals: Vec::new(), terms: Vec::new() };
+        let mut next = 0;
+        for root in 0..no_constraints {
+            if position[root] > 0 {
+                clusters.starts.push(next);
+                clusters.num_signals.push(num_signals[root]);
+                clusters.terms.push(terms[root]);
+                next += std::mem::replace(&mut position[root], next);
+            }
+        }
+        clusters.starts.push(next);
+        clusters.members = vec![0; next];
+        for c_id in (0..no_constraints).filter(|c_id| in_cluster[*c_id]) {
+            let root = parent[c_id];
+            clusters.members[position[root]] = c_id;
+            position[root] += 1;
+        }
+        clusters
+    }
+
+    fn len(&self) -> usize {
+        self.num_signals.len()
+    }
+
+    fn size(&self, cluster: usize) -> usize {
+        self.starts[cluster + 1] - self.starts[cluster]
+    }
+
+    fn bytes(&self, cluster: usize) -> usize {
+        self.terms[cluster] * TERM_BYTES
+    }
+
+    fn read(&self, cluster: usize, storage: &ConstraintStorage) -> Cluster {
+        let members = &self.members[self.starts[cluster]..self.starts[cluster + 1]];
+        let constraints = members.iter().map(|c_id| storage.read_constraint(*c_id).unwrap()).collect();
+        Cluster { constraints, num_signals: self.num_signals[cluster] }
+    }
+}
+
+// Simplifies the clusters in the given order with the threadpool. They are
+// read in batches whose constraints take about the limit, a cluster bigger
+// than it goes alone, and the results are processed in the same order.
+fn simplify_clusters<R, F>(
+    storage: &ConstraintStorage,
+    clusters: &Clusters,
+    order: &[usize],
+    limit: usize,
+    simplify: F,
+    mut process: impl FnMut(R),
+) where
+    R: Send + 'static,
+    F: Fn(Cluster) -> R + Send + Sync + 'static,
+{
+    use std::sync::mpsc;
+    use threadpool::ThreadPool;
+    let simplify = Arc::new(simplify);
+    let pool = ThreadPool::new(num_cpus::get());
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start;
+        let mut bytes = 0;
+        while end < order.len() && (end == start || bytes + clusters.bytes(order[end]) <= limit) {
+            bytes += clusters.bytes(order[end]);
+            end += 1;
+        }
+        let (cluster_tx, simplified_rx) = mpsc::channel();
+        for (id, cluster) in order[start..end].iter().enumerate() {
+            let cluster = clusters.read(*cluster, storage);
+            let cluster_tx = cluster_tx.clone();
+            let simplify = Arc::clone(&simplify);
+            ThreadPool::execute(&pool, move || {
+                cluster_tx.send((id, simplify(cluster))).unwrap();
+            });
+        }
+        ThreadPool::join(&pool);
+        let mut results: Vec<_> = (start..end).map(|_| None).collect();
+        for _ in start..end {
+            let (id, result) = simplified_rx.recv().unwrap();
+            results[id] = Some(result);
+        }
+        for result in results {
+            process(result.unwrap());
+        }
+        start = end;
+    }
+}
+
+// Applies the frame to the constraints of the storage, like
+// fast_encoded_constraint_substitution
+fn apply_frame(storage: &mut ConstraintStorage, frame: &Frame, field: &BigInt) {
+    if frame.is_empty() {
+        return;
+    }
+    for c_id in storage.get_ids() {
+        let mut constraint = storage.read_constraint(c_id).unwrap();
+        if frame.apply_to(&mut constraint, field) {
+            C::fix_constraint(&mut constraint, field);
+            storage.replace(c_id, constraint);
+        }
+    }
+}
+
+fn renamed_to(substitution: &S) -> Option<usize> {
+    let mut to = substitution.to().iter();
+    match (to.next(), to.next()) {
+        (Some((signal, value)), None) if *signal != C::constant_coefficient() && *value == BigInt::from(1) => {
+            Some(*signal)
+        }
+        _ => None,
+    }
+}
+
+fn build_relevant_set(
+    mut iter: EncodingIterator,
+    relevant: &mut SignalSet,
+    renames: Option<&Frame>,
+    deletes: Option<&Frame>,
+) {
+    let (_, non_linear) = EncodingIterator::take(&mut iter);
+    for c in non_linear {
+        for signal in C::take_cloned_signals(&c) {
+            let renamed = renames.and_then(|renames| renames.get(signal)).and_then(|s| renamed_to(&s));
+            let signal = renamed.unwrap_or(signal);
+            if !deletes.is_some_and(|deletes| deletes.contains(signal)) {
+                relevant.insert(signal);
+            }
+        }
+    }
+
+    for edge in EncodingIterator::edges(&iter) {
+        let next = EncodingIterator::next(&iter, edge);
+        build_relevant_set(next, relevant, renames, deletes)
+    }
+}
+
+// Like obtain_and_simplify_non_linear, the signals of the non-linear
+// constraints are marked as used
+fn obtain_and_simplify_non_linear(
+    mut iter: EncodingIterator,
+    storage: &mut ConstraintStorage,
+    linear: &mut ConstraintStorage,
+    origins: &mut Vec<Instance>,
+    used: &mut SignalSet,
+    frames: &[&Frame],
+    field: &BigInt,
+) {
+    let (_, non_linear) = EncodingIterator::take(&mut iter);
+    for mut constraint in non_linear {
+        for frame in frames {
+            frame.apply_to(&mut constraint, field);
+        }
+        C::fix_constraint(&mut constraint, field);
+        if C::is_linear(&constraint) {
+            linear.add_constraint(constraint);
+        } else {
+            for signal in C::take_cloned_signals(&constraint) {
+                used.insert(signal);
+            }
+            storage.add_constraint(constraint);
+            origins.push((iter.node_id, iter.offset));
+        }
+    }
+    for edge in EncodingIterator::edges(&iter) {
+        let next = EncodingIterator::next(&iter, edge);
+        obtain_and_simplify_non_linear(next, storage, linear, origins, used, frames, field);
+    }
+}
+
+struct LinearPhase<'a> {
+    forbidden: &'a Arc<HashSet<usize>>,
+    no_labels: usize,
+    field: &'a BigInt,
+    use_old_heuristics: bool,
+    limit: usize,
+}
+
+// Like linear_simplification, the biggest clusters go first. The constraints
+// that remain are added to lconst and the substitutions are processed in the
+// order of the clusters. Returns the number of substitutions and of remaining
+// constraints.
+fn linear_simplification(
+    phase: &LinearPhase,
+    linear: &ConstraintStorage,
+    lconst: &mut ConstraintStorage,
+    log: &mut SubstitutionLog,
+    mut process: impl FnMut(S),
+) -> (usize, usize) {
+    let clusters = Clusters::build(linear, phase.no_labels);
+    let mut order: Vec<_> = (0..clusters.len()).collect();
+    order.sort_by_key(|cluster| std::cmp::Reverse(clusters.size(*cluster)));
+    let forbidden = Arc::clone(phase.forbidden);
+    let field = phase.field.clone();
+    let use_old_heuristics = phase.use_old_heuristics;
+    let simplify = move |cluster: Cluster| {
+        full_simplification(Config {
+            field: field.clone(),
+            constraints: cluster.constraints,
+            forbidden: Arc::clone(&forbidden),
+            num_signals: cluster.num_signals,
+            use_old_heuristics,
+        })
+    };
+    let (mut no_substitutions, mut no_constraints) = (0, 0);
+    simplify_clusters(linear, &clusters, &order, phase.limit, simplify, |result| {
+        log_substitutions(&result.substitutions, "linear", log);
+        no_constraints += result.constraints.len();
+        no_substitutions += result.substitutions.len();
+        for constraint in result.constraints {
+            lconst.add_constraint(constraint);
+        }
+        for substitution in result.substitutions {
+            process(substitution);
+        }
+    });
+    (no_substitutions, no_constraints)
+}
+
+pub fn simplification(smp: &mut Simplifier, input: SpilledInput) -> SimplificationResult {
+    let SpilledInput { memory, mut cons_equalities, equalities, mut linear, local_substitutions } = input;
+    let mut substitution_log = SubstitutionLog::new(smp);
+    let apply_linear = !smp.flag_s;
+    let field = smp.field.clone();
+    let forbidden = Arc::new(std::mem::take(&mut smp.forbidden));
+    let no_labels = Simplifier::no_labels(smp);
+    let max_signal = smp.max_signal;
+    let mut no_rounds = smp.no_rounds;
+    let remove_unused = true;
+    let linear_phase = LinearPhase {
+        forbidden: &forbidden,
+        no_labels,
+        field: &field,
+        use_old_heuristics: smp.flag_old_heuristics,
+        limit: memory.limit(),
+    };
+    let mut deleted = SignalSet::new(max_signal);
+    local_substitutions.for_each_in_order(|substitution| {
+        log_substitutions(std::iter::once(&substitution), "local", &mut substitution_log);
+        deleted.insert(*substitution.from());
+    });
+    drop(local_substitutions);
+    let mut lconst = ConstraintStorage::with_memory_limit(&memory);
+
+    let relevant_signals = {
+        let mut relevant = SignalSet::new(max_signal);
+        build_relevant_set(EncodingIterator::new(&smp.dag_encoding), &mut relevant, None, None);
+        relevant
+    };
+
+    let single_substitutions = {
+        let now = SystemTime::now();
+        let no_equalities = equalities.get_ids().len();
+        let clusters = Clusters::build(&equalities, no_labels);
+        let order: Vec<_> = (0..clusters.len()).collect();
+        let (eq_forbidden, eq_field) = (Arc::clone(&forbidden), field.clone());
+        let simplify = move |cluster| eq_cluster_simplification(cluster, &eq_forbidden, &eq_field);
+        let mut substitutions = Frame::new(&memory);
+        let (mut no_substitutions, mut no_constraints) = (0, 0);
+        simplify_clusters(&equalities, &clusters, &order, memory.limit(), simplify, |(subs, cons)| {
+            log_substitutions(&subs, "equality", &mut substitution_log);
+            no_substitutions += subs.len();
+            no_constraints += cons.len();
+            for substitution in subs {
+                deleted.insert(*substitution.from());
+                substitutions.add(&substitution);
+            }
+            for constraint in cons {
+                lconst.add_constraint(constraint);
+            }
+        });
+        drop(equalities);
+        smp.statistics.add_phase("equality", no_substitutions, no_equalities - no_constraints, now);
+        apply_frame(&mut linear, &substitutions, &field);
+        apply_frame(&mut cons_equalities, &substitutions, &field);
+        substitutions.retain(|signal| relevant_signals.contains(signal));
+        substitutions
+    };
+
+    let cons_substitutions = {
+        let now = SystemTime::now();
+        let no_equalities = cons_equalities.get_ids().len();
+        let mut substitutions = Frame::new(&memory);
+        let (mut no_substitutions, mut no_constraints) = (0, 0);
+        let ids: Vec<_> = cons_equalities.get_ids().collect();
+        for batch in ids.chunks(CONSTANT_BATCH) {
+            let batch = batch.iter().map(|c_id| cons_equalities.read_constraint(*c_id).unwrap()).collect();
+            let (subs, cons) = constant_eq_simplification(batch, &forbidden, &field, &mut substitution_log);
+            no_substitutions += subs.len();
+            no_constraints += cons.len();
+            for substitution in subs {
+                deleted.insert(*substitution.from());
+                substitutions.add(&substitution);
+            }
+            for constraint in cons {
+                lconst.add_constraint(constraint);
+            }
+        }
+        drop(cons_equalities);
+        smp.statistics.add_phase("constant", no_substitutions, no_equalities - no_constraints, now);
+        apply_frame(&mut linear, &substitutions, &field);
+        substitutions
+    };
+
+    let relevant_signals = {
+        let mut relevant = SignalSet::new(max_signal);
+        let iter = EncodingIterator::new(&smp.dag_encoding);
+        build_relevant_set(iter, &mut relevant, Some(&single_substitutions), Some(&cons_substitutions));
+        relevant
+    };
+
+    let mut linear_substitutions = Frame::new(&memory);
+    if apply_linear {
+        let now = SystemTime::now();
+        let no_linear = linear.get_ids().len();
+        let (no_substitutions, no_constraints) =
+            linear_simplification(&linear_phase, &linear, &mut lconst, &mut substitution_log, |substitution| {
+                deleted.insert(*substitution.from());
+                if relevant_signals.contains(*substitution.from()) {
+                    linear_substitutions.add(&substitution);
+                }
+            });
+        smp.statistics.add_phase("linear", no_substitutions, no_linear - no_constraints, now);
+        apply_frame(&mut lconst, &linear_substitutions, &field);
+    } else {
+        for c_id in linear.get_ids() {
+            lconst.add_constraint(linear.read_constraint(c_id).unwrap());
+        }
+    }
+    drop(relevant_signals);
+
+    let mut origins = Vec::new();
+    let mut used = SignalSet::new(max_signal);
+    let mut constraint_storage = ConstraintStorage::with_memory_limit(&memory);
+    let mut linear = {
+        let mut with_linear = ConstraintStorage::with_memory_limit(&memory);
+        let frames = [&single_substitutions, &cons_substitutions, &linear_substitutions];
+        obtain_and_simplify_non_linear(
+            EncodingIterator::new(&smp.dag_encoding),
+            &mut constraint_storage,
+            &mut with_linear,
+            &mut origins,
+            &mut used,
+            &frames,
+            &field,
+        );
+        crate::state_utils::empty_encoding_constraints(&mut smp.dag_encoding);
+        no_rounds -= 1;
+        with_linear
+    };
+    drop((single_substitutions, cons_substitutions, linear_substitutions));
+
+    let mut round_id = 0;
+    let mut apply_round = apply_linear && no_rounds > 0 && !linear.get_ids().is_empty();
+    while apply_round {
+        let now = SystemTime::now();
+        let no_linear = linear.get_ids().len();
+        let mut substitutions = Frame::new(&memory);
+        let (no_substitutions, no_constraints) =
+            linear_simplification(&linear_phase, &linear, &mut lconst, &mut substitution_log, |substitution| {
+                deleted.insert(*substitution.from());
+                // the signals that replace a used signal are used
+                if used.contains(*substitution.from()) {
+                    for signal in substitution.to().keys() {
+                        used.insert(*signal);
+                    }
+                }
+                substitutions.add(&substitution);
+            });
+        round_id += 1;
+        smp.statistics.add_phase(
+            &format!("linear round {}", round_id),
+            no_substitutions,
+            no_linear - no_constraints,
+            now,
+        );
+        apply_frame(&mut lconst, &substitutions, &field);
+        linear = ConstraintStorage::with_memory_limit(&memory);
+        for c_id in constraint_storage.get_ids() {
+            let mut constraint = constraint_storage.read_constraint(c_id).unwrap();
+            if substitutions.apply_to(&mut constraint, &field) {
+                C::fix_constraint(&mut constraint, &field);
+                if C::is_linear(&constraint) {
+                    linear.add_constraint(constraint);
+                    constraint_storage.replace(c_id, C::empty());
+                } else {
+                    constraint_storage.replace(c_id, constraint);
+                }
+            }
+        }
+        no_rounds -= 1;
+        apply_round = !linear.get_ids().is_empty() && no_rounds > 0;
+    }
+
+    for c_id in linear.get_ids() {
+        let constraint = linear.read_constraint(c_id).unwrap();
+        for signal in C::take_cloned_signals(&constraint) {
+            used.insert(signal);
+        }
+        constraint_storage.add_constraint(constraint);
+    }
+    drop(linear);
+    for c_id in lconst.get_ids() {
+        let mut constraint = lconst.read_constraint(c_id).unwrap();
+        C::fix_constraint(&mut constraint, &field);
+        for signal in C::take_cloned_signals(&constraint) {
+            used.insert(signal);
+        }
+        constraint_storage.add_constraint(constraint);
+    }
+    drop(lconst);
+
+    let now = SystemTime::now();
+    let removed_duplicates = crate::deduplication::remove_duplicates(
+        &mut constraint_storage,
+        &origins,
+        &smp.dag_encoding,
+        &field,
+    );
+    smp.statistics.add_phase("duplicates", 0, removed_duplicates.values().sum(), now);
+
+    if apply_linear {
+        let now = SystemTime::now();
+        let no_constraints = count_constraints(&constraint_storage);
+        let substitutions =
+            crate::non_linear_simplification::simplify(&mut constraint_storage, &forbidden, &field);
+        let removed = no_constraints - count_constraints(&constraint_storage);
+        smp.statistics.add_phase("non_linear", substitutions.len(), removed, now);
+        log_substitutions(&substitutions, "non_linear", &mut substitution_log);
+        for substitution in substitutions {
+            deleted.insert(*substitution.from());
+        }
+    }
+
+    let constraints_by_instance =
+        count_constraints_by_template(&constraint_storage, &origins, &smp.dag_encoding, &mut smp.statistics);
+    let _trash = constraint_storage.extract_with(&|c| C::is_empty(c));
+    let signal_map = rebuild_witness(
+        max_signal,
+        |signal| deleted.contains(signal),
+        &forbidden,
+        |signal| used.contains(signal),
+        remove_unused,
+    );
+    substitution_log.end();
+    (constraint_storage, signal_map, removed_duplicates, constraints_by_instance)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use circom_algebra::algebra::ArithmeticExpression;
+    use circom_algebra::constraint_storage::MemoryLimit;
+    use std::collections::HashMap;
+    type A = ArithmeticExpression<usize>;
+
+    // the sum of the signals is 0
+    fn constraint(signals: &[usize], field: &BigInt) -> C {
+        let mut coefficients: HashMap<_, _> = signals.iter().map(|s| (*s, BigInt::from(1))).collect();
+        coefficients.insert(C::constant_coefficient(), BigInt::from(0));
+        A::transform_expression_to_constraint_form(A::Linear { coefficients }, field).unwrap()
+    }
+
+    #[test]
+    fn clusters_by_their_last_constraint() {
+        let field = BigInt::from(257);
+        let memory = MemoryLimit::new(1024);
+        let mut storage = ConstraintStorage::with_memory_limit(&memory);
+        for signals in [vec![1, 2], vec![3, 4], vec![2, 5], vec![4, 6, 3], vec![7, 8]] {
+            storage.add_constraint(constraint(&signals, &field));
+        }
+        storage.replace(3, C::empty());
+        storage.add_constraint(constraint(&[4, 6, 3], &field));
+        let clusters = Clusters::build(&storage, 9);
+        assert_eq!(clusters.len(), 3);
+        assert_eq!(clusters.members, vec![0, 2, 4, 1, 5]);
+        assert_eq!(clusters.num_signals, vec![3, 2, 3]);
+
+        // each cluster goes alone when none fits in the limit
+        let mut simplified = Vec::new();
+        let order = [1, 0, 2];
+        let simplify = |cluster: Cluster| (cluster.constraints.len(), cluster.num_signals);
+        simplify_clusters(&storage, &clusters, &order, 0, simplify, |result| simplified.push(result));
+        assert_eq!(simplified, vec![(1, 2), (2, 3), (2, 3)]);
+        assert!(memory.error().is_none());
+    }
+}
@@ -0,0 +1,90 @@
+use json::JsonValue;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::time::SystemTime;
+
+// What a phase of the simplification removed and the milliseconds it took
+pub struct PhaseStatistics {
+    pub phase: String,
+    pub removed_signals: usize,
+    pub removed_constraints: usize,
+    pub time: u128,
+}
+
+// Constraints of all the instances of a template before the simplification
+// and the ones that remain with the template still known. The linear
+// constraints that remain are combinations of several templates, they are
+// counted in SimplificationStatistics::without_template.
+#[derive(Default)]
+pub struct TemplateStatistics {
+    pub instances: usize,
+    pub before: usize,
+    pub after: usize,
+}
+
+impl TemplateStatistics {
+    pub fn removed(&self) -> usize {
+        self.before.saturating_sub(self.after)
+    }
+}
+
+#[derive(Default)]
+pub struct SimplificationStatistics {
+    pub phases: Vec<PhaseStatistics>,
+    pub templates: BTreeMap<String, TemplateStatistics>,
+    pub without_template: usize,
+}
+
+impl SimplificationStatistics {
+    pub fn add_phase(&mut self, phase: &str, removed_signals: usize, removed_constraints: usize, start: SystemTime) {
+        self.phases.push(PhaseStatistics {
+            phase: phase.to_string(),
+            removed_signals,
+            removed_constraints,
+            time: start.elapsed().map_or(0, |time| time.as_millis()),
+        });
+    }
+
+    // The templates that lost more constraints first
+    pub fn most_reduced(&self) -> Vec<(&String, &TemplateStatistics)> {
+        let mut templates: Vec<_> = self.templates.iter().collect();
+        templates.sort_by_key(|(_, template)| std::cmp::Reverse(template.removed()));
+        templates
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let mut phases = JsonValue::new_array();
+        for phase in &self.phases {
+            let value = json::object! {
+                "phase": phase.phase.as_str(),
+                "removed_signals": phase.removed_signals,
+                "removed_constraints": phase.removed_constraints,
+                "time_ms": phase.time as u64,
+            };
+            phases.push(value).unwrap();
+        }
+        let mut templates = JsonValue::new_array();
+        for (name, template) in self.most_reduced() {
+            let value = json::object! {
+                "template": name.as_str(),
+                "instances": template.instances,
+                "constraints_before": template.before,
+                "constraints_after": template.after,
+                "removed_constraints": template.removed(),
+            };
+            templates.push(value).unwrap();
+        }
+        json::object! {
+            "phases": phases,
+            "templates": templates,
+            "constraints_without_template": self.without_template,
+        }
+    }
+
+    pub fn write_json(&self, file: &str) -> Result<(), ()> {
+        let mut file = File::create(file).map_err(|_err| {})?;
+        file.write_all(self.to_json().pretty(4).as_bytes()).map_err(|_err| {})?;
+        file.write_all(b"\n").map_err(|_err| {})
+    }
+}
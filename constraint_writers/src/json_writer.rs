@@ -65,36 +65,51 @@ impl SignalsJSON {
     }
 }
 
+// JSON Lines: one object per substitution, written as they are found
 pub struct SubstitutionJSON {
     writer_substitutions: BufWriter<File>,
-    first: bool,
 }
 impl SubstitutionJSON {
     pub fn new(file: &str) -> Result<SubstitutionJSON, ()> {
-        let first = true;
         let file_substitutions = File::create(file).map_err(|_err| {})?;
-        let mut writer_substitutions = BufWriter::new(file_substitutions);
-        writer_substitutions.write_all(b"{").map_err(|_err| {})?;
-        writer_substitutions.flush().map_err(|_err| {})?;
-        writer_substitutions.write_all(b"\n\"substitution\": {").map_err(|_err| {})?;
-        writer_substitutions.flush().map_err(|_err| {})?;
-        Result::Ok(SubstitutionJSON { writer_substitutions, first })
+        let writer_substitutions = BufWriter::new(file_substitutions);
+        Result::Ok(SubstitutionJSON { writer_substitutions })
+    }
+    pub fn write_substitution(&mut self, substitution: &str) -> Result<(), ()> {
+        self.writer_substitutions.write_all(substitution.as_bytes()).map_err(|_err| {})?;
+        self.writer_substitutions.write_all(b"\n").map_err(|_err| {})
+    }
+    pub fn end(mut self) -> Result<(), ()> {
+        self.writer_substitutions.flush().map_err(|_err| {})
+    }
+}
+
+// Substitutions applied by the simplification, in the order they were found
+pub struct CertificateJSON {
+    writer_certificate: BufWriter<File>,
+    first: bool,
+}
+impl CertificateJSON {
+    pub fn new(file: &str, prime: &str) -> Result<CertificateJSON, ()> {
+        let file_certificate = File::create(file).map_err(|_err| {})?;
+        let mut writer_certificate = BufWriter::new(file_certificate);
+        let header = format!("{{\n\"prime\": \"{}\",\n\"substitutions\": [", prime);
+        writer_certificate.write_all(header.as_bytes()).map_err(|_err| {})?;
+        Result::Ok(CertificateJSON { writer_certificate, first: true })
     }
-    pub fn write_substitution(&mut self, signal: &str, substitution: &str) -> Result<(), ()> {
+    pub fn write_substitution(&mut self, signal: &str, phase: &str, substitution: &str) -> Result<(), ()> {
         if self.first {
             self.first = false;
-            self.writer_substitutions.write_all(b"\n").map_err(|_err| {})?;
+            self.writer_certificate.write_all(b"\n").map_err(|_err| {})?;
         } else {
-            self.writer_substitutions.write_all(b",\n").map_err(|_err| {})?;
+            self.writer_certificate.write_all(b",\n").map_err(|_err| {})?;
         }
-        let substitution = format!("\"{}\" : {}", signal, substitution);
-        self.writer_substitutions.flush().map_err(|_err| {})?;
-        self.writer_substitutions.write_all(substitution.as_bytes()).map_err(|_err| {})?;
-        self.writer_substitutions.flush().map_err(|_err| {})?;
-        Result::Ok(())
+        let substitution =
+            format!("{{\"signal\": {}, \"phase\": \"{}\", \"to\": {}}}", signal, phase, substitution);
+        self.writer_certificate.write_all(substitution.as_bytes()).map_err(|_err| {})
     }
     pub fn end(mut self) -> Result<(), ()> {
-        self.writer_substitutions.write_all(b"\n}\n}").map_err(|_err| {})?;
-        self.writer_substitutions.flush().map_err(|_err| {})
+        self.writer_certificate.write_all(b"\n]\n}\n").map_err(|_err| {})?;
+        self.writer_certificate.flush().map_err(|_err| {})
     }
 }
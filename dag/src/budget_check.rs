@@ -0,0 +1,78 @@
+use super::statistics_porting::compute_statistics;
+use super::DAG;
+use program_structure::budget::{Budgets, Overrun};
+use program_structure::error_definition::ReportCollection;
+
+// All the instances of a node have the same constraints and signals before
+// the simplification, so the nodes are checked and each overrun is shown
+// with the first instance of the first node of the template that exceeds
+// the budget
+pub fn check(dag: &DAG, budgets: &Budgets, is_error: bool) -> ReportCollection {
+    const STAGE: &str = "after the execution";
+    let mut reports = ReportCollection::new();
+    if dag.number_of_nodes() == 0 || budgets.is_empty() {
+        return reports;
+    }
+    let statistics = compute_statistics(dag);
+    for (template, budget) in &budgets.templates {
+        let mut overrun: Option<Overrun> = None;
+        let mut instances = 0;
+        for (node_id, node) in dag.nodes.iter().enumerate() {
+            if Budgets::template_of(&node.template_name) != template {
+                continue;
+            }
+            let node_statistics = &statistics[node_id];
+            instances += node_statistics.number_of_instances;
+            if !budget.is_exceeded(node_statistics.total_constraints, node_statistics.total_signals) {
+                continue;
+            }
+            match &mut overrun {
+                Some(overrun) => overrun.instances_over += node_statistics.number_of_instances,
+                None => {
+                    overrun = Some(Overrun {
+                        template: Some(template.clone()),
+                        path: first_path(dag, node_id),
+                        constraints: node_statistics.total_constraints,
+                        signals: node_statistics.total_signals,
+                        instances_over: node_statistics.number_of_instances,
+                        instances: 0,
+                    })
+                }
+            }
+        }
+        if let Some(mut overrun) = overrun {
+            overrun.instances = instances;
+            reports.push(budget.report(&overrun, STAGE, is_error));
+        }
+    }
+    let main = &statistics[dag.main_id()];
+    if budgets.circuit.is_exceeded(main.total_constraints, main.total_signals) {
+        let overrun = Overrun {
+            template: None,
+            path: dag.get_entry().unwrap().label.clone(),
+            constraints: main.total_constraints,
+            signals: main.total_signals,
+            instances_over: 1,
+            instances: 1,
+        };
+        reports.push(budgets.circuit.report(&overrun, STAGE, is_error));
+    }
+    reports
+}
+
+// Path of the first instance of the node, following the first edge that
+// reaches it from each component
+fn first_path(dag: &DAG, target: usize) -> String {
+    let mut reaches = vec![false; dag.number_of_nodes()];
+    for node_id in 0..dag.number_of_nodes() {
+        reaches[node_id] = node_id == target || dag.adjacency[node_id].iter().any(|edge| reaches[edge.goes_to]);
+    }
+    let mut node_id = dag.main_id();
+    let mut path = dag.get_entry().unwrap().label.clone();
+    while node_id != target {
+        let edge = dag.adjacency[node_id].iter().find(|edge| reaches[edge.goes_to]).unwrap();
+        path = format!("{}.{}", path, edge.label);
+        node_id = edge.goes_to;
+    }
+    path
+}
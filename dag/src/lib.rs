@@ -1,13 +1,17 @@
+mod budget_check;
 mod constraint_correctness_analysis;
 mod json_porting;
+mod local_simplification;
 mod map_to_constraint_list;
 mod r1cs_porting;
+mod statistics_porting;
 mod sym_porting;
 mod witness_producer;
 use circom_algebra::num_bigint::BigInt;
 use constraint_list::ConstraintList;
 use constraint_writers::debug_writer::DebugWriter;
 use constraint_writers::ConstraintExporter;
+use program_structure::budget::Budgets;
 use program_structure::constants::UsefulConstants;
 use program_structure::error_definition::ReportCollection;
 use std::collections::{HashMap, HashSet};
@@ -146,6 +150,7 @@ pub struct Node {
     ordered_signals: Vec<String>,
     locals: HashSet<usize>,
     forbidden_if_main: HashSet<usize>,
+    kept: HashSet<usize>,
     io_signals: Vec<usize>,
     constraints: Vec<Constraint>,
     is_parallel: bool,
@@ -210,6 +215,12 @@ impl Node {
         self.intermediates_length += 1;
     }
 
+    fn add_kept_signal(&mut self, name: &str) {
+        if let Option::Some(id) = self.signal_correspondence.get(name) {
+            self.kept.insert(*id);
+        }
+    }
+
     fn add_constraint(&mut self, constraint: Constraint) {
         self.constraints.push(constraint)
     }
@@ -383,6 +394,12 @@ impl DAG {
         }
     }
 
+    pub fn add_kept_signal(&mut self, name: &str) {
+        if let Option::Some(node) = self.get_mut_main() {
+            node.add_kept_signal(name);
+        }
+    }
+
     pub fn add_constraint(&mut self, constraint: Constraint) {
         if let Option::Some(node) = self.get_mut_main() {
             node.add_constraint(constraint);
@@ -442,6 +459,12 @@ impl DAG {
         }
     }
 
+    // The budgets exceeded before the simplification are warnings when they
+    // are checked again after it
+    pub fn check_budgets(&self, budgets: &Budgets, is_error: bool) -> ReportCollection {
+        budget_check::check(self, budgets, is_error)
+    }
+
     pub fn generate_r1cs_output(&self, output_file: &str, custom_gates: bool) -> Result<(), ()> {
         r1cs_porting::write(self, output_file, custom_gates)
     }
@@ -511,7 +534,10 @@ pub struct SimplificationFlags {
     pub no_rounds: usize,
     pub flag_s: bool,
     pub parallel_flag: bool,
-    pub port_substitution: bool,
+    pub substitution_log: Option<String>,
+    pub certificate: Option<String>,
     pub flag_old_heuristics: bool,
     pub prime : String,
+    pub keep: Vec<String>,
+    pub memory_limit: Option<usize>,
 }
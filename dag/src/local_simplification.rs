@@ -0,0 +1,94 @@
+use super::{Constraint, Node, Substitution, DAG};
+use circom_algebra::num_bigint::BigInt;
+use circom_algebra::simplification_utils::{
+    build_encoded_fast_substitutions, fast_encoded_constraint_substitution,
+};
+use circom_algebra::sparse_elimination;
+use std::collections::{HashSet, LinkedList};
+
+// Result of the simplification of a node: the substitutions are valid for an
+// instance after applying its offset
+#[derive(Default)]
+pub struct NodeSimplification {
+    pub substitutions: Vec<Substitution>,
+    pub constraints_before: usize,
+    pub constraints_after: usize,
+}
+
+// The intermediate signals of a node only appear in the constraints of the
+// node, so they can be removed with its linear constraints once for all the
+// instances of the node. The constraints of each node are replaced by the
+// simplified ones. Signals in kept[node] are not removed.
+pub fn simplify_nodes(dag: &mut DAG, kept: &[HashSet<usize>], field: &BigInt) -> Vec<NodeSimplification> {
+    let mut simplified = Vec::with_capacity(dag.nodes.len());
+    for (node_id, node) in dag.nodes.iter_mut().enumerate() {
+        let constraints_before = count_constraints(node);
+        let substitutions = simplify_node(node, &kept[node_id], field);
+        let constraints_after = count_constraints(node);
+        simplified.push(NodeSimplification { substitutions, constraints_before, constraints_after });
+    }
+    simplified
+}
+
+// The nodes as they are, when the simplification is not applied
+pub fn unsimplified_nodes(dag: &DAG) -> Vec<NodeSimplification> {
+    let mut unsimplified = Vec::with_capacity(dag.nodes.len());
+    for node in &dag.nodes {
+        let constraints = count_constraints(node);
+        unsimplified.push(NodeSimplification {
+            substitutions: Vec::new(),
+            constraints_before: constraints,
+            constraints_after: constraints,
+        });
+    }
+    unsimplified
+}
+
+fn count_constraints(node: &Node) -> usize {
+    node.constraints.iter().filter(|c| !c.is_empty()).count()
+}
+
+fn simplify_node(node: &mut Node, kept: &HashSet<usize>, field: &BigInt) -> Vec<Substitution> {
+    if node.is_custom_gate {
+        return Vec::new();
+    }
+    let constraints = std::mem::take(&mut node.constraints);
+    let io: HashSet<_> = node.io_signals.iter().cloned().collect();
+    let is_removable = |signal: &usize| {
+        node.is_local_signal(*signal)
+            && !io.contains(signal)
+            && !node.kept.contains(signal)
+            && !kept.contains(signal)
+    };
+
+    let mut linear = LinkedList::new();
+    let mut others = Vec::new();
+    let mut forbidden = HashSet::new();
+    for constraint in constraints {
+        let signals = constraint.take_cloned_signals();
+        if Constraint::is_linear(&constraint) && signals.iter().any(is_removable) {
+            forbidden.extend(signals.into_iter().filter(|s| !is_removable(s)));
+            linear.push_back(constraint);
+        } else {
+            others.push(constraint);
+        }
+    }
+    if linear.is_empty() {
+        node.constraints = others;
+        return Vec::new();
+    }
+
+    let eliminated = sparse_elimination::eliminate(linear, &forbidden, field);
+    let substitutions: Vec<_> = eliminated.substitutions.into_values().collect();
+    let encoded = build_encoded_fast_substitutions(substitutions.iter().cloned().collect());
+    for mut constraint in others {
+        if fast_encoded_constraint_substitution(&mut constraint, &encoded, field) {
+            Constraint::fix_constraint(&mut constraint, field);
+        }
+        if !constraint.is_empty() {
+            node.constraints.push(constraint);
+        }
+    }
+    node.constraints.extend(eliminated.constraints);
+    substitutions
+}
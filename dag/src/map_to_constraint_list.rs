@@ -1,5 +1,7 @@
-use super::{Constraint, Edge, Node, SimplificationFlags, Tree, DAG};
-use constraint_list::{ConstraintList, DAGEncoding, EncodingEdge, EncodingNode, SignalInfo, Simplifier};
+use super::local_simplification::{self, NodeSimplification};
+use super::{Constraint, Edge, Node, SimplificationFlags, Substitution, Tree, DAG};
+use constraint_list::statistics::SimplificationStatistics;
+use constraint_list::{ConstraintList, DAGEncoding, EncodingEdge, EncodingNode, SignalInfo, Simplifier, SpilledInput};
 use program_structure::utils::constants::UsefulConstants;
 use std::collections::{HashSet, LinkedList};
 #[derive(Default)]
@@ -7,30 +9,95 @@ struct CHolder {
     linear: LinkedList<Constraint>,
     equalities: LinkedList<Constraint>,
     constant_equalities: LinkedList<Constraint>,
+    // substitutions of the signals removed in each node, replayed in every
+    // instance when they are logged
+    substitutions: LinkedList<Substitution>,
+    removed_signals: usize,
+    removed_constraints: usize,
+    statistics: SimplificationStatistics,
+    // with a memory limit the constraints and substitutions go here
+    spilled: Option<SpilledInput>,
+}
+
+impl CHolder {
+    fn add_linear(&mut self, constraint: &Constraint) {
+        match &mut self.spilled {
+            Some(spilled) => {
+                spilled.linear.add_constraint(constraint.clone());
+            }
+            None => self.linear.push_back(constraint.clone()),
+        }
+    }
+
+    fn add_equality(&mut self, constraint: &Constraint) {
+        match &mut self.spilled {
+            Some(spilled) => {
+                spilled.equalities.add_constraint(constraint.clone());
+            }
+            None => self.equalities.push_back(constraint.clone()),
+        }
+    }
+
+    fn add_constant_equality(&mut self, constraint: &Constraint) {
+        match &mut self.spilled {
+            Some(spilled) => {
+                spilled.cons_equalities.add_constraint(constraint.clone());
+            }
+            None => self.constant_equalities.push_back(constraint.clone()),
+        }
+    }
+
+    fn add_substitution(&mut self, substitution: Substitution) {
+        match &mut self.spilled {
+            Some(spilled) => spilled.local_substitutions.add(&substitution),
+            None => self.substitutions.push_back(substitution),
+        }
+    }
 }
 
 fn map_tree(
     tree: &Tree,
     witness: &mut Vec<usize>,
     c_holder: &mut CHolder,
-    forbidden: &mut HashSet<usize>
+    forbidden: &mut HashSet<usize>,
+    keep: &[String],
+    local: &[NodeSimplification],
+    log_local: bool,
 ) -> usize {
     let mut no_constraints = 0;
 
+    let node = &tree.dag.nodes[tree.node_id];
     for signal in &tree.signals {
         Vec::push(witness, *signal);
-        if tree.dag.nodes[tree.node_id].is_custom_gate {
+        if node.is_custom_gate || node.kept.contains(&(*signal - tree.offset)) {
             forbidden.insert(*signal);
+        } else if !keep.is_empty() {
+            let name = format!("{}.{}", tree.path, tree.id_to_name[signal]);
+            if keep.iter().any(|pattern| matches_pattern(pattern, &name)) {
+                forbidden.insert(*signal);
+            }
+        }
+    }
+
+    let simplified = &local[tree.node_id];
+    c_holder.removed_signals += simplified.substitutions.len();
+    c_holder.removed_constraints += simplified.constraints_before - simplified.constraints_after;
+    let template = c_holder.statistics.templates.entry(node.template_name.clone()).or_default();
+    template.instances += 1;
+    template.before += simplified.constraints_before;
+    if log_local {
+        for substitution in &simplified.substitutions {
+            c_holder.add_substitution(substitution.apply_offset(tree.offset));
         }
     }
 
     for constraint in &tree.constraints {
         if Constraint::is_constant_equality(constraint) {
-            LinkedList::push_back(&mut c_holder.constant_equalities, constraint.clone());
+            c_holder.add_constant_equality(constraint);
         } else if Constraint::is_equality(constraint, &tree.field) {
-            LinkedList::push_back(&mut c_holder.equalities, constraint.clone());
+            c_holder.add_equality(constraint);
         } else if Constraint::is_linear(constraint) {
-            LinkedList::push_back(&mut c_holder.linear, constraint.clone());
+            c_holder.add_linear(constraint);
         } else {
             no_constraints += 1;
         }
@@ -38,11 +105,62 @@ fn map_tree(
 
     for edge in Tree::get_edges(tree) {
         let subtree = Tree::go_to_subtree(tree, edge);
-        no_constraints += map_tree(&subtree, witness, c_holder, forbidden);
+        no_constraints += map_tree(&subtree, witness, c_holder, forbidden, keep, local, log_local);
     }
     no_constraints
 }
 
+// Patterns given with --keep: a * matches any sequence of characters, the
+// rest of the pattern is compared literally with the full name of the signal.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == name[n] {
+            p += 1;
+            n += 1;
+        } else if let Some((star, matched)) = backtrack {
+            backtrack = Some((star, matched + 1));
+            p = star + 1;
+            n = matched + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// Local signals of each node whose name matches a --keep pattern in any of
+// the instances of the node
+fn kept_by_patterns(dag: &DAG, keep: &[String]) -> Vec<HashSet<usize>> {
+    fn visit(dag: &DAG, node_id: usize, path: &str, keep: &[String], kept: &mut Vec<HashSet<usize>>) {
+        let node = &dag.nodes[node_id];
+        for (name, id) in node.correspondence() {
+            if node.is_local_signal(*id) && !kept[node_id].contains(id) {
+                let name = format!("{}.{}", path, name);
+                if keep.iter().any(|pattern| matches_pattern(pattern, &name)) {
+                    kept[node_id].insert(*id);
+                }
+            }
+        }
+        for edge in &dag.adjacency[node_id] {
+            visit(dag, edge.goes_to, &format!("{}.{}", path, edge.label), keep, kept);
+        }
+    }
+
+    let mut kept = vec![HashSet::new(); dag.nodes.len()];
+    if !keep.is_empty() {
+        let path = dag.get_entry().unwrap().label.clone();
+        visit(dag, dag.main_id(), &path, keep, &mut kept);
+    }
+    kept
+}
+
 fn produce_encoding(
     no_constraints: usize,
     init: usize,
@@ -107,7 +225,7 @@ fn map_edge_to_encoding(edge: Edge) -> EncodingEdge {
     EncodingEdge { goes_to: edge.goes_to, path: edge.label, offset: edge.in_number }
 }
 
-pub fn map(dag: DAG, flags: SimplificationFlags) -> ConstraintList {
+pub fn map(mut dag: DAG, flags: SimplificationFlags) -> ConstraintList {
     use std::time::SystemTime;
     // println!("Start of dag to list mapping");
     let now = SystemTime::now();
@@ -118,9 +236,28 @@ pub fn map(dag: DAG, flags: SimplificationFlags) -> ConstraintList {
     let no_public_outputs = dag.public_outputs();
     let no_private_inputs = dag.private_inputs();
     let mut forbidden = dag.get_main().unwrap().forbidden_if_main.clone();
-    let mut c_holder = CHolder::default();
+    let local = if flags.flag_s {
+        local_simplification::unsimplified_nodes(&dag)
+    } else {
+        let kept = kept_by_patterns(&dag, &flags.keep);
+        local_simplification::simplify_nodes(&mut dag, &kept, &field)
+    };
+    let log_local = !flags.flag_s && (flags.substitution_log.is_some() || flags.certificate.is_some());
+    let mut c_holder = CHolder { spilled: flags.memory_limit.map(SpilledInput::new), ..CHolder::default() };
     let mut signal_map = vec![0];
-    let no_constraints = map_tree(&Tree::new(&dag), &mut signal_map, &mut c_holder, &mut forbidden);
+    let no_constraints = map_tree(
+        &Tree::new(&dag),
+        &mut signal_map,
+        &mut c_holder,
+        &mut forbidden,
+        &flags.keep,
+        &local,
+        log_local,
+    );
+    if !flags.flag_s {
+        let (removed_signals, removed_constraints) = (c_holder.removed_signals, c_holder.removed_constraints);
+        c_holder.statistics.add_phase("local", removed_signals, removed_constraints, now);
+    }
     let max_signal = Vec::len(&signal_map);
     let name_encoding = produce_encoding(no_constraints, init_id, dag.nodes, dag.adjacency);
     let _dur = now.elapsed().unwrap().as_millis();
@@ -136,11 +273,15 @@ pub fn map(dag: DAG, flags: SimplificationFlags) -> ConstraintList {
         linear: c_holder.linear,
         equalities: c_holder.equalities,
         cons_equalities: c_holder.constant_equalities,
+        local_substitutions: c_holder.substitutions,
+        statistics: c_holder.statistics,
         no_rounds: flags.no_rounds,
         flag_s: flags.flag_s,
         parallel_flag: flags.parallel_flag,
         flag_old_heuristics: flags.flag_old_heuristics,
-        port_substitution: flags.port_substitution,
+        spilled: c_holder.spilled,
+        substitution_log: flags.substitution_log,
+        certificate: flags.certificate,
     }
     .simplify_constraints()
 }
@@ -0,0 +1,352 @@
+use crate::define_logic::{declared_names, evaluate, Defines, EvaluationError};
+use crate::expression_visitor::{visit_expression, visit_requirements, visit_statement};
+use crate::namespace_logic::Namespaces;
+use num_bigint::BigInt;
+use num_traits::{ToPrimitive, Zero};
+use program_structure::ast::{
+    Access, AssignOp, Constant, Definition, Expression, ExpressionPrefixOpcode, MainComponent, Meta, Statement,
+    VariableType,
+};
+use program_structure::ast_shortcuts::{split_declaration_into_single_nodes, Symbol};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::expression_builders::{build_array_in_line, build_number, build_prefix};
+use program_structure::file_definition::FileID;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Clone)]
+enum ConstValue {
+    Number(BigInt),
+    Array(Vec<ConstValue>),
+}
+
+impl ConstValue {
+    fn index(&self, indexes: &[usize]) -> Option<&ConstValue> {
+        match indexes.split_first() {
+            None => Some(self),
+            Some((index, rest)) => match self {
+                ConstValue::Array(values) => values.get(*index)?.index(rest),
+                ConstValue::Number(_) => None,
+            },
+        }
+    }
+
+    // None when the arrays of the same level have different lengths
+    fn dimensions(&self) -> Option<Vec<usize>> {
+        match self {
+            ConstValue::Number(_) => Some(Vec::new()),
+            ConstValue::Array(values) => {
+                let inner = match values.first() {
+                    Some(first) => first.dimensions()?,
+                    None => Vec::new(),
+                };
+                for value in values {
+                    if value.dimensions()? != inner {
+                        return None;
+                    }
+                }
+                let mut dimensions = vec![values.len()];
+                dimensions.extend(inner);
+                Some(dimensions)
+            }
+        }
+    }
+
+    fn to_expression(&self, meta: &Meta) -> Expression {
+        match self {
+            ConstValue::Number(value) if value < &BigInt::zero() => {
+                build_prefix(meta.clone(), ExpressionPrefixOpcode::Sub, build_number(meta.clone(), -value))
+            }
+            ConstValue::Number(value) => build_number(meta.clone(), value.clone()),
+            ConstValue::Array(values) => {
+                build_array_in_line(meta.clone(), values.iter().map(|value| value.to_expression(meta)).collect())
+            }
+        }
+    }
+}
+
+// Values of the top-level constants, named prefix.NAME in the namespaces of
+// the includes with an alias. They are replaced where they are used as
+// numbers or indexed with numbers, and the arrays are declared as a var in
+// the templates and functions that index them with other expressions.
+pub struct Constants {
+    values: BTreeMap<String, ConstValue>,
+    field: BigInt,
+}
+
+struct Evaluator<'a> {
+    namespaces: &'a Namespaces<'a>,
+    declarations: BTreeMap<String, (FileID, &'a Constant)>,
+    defines: &'a Defines,
+    field: &'a BigInt,
+    values: HashMap<String, ConstValue>,
+    in_progress: HashSet<String>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn constant(&mut self, name: &str) -> Result<ConstValue, ReportCollection> {
+        if let Some(value) = self.values.get(name) {
+            return Ok(value.clone());
+        }
+        let (file_id, constant) = self.declarations[name];
+        if !self.in_progress.insert(name.to_string()) {
+            let message = format!("the value of {} depends on itself", name);
+            return Err(vec![invalid_constant(name, constant.meta.location.clone(), file_id, message)]);
+        }
+        let value = self.value_of(&constant.value, file_id).map_err(|error| match error {
+            Ok((location, message)) => vec![invalid_constant(name, location, file_id, message)],
+            Err(reports) => reports,
+        })?;
+        if value.dimensions().is_none() {
+            let message = "the arrays of a level must have the same length".to_string();
+            return Err(vec![invalid_constant(name, constant.value.get_meta().location.clone(), file_id, message)]);
+        }
+        self.in_progress.remove(name);
+        self.values.insert(name.to_string(), value.clone());
+        Ok(value)
+    }
+
+    fn declared<'e>(&self, file_id: FileID, name: &str, access: &'e [Access]) -> Option<(String, &'e [Access])> {
+        self.namespaces.constant(file_id, name, access, &self.declarations)
+    }
+
+    // the error is either in this expression, written in the file, or in the
+    // value of another constant
+    fn value_of(
+        &mut self,
+        expr: &Expression,
+        file_id: FileID,
+    ) -> Result<ConstValue, Result<EvaluationError, ReportCollection>> {
+        match expr {
+            Expression::ArrayInLine { values, .. } => {
+                let values: Result<Vec<_>, _> = values.iter().map(|value| self.value_of(value, file_id)).collect();
+                Ok(ConstValue::Array(values?))
+            }
+            Expression::Variable { meta, name, access } if self.declared(file_id, name, access).is_some() => {
+                let (constant, access) = self.declared(file_id, name, access).unwrap();
+                let value = self.constant(&constant).map_err(Err)?;
+                let mut indexes = Vec::new();
+                for acc in access {
+                    match acc {
+                        Access::ArrayAccess(index) => match self.value_of(index, file_id)? {
+                            ConstValue::Number(index) => indexes.push(index.to_usize().unwrap_or(usize::MAX)),
+                            ConstValue::Array(_) => {
+                                return Err(Ok((index.get_meta().location.clone(), "the index is an array".to_string())))
+                            }
+                        },
+                        Access::ComponentAccess(_) => {
+                            return Err(Ok((meta.location.clone(), format!("{} is not a component", constant))))
+                        }
+                    }
+                }
+                match value.index(&indexes) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(Ok((meta.location.clone(), format!("the index is out of the bounds of {}", constant)))),
+                }
+            }
+            _ => {
+                let mut nested = None;
+                let (defines, field) = (self.defines, self.field);
+                let number = evaluate(expr, field, &mut |var| {
+                    let location = var.get_meta().location.clone();
+                    match var {
+                        Expression::Variable { name, access, .. } if access.is_empty() && defines.contains_key(name) => {
+                            Ok(defines[name].clone())
+                        }
+                        Expression::Variable { name, access, .. } if self.declared(file_id, name, access).is_none() => {
+                            Err((location, format!("{} is not a constant or a define", name)))
+                        }
+                        _ => match self.value_of(var, file_id) {
+                            Ok(ConstValue::Number(value)) => Ok(value),
+                            Ok(ConstValue::Array(_)) => Err((location, "an array is used as a number".to_string())),
+                            Err(Ok(error)) => Err(error),
+                            Err(Err(reports)) => {
+                                nested = Some(reports);
+                                Err((location, String::new()))
+                            }
+                        },
+                    }
+                });
+                match (number, nested) {
+                    (_, Some(reports)) => Err(Err(reports)),
+                    (Ok(number), None) => Ok(ConstValue::Number(number)),
+                    (Err(error), None) => Err(Ok(error)),
+                }
+            }
+        }
+    }
+}
+
+// A constant is declared once in each namespace, so a file loaded in two
+// namespaces declares its constants in both
+pub fn evaluate_constants(
+    constants: &[(FileID, Constant)],
+    namespaces: &Namespaces,
+    defines: &Defines,
+    field: &BigInt,
+) -> Result<Constants, ReportCollection> {
+    let mut reports = ReportCollection::new();
+    let mut declarations: BTreeMap<String, (FileID, &Constant)> = BTreeMap::new();
+    for (file_id, constant) in constants {
+        let name = namespaces.qualified_constant(*file_id, &constant.name);
+        if let Some((previous_file, previous)) = declarations.get(&name) {
+            let mut report =
+                Report::error(format!("The constant {} is declared twice", name), ReportCode::ConstantDeclaredTwice);
+            report.add_primary(constant.meta.location.clone(), *file_id, "Declared again here".to_string());
+            report.add_secondary(previous.meta.location.clone(), *previous_file, Some("First declared here".to_string()));
+            reports.push(report);
+        } else if defines.contains_key(&constant.name) {
+            let message = format!("{} is also given as a define", constant.name);
+            reports.push(invalid_constant(&name, constant.meta.location.clone(), *file_id, message));
+        } else {
+            declarations.insert(name, (*file_id, constant));
+        }
+    }
+    if !reports.is_empty() {
+        return Err(reports);
+    }
+    let mut evaluator =
+        Evaluator { namespaces, declarations, defines, field, values: HashMap::new(), in_progress: HashSet::new() };
+    let mut result = Constants { values: BTreeMap::new(), field: field.clone() };
+    for (file_id, constant) in constants {
+        let name = namespaces.qualified_constant(*file_id, &constant.name);
+        match evaluator.constant(&name) {
+            Ok(value) => {
+                result.values.insert(name, value);
+            }
+            Err(mut errors) => {
+                reports.append(&mut errors);
+                break;
+            }
+        }
+    }
+    if reports.is_empty() {
+        Ok(result)
+    } else {
+        Err(reports)
+    }
+}
+
+fn invalid_constant(name: &str, location: std::ops::Range<usize>, file_id: FileID, message: String) -> Report {
+    let mut report =
+        Report::error(format!("The value of the constant {} cannot be computed", name), ReportCode::InvalidConstant);
+    report.add_primary(location, file_id, message);
+    report
+}
+
+// Replaces the constants in templates, functions and the main component,
+// after the defines
+pub fn apply_constants(
+    constants: &Constants,
+    namespaces: &Namespaces,
+    definitions: &mut [(FileID, Vec<Definition>)],
+    main_components: &mut [(Option<String>, FileID, MainComponent)],
+) -> Result<(), ReportCollection> {
+    if constants.values.is_empty() {
+        return Ok(());
+    }
+    let mut reports = ReportCollection::new();
+    let (values, field) = (&constants.values, &constants.field);
+    for (file_id, file_definitions) in definitions.iter_mut() {
+        let file_id = *file_id;
+        for definition in file_definitions {
+            let (args, body, requirements) = match definition {
+                Definition::Template { args, body, requirements, .. } => (args, body, requirements.as_mut_slice()),
+                Definition::Function { args, body, .. } => (args, body, &mut [][..]),
+            };
+            let mut hidden: HashSet<String> = args.iter().cloned().collect();
+            declared_names(body, &mut hidden);
+            // the requirements are checked before the body is executed, so
+            // they can only index the arrays with numbers
+            let mut indexed_in_requirements = BTreeMap::new();
+            visit_requirements(requirements, &mut |expr| {
+                replace_constant(namespaces, file_id, values, field, &hidden, expr, &mut indexed_in_requirements)
+            });
+            for (name, location) in indexed_in_requirements {
+                let mut report = Report::error("Invalid requirement".to_string(), ReportCode::InvalidRequirement);
+                let message = format!("{} can only be indexed with numbers in a requirement", name);
+                report.add_primary(location, file_id, message);
+                reports.push(report);
+            }
+            let mut indexed = BTreeMap::new();
+            visit_statement(body, &mut |expr| {
+                replace_constant(namespaces, file_id, values, field, &hidden, expr, &mut indexed)
+            });
+            if let Statement::Block { meta, stmts } = body {
+                let declarations = indexed.keys().map(|name| declare_constant(name, &values[name], meta));
+                stmts.splice(0..0, declarations.collect::<Vec<_>>());
+            }
+        }
+    }
+    for (_, file_id, (_, call)) in main_components.iter_mut() {
+        let mut indexed = BTreeMap::new();
+        visit_expression(call, &mut |expr| {
+            replace_constant(namespaces, *file_id, values, field, &HashSet::new(), expr, &mut indexed)
+        });
+    }
+    if reports.is_empty() {
+        Ok(())
+    } else {
+        Err(reports)
+    }
+}
+
+// The arrays indexed with expressions that are not numbers are renamed to
+// the name of the constant, which is added to indexed with the location of
+// its first use
+fn replace_constant(
+    namespaces: &Namespaces,
+    file_id: FileID,
+    values: &BTreeMap<String, ConstValue>,
+    field: &BigInt,
+    hidden: &HashSet<String>,
+    expr: &mut Expression,
+    indexed: &mut BTreeMap<String, std::ops::Range<usize>>,
+) {
+    let (meta, name, access) = match expr {
+        Expression::Variable { meta, name, access } if !hidden.contains(name) => (meta, name, access),
+        _ => return,
+    };
+    let (constant, rest) = match namespaces.constant(file_id, name, access, values) {
+        Some(found) => found,
+        None => return,
+    };
+    let consumed = access.len() - rest.len();
+    let mut indexes = Vec::new();
+    let mut with_numbers = true;
+    for acc in rest {
+        let index = match acc {
+            Access::ArrayAccess(index) => {
+                evaluate(index, field, &mut |var| Err((var.get_meta().location.clone(), String::new()))).ok()
+            }
+            Access::ComponentAccess(_) => return,
+        };
+        match index.and_then(|index| index.to_usize()) {
+            Some(index) => indexes.push(index),
+            None => {
+                with_numbers = false;
+                break;
+            }
+        }
+    }
+    let value = if with_numbers { values[&constant].index(&indexes) } else { None };
+    match value {
+        Some(value) => *expr = value.to_expression(&meta.clone()),
+        // the bounds are checked when the template is executed
+        None => {
+            indexed.entry(constant.clone()).or_insert_with(|| meta.location.clone());
+            access.drain(..consumed);
+            *name = constant;
+        }
+    }
+}
+
+fn declare_constant(name: &str, value: &ConstValue, meta: &Meta) -> Statement {
+    let dimensions = value.dimensions().unwrap_or_default();
+    let symbol = Symbol {
+        name: name.to_string(),
+        is_array: dimensions.into_iter().map(|dim| build_number(meta.clone(), BigInt::from(dim))).collect(),
+        init: Some(value.to_expression(meta)),
+    };
+    split_declaration_into_single_nodes(meta.clone(), VariableType::Var, vec![symbol], AssignOp::AssignVar)
+}
@@ -0,0 +1,205 @@
+use crate::expression_visitor::{visit_expression, visit_requirements, visit_statement};
+use num_bigint::BigInt;
+use circom_algebra::modular_arithmetic::{self, ArithmeticError};
+use num_traits::Zero;
+use program_structure::ast::{
+    Definition, Expression, ExpressionInfixOpcode, ExpressionPrefixOpcode, MainComponent, Statement, VariableType,
+};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::Report;
+use program_structure::expression_builders::{build_number, build_prefix};
+use program_structure::file_definition::FileID;
+use std::collections::{BTreeMap, HashSet};
+
+// Named constants given with -D NAME=value or in the manifest
+pub type Defines = BTreeMap<String, BigInt>;
+
+// Value of a define: a decimal or hexadecimal integer, possibly negative
+pub fn parse_define_value(value: &str) -> Option<BigInt> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value),
+    };
+    let number = match digits.strip_prefix("0x") {
+        Some(hex) => BigInt::parse_bytes(hex.as_bytes(), 16)?,
+        None => BigInt::parse_bytes(digits.as_bytes(), 10)?,
+    };
+    Some(if negative { -number } else { number })
+}
+
+// Statements of the branch of #if (cond) if_case else else_case selected by
+// the condition, which may only use defines and numbers. The branches are
+// blocks, whose statements are placed in the enclosing block, so the other
+// branch never reaches type analysis and the selected one may declare
+// signals and components as if it was written without the #if.
+pub fn resolve_static_if(
+    cond: Expression,
+    if_case: Statement,
+    else_case: Option<Statement>,
+    defines: &Defines,
+    field: &BigInt,
+    file_id: FileID,
+    errors: &mut Vec<Report>,
+) -> Vec<Statement> {
+    let selected = match evaluate(&cond, field, &mut |expr| define_value(expr, defines)) {
+        Ok(value) if modular_arithmetic::as_bool(&value, field) => Some(if_case),
+        Ok(_) => else_case,
+        Err((location, message)) => {
+            let mut report = Report::error(
+                "The condition of #if must be computed from the defines".to_string(),
+                ReportCode::InvalidStaticCondition,
+            );
+            report.add_primary(location, file_id, message);
+            errors.push(report);
+            None
+        }
+    };
+    match selected {
+        Some(Statement::Block { stmts, .. }) => stmts,
+        Some(stmt) => vec![stmt],
+        None => Vec::new(),
+    }
+}
+
+pub type EvaluationError = (std::ops::Range<usize>, String);
+
+fn define_value(expr: &Expression, defines: &Defines) -> Result<BigInt, EvaluationError> {
+    match expr {
+        Expression::Variable { meta, name, access } if access.is_empty() => match defines.get(name) {
+            Some(value) => Ok(value.clone()),
+            None => Err((meta.location.clone(), format!("{} is not defined, use -D {}=value", name, name))),
+        },
+        _ => Err((expr.get_meta().location.clone(), "only defines, numbers and operators are allowed".to_string())),
+    }
+}
+
+// Value of an expression made of numbers and operators, computed in the
+// field like in the functions, where the values of the variables are given
+// by variable
+pub fn evaluate(
+    expr: &Expression,
+    field: &BigInt,
+    variable: &mut dyn FnMut(&Expression) -> Result<BigInt, EvaluationError>,
+) -> Result<BigInt, EvaluationError> {
+    use ExpressionInfixOpcode::*;
+    match expr {
+        Expression::Number(_, value) => Ok(value.clone()),
+        Expression::Variable { .. } => Ok(modular_arithmetic::add(&variable(expr)?, &BigInt::zero(), field)),
+        Expression::PrefixOp { prefix_op, rhe, .. } => {
+            let value = evaluate(rhe, field, variable)?;
+            Ok(match prefix_op {
+                ExpressionPrefixOpcode::Sub => modular_arithmetic::prefix_sub(&value, field),
+                ExpressionPrefixOpcode::BoolNot => modular_arithmetic::not(&value, field),
+                ExpressionPrefixOpcode::Complement => modular_arithmetic::complement_256(&value, field),
+            })
+        }
+        Expression::InlineSwitchOp { cond, if_true, if_false, .. } => {
+            if modular_arithmetic::as_bool(&evaluate(cond, field, variable)?, field) {
+                evaluate(if_true, field, variable)
+            } else {
+                evaluate(if_false, field, variable)
+            }
+        }
+        Expression::InfixOp { meta, lhe, infix_op, rhe } => {
+            let lhs = &evaluate(lhe, field, variable)?;
+            let rhs = &evaluate(rhe, field, variable)?;
+            let value = match infix_op {
+                Add => Ok(modular_arithmetic::add(lhs, rhs, field)),
+                Sub => Ok(modular_arithmetic::sub(lhs, rhs, field)),
+                Mul => Ok(modular_arithmetic::mul(lhs, rhs, field)),
+                Div => modular_arithmetic::div(lhs, rhs, field),
+                IntDiv => modular_arithmetic::idiv(lhs, rhs, field),
+                Mod => modular_arithmetic::mod_op(lhs, rhs, field),
+                Pow => Ok(modular_arithmetic::pow(lhs, rhs, field)),
+                ShiftL => modular_arithmetic::shift_l(lhs, rhs, field),
+                ShiftR => modular_arithmetic::shift_r(lhs, rhs, field),
+                Lesser => Ok(modular_arithmetic::lesser(lhs, rhs, field)),
+                LesserEq => Ok(modular_arithmetic::lesser_eq(lhs, rhs, field)),
+                Greater => Ok(modular_arithmetic::greater(lhs, rhs, field)),
+                GreaterEq => Ok(modular_arithmetic::greater_eq(lhs, rhs, field)),
+                Eq => Ok(modular_arithmetic::eq(lhs, rhs, field)),
+                NotEq => Ok(modular_arithmetic::not_eq(lhs, rhs, field)),
+                BoolAnd => Ok(modular_arithmetic::bool_and(lhs, rhs, field)),
+                BoolOr => Ok(modular_arithmetic::bool_or(lhs, rhs, field)),
+                BitAnd => Ok(modular_arithmetic::bit_and(lhs, rhs, field)),
+                BitOr => Ok(modular_arithmetic::bit_or(lhs, rhs, field)),
+                BitXor => Ok(modular_arithmetic::bit_xor(lhs, rhs, field)),
+            };
+            value.map_err(|error| {
+                let message = match error {
+                    ArithmeticError::DivisionByZero => "division by zero",
+                    ArithmeticError::BitOverFlowInShift => "the shift causes a bit overflow",
+                };
+                (meta.location.clone(), message.to_string())
+            })
+        }
+        _ => Err((expr.get_meta().location.clone(), "this expression cannot be computed when the program is parsed".to_string())),
+    }
+}
+
+// Replaces the uses of the defines in templates, functions and the main
+// component by their values. A parameter or a declaration with the name of
+// a define hides it in the whole template or function.
+pub fn apply_defines(
+    defines: &Defines,
+    definitions: &mut [(FileID, Vec<Definition>)],
+    main_components: &mut [(Option<String>, FileID, MainComponent)],
+) {
+    if defines.is_empty() {
+        return;
+    }
+    for (_, file_definitions) in definitions.iter_mut() {
+        for definition in file_definitions {
+            let (args, body, requirements) = match definition {
+                Definition::Template { args, body, requirements, .. } => (args, body, requirements.as_mut_slice()),
+                Definition::Function { args, body, .. } => (args, body, &mut [][..]),
+            };
+            let mut hidden: HashSet<String> = args.iter().cloned().collect();
+            declared_names(body, &mut hidden);
+            visit_statement(body, &mut |expr| replace_define(defines, &hidden, expr));
+            visit_requirements(requirements, &mut |expr| replace_define(defines, &hidden, expr));
+        }
+    }
+    for (_, _, (_, call)) in main_components.iter_mut() {
+        visit_expression(call, &mut |expr| replace_define(defines, &HashSet::new(), expr));
+    }
+}
+
+pub fn declared_names(stmt: &Statement, names: &mut HashSet<String>) {
+    use Statement::*;
+    match stmt {
+        IfThenElse { if_case, else_case, .. } => {
+            declared_names(if_case, names);
+            if let Some(else_stmt) = else_case {
+                declared_names(else_stmt, names);
+            }
+        }
+        While { stmt, .. } => declared_names(stmt, names),
+        InitializationBlock { initializations, .. } => {
+            initializations.iter().for_each(|init| declared_names(init, names))
+        }
+        Block { stmts, .. } => stmts.iter().for_each(|stmt| declared_names(stmt, names)),
+        Declaration { xtype, name, .. } if !matches!(xtype, VariableType::AnonymousComponent) => {
+            names.insert(name.clone());
+        }
+        _ => {}
+    }
+}
+
+fn replace_define(defines: &Defines, hidden: &HashSet<String>, expr: &mut Expression) {
+    let value = match expr {
+        Expression::Variable { name, access, .. } if access.is_empty() && !hidden.contains(name) => {
+            match defines.get(name) {
+                Some(value) => value,
+                None => return,
+            }
+        }
+        _ => return,
+    };
+    let meta = expr.get_meta().clone();
+    *expr = if value < &BigInt::zero() {
+        build_prefix(meta.clone(), ExpressionPrefixOpcode::Sub, build_number(meta, -value))
+    } else {
+        build_number(meta, value.clone())
+    };
+}
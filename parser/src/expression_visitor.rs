@@ -0,0 +1,95 @@
+use program_structure::ast::{Access, Expression, LogArgument, Requirement, Statement};
+
+// Calls f on every expression of the statement, each expression before its
+// subexpressions, so f may replace the expression it receives
+pub fn visit_statement(stmt: &mut Statement, f: &mut dyn FnMut(&mut Expression)) {
+    use Statement::*;
+    match stmt {
+        IfThenElse { cond, if_case, else_case, .. } => {
+            visit_expression(cond, f);
+            visit_statement(if_case, f);
+            if let Some(else_stmt) = else_case {
+                visit_statement(else_stmt, f);
+            }
+        }
+        While { cond, stmt, .. } => {
+            visit_expression(cond, f);
+            visit_statement(stmt, f);
+        }
+        Return { value, .. } => visit_expression(value, f),
+        InitializationBlock { initializations, .. } => {
+            initializations.iter_mut().for_each(|init| visit_statement(init, f))
+        }
+        Declaration { dimensions, .. } => dimensions.iter_mut().for_each(|dim| visit_expression(dim, f)),
+        Substitution { access, rhe, .. } => {
+            visit_access(access, f);
+            visit_expression(rhe, f);
+        }
+        MultSubstitution { lhe, rhe, .. } | ConstraintEquality { lhe, rhe, .. } => {
+            visit_expression(lhe, f);
+            visit_expression(rhe, f);
+        }
+        UnderscoreSubstitution { rhe, .. } => visit_expression(rhe, f),
+        LogCall { args, .. } => visit_log_arguments(args, f),
+        Block { stmts, .. } => stmts.iter_mut().for_each(|stmt| visit_statement(stmt, f)),
+        Assert { arg, message, .. } => {
+            visit_expression(arg, f);
+            visit_log_arguments(message, f);
+        }
+        Break { .. } | Continue { .. } => {}
+    }
+}
+
+pub fn visit_requirements(requirements: &mut [Requirement], f: &mut dyn FnMut(&mut Expression)) {
+    for requirement in requirements {
+        for expr in requirement.expressions_mut() {
+            visit_expression(expr, f);
+        }
+    }
+}
+
+pub fn visit_expression(expr: &mut Expression, f: &mut dyn FnMut(&mut Expression)) {
+    use Expression::*;
+    f(expr);
+    match expr {
+        InfixOp { lhe, rhe, .. } => {
+            visit_expression(lhe, f);
+            visit_expression(rhe, f);
+        }
+        PrefixOp { rhe, .. } | ParallelOp { rhe, .. } => visit_expression(rhe, f),
+        InlineSwitchOp { cond, if_true, if_false, .. } => {
+            visit_expression(cond, f);
+            visit_expression(if_true, f);
+            visit_expression(if_false, f);
+        }
+        Variable { access, .. } => visit_access(access, f),
+        Number(..) => {}
+        Call { args, .. } => args.iter_mut().for_each(|arg| visit_expression(arg, f)),
+        AnonymousComp { params, signals, .. } => {
+            params.iter_mut().chain(signals.iter_mut()).for_each(|arg| visit_expression(arg, f))
+        }
+        ArrayInLine { values, .. } | Tuple { values, .. } => {
+            values.iter_mut().for_each(|value| visit_expression(value, f))
+        }
+        UniformArray { value, dimension, .. } => {
+            visit_expression(value, f);
+            visit_expression(dimension, f);
+        }
+    }
+}
+
+fn visit_access(access: &mut [Access], f: &mut dyn FnMut(&mut Expression)) {
+    for acc in access {
+        if let Access::ArrayAccess(index) = acc {
+            visit_expression(index, f);
+        }
+    }
+}
+
+fn visit_log_arguments(args: &mut [LogArgument], f: &mut dyn FnMut(&mut Expression)) {
+    for arg in args {
+        for expr in arg.expressions_mut() {
+            visit_expression(expr, f);
+        }
+    }
+}
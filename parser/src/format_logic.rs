@@ -0,0 +1,108 @@
+use program_structure::ast::{FormatPiece, LogArgument, NumberFormat};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::Report;
+use program_structure::file_definition::FileID;
+
+enum Piece {
+    Text(String),
+    Placeholder(NumberFormat),
+}
+
+// Arguments of log and of the message of assert. A string that contains {}
+// or {: is a format string: its placeholders {} or {:d} (decimal) and {:x}
+// (hexadecimal) are replaced by the arguments that follow it, and {{ and }}
+// print a brace. The other strings are printed as they are written.
+pub fn apply_formats(
+    args: Vec<LogArgument>,
+    location: std::ops::Range<usize>,
+    file_id: FileID,
+    errors: &mut Vec<Report>,
+) -> Vec<LogArgument> {
+    let mut new_args = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let string = match arg {
+            LogArgument::LogStr(string) if string.contains("{}") || string.contains("{:") => string,
+            arg => {
+                new_args.push(arg);
+                continue;
+            }
+        };
+        let pieces = match parse_format(&string) {
+            Ok(pieces) => pieces,
+            Err(message) => {
+                errors.push(invalid_format(location.clone(), file_id, message));
+                continue;
+            }
+        };
+        let placeholders = pieces.iter().filter(|piece| matches!(piece, Piece::Placeholder(_))).count();
+        let mut format = Vec::new();
+        for piece in pieces {
+            match piece {
+                Piece::Text(text) => format.push(FormatPiece::Text(text)),
+                Piece::Placeholder(number_format) => match args.next() {
+                    Some(LogArgument::LogExp(value)) => format.push(FormatPiece::Value(Box::new(value), number_format)),
+                    _ => {
+                        let message = format!(
+                            "\"{}\" has {} placeholders, each one must be followed by an expression",
+                            string, placeholders
+                        );
+                        errors.push(invalid_format(location, file_id, message));
+                        return new_args;
+                    }
+                },
+            }
+        }
+        new_args.push(LogArgument::LogFormat(format));
+    }
+    new_args
+}
+
+fn parse_format(string: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = string.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut spec = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => spec.push(c),
+                        None => return Err(format!("the placeholder {{{} is not closed", spec)),
+                    }
+                }
+                let number_format = match spec.as_str() {
+                    "" | ":d" => NumberFormat::Decimal,
+                    ":x" => NumberFormat::Hexadecimal,
+                    _ => return Err(format!("unknown placeholder {{{}}}, use {{}}, {{:d}} or {{:x}}", spec)),
+                };
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Placeholder(number_format));
+            }
+            '}' => return Err("use }} to print a closing brace".to_string()),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+fn invalid_format(location: std::ops::Range<usize>, file_id: FileID, message: String) -> Report {
+    let mut report = Report::error("Invalid format string".to_string(), ReportCode::InvalidFormatString);
+    report.add_primary(location, file_id, message);
+    report
+}
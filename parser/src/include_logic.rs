@@ -1,66 +1,139 @@
-use program_structure::ast::produce_report_with_message;
+use crate::manifest::Package;
+use program_structure::ast::{produce_report_with_message, Include};
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::Report;
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
+// Files included with an alias and the files they include without one form
+// a namespace, whose definitions are renamed to prefix.name. The main file
+// is in the namespace 0, with the empty prefix.
+pub type NamespaceID = usize;
+
 pub struct FileStack {
     current_location: PathBuf,
-    black_paths: HashSet<PathBuf>,
-    stack: Vec<PathBuf>,
+    current_namespace: NamespaceID,
+    black_paths: HashSet<(PathBuf, NamespaceID)>,
+    stack: Vec<(PathBuf, NamespaceID)>,
+    prefixes: Vec<String>,
+    namespaces: HashMap<PathBuf, NamespaceID>,
+    packages: Vec<Package>,
 }
 
 impl FileStack {
-    pub fn new(src: PathBuf) -> FileStack {
+    pub fn new(src: PathBuf, packages: Vec<Package>) -> FileStack {
         let mut location = src.clone();
         location.pop();
-        FileStack { current_location: location, black_paths: HashSet::new(), stack: vec![src] }
+        FileStack {
+            current_location: location,
+            current_namespace: 0,
+            black_paths: HashSet::new(),
+            stack: vec![(src, 0)],
+            prefixes: vec![String::new()],
+            namespaces: HashMap::new(),
+            packages,
+        }
     }
 
     pub fn add_include(
         f_stack: &mut FileStack,
-        name: String,
+        include: &Include,
         libraries: &Vec<PathBuf>,
-    ) -> Result<String, Report> {
-        let mut libraries2 = Vec::new();
-        libraries2.push(f_stack.current_location.clone());
-        libraries2.append(&mut libraries.clone());
-        for lib in libraries2 {
-            let mut path = PathBuf::new();
-            path.push(lib);
-            path.push(name.clone());
+    ) -> Result<(String, NamespaceID), Report> {
+        let mut candidates = Vec::new();
+        candidates.push(f_stack.current_location.join(&include.path));
+        let package = f_stack.package_of(&include.path);
+        if let Some((package, path_in_package)) = package {
+            candidates.push(package.directory.join(path_in_package));
+        }
+        for lib in libraries {
+            candidates.push(lib.join(&include.path));
+        }
+        for path in candidates {
             let path = std::fs::canonicalize(path);
             match path {
                 Err(_) => {}
                 Ok(path) => {
                     if path.is_file() {
-                        if !f_stack.black_paths.contains(&path) {
-                            f_stack.stack.push(path.clone());
+                        let namespace = match &include.alias {
+                            Some(alias) => f_stack.namespace_of(&path, alias),
+                            None => f_stack.current_namespace,
+                        };
+                        let entry = (path.clone(), namespace);
+                        if !f_stack.black_paths.contains(&entry) {
+                            f_stack.stack.push(entry);
                         }
-                        return Result::Ok(path.to_str().unwrap().to_string());
+                        return Result::Ok((path.to_str().unwrap().to_string(), namespace));
                     }
                 }
             }
         }
-        Result::Err( produce_report_with_message(ReportCode::IncludeNotFound, name))
+        let mut report = produce_report_with_message(ReportCode::IncludeNotFound, include.path.clone());
+        match package {
+            Some((package, _)) => {
+                report.add_note(format!(
+                    "The package is resolved by the manifest to {}, as pinned in circom.lock",
+                    package.describe()
+                ));
+            }
+            None if !f_stack.packages.is_empty() => {
+                let packages: Vec<_> = f_stack.packages.iter().map(Package::describe).collect();
+                report.add_note(format!(
+                    "The packages of the manifest are {}, as pinned in circom.lock",
+                    packages.join(", ")
+                ));
+            }
+            None => {}
+        }
+        Result::Err(report)
     }
 
-    pub fn take_next(f_stack: &mut FileStack) -> Option<PathBuf> {
+    // package named by the first component of the path and the rest of the path
+    fn package_of<'a>(&self, path: &'a str) -> Option<(&Package, &'a str)> {
+        let (name, path_in_package) = path.split_once('/')?;
+        self.packages.iter().find(|package| package.name == name).map(|package| (package, path_in_package))
+    }
+
+    pub fn take_next(f_stack: &mut FileStack) -> Option<(PathBuf, NamespaceID)> {
         loop {
             match f_stack.stack.pop() {
                 None => {
                     break None;
                 }
-                Some(file) if !f_stack.black_paths.contains(&file) => {
-                    f_stack.current_location = file.clone();
+                Some(entry) if !f_stack.black_paths.contains(&entry) => {
+                    f_stack.current_location = entry.0.clone();
                     f_stack.current_location.pop();
-                    f_stack.black_paths.insert(file.clone());
-                    break Some(file);
+                    f_stack.current_namespace = entry.1;
+                    f_stack.black_paths.insert(entry.clone());
+                    break Some(entry);
                 }
                 _ => {}
             }
         }
     }
+
+    pub fn get_prefixes(&self) -> &Vec<String> {
+        &self.prefixes
+    }
+
+    // a file included with an alias from several places is loaded only once,
+    // its prefix is given by the first of these includes
+    fn namespace_of(&mut self, path: &PathBuf, alias: &str) -> NamespaceID {
+        if let Some(namespace) = self.namespaces.get(path) {
+            return *namespace;
+        }
+        let parent = &self.prefixes[self.current_namespace];
+        let base = if parent.is_empty() { alias.to_string() } else { format!("{}.{}", parent, alias) };
+        let mut prefix = base.clone();
+        let mut copy = 1;
+        while self.prefixes.contains(&prefix) {
+            copy += 1;
+            prefix = format!("{}_{}", base, copy);
+        }
+        self.prefixes.push(prefix);
+        self.namespaces.insert(path.clone(), self.prefixes.len() - 1);
+        self.prefixes.len() - 1
+    }
 }
 
 pub struct IncludesNode {
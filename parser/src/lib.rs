@@ -2,21 +2,35 @@ extern crate num_bigint_dig as num_bigint;
 extern crate num_traits;
 extern crate serde;
 extern crate serde_derive;
+extern crate serde_json;
 #[macro_use]
 extern crate lalrpop_util;
 
 lalrpop_mod!(pub lang);
 
+mod const_logic;
+mod define_logic;
+mod expression_visitor;
+mod format_logic;
 mod include_logic;
+mod main_logic;
+pub mod manifest;
+mod namespace_logic;
 mod parser_logic;
 mod syntax_sugar_remover;
 
 use include_logic::{FileStack, IncludesGraph};
+pub use define_logic::{parse_define_value, Defines};
+pub use main_logic::MainDeclaration;
+use manifest::Package;
+use namespace_logic::{FileScope, Namespaces};
+use num_bigint::BigInt;
 use program_structure::ast::{produce_compiler_version_report, produce_report, produce_report_with_message, produce_version_warning_report, Expression};
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::ReportCollection;
 use program_structure::error_definition::Report;
 use program_structure::file_definition::{FileLibrary};
+use std::collections::HashMap;
 use program_structure::program_archive::ProgramArchive;
 use std::path::{PathBuf, Path};
 use syntax_sugar_remover::{apply_syntactic_sugar};
@@ -60,17 +74,24 @@ pub fn run_parser(
     file: String,
     version: &str,
     link_libraries: Vec<PathBuf>,
+    packages: Vec<Package>,
+    defines: &Defines,
+    field: &BigInt,
+    mains: Vec<MainDeclaration>,
 ) -> Result<(ProgramArchive, ReportCollection), (FileLibrary, ReportCollection)> {
     let mut file_library = FileLibrary::new();
     let mut definitions = Vec::new();
+    let mut constants = Vec::new();
     let mut main_components = Vec::new();
-    let mut file_stack = FileStack::new(PathBuf::from(file));
+    let mut root_custom_gates = None;
+    let mut scopes = HashMap::new();
+    let mut file_stack = FileStack::new(PathBuf::from(file), packages);
     let mut includes_graph = IncludesGraph::new();
     let mut warnings = Vec::new();
     let mut link_libraries2 = link_libraries.clone();
     let mut ext_link_libraries = vec![Path::new("").to_path_buf()];
     ext_link_libraries.append(&mut link_libraries2);
-    while let Some(crr_file) = FileStack::take_next(&mut file_stack) {
+    while let Some((crr_file, namespace)) = FileStack::take_next(&mut file_stack) {
         let (found, path, src, crr_str_file, reports) =
             find_file(crr_file, ext_link_libraries.clone());
         if !found {
@@ -78,19 +99,34 @@ pub fn run_parser(
         }
         let file_id = file_library.add_file(path.clone(), src.clone());
         let program =
-            parser_logic::parse_file(&src, file_id).map_err(|e| (file_library.clone(), e))?;
+            parser_logic::parse_file(&src, file_id, defines, field).map_err(|e| (file_library.clone(), e))?;
+        root_custom_gates.get_or_insert(program.custom_gates);
         if let Some(main) = program.main_component {
             main_components.push((file_id, main, program.custom_gates));
         }
         includes_graph.add_node(crr_str_file, program.custom_gates, program.custom_gates_declared);
         let includes = program.includes;
         definitions.push((file_id, program.definitions));
+        constants.extend(program.constants.into_iter().map(|constant| (file_id, constant)));
+        let mut scope = FileScope::new(namespace);
         for include in includes {
-            let path_include =
-                FileStack::add_include(&mut file_stack, include.clone(), &link_libraries.clone())
+            let (path_include, include_namespace) =
+                FileStack::add_include(&mut file_stack, &include, &link_libraries.clone())
                     .map_err(|e| (file_library.clone(), vec![e]))?;
+            if let Some(alias) = include.alias {
+                match scope.aliases.get(&alias) {
+                    Some(previous) if *previous != include_namespace => {
+                        let report = produce_report(ReportCode::IncludeAliasDeclaredTwice, include.meta.location, file_id);
+                        return Result::Err((file_library.clone(), vec![report]));
+                    }
+                    _ => {
+                        scope.aliases.insert(alias, include_namespace);
+                    }
+                }
+            }
             includes_graph.add_edge(path_include).map_err(|e| (file_library.clone(), vec![e]))?;
         }
+        scopes.insert(file_id, scope);
         warnings.append(
             &mut check_number_version(
                 path.clone(),
@@ -109,11 +145,24 @@ pub fn run_parser(
         }
     }
 
+    // the mains given in the command line or the manifest replace the one of the sources
+    let external_mains = !mains.is_empty();
+    if external_mains {
+        let root_scope = scopes[&0].clone();
+        main_components.clear();
+        for declaration in &mains {
+            let (file_id, main) = main_logic::parse_main(declaration, &mut file_library, defines, field)
+                .map_err(|e| (file_library.clone(), e))?;
+            scopes.insert(file_id, root_scope.clone());
+            main_components.push((file_id, main, root_custom_gates.unwrap_or(false)));
+        }
+    }
+
     if main_components.len() == 0 {
         let report = produce_report(ReportCode::NoMainFoundInProject,0..0, 0);
         warnings.push(report);
         Err((file_library, warnings))
-    } else if main_components.len() > 1 {
+    } else if main_components.len() > 1 && !external_mains {
         let report = produce_report_with_main_components(main_components);
         warnings.push(report);
         Err((file_library, warnings))
@@ -132,11 +181,30 @@ pub fn run_parser(
             warnings.append(& mut errors);
             Err((file_library, warnings))
         } else {
-            let (main_id, main_component, custom_gates) = main_components.pop().unwrap();
+            let custom_gates = main_components[0].2;
+            let names = mains.into_iter().map(|declaration| declaration.name).chain(std::iter::repeat(None));
+            let mut main_components: Vec<_> =
+                names.zip(main_components).map(|(name, (file_id, main, _))| (name, file_id, main)).collect();
+            if let Err(mut errors) = namespace_logic::resolve_names(file_stack.get_prefixes(), &scopes, &mut definitions, &mut main_components) {
+                warnings.append(&mut errors);
+                return Err((file_library, warnings));
+            }
+            let namespaces = Namespaces::new(file_stack.get_prefixes(), &scopes);
+            let constants = match const_logic::evaluate_constants(&constants, &namespaces, defines, field) {
+                Ok(constants) => constants,
+                Err(mut errors) => {
+                    warnings.append(&mut errors);
+                    return Err((file_library, warnings));
+                }
+            };
+            define_logic::apply_defines(defines, &mut definitions, &mut main_components);
+            if let Err(mut errors) = const_logic::apply_constants(&constants, &namespaces, &mut definitions, &mut main_components) {
+                warnings.append(&mut errors);
+                return Err((file_library, warnings));
+            }
             let result_program_archive = ProgramArchive::new(
                 file_library,
-                main_id,
-                main_component,
+                main_components,
                 definitions,
                 custom_gates,
             );
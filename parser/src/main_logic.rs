@@ -0,0 +1,65 @@
+use crate::define_logic::Defines;
+use num_bigint::BigInt;
+use crate::parser_logic;
+use program_structure::ast::MainComponent;
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::{FileID, FileLibrary};
+
+// Main component given in the command line or in the manifest instead of
+// the one written in the sources
+#[derive(Clone)]
+pub struct MainDeclaration {
+    pub name: Option<String>,
+    pub template_call: String,
+    pub public: Vec<String>,
+}
+
+impl MainDeclaration {
+    // name of the outputs of a main without a name when several mains are
+    // built, Tree(20, 2) gives Tree_20_2
+    pub fn derived_name(&self) -> String {
+        let name: String = self
+            .template_call
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        name.split('_').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("_")
+    }
+
+    fn source(&self) -> String {
+        if self.public.is_empty() {
+            format!("component main = {};\n", self.template_call)
+        } else {
+            format!("component main {{public [{}]}} = {};\n", self.public.join(", "), self.template_call)
+        }
+    }
+}
+
+// The declaration is parsed as a file of its own, so the errors point to
+// the text given by the user and the defines apply to the parameters.
+pub fn parse_main(
+    declaration: &MainDeclaration,
+    file_library: &mut FileLibrary,
+    defines: &Defines,
+    field: &BigInt,
+) -> Result<(FileID, MainComponent), ReportCollection> {
+    let origin = match &declaration.name {
+        Some(name) => format!("main {}", name),
+        None => format!("main {}", declaration.template_call),
+    };
+    let src = declaration.source();
+    let file_id = file_library.add_file(origin, src.clone());
+    let program = parser_logic::parse_file(&src, file_id, defines, field)?;
+    match program.main_component {
+        Some(main) if program.includes.is_empty() && program.definitions.is_empty() => Ok((file_id, main)),
+        _ => {
+            let mut report = Report::error(
+                format!("{} is not a template call", declaration.template_call),
+                ReportCode::NoMainFoundInProject,
+            );
+            report.add_primary(0..src.len(), file_id, "Expected a call like Template(params)".to_string());
+            Err(vec![report])
+        }
+    }
+}
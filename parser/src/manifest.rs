@@ -0,0 +1,352 @@
+use crate::define_logic::{parse_define_value, Defines};
+use crate::main_logic::MainDeclaration;
+use num_bigint::BigInt;
+use program_structure::budget::{Budget, Budgets};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "circom.json";
+pub const LOCK_FILE: &str = "circom.lock";
+
+// Project manifest (circom.json). The dependencies map the name of a package,
+// used as the first component of the path of an include, to a local directory.
+// The defines are the default values of the constants given with -D, and
+// the mains are main components built instead of the one of the sources.
+// The budget bounds the cost of the circuit and of the instances of some
+// templates.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(skip)]
+    directory: PathBuf,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+    #[serde(default)]
+    defines: BTreeMap<String, DefineValue>,
+    #[serde(default)]
+    mains: BTreeMap<String, ManifestMain>,
+    #[serde(default)]
+    budget: ManifestBudget,
+}
+
+// numbers that do not fit in 64 bits are given as strings
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DefineValue {
+    Number(i64),
+    Text(String),
+}
+
+// The parameters are numbers or expressions, which may use the defines.
+// A main with a file is only built when that file is compiled.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestMain {
+    file: Option<String>,
+    template: String,
+    #[serde(default)]
+    params: Vec<DefineValue>,
+    #[serde(default)]
+    public: Vec<String>,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+struct ManifestLimits {
+    constraints: Option<usize>,
+    signals: Option<usize>,
+}
+
+impl From<ManifestLimits> for Budget {
+    fn from(limits: ManifestLimits) -> Budget {
+        Budget { constraints: limits.constraints, signals: limits.signals }
+    }
+}
+
+// The limits of the whole circuit and the ones of the templates, which
+// replace those given with #[budget(...)] in the sources
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ManifestBudget {
+    constraints: Option<usize>,
+    signals: Option<usize>,
+    #[serde(default)]
+    templates: BTreeMap<String, ManifestLimits>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Dependency {
+    Path(String),
+    Detailed { path: String, version: Option<String> },
+}
+
+impl Dependency {
+    fn path(&self) -> &str {
+        match self {
+            Dependency::Path(path) | Dependency::Detailed { path, .. } => path,
+        }
+    }
+
+    fn version(&self) -> Option<&String> {
+        match self {
+            Dependency::Path(_) => None,
+            Dependency::Detailed { version, .. } => version.as_ref(),
+        }
+    }
+}
+
+// Versions of the packages used in the last compilation (circom.lock)
+#[derive(Deserialize, Serialize, Default, PartialEq)]
+struct Lock {
+    packages: BTreeMap<String, LockedPackage>,
+}
+
+// Packages without a version of their own are pinned by the content of
+// their sources
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
+struct LockedPackage {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+}
+
+pub struct Package {
+    pub name: String,
+    pub directory: PathBuf,
+    pub version: Option<String>,
+    pub hash: Option<String>,
+}
+
+impl Package {
+    pub fn describe(&self) -> String {
+        match (&self.version, &self.hash) {
+            (Some(version), Some(hash)) => {
+                format!("{} {} with the sources {} ({})", self.name, version, hash, self.directory.display())
+            }
+            (Some(version), None) => format!("{} {} ({})", self.name, version, self.directory.display()),
+            (None, Some(hash)) => format!("{} with the sources {} ({})", self.name, hash, self.directory.display()),
+            (None, None) => format!("{} ({})", self.name, self.directory.display()),
+        }
+    }
+}
+
+impl Manifest {
+    // looks for the manifest in the directory of the main file and its ancestors
+    pub fn find(main_file: &Path) -> Option<PathBuf> {
+        let main_file = std::fs::canonicalize(main_file).ok()?;
+        main_file.ancestors().skip(1).map(|dir| dir.join(MANIFEST_FILE)).find(|file| file.is_file())
+    }
+
+    pub fn load(file: &Path) -> Result<Manifest, ReportCollection> {
+        let src = std::fs::read_to_string(file)
+            .map_err(|_| vec![manifest_error(format!("Could not open the manifest {}", file.display()))])?;
+        let mut manifest: Manifest = serde_json::from_str(&src)
+            .map_err(|e| vec![manifest_error(format!("Invalid manifest {}: {}", file.display(), e))])?;
+        manifest.directory = file.parent().map_or_else(PathBuf::new, Path::to_path_buf);
+        Ok(manifest)
+    }
+
+    pub fn defines(&self) -> Result<Defines, ReportCollection> {
+        let mut defines = Defines::new();
+        for (name, value) in &self.defines {
+            let value = match value {
+                DefineValue::Number(value) => BigInt::from(*value),
+                DefineValue::Text(value) => parse_define_value(value).ok_or_else(|| {
+                    vec![manifest_error(format!("The value {} of the define {} is not a number", value, name))]
+                })?,
+            };
+            defines.insert(name.clone(), value);
+        }
+        Ok(defines)
+    }
+
+    pub fn budgets(&self) -> Budgets {
+        let templates = self.budget.templates.iter().map(|(name, limits)| (name.clone(), Budget::from(*limits)));
+        let circuit = Budget { constraints: self.budget.constraints, signals: self.budget.signals };
+        Budgets { templates: templates.collect(), circuit }
+    }
+
+    pub fn main(&self, name: &str) -> Option<MainDeclaration> {
+        let main = self.mains.get(name)?;
+        let params: Vec<String> = main
+            .params
+            .iter()
+            .map(|param| match param {
+                DefineValue::Number(value) => value.to_string(),
+                DefineValue::Text(value) => value.clone(),
+            })
+            .collect();
+        Some(MainDeclaration {
+            name: Some(name.to_string()),
+            template_call: format!("{}({})", main.template, params.join(", ")),
+            public: main.public.clone(),
+        })
+    }
+
+    // mains to build when the given file is compiled
+    pub fn mains_of(&self, main_file: &Path) -> Vec<MainDeclaration> {
+        let main_file = std::fs::canonicalize(main_file).ok();
+        let applies = |main: &ManifestMain| match &main.file {
+            Some(file) => std::fs::canonicalize(self.directory.join(file)).ok() == main_file,
+            None => true,
+        };
+        self.mains.iter().filter(|(_, main)| applies(main)).filter_map(|(name, _)| self.main(name)).collect()
+    }
+
+    // Resolves the directories and versions of the dependencies. The version
+    // of a package is the one of the circom.json or package.json of its
+    // directory or, if it has none, the one given in the manifest; a package
+    // without a version of its own is also pinned by the hash of its sources.
+    // The versions and hashes are pinned in the lock file the first time a
+    // package is used, and a package that changes afterwards is an error until
+    // the lock file is removed or updated. Returns the lock file when it is
+    // written.
+    pub fn resolve_packages(&self) -> Result<(Vec<Package>, Option<PathBuf>), ReportCollection> {
+        let lock_file = self.directory.join(LOCK_FILE);
+        let old_lock = if lock_file.is_file() {
+            let src = std::fs::read_to_string(&lock_file)
+                .map_err(|_| vec![manifest_error(format!("Could not open the lock file {}", lock_file.display()))])?;
+            serde_json::from_str(&src)
+                .map_err(|e| vec![manifest_error(format!("Invalid lock file {}: {}", lock_file.display(), e))])?
+        } else {
+            Lock::default()
+        };
+        let mut lock = Lock::default();
+        let mut packages = Vec::new();
+        for (name, dependency) in &self.dependencies {
+            let directory = std::fs::canonicalize(self.directory.join(dependency.path())).map_err(|_| {
+                vec![manifest_error(format!("The directory {} of the package {} does not exist", dependency.path(), name))]
+            })?;
+            let (version, hash) = match package_version(&directory) {
+                Some(version) => {
+                    if let Some(required) = dependency.version() {
+                        if version != *required {
+                            return Err(vec![manifest_error(format!(
+                                "The package {} requires the version {} but {} has {}",
+                                name,
+                                required,
+                                directory.display(),
+                                version
+                            ))]);
+                        }
+                    }
+                    (Some(version), None)
+                }
+                None => {
+                    let hash = sources_hash(&directory).map_err(|_| {
+                        vec![manifest_error(format!("Could not read the sources of the package {}", name))]
+                    })?;
+                    (dependency.version().cloned(), Some(hash))
+                }
+            };
+            let locked = LockedPackage { path: dependency.path().to_string(), version: version.clone(), hash };
+            if let Some(pinned) = old_lock.packages.get(name) {
+                if pinned.path == locked.path && pinned.version != locked.version {
+                    let mut report = manifest_error(format!(
+                        "The package {} is pinned to the version {} in {} but {} has {}",
+                        name,
+                        pinned.version.as_deref().unwrap_or("without version"),
+                        lock_file.display(),
+                        directory.display(),
+                        version.as_deref().unwrap_or("no version")
+                    ));
+                    report.add_note(format!("Remove the package from {} to pin the new version", LOCK_FILE));
+                    return Err(vec![report]);
+                }
+                if let (Some(pinned_hash), Some(hash)) = (&pinned.hash, &locked.hash) {
+                    if pinned.path == locked.path && pinned_hash != hash {
+                        let mut report = manifest_error(format!(
+                            "The package {} is pinned to the sources {} in {} but the sources of {} are {}",
+                            name,
+                            pinned_hash,
+                            lock_file.display(),
+                            directory.display(),
+                            hash
+                        ));
+                        report.add_note(format!("Remove the package from {} to pin the new sources", LOCK_FILE));
+                        return Err(vec![report]);
+                    }
+                }
+            }
+            packages.push(Package { name: name.clone(), directory, version, hash: locked.hash.clone() });
+            lock.packages.insert(name.clone(), locked);
+        }
+        if lock == old_lock {
+            return Ok((packages, None));
+        }
+        let src = serde_json::to_string_pretty(&lock).unwrap();
+        std::fs::write(&lock_file, src + "\n")
+            .map_err(|_| vec![manifest_error(format!("Could not write the lock file {}", lock_file.display()))])?;
+        Ok((packages, Some(lock_file)))
+    }
+}
+
+// version given in the circom.json or package.json of the directory of the
+// package
+fn package_version(directory: &Path) -> Option<String> {
+    #[derive(Deserialize)]
+    struct Versioned {
+        version: Option<String>,
+    }
+    for file in [MANIFEST_FILE, "package.json"] {
+        let versioned = std::fs::read_to_string(directory.join(file))
+            .ok()
+            .and_then(|src| serde_json::from_str::<Versioned>(&src).ok());
+        if let Some(Versioned { version: Some(version) }) = versioned {
+            return Some(version);
+        }
+    }
+    None
+}
+
+// FNV-1a hash of the .circom files of the directory and its subdirectories
+// (except node_modules and the hidden ones), with their paths, in order
+fn sources_hash(directory: &Path) -> std::io::Result<String> {
+    fn collect(directory: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            let name = path.file_name().map_or(String::new(), |name| name.to_string_lossy().to_string());
+            if path.is_dir() {
+                if !name.starts_with('.') && name != "node_modules" {
+                    collect(&path, files)?;
+                }
+            } else if path.extension().is_some_and(|extension| extension == "circom") {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+    fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+        bytes.iter().fold(hash, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3))
+    }
+
+    let mut files = Vec::new();
+    collect(directory, &mut files)?;
+    let mut relative: Vec<(String, PathBuf)> = files
+        .into_iter()
+        .map(|file| (file.strip_prefix(directory).unwrap().to_string_lossy().replace('\\', "/"), file))
+        .collect();
+    relative.sort();
+    let mut hash = 0xcbf29ce484222325;
+    for (name, file) in relative {
+        hash = fnv1a(hash, name.as_bytes());
+        hash = fnv1a(hash, &[0]);
+        hash = fnv1a(hash, &std::fs::read(file)?);
+        hash = fnv1a(hash, &[0]);
+    }
+    Ok(format!("fnv1a:{:016x}", hash))
+}
+
+fn manifest_error(message: String) -> Report {
+    Report::error(message, ReportCode::ManifestError)
+}
@@ -0,0 +1,135 @@
+use crate::expression_visitor::{visit_expression, visit_requirements, visit_statement};
+use crate::include_logic::NamespaceID;
+use program_structure::ast::{Access, Definition, Expression, MainComponent};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::FileID;
+use std::collections::{BTreeMap, HashMap};
+
+// Namespace of a file and the aliases given in its includes
+#[derive(Clone)]
+pub struct FileScope {
+    pub namespace: NamespaceID,
+    pub aliases: HashMap<String, NamespaceID>,
+}
+
+impl FileScope {
+    pub fn new(namespace: NamespaceID) -> FileScope {
+        FileScope { namespace, aliases: HashMap::new() }
+    }
+}
+
+pub struct Namespaces<'a> {
+    prefixes: &'a [String],
+    scopes: &'a HashMap<FileID, FileScope>,
+}
+
+impl<'a> Namespaces<'a> {
+    pub fn new(prefixes: &'a [String], scopes: &'a HashMap<FileID, FileScope>) -> Namespaces<'a> {
+        Namespaces { prefixes, scopes }
+    }
+
+    fn qualified_name(&self, namespace: NamespaceID, name: &str) -> String {
+        let prefix = &self.prefixes[namespace];
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", prefix, name)
+        }
+    }
+
+    // the names of the own namespace are qualified even if they are not defined
+    // in it, so a missing definition is reported with the name it would have
+    fn resolve(&self, scope: &FileScope, id: &str) -> Option<String> {
+        match id.split_once('.') {
+            Some((alias, name)) => scope.aliases.get(alias).map(|namespace| self.qualified_name(*namespace, name)),
+            None => Some(self.qualified_name(scope.namespace, id)),
+        }
+    }
+
+    // Name of the constant a variable of the file refers to, if it is in
+    // constants, and the accesses that follow it. alias.NAME is parsed as
+    // the access to the component NAME of alias.
+    pub fn constant<'e, T>(
+        &self,
+        file_id: FileID,
+        name: &str,
+        access: &'e [Access],
+        constants: &BTreeMap<String, T>,
+    ) -> Option<(String, &'e [Access])> {
+        let scope = &self.scopes[&file_id];
+        let qualified = self.qualified_name(scope.namespace, name);
+        if constants.contains_key(&qualified) {
+            return Some((qualified, access));
+        }
+        match (scope.aliases.get(name), access.split_first()) {
+            (Some(namespace), Some((Access::ComponentAccess(field), rest))) => {
+                let qualified = self.qualified_name(*namespace, field);
+                constants.contains_key(&qualified).then_some((qualified, rest))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn qualified_constant(&self, file_id: FileID, name: &str) -> String {
+        self.qualified_name(self.scopes[&file_id].namespace, name)
+    }
+}
+
+// Renames the definitions of each namespace to prefix.name and the calls
+// to the names they refer to from the file where they are written
+pub fn resolve_names(
+    prefixes: &[String],
+    scopes: &HashMap<FileID, FileScope>,
+    definitions: &mut [(FileID, Vec<Definition>)],
+    main_components: &mut [(Option<String>, FileID, MainComponent)],
+) -> Result<(), ReportCollection> {
+    let namespaces = Namespaces::new(prefixes, scopes);
+    let mut reports = ReportCollection::new();
+    for (file_id, file_definitions) in definitions.iter_mut() {
+        let scope = &scopes[file_id];
+        for definition in file_definitions {
+            let (name, body, requirements) = match definition {
+                Definition::Template { name, body, requirements, .. } => (name, body, requirements.as_mut_slice()),
+                Definition::Function { name, body, .. } => (name, body, &mut [][..]),
+            };
+            *name = namespaces.qualified_name(scope.namespace, name);
+            visit_statement(body, &mut |expr| resolve_call(&namespaces, scope, *file_id, expr, &mut reports));
+            visit_requirements(requirements, &mut |expr| resolve_call(&namespaces, scope, *file_id, expr, &mut reports));
+        }
+    }
+    for (_, file_id, (_, call)) in main_components.iter_mut() {
+        let scope = &scopes[file_id];
+        visit_expression(call, &mut |expr| resolve_call(&namespaces, scope, *file_id, expr, &mut reports));
+    }
+    if reports.is_empty() {
+        Result::Ok(())
+    } else {
+        Result::Err(reports)
+    }
+}
+
+fn resolve_call(
+    namespaces: &Namespaces,
+    scope: &FileScope,
+    file_id: FileID,
+    expr: &mut Expression,
+    reports: &mut ReportCollection,
+) {
+    let (meta, id) = match expr {
+        Expression::Call { meta, id, .. } | Expression::AnonymousComp { meta, id, .. } => (meta, id),
+        _ => return,
+    };
+    match namespaces.resolve(scope, id) {
+        Some(name) => *id = name,
+        None => {
+            let (alias, _) = id.split_once('.').unwrap();
+            let mut report = Report::error(
+                format!("{} is not the alias of any include of this file", alias),
+                ReportCode::UndefinedIncludeAlias,
+            );
+            report.add_primary(meta.location.clone(), file_id, format!("{} is used here", id));
+            reports.push(report);
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use super::lang;
-use program_structure::ast::{AST};
+use crate::define_logic::Defines;
+use num_bigint::BigInt;
+use program_structure::ast::{Definition, Statement, AST};
 use program_structure::ast::produce_report;
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::{ReportCollection, Report};
@@ -83,14 +85,19 @@ pub fn preprocess(expr: &str, file_id: FileID) -> Result<String, ReportCollectio
     }
 }
 
-pub fn parse_file(src: &str, file_id: FileID) -> Result<AST, ReportCollection> {
+pub fn parse_file(
+    src: &str,
+    file_id: FileID,
+    defines: &Defines,
+    field: &BigInt,
+) -> Result<AST, ReportCollection> {
     use lalrpop_util::ParseError::*;
 
     let mut errors = Vec::new();
     let preprocess = preprocess(src, file_id)?;
 
     let ast = lang::ParseAstParser::new()
-        .parse(file_id, &mut errors, &preprocess)
+        .parse(file_id, &mut errors, defines, field, &preprocess)
         // TODO: is this always fatal?
         .map_err(|parse_error| match parse_error {
             InvalidToken { location } => 
@@ -114,6 +121,13 @@ pub fn parse_file(src: &str, file_id: FileID) -> Result<AST, ReportCollection> {
         })
         .map_err(|e| vec![e])?;
 
+    for definition in &ast.definitions {
+        let body = match definition {
+            Definition::Template { body, .. } | Definition::Function { body, .. } => body,
+        };
+        check_loop_control(body, false, file_id, &mut errors);
+    }
+
     if !errors.is_empty() {
         return Err(errors.into_iter().collect());
     }
@@ -121,6 +135,29 @@ pub fn parse_file(src: &str, file_id: FileID) -> Result<AST, ReportCollection> {
     Ok(ast)
 }
 
+fn check_loop_control(stmt: &Statement, in_loop: bool, file_id: FileID, errors: &mut Vec<Report>) {
+    use Statement::*;
+    match stmt {
+        IfThenElse { if_case, else_case, .. } => {
+            check_loop_control(if_case, in_loop, file_id, errors);
+            if let Some(else_stmt) = else_case {
+                check_loop_control(else_stmt, in_loop, file_id, errors);
+            }
+        }
+        While { stmt, .. } => check_loop_control(stmt, true, file_id, errors),
+        Block { stmts, .. } => stmts.iter().for_each(|stmt| check_loop_control(stmt, in_loop, file_id, errors)),
+        Break { meta } | Continue { meta } if !in_loop => {
+            let mut report = Report::error(
+                "break and continue can only be used inside a loop".to_string(),
+                ReportCode::LoopControlOutsideLoop,
+            );
+            report.add_primary(meta.location.clone(), file_id, "Outside of any loop".to_string());
+            errors.push(report);
+        }
+        _ => {}
+    }
+}
+
 fn produce_generic_report(format: String, token: std::ops::Range<usize>, file_id: usize) -> Report {
     let mut report = Report::error(format, ReportCode::IllegalExpression);
     report.add_primary(token, file_id, "here".to_string());
@@ -1,4 +1,5 @@
 use program_structure::ast::*;
+use program_structure::ast_shortcuts::continue_with_step;
 use program_structure::statement_builders::{build_block, build_substitution};
 use program_structure::error_definition::{Report};
 use program_structure::expression_builders::{build_call, build_tuple, build_parallel_op};
@@ -13,9 +14,10 @@ use num_bigint::BigInt;
 
 pub fn apply_syntactic_sugar(program_archive : &mut  ProgramArchive) -> Result<(), Report> {
     let mut new_templates : HashMap<String, TemplateData> = HashMap::new();
-    if program_archive.get_main_expression().is_anonymous_comp() {
-        return Result::Err(anonymous_general_error(program_archive.get_main_expression().get_meta().clone(),"The main component cannot contain an anonymous call  ".to_string()));
-     
+    for main in program_archive.get_mains() {
+        if main.initial_template_call.is_anonymous_comp() {
+            return Result::Err(anonymous_general_error(main.initial_template_call.get_meta().clone(),"The main component cannot contain an anonymous call  ".to_string()));
+        }
     }
     for temp in program_archive.templates.clone() {
         let t = temp.1.clone();
@@ -33,7 +35,7 @@ pub fn apply_syntactic_sugar(program_archive : &mut  ProgramArchive) -> Result<(
             check_tuples_statement(&new_body_with_inits)?;
             let new_body = remove_tuples_from_statement(new_body_with_inits)?;
             let t2 = TemplateData::copy(t.get_name().to_string(), t.get_file_id(), new_body, t.get_num_of_params(), t.get_name_of_params().clone(),
-                                t.get_param_location(), t.get_inputs().clone(), t.get_outputs().clone(), t.is_parallel(), t.is_custom_gate(), t.get_declaration_inputs().clone(), t.get_declaration_outputs().clone());
+                                t.get_param_location(), t.get_inputs().clone(), t.get_outputs().clone(), t.is_parallel(), t.is_custom_gate(), t.get_declaration_inputs().clone(), t.get_declaration_outputs().clone(), t.get_requirements().clone(), *t.get_budget());
             new_templates.insert(temp.0.clone(), t2);            
         } else{
             unreachable!()
@@ -79,7 +81,7 @@ fn check_anonymous_components_statement(
         }     
         Statement::LogCall {meta, args } => {
             for arg in args {
-                if let program_structure::ast::LogArgument::LogExp( exp ) = arg {
+                for exp in arg.expressions() {
                     if exp.contains_anonymous_comp() {
                         return Result::Err(anonymous_general_error(meta.clone() ,"An anonymous component cannot be used inside a log".to_string()))
                     }
@@ -87,8 +89,9 @@ fn check_anonymous_components_statement(
             }
             Result::Ok(())
         }  
-        Statement::Assert { meta, arg}   => {
-            if arg.contains_anonymous_comp() {
+        Statement::Assert { meta, arg, message }   => {
+            let in_message = message.iter().any(|arg| arg.expressions().iter().any(|exp| exp.contains_anonymous_comp()));
+            if arg.contains_anonymous_comp() || in_message {
                 Result::Err(anonymous_general_error(meta.clone(), "An anonymous component cannot be used inside an assert".to_string()))
             } else{
                 Result::Ok(())
@@ -147,6 +150,7 @@ fn check_anonymous_components_statement(
             check_anonymous_components_expression(rhe)
         }
         Statement::UnderscoreSubstitution { .. } => unreachable!(),
+        Statement::Break { .. } | Statement::Continue { .. } => Result::Ok(()),
     }
 }
 
@@ -321,6 +325,8 @@ fn remove_anonymous_from_statement(
                     rhe: next_access,
                 };
                     
+                let mut while_ok = while_ok;
+                continue_with_step(&mut while_ok, &subs_access);
                 let new_block = Statement::Block{
                     meta: meta.clone(),
                     stmts: vec![while_ok, subs_access],
@@ -334,8 +340,8 @@ fn remove_anonymous_from_statement(
         Statement::LogCall {meta, args } => {
             Result::Ok((build_log_call(meta, args),Vec::new()))
         }  
-        Statement::Assert { meta, arg}   => { 
-            Result::Ok((build_assert(meta, arg),Vec::new()))
+        Statement::Assert { meta, arg, message }   => { 
+            Result::Ok((build_assert(meta, arg, message),Vec::new()))
         }
         Statement::Return {  meta, value: arg}=> {
             Result::Ok((build_return(meta, arg),Vec::new()))
@@ -381,6 +387,7 @@ fn remove_anonymous_from_statement(
             }
         }
         Statement::UnderscoreSubstitution { .. } => unreachable!(),
+        Statement::Break { .. } | Statement::Continue { .. } => Result::Ok((stm, Vec::new())),
     }
 }
 
@@ -608,23 +615,13 @@ fn check_tuples_statement(stm: &Statement)-> Result<(), Report>{
                 check_tuples_statement(stmt)
             }
         }     
-        Statement::LogCall {args, .. } => {
-            for arg in args {
-                match arg {
-                    LogArgument::LogStr(_) => {},
-                    LogArgument::LogExp(exp) => {
-                        check_tuples_expression(&exp)?;
-                    },
-                }
-            }
-            Result::Ok(())
-        }  
-        Statement::Assert { meta, arg}   => { 
+        Statement::LogCall {meta, args } => check_tuples_log_arguments(meta, args),
+        Statement::Assert { meta, arg, message }   => { 
             if arg.contains_tuple(){
                 Result::Err(tuple_general_error(meta.clone(),"A tuple cannot be used in a return ".to_string()))       
             }
             else{ 
-                Result::Ok(())
+                check_tuples_log_arguments(meta, message)
             }
         }
         Statement::Return {  meta, value: arg}=> {
@@ -682,6 +679,7 @@ fn check_tuples_statement(stm: &Statement)-> Result<(), Report>{
             check_tuples_expression(rhe)
         }
         Statement::UnderscoreSubstitution { .. } => unreachable!(),
+        Statement::Break { .. } | Statement::Continue { .. } => Result::Ok(()),
     }
 }
 
@@ -827,20 +825,11 @@ fn remove_tuples_from_statement(stm: Statement) -> Result<Statement, Report> {
             Result::Ok(Statement::While { meta : meta, cond : cond, stmt : b_while})
         }     
         Statement::LogCall {meta, args } => {
-            let mut newargs = Vec::new();
-            for arg in args {
-                match arg {
-                    LogArgument::LogStr(str) => {
-                        newargs.push(LogArgument::LogStr(str));
-                    },
-                    LogArgument::LogExp(exp) => {
-                        let mut args2 = separate_tuple_for_logcall(vec![exp]);
-                        newargs.append(&mut args2);
-                    },
-                }
-            }
-            Result::Ok(build_log_call(meta, newargs))
+            Result::Ok(build_log_call(meta, remove_tuples_from_log_arguments(args)))
         }  
+        Statement::Assert { meta, arg, message } => {
+            Result::Ok(build_assert(meta, arg, remove_tuples_from_log_arguments(message)))
+        }
         Statement::InitializationBlock { meta, xtype, initializations } =>
         {
             let mut new_inits = Vec::new();
@@ -875,6 +864,37 @@ fn remove_tuples_from_statement(stm: Statement) -> Result<Statement, Report> {
     }
 }
 
+fn check_tuples_log_arguments(meta: &Meta, args: &[LogArgument]) -> Result<(), Report> {
+    for arg in args {
+        match arg {
+            LogArgument::LogStr(_) => {},
+            LogArgument::LogExp(exp) => {
+                check_tuples_expression(exp)?;
+            },
+            LogArgument::LogFormat(_) => {
+                if arg.expressions().iter().any(|exp| exp.contains_tuple()) {
+                    return Result::Err(tuple_general_error(meta.clone(),"A tuple cannot be used as the value of a placeholder".to_string()));
+                }
+            },
+        }
+    }
+    Result::Ok(())
+}
+
+fn remove_tuples_from_log_arguments(args: Vec<LogArgument>) -> Vec<LogArgument> {
+    let mut newargs = Vec::new();
+    for arg in args {
+        match arg {
+            LogArgument::LogExp(exp) => {
+                let mut args2 = separate_tuple_for_logcall(vec![exp]);
+                newargs.append(&mut args2);
+            },
+            arg => newargs.push(arg),
+        }
+    }
+    newargs
+}
+
 fn separate_tuple_for_logcall(values: Vec<Expression>) ->  Vec<LogArgument> {
     let mut new_values = Vec::new();
     for val in values {
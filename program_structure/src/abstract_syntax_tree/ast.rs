@@ -1,4 +1,4 @@
-use crate::{file_definition::{FileLocation, FileID}, error_definition::Report, error_code::{ReportCode}};
+use crate::{file_definition::{FileLocation, FileID}, error_definition::Report, error_code::{ReportCode}, budget::Budget};
 use num_bigint::BigInt;
 use serde_derive::{Deserialize, Serialize};
 
@@ -20,6 +20,34 @@ pub fn build_main_component(public: Vec<String>, call: Expression) -> MainCompon
 
 pub type Version = (usize, usize, usize);
 
+#[derive(Clone)]
+pub struct Include {
+    pub meta: Meta,
+    pub path: String,
+    // the definitions of the file are accessed as alias.name
+    pub alias: Option<String>,
+}
+pub fn build_include(meta: Meta, path: String, alias: Option<String>) -> Include {
+    Include { meta, path, alias }
+}
+
+// Top-level const NAME = value, evaluated when the program is parsed
+#[derive(Clone)]
+pub struct Constant {
+    pub meta: Meta,
+    pub name: String,
+    pub value: Expression,
+}
+pub fn build_constant(meta: Meta, name: String, value: Expression) -> Constant {
+    Constant { meta, name, value }
+}
+
+// templates, functions and constants may be written in any order
+pub enum TopLevelItem {
+    Definition(Box<Definition>),
+    Constant(Box<Constant>),
+}
+
 #[derive(Clone)]
 pub struct Meta {
     pub elem_id: usize,
@@ -87,8 +115,9 @@ pub struct AST {
     pub compiler_version: Option<Version>,
     pub custom_gates: bool,
     pub custom_gates_declared: bool,
-    pub includes: Vec<String>,
+    pub includes: Vec<Include>,
     pub definitions: Vec<Definition>,
+    pub constants: Vec<Constant>,
     pub main_component: Option<MainComponent>,
 }
 
@@ -96,8 +125,8 @@ impl AST {
     pub fn new(
         meta: Meta,
         pragmas: Vec<Pragma>,
-        includes: Vec<String>,
-        definitions: Vec<Definition>,
+        includes: Vec<Include>,
+        items: Vec<TopLevelItem>,
         main_component: Option<MainComponent>,
     ) -> (AST,Vec<Report>) {
         let mut custom_gates = None;
@@ -121,6 +150,15 @@ impl AST {
             }
         }
 
+        let mut definitions = Vec::new();
+        let mut constants = Vec::new();
+        for item in items {
+            match item {
+                TopLevelItem::Definition(definition) => definitions.push(*definition),
+                TopLevelItem::Constant(constant) => constants.push(*constant),
+            }
+        }
+
         let custom_gates_declared = definitions.iter().any(|definition| {
             matches!(definition, Definition::Template { is_custom_gate: true, .. })
         });
@@ -132,6 +170,7 @@ impl AST {
             custom_gates_declared,
             includes,
             definitions,
+            constants,
             main_component,
         }, reports)
     }
@@ -147,6 +186,8 @@ pub enum Definition {
         body: Statement,
         parallel: bool,
         is_custom_gate: bool,
+        requirements: Vec<Requirement>,
+        budget: Budget,
     },
     Function {
         meta: Meta,
@@ -161,11 +202,36 @@ pub fn build_template(
     name: String,
     args: Vec<String>,
     arg_location: FileLocation,
+    requirements: Vec<Requirement>,
     body: Statement,
     parallel: bool,
     is_custom_gate: bool,
+    budget: Budget,
 ) -> Definition {
-    Definition::Template { meta, name, args, arg_location, body, parallel, is_custom_gate }
+    Definition::Template { meta, name, args, arg_location, body, parallel, is_custom_gate, requirements, budget }
+}
+
+// Precondition on the parameters of a template, written with require at the
+// beginning of its body. It is checked every time the template is instantiated
+#[derive(Clone)]
+pub struct Requirement {
+    pub meta: Meta,
+    pub condition: Expression,
+    pub message: Vec<LogArgument>,
+}
+
+pub fn build_requirement(meta: Meta, condition: Expression, message: Vec<LogArgument>) -> Requirement {
+    Requirement { meta, condition, message }
+}
+
+impl Requirement {
+    pub fn expressions_mut(&mut self) -> Vec<&mut Expression> {
+        let mut expressions = vec![&mut self.condition];
+        for arg in &mut self.message {
+            expressions.append(&mut arg.expressions_mut());
+        }
+        expressions
+    }
 }
 
 pub fn build_function(
@@ -241,6 +307,14 @@ pub enum Statement {
     Assert {
         meta: Meta,
         arg: Expression,
+        // printed like the arguments of log when the assertion fails
+        message: Vec<LogArgument>,
+    },
+    Break {
+        meta: Meta,
+    },
+    Continue {
+        meta: Meta,
     },
 }
 
@@ -256,7 +330,8 @@ pub type TagList = Vec<String>;
 #[derive(Clone, PartialEq, Ord, PartialOrd, Eq)]
 pub enum VariableType {
     Var,
-    Signal(SignalType, TagList),
+    // the bool is set when the declaration has the attribute #[keep]
+    Signal(SignalType, TagList, bool),
     Component,
     AnonymousComponent,
 }
@@ -382,6 +457,9 @@ pub enum TypeReduction {
 pub enum LogArgument {
     LogStr(String),
     LogExp(Expression),
+    // string with placeholders and the values that replace them, printed
+    // as a single argument
+    LogFormat(Vec<FormatPiece>),
 }
 pub fn build_log_string(acc: String) -> LogArgument {
     LogArgument::LogStr(acc)
@@ -390,6 +468,48 @@ pub fn build_log_expression(expr: Expression) -> LogArgument {
     LogArgument::LogExp(expr)
 }
 
+impl LogArgument {
+    pub fn expressions(&self) -> Vec<&Expression> {
+        match self {
+            LogArgument::LogStr(_) => Vec::new(),
+            LogArgument::LogExp(expr) => vec![expr],
+            LogArgument::LogFormat(pieces) => pieces
+                .iter()
+                .filter_map(|piece| match piece {
+                    FormatPiece::Value(expr, _) => Some(expr.as_ref()),
+                    FormatPiece::Text(_) => None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn expressions_mut(&mut self) -> Vec<&mut Expression> {
+        match self {
+            LogArgument::LogStr(_) => Vec::new(),
+            LogArgument::LogExp(expr) => vec![expr],
+            LogArgument::LogFormat(pieces) => pieces
+                .iter_mut()
+                .filter_map(|piece| match piece {
+                    FormatPiece::Value(expr, _) => Some(expr.as_mut()),
+                    FormatPiece::Text(_) => None,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum NumberFormat {
+    Decimal,
+    Hexadecimal,
+}
+
+#[derive(Clone)]
+pub enum FormatPiece {
+    Text(String),
+    Value(Box<Expression>, NumberFormat),
+}
+
 #[derive(Default, Clone)]
 pub struct TypeKnowledge {
     reduces_to: Option<TypeReduction>,
@@ -487,6 +607,14 @@ impl MemoryKnowledge {
                 report.add_primary(location, file_id, "A semicolon is needed here".to_string());
                 report
             }
+            IncludeAliasDeclaredTwice => {
+                let mut report = Report::error(
+                    "The same alias is given to different included files".to_string(),
+                    ReportCode::IncludeAliasDeclaredTwice,
+                );
+                report.add_primary(location, file_id, "this alias is already in use".to_string());
+                report
+            }
             UnrecognizedInclude => {
                 let mut report =
                 Report::error("unrecognized argument in include directive".to_string(), ReportCode::UnrecognizedInclude);
@@ -519,6 +647,18 @@ impl MemoryKnowledge {
             report.add_primary(location, file_id, "here".to_string());
             report
             },
+            UnrecognizedAttribute => {
+                let mut report =
+                Report::error("unrecognized attribute".to_string(), ReportCode::UnrecognizedAttribute);
+            report.add_primary(location, file_id, "only #[keep] can be given to a signal and #[budget(...)] to a template".to_string());
+            report
+            },
+            InvalidBudget => {
+                let mut report =
+                Report::error("invalid budget".to_string(), ReportCode::InvalidBudget);
+            report.add_primary(location, file_id, "expected #[budget(constraints = n, signals = n)]".to_string());
+            report
+            },
             _ => unreachable!(),    
     };
     report
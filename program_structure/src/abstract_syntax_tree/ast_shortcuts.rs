@@ -44,11 +44,34 @@ pub fn for_into_while(
     step: Statement,
     body: Statement,
 ) -> Statement {
+    let mut body = body;
+    continue_with_step(&mut body, &step);
     let while_body = build_block(body.get_meta().clone(), vec![body, step]);
     let while_statement = build_while_block(meta.clone(), cond, while_body);
     build_block(meta, vec![init, while_statement])
 }
 
+// the continue statements of the body of a for run the step before the
+// condition is checked again
+pub fn continue_with_step(stmt: &mut Statement, step: &Statement) {
+    use Statement::*;
+    match stmt {
+        Continue { meta } => {
+            let meta = meta.clone();
+            *stmt = build_block(meta.clone(), vec![step.clone(), build_continue(meta)]);
+        }
+        IfThenElse { if_case, else_case, .. } => {
+            continue_with_step(if_case, step);
+            if let Some(else_case) = else_case {
+                continue_with_step(else_case, step);
+            }
+        }
+        Block { stmts, .. } => stmts.iter_mut().for_each(|stmt| continue_with_step(stmt, step)),
+        // the continue statements of an inner loop belong to it
+        _ => {}
+    }
+}
+
 pub fn split_declaration_into_single_nodes(
     meta: Meta,
     xtype: VariableType,
@@ -29,6 +29,14 @@ pub fn build_return(meta: Meta, value: Expression) -> Statement {
     Return { meta, value }
 }
 
+pub fn build_break(meta: Meta) -> Statement {
+    Break { meta }
+}
+
+pub fn build_continue(meta: Meta) -> Statement {
+    Continue { meta }
+}
+
 pub fn build_declaration(
     meta: Meta,
     xtype: VariableType,
@@ -59,11 +67,31 @@ pub fn build_log_call(meta: Meta, args: Vec<LogArgument>) -> Statement {
         match arg {
             LogArgument::LogExp(..) => { new_args.push(arg);}
             LogArgument::LogStr(str) => { new_args.append(&mut split_string(str));}
+            LogArgument::LogFormat(pieces) => { new_args.push(LogArgument::LogFormat(split_format(pieces)));}
         }
     }
     LogCall { meta, args: new_args }
 }
 
+// the texts of a format are split like the strings, but the chunks are
+// printed without separators
+fn split_format(pieces: Vec<FormatPiece>) -> Vec<FormatPiece> {
+    let mut new_pieces = Vec::new();
+    for piece in pieces {
+        match piece {
+            FormatPiece::Text(str) => {
+                for chunk in split_string(str) {
+                    if let LogArgument::LogStr(chunk) = chunk {
+                        new_pieces.push(FormatPiece::Text(chunk));
+                    }
+                }
+            }
+            value => new_pieces.push(value),
+        }
+    }
+    new_pieces
+}
+
 fn split_string(str: String) -> Vec<LogArgument> {
     let mut v = vec![];
     let sub_len = 230;
@@ -76,8 +104,12 @@ fn split_string(str: String) -> Vec<LogArgument> {
     v
 }
 
-pub fn build_assert(meta: Meta, arg: Expression) -> Statement {
-    Assert { meta, arg }
+pub fn build_assert(meta: Meta, arg: Expression, message: Vec<LogArgument>) -> Statement {
+    let message = match build_log_call(meta.clone(), message) {
+        LogCall { args, .. } => args,
+        _ => unreachable!(),
+    };
+    Assert { meta, arg, message }
 }
 
 pub fn build_mult_substitution(meta: Meta, lhe: Expression, op : AssignOp, rhe: Expression) -> Statement {
@@ -16,6 +16,7 @@ impl Statement {
             | InitializationBlock { meta, .. } => meta,
             | MultSubstitution { meta, ..} => meta,
             | UnderscoreSubstitution { meta, .. } => meta,
+            | Break { meta } | Continue { meta } => meta,
         }
     }
     pub fn get_mut_meta(&mut self) -> &mut Meta {
@@ -33,6 +34,7 @@ impl Statement {
             | InitializationBlock { meta, .. } => meta,
             | MultSubstitution { meta, ..} => meta,
             | UnderscoreSubstitution { meta, .. } => meta,
+            | Break { meta } | Continue { meta } => meta,
         }
     }
 
@@ -125,6 +127,12 @@ impl Statement {
             false
         }
     }
+    pub fn is_break(&self) -> bool {
+        matches!(self, Statement::Break { .. })
+    }
+    pub fn is_continue(&self) -> bool {
+        matches!(self, Statement::Continue { .. })
+    }
 }
 
 impl FillMeta for Statement {
@@ -156,10 +164,11 @@ impl FillMeta for Statement {
             }
             LogCall { meta, args, .. } => fill_log_call(meta, args, file_id, element_id),
             Block { meta, stmts, .. } => fill_block(meta, stmts, file_id, element_id),
-            Assert { meta, arg, .. } => fill_assert(meta, arg, file_id, element_id),
+            Assert { meta, arg, message } => fill_assert(meta, arg, message, file_id, element_id),
             UnderscoreSubstitution { meta, rhe, .. } => {
                 fill_underscore_substitution(meta, rhe, file_id, element_id);
             },
+            Break { meta } | Continue { meta } => meta.set_file_id(file_id),
             
         }
     }
@@ -266,7 +275,7 @@ fn fill_constraint_equality(
 fn fill_log_call(meta: &mut Meta, args: &mut Vec<LogArgument>, file_id: usize, element_id: &mut usize) {
     meta.set_file_id(file_id);
     for arg in args {
-        if let LogArgument::LogExp(e) = arg {
+        for e in arg.expressions_mut() {
             e.fill(file_id, element_id);
         }
     }
@@ -279,9 +288,15 @@ fn fill_block(meta: &mut Meta, stmts: &mut [Statement], file_id: usize, element_
     }
 }
 
-fn fill_assert(meta: &mut Meta, arg: &mut Expression, file_id: usize, element_id: &mut usize) {
-    meta.set_file_id(file_id);
+fn fill_assert(
+    meta: &mut Meta,
+    arg: &mut Expression,
+    message: &mut Vec<LogArgument>,
+    file_id: usize,
+    element_id: &mut usize,
+) {
     arg.fill(file_id, element_id);
+    fill_log_call(meta, message, file_id, element_id);
 }
 
 fn fill_underscore_substitution(meta: &mut Meta, rhe: &mut Expression, file_id: usize, element_id: &mut usize) {
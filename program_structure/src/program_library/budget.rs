@@ -0,0 +1,114 @@
+use crate::error_code::ReportCode;
+use crate::error_definition::Report;
+use std::collections::BTreeMap;
+
+// Maximum number of constraints and signals of each instance of a template,
+// counting the ones of its subcomponents, or of the whole circuit
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Budget {
+    pub constraints: Option<usize>,
+    pub signals: Option<usize>,
+}
+
+impl Budget {
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_none() && self.signals.is_none()
+    }
+
+    // the limits of other replace the ones of self
+    pub fn overridden_by(&self, other: &Budget) -> Budget {
+        Budget {
+            constraints: other.constraints.or(self.constraints),
+            signals: other.signals.or(self.signals),
+        }
+    }
+
+    pub fn is_exceeded(&self, constraints: usize, signals: usize) -> bool {
+        self.constraints.is_some_and(|limit| constraints > limit) || self.signals.is_some_and(|limit| signals > limit)
+    }
+
+    // stage is when the budget is checked, after the execution or after the
+    // simplification. The ones checked again later are warnings.
+    pub fn report(&self, overrun: &Overrun, stage: &str, is_error: bool) -> Report {
+        let message = match &overrun.template {
+            Some(template) => format!("The instance {} of {} exceeds its budget {}", overrun.path, template, stage),
+            None => format!("The circuit exceeds its budget {}", stage),
+        };
+        let mut report = if is_error {
+            Report::error(message, ReportCode::BudgetExceeded)
+        } else {
+            Report::warning(message, ReportCode::BudgetExceeded)
+        };
+        let limits = [("constraints", overrun.constraints, self.constraints), ("signals", overrun.signals, self.signals)];
+        for (kind, value, limit) in limits {
+            if let Some(limit) = limit.filter(|limit| value > *limit) {
+                report.add_note(format!("It has {} {} and the budget is {}", value, kind, limit));
+            }
+        }
+        if let Some(template) = &overrun.template {
+            if overrun.instances > 1 {
+                report.add_note(format!(
+                    "{} of the {} instances of {} exceed the budget",
+                    overrun.instances_over, overrun.instances, template
+                ));
+            }
+        }
+        if !is_error {
+            report.add_note("The budget is checked again after the simplification".to_string());
+        }
+        report
+    }
+}
+
+// First instance of a template, or the whole circuit when there is no
+// template, that exceeds its budget
+pub struct Overrun {
+    pub template: Option<String>,
+    pub path: String,
+    pub constraints: usize,
+    pub signals: usize,
+    // instances of the template that exceed the budget and in total
+    pub instances_over: usize,
+    pub instances: usize,
+}
+
+// Budgets given with #[budget(...)] in the templates and in the manifest
+#[derive(Clone, Default)]
+pub struct Budgets {
+    pub templates: BTreeMap<String, Budget>,
+    pub circuit: Budget,
+}
+
+impl Budgets {
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty() && self.circuit.is_empty()
+    }
+
+    // the instances are named with their parameters, like Tree(20)
+    pub fn template_of(instance: &str) -> &str {
+        instance.split('(').next().unwrap_or(instance)
+    }
+
+    pub fn of_instance(&self, instance: &str) -> Option<(&String, &Budget)> {
+        self.templates.get_key_value(Budgets::template_of(instance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Budget, Budgets};
+
+    #[test]
+    fn budget_limits() {
+        let annotation = Budget { constraints: Some(10), signals: Some(20) };
+        let manifest = Budget { constraints: None, signals: Some(5) };
+        let budget = annotation.overridden_by(&manifest);
+        assert_eq!(budget, Budget { constraints: Some(10), signals: Some(5) });
+        assert!(!budget.is_exceeded(10, 5));
+        assert!(budget.is_exceeded(11, 5));
+        assert!(budget.is_exceeded(0, 6));
+        assert!(!Budget::default().is_exceeded(usize::MAX, usize::MAX));
+        assert_eq!(Budgets::template_of("Tree(20, 2)"), "Tree");
+        assert_eq!(Budgets::template_of("Main()"), "Main");
+    }
+}
@@ -13,6 +13,16 @@ pub enum ReportCode {
     UnrecognizedVersion,
     UnrecognizedPragma,
     IncludeNotFound,
+    IncludeAliasDeclaredTwice,
+    UndefinedIncludeAlias,
+    ManifestError,
+    InvalidStaticCondition,
+    InvalidConstant,
+    ConstantDeclaredTwice,
+    LoopControlOutsideLoop,
+    InvalidFormatString,
+    UnrecognizedAttribute,
+    InvalidBudget,
     IllegalExpression,
     MultiplePragma,
     CompilerVersionError,
@@ -47,6 +57,7 @@ pub enum ReportCode {
     NonEqualTypesInExpression,
     NonExistentSymbol,
     MainComponentWithTags,
+    InvalidRequirement,
     TemplateCallAsArgument,
     TemplateWrongNumberOfArguments,
     TemplateWithReturnStatement,
@@ -79,6 +90,7 @@ pub enum ReportCode {
     ExpectedDimDiffGotDim(usize, usize),
     RuntimeError,
     RuntimeWarning,
+    UnsatisfiedRequirement,
     UnknownTemplate,
     NonQuadratic,
     NonConstantArrayLength,
@@ -87,6 +99,7 @@ pub enum ReportCode {
     UnconstrainedSignal,
     OneConstraintIntermediate,
     NoOutputInInstance,
+    BudgetExceeded,
     ErrorWat2Wasm,
     CustomGateIntermediateSignalWarning,
     CustomGateConstraintError,
@@ -115,6 +128,16 @@ impl fmt::Display for ReportCode {
             IllegalExpression => "P1012",
             MultiplePragma => "P1013",
             IncludeNotFound => "P1014",
+            IncludeAliasDeclaredTwice => "P1015",
+            UndefinedIncludeAlias => "P1016",
+            ManifestError => "P1017",
+            InvalidStaticCondition => "P1018",
+            InvalidConstant => "P1019",
+            ConstantDeclaredTwice => "P1020",
+            LoopControlOutsideLoop => "P1021",
+            InvalidFormatString => "P1022",
+            UnrecognizedAttribute => "P1023",
+            InvalidBudget => "P1024",
             WrongTypesInAssignOperation => "T2000",
             UndefinedFunction => "T2001",
             UndefinedTemplate => "T2002",
@@ -173,8 +196,10 @@ impl fmt::Display for ReportCode {
             UnreachableTags => "T2049",
             UnreachableSignals => "T2050",
             MainComponentWithTags => "T2051",
+            InvalidRequirement => "T2052",
             RuntimeError => "T3001",
             RuntimeWarning => "T3002",
+            UnsatisfiedRequirement => "T3003",
             UnknownDimension => "T20460",
             UnknownTemplate => "T20461",
             NonQuadratic => "T20462",
@@ -185,6 +210,7 @@ impl fmt::Display for ReportCode {
             UnconstrainedSignal => "CA01",
             OneConstraintIntermediate => "CA02",
             NoOutputInInstance => "CA03",
+            BudgetExceeded => "CA04",
             ErrorWat2Wasm => "W01",
             CustomGateIntermediateSignalWarning => "CG01",
             CustomGateConstraintError => "CG02",
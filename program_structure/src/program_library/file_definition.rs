@@ -31,6 +31,9 @@ impl FileLibrary {
             None => None,
         }
     }
+    pub fn get_filename(&self, file_id: FileID) -> Option<String> {
+        self.files.get(file_id).map(|file| file.name().clone())
+    }
     pub fn to_storage(&self) -> &FileStorage {
         &self.get_files()
     }
@@ -1,4 +1,5 @@
 use super::ast;
+pub mod budget;
 pub mod error_code;
 pub mod error_definition;
 pub mod file_definition;
@@ -9,24 +9,32 @@ use crate::error_definition::Report;
 
 type Contents = Vec<(FileID, Vec<Definition>)>;
 
+// A main component of the program. The name is given to the outputs when
+// the main was chosen by name or there are several of them.
+#[derive(Clone)]
+pub struct MainInfo {
+    pub name: Option<String>,
+    pub file_id: FileID,
+    pub public_inputs: Vec<String>,
+    pub initial_template_call: Expression,
+}
+
 #[derive(Clone)]
 pub struct ProgramArchive {
     pub id_max: usize,
-    pub file_id_main: FileID,
+    pub mains: Vec<MainInfo>,
+    pub selected_main: usize,
     pub file_library: FileLibrary,
     pub functions: FunctionInfo,
     pub templates: TemplateInfo,
     pub function_keys: HashSet<String>,
     pub template_keys: HashSet<String>,
-    pub public_inputs: Vec<String>,
-    pub initial_template_call: Expression,
     pub custom_gates: bool,
 }
 impl ProgramArchive {
     pub fn new(
         file_library: FileLibrary,
-        file_id_main: FileID,
-        main_component: MainComponent,
+        main_components: Vec<(Option<String>, FileID, MainComponent)>,
         program_contents: Contents,
         custom_gates: bool,
     ) -> Result<ProgramArchive, (FileLibrary, Vec<Report>)> {
@@ -46,17 +54,19 @@ impl ProgramArchive {
         for key in templates.keys() {
             template_keys.insert(key.clone());
         }
-        let (public_inputs, mut initial_template_call) = main_component;
-        initial_template_call.fill(file_id_main, &mut fresh_id);
+        let mut mains = Vec::new();
+        for (name, file_id, (public_inputs, mut initial_template_call)) in main_components {
+            initial_template_call.fill(file_id, &mut fresh_id);
+            mains.push(MainInfo { name, file_id, public_inputs, initial_template_call });
+        }
         if reports.is_empty() {
             Ok(ProgramArchive {
                 id_max: fresh_id,
-                file_id_main,
+                mains,
+                selected_main: 0,
                 file_library,
                 functions,
                 templates,
-                public_inputs,
-                initial_template_call,
                 function_keys,
                 template_keys,
                 custom_gates,
@@ -68,7 +78,7 @@ impl ProgramArchive {
     }
     //file_id_main
     pub fn get_file_id_main(&self) -> &FileID {
-        &self.file_id_main
+        &self.mains[self.selected_main].file_id
     }
     //template functions
     pub fn contains_template(&self, template_name: &str) -> bool {
@@ -125,10 +135,18 @@ impl ProgramArchive {
 
     //main_component functions
     pub fn get_public_inputs_main_component(&self) -> &Vec<String> {
-        &self.public_inputs
+        &self.mains[self.selected_main].public_inputs
     }
     pub fn get_main_expression(&self) -> &Expression {
-        &self.initial_template_call
+        &self.mains[self.selected_main].initial_template_call
+    }
+    pub fn get_mains(&self) -> &Vec<MainInfo> {
+        &self.mains
+    }
+    // the main component used by the getters above and by the execution
+    pub fn select_main(&mut self, index: usize) {
+        assert!(index < self.mains.len());
+        self.selected_main = index;
     }
     // FileLibrary functions
     pub fn get_file_library(&self) -> &FileLibrary {
@@ -29,7 +29,7 @@ impl Merger {
         let mut reports = vec![];
         for definition in definitions {
             let (name, meta) = match definition {
-                Definition::Template { name, args, arg_location, body, meta, parallel, is_custom_gate } => {
+                Definition::Template { name, args, arg_location, body, meta, parallel, is_custom_gate, requirements, budget } => {
                     if self.contains_function(&name) || self.contains_template(&name) {
                         (Option::Some(name), meta)
                     } else {
@@ -43,6 +43,8 @@ impl Merger {
                             &mut self.fresh_id,
                             parallel,
                             is_custom_gate,
+                            requirements,
+                            budget,
                         );
                         self.get_mut_template_info().insert(name.clone(), new_data);
                         (Option::None, meta)
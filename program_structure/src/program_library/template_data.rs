@@ -1,5 +1,6 @@
 use super::ast;
-use super::ast::{FillMeta, Statement};
+use super::ast::{FillMeta, Requirement, Statement};
+use super::budget::Budget;
 use super::file_definition::FileID;
 use crate::file_definition::FileLocation;
 use std::collections::{HashMap, HashSet, BTreeMap};
@@ -24,6 +25,8 @@ pub struct TemplateData {
     /* Only used to know the order in which signals are declared.*/
     input_declarations: SignalDeclarationOrder,
     output_declarations: SignalDeclarationOrder,
+    requirements: Vec<Requirement>,
+    budget: Budget,
 }
 
 impl TemplateData {
@@ -37,8 +40,16 @@ impl TemplateData {
         elem_id: &mut usize,
         is_parallel: bool,
         is_custom_gate: bool,
+        mut requirements: Vec<Requirement>,
+        budget: Budget,
     ) -> TemplateData {
         body.fill(file_id, elem_id);
+        for requirement in &mut requirements {
+            requirement.meta.set_file_id(file_id);
+            for expression in requirement.expressions_mut() {
+                expression.fill(file_id, elem_id);
+            }
+        }
         let mut input_signals = SignalInfo::new();
         let mut output_signals = SignalInfo::new();
         let mut input_declarations =  SignalDeclarationOrder::new();
@@ -56,7 +67,9 @@ impl TemplateData {
             is_parallel,
             is_custom_gate,
             input_declarations,
-            output_declarations
+            output_declarations,
+            requirements,
+            budget,
         }
     }
 
@@ -72,7 +85,9 @@ impl TemplateData {
         is_parallel: bool,
         is_custom_gate: bool,
         input_declarations :SignalDeclarationOrder,
-        output_declarations : SignalDeclarationOrder
+        output_declarations : SignalDeclarationOrder,
+        requirements: Vec<Requirement>,
+        budget: Budget,
     ) -> TemplateData {
         TemplateData {
             name,
@@ -86,7 +101,9 @@ impl TemplateData {
             is_parallel,
             is_custom_gate,
             input_declarations,
-            output_declarations
+            output_declarations,
+            requirements,
+            budget,
         }
     }
     pub fn get_file_id(&self) -> FileID {
@@ -104,6 +121,15 @@ impl TemplateData {
     pub fn get_mut_body(&mut self) -> &mut Statement {
         &mut self.body
     }
+    pub fn get_requirements(&self) -> &Vec<Requirement> {
+        &self.requirements
+    }
+    pub fn get_mut_requirements(&mut self) -> &mut Vec<Requirement> {
+        &mut self.requirements
+    }
+    pub fn get_budget(&self) -> &Budget {
+        &self.budget
+    }
     pub fn get_mut_body_as_vec(&mut self) -> &mut Vec<Statement> {
         match &mut self.body {
             Statement::Block { stmts, .. } => stmts,
@@ -176,7 +202,7 @@ fn fill_inputs_and_outputs(
             }
         }
         Statement::Declaration { xtype, name, dimensions, .. } => {
-            if let ast::VariableType::Signal(stype, tag_list) = xtype {
+            if let ast::VariableType::Signal(stype, tag_list, _) = xtype {
                 let signal_name = name.clone();
                 let dim = dimensions.len();
                 let mut tag_info = HashSet::new();
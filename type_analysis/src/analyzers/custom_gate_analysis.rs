@@ -31,7 +31,7 @@ pub fn custom_gate_analysis(
             Declaration { meta, xtype, name, .. } => {
                 use VariableType::*;
                 match xtype {
-                    Signal(SignalType::Intermediate, _) => {
+                    Signal(SignalType::Intermediate, ..) => {
                         let mut warning = Report::warning(
                             String::from("Intermediate signal inside custom template"),
                             ReportCode::CustomGateIntermediateSignalWarning
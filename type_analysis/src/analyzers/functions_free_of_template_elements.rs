@@ -28,6 +28,7 @@ fn analyse_statement(
     let file_id = stmt.get_meta().get_file_id();
     match stmt {
         MultSubstitution { .. } => unreachable!(),
+        Break { .. } | Continue { .. } => {}
         IfThenElse { cond, if_case, else_case, .. } => {
             analyse_expression(cond, function_names, reports);
             analyse_statement(if_case, function_names, reports);
@@ -104,13 +105,18 @@ fn analyse_statement(
         }
         LogCall { args, .. } => {
             for logarg in args {
-                if let LogArgument::LogExp(arg) = logarg {
+                for arg in logarg.expressions() {
                     analyse_expression(arg, function_names, reports);
                 }
             }
         }
-        Assert { arg, .. } => {
+        Assert { arg, message, .. } => {
             analyse_expression(arg, function_names, reports);
+            for logarg in message {
+                for arg in logarg.expressions() {
+                    analyse_expression(arg, function_names, reports);
+                }
+            }
         }
         Return { value, .. } => {
             analyse_expression(value, function_names, reports);
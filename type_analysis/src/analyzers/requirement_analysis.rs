@@ -0,0 +1,98 @@
+use program_structure::ast::{Access, Expression, Requirement};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::{self, FileID};
+use program_structure::program_archive::ProgramArchive;
+use program_structure::template_data::TemplateData;
+
+// The requirements are evaluated before the body of the template, so they can
+// only use the parameters and call functions
+pub fn check_requirements(
+    template_data: &TemplateData,
+    program_archive: &ProgramArchive,
+) -> Result<(), ReportCollection> {
+    let file_id = template_data.get_file_id();
+    let params = template_data.get_name_of_params();
+    let mut reports = ReportCollection::new();
+    for requirement in template_data.get_requirements() {
+        let Requirement { condition, message, .. } = requirement;
+        let mut expressions = vec![condition];
+        for arg in message {
+            expressions.append(&mut arg.expressions());
+        }
+        for expression in expressions {
+            check_expression(expression, params, program_archive, file_id, &mut reports);
+        }
+    }
+    if reports.is_empty() {
+        Result::Ok(())
+    } else {
+        Result::Err(reports)
+    }
+}
+
+fn check_expression(
+    expression: &Expression,
+    params: &[String],
+    program_archive: &ProgramArchive,
+    file_id: FileID,
+    reports: &mut ReportCollection,
+) {
+    use Expression::*;
+    match expression {
+        InfixOp { lhe, rhe, .. } => {
+            check_expression(lhe, params, program_archive, file_id, reports);
+            check_expression(rhe, params, program_archive, file_id, reports);
+        }
+        PrefixOp { rhe, .. } => check_expression(rhe, params, program_archive, file_id, reports),
+        InlineSwitchOp { cond, if_true, if_false, .. } => {
+            check_expression(cond, params, program_archive, file_id, reports);
+            check_expression(if_true, params, program_archive, file_id, reports);
+            check_expression(if_false, params, program_archive, file_id, reports);
+        }
+        Variable { meta, name, access } => {
+            if !params.contains(name) {
+                let message = format!("{} is not a parameter of the template", name);
+                add_report(meta.get_start(), meta.get_end(), message, file_id, reports);
+            }
+            for acc in access {
+                match acc {
+                    Access::ArrayAccess(index) => check_expression(index, params, program_archive, file_id, reports),
+                    Access::ComponentAccess(_) => {
+                        let message = "Components can not be accessed in a requirement".to_string();
+                        add_report(meta.get_start(), meta.get_end(), message, file_id, reports);
+                    }
+                }
+            }
+        }
+        Number(..) => {}
+        Call { meta, id, args } => {
+            if !program_archive.contains_function(id) {
+                let message = "Only functions can be called in a requirement".to_string();
+                add_report(meta.get_start(), meta.get_end(), message, file_id, reports);
+            }
+            for arg in args {
+                check_expression(arg, params, program_archive, file_id, reports);
+            }
+        }
+        ArrayInLine { values, .. } => {
+            for value in values {
+                check_expression(value, params, program_archive, file_id, reports);
+            }
+        }
+        UniformArray { value, dimension, .. } => {
+            check_expression(value, params, program_archive, file_id, reports);
+            check_expression(dimension, params, program_archive, file_id, reports);
+        }
+        ParallelOp { meta, .. } | AnonymousComp { meta, .. } | Tuple { meta, .. } => {
+            let message = "This expression can not be used in a requirement".to_string();
+            add_report(meta.get_start(), meta.get_end(), message, file_id, reports);
+        }
+    }
+}
+
+fn add_report(start: usize, end: usize, message: String, file_id: FileID, reports: &mut ReportCollection) {
+    let mut report = Report::error("Invalid requirement".to_string(), ReportCode::InvalidRequirement);
+    report.add_primary(file_definition::generate_file_location(start, end), file_id, message);
+    reports.push(report);
+}
@@ -1,9 +1,9 @@
-use program_structure::ast::{Access, Expression, Meta, Statement, LogArgument};
+use program_structure::ast::{Access, Expression, Meta, Statement};
 use program_structure::error_code::ReportCode;
 use program_structure::error_definition::{Report, ReportCollection};
 use program_structure::file_definition::{self, FileID, FileLocation};
 use program_structure::function_data::FunctionInfo;
-use program_structure::program_archive::ProgramArchive;
+use program_structure::program_archive::{MainInfo, ProgramArchive};
 use program_structure::template_data::TemplateInfo;
 use std::collections::HashSet;
 type Block = HashSet<String>;
@@ -32,8 +32,10 @@ pub fn check_naming_correctness(program_archive: &ProgramArchive) -> Result<(),
         );
         instances.push(instance);
     }
-    if let Err(mut r) = analyze_main(program_archive) {
-        reports.append(&mut r);
+    for main in program_archive.get_mains() {
+        if let Err(mut r) = analyze_main(program_archive, main) {
+            reports.append(&mut r);
+        }
     }
     for (file_id, param_location, params_names, body) in instances {
         let res = analyze_symbols(
@@ -55,9 +57,9 @@ pub fn check_naming_correctness(program_archive: &ProgramArchive) -> Result<(),
     }
 }
 
-fn analyze_main(program: &ProgramArchive) -> Result<(), Vec<Report>> {
-    let call = program.get_main_expression();
-    let signals = program.get_public_inputs_main_component();
+fn analyze_main(program: &ProgramArchive, main: &MainInfo) -> Result<(), Vec<Report>> {
+    let call = &main.initial_template_call;
+    let signals = &main.public_inputs;
     let template_info = program.get_templates();
     let function_info = program.get_functions();
 
@@ -165,6 +167,7 @@ fn analyze_statement(
 ) {
     match stmt {
         Statement::MultSubstitution { .. } => unreachable!(),
+        Statement::Break { .. } | Statement::Continue { .. } => {}
         Statement::Return { value, .. } => {
             analyze_expression(value, file_id, function_info, template_info, reports, environment)
         }
@@ -226,13 +229,18 @@ fn analyze_statement(
         }
         Statement::LogCall { args, .. } => {
             for logarg in args {
-                if let LogArgument::LogExp(arg) = logarg {
+                for arg in logarg.expressions() {
                     analyze_expression(arg, file_id, function_info, template_info, reports, environment);
                 }
             }
         }
-        Statement::Assert { arg, .. } => {
-            analyze_expression(arg, file_id, function_info, template_info, reports, environment)
+        Statement::Assert { arg, message, .. } => {
+            analyze_expression(arg, file_id, function_info, template_info, reports, environment);
+            for logarg in message {
+                for arg in logarg.expressions() {
+                    analyze_expression(arg, file_id, function_info, template_info, reports, environment);
+                }
+            }
         }
         Statement::Block { stmts, .. } => {
             environment.push(Block::new());
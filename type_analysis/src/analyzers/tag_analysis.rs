@@ -1,238 +1,346 @@
-// use program_structure::ast::{
-//     Access, AssignOp, Expression, ExpressionPrefixOpcode, Meta, SignalElementType, SignalType,
-//     Statement, VariableType,
-// };
-// use program_structure::environment::CircomEnvironment;
-// use program_structure::error_code::ReportCode;
-// use program_structure::error_definition::{Report, ReportCollection};
-// use program_structure::file_definition::{generate_file_location, FileID};
-// use program_structure::program_archive::ProgramArchive;
-// use program_structure::template_data::TemplateData;
-// use std::collections::HashMap;
-
-// type TemplateInfo = HashMap<String, TemplateData>;
-// type Environment = CircomEnvironment<Option<String>, SignalElementType, SignalElementType>;
-// enum ExpressionResult {
-//     ArithmeticExpression(SignalElementType),
-//     Template(Option<String>),
-// }
-
-// pub fn tag_analysis(
-//     template_name: &str,
-//     program_archive: &ProgramArchive,
-// ) -> Result<(), ReportCollection> {
-//     let template_body = program_archive.get_template_data(template_name).get_body_as_vec();
-//     let file_id = program_archive.get_template_data(template_name).get_file_id();
-//     let template_info = program_archive.get_templates();
-
-//     let mut environment = Environment::new();
-//     let args = program_archive.get_template_data(template_name).get_name_of_params().clone();
-//     for arg in args.iter() {
-//         environment.add_variable(arg, SignalElementType::FieldElement);
-//     }
-
-//     let mut reports = ReportCollection::new();
-//     treat_sequence_of_statements(
-//         template_body,
-//         file_id,
-//         template_info,
-//         &mut reports,
-//         &mut environment,
-//     );
-//     if reports.is_empty() {
-//         Result::Ok(())
-//     } else {
-//         Result::Err(reports)
-//     }
-// }
-
-// fn statement_inspection(
-//     stmt: &Statement,
-//     file_id: FileID,
-//     template_info: &TemplateInfo,
-//     reports: &mut ReportCollection,
-//     environment: &mut Environment,
-// ) {
-//     use Statement::*;
-//     match stmt {
-//         IfThenElse { if_case, else_case, .. } => {
-//             statement_inspection(if_case, file_id, template_info, reports, environment);
-//             if let Option::Some(else_stmt) = else_case {
-//                 statement_inspection(else_stmt, file_id, template_info, reports, environment);
-//             }
-//         }
-//         While { stmt, .. } => {
-//             statement_inspection(stmt, file_id, template_info, reports, environment);
-//         }
-//         Block { stmts, .. } => {
-//             environment.add_variable_block();
-//             treat_sequence_of_statements(stmts, file_id, template_info, reports, environment);
-//             environment.remove_variable_block();
-//         }
-//         InitializationBlock { initializations, .. } => {
-//             treat_sequence_of_statements(
-//                 initializations,
-//                 file_id,
-//                 template_info,
-//                 reports,
-//                 environment,
-//             );
-//         }
-//         Declaration { xtype, name, meta, .. } => {
-//             use SignalType::*;
-//             use VariableType::*;
-//             match xtype {
-//                 Var => environment.add_variable(name, SignalElementType::FieldElement),
-//                 Component => environment.add_component(name, meta.component_inference.clone()),
-//                 Signal(signal_type, signal_element) => match signal_type {
-//                     Output => environment.add_output(name, *signal_element),
-//                     Intermediate => environment.add_intermediate(name, *signal_element),
-//                     Input => environment.add_input(name, *signal_element),
-//                 },
-//             }
-//         }
-//         Substitution { meta, var, access, rhe, op, .. } => {
-//             use ExpressionResult::*;
-//             let var_info = variable_inspection(var, access, environment, template_info);
-//             let rhe_info = expression_inspection(rhe, template_info, reports, environment);
-//             match (var_info, rhe_info) {
-//                 (Template(_), Template(assign)) => {
-//                     *environment.get_mut_component_or_break(var, file!(), line!()) = assign;
-//                 }
-//                 (ArithmeticExpression(tag_0), ArithmeticExpression(tag_1))
-//                     if tag_0 == SignalElementType::Binary
-//                         && tag_1 == SignalElementType::FieldElement
-//                         && *op == AssignOp::AssignConstraintSignal =>
-//                 {
-//                     add_report(ReportCode::WrongSignalTags, meta, file_id, reports)
-//                 }
-//                 _ => {}
-//             }
-//         }
-//         _ => {}
-//     }
-// }
-
-      
-// fn expression_inspection(
-//     expr: &Expression,
-//     template_info: &TemplateInfo,
-//     reports: &mut ReportCollection,
-//     environment: &Environment,
-// ) -> ExpressionResult {
-//     use Expression::*;
-//     match expr {
-//         InfixOp { .. } => ExpressionResult::ArithmeticExpression(SignalElementType::FieldElement),
-//         PrefixOp { rhe, prefix_op, .. } => {
-//             let rhe_info = expression_inspection(rhe, template_info, reports, environment);
-//             match prefix_op {
-//                 ExpressionPrefixOpcode::BoolNot => rhe_info,
-//                 _ => ExpressionResult::ArithmeticExpression(SignalElementType::FieldElement),
-//             }
-//         }
-//         InlineSwitchOp { if_true, if_false, .. } => {
-//             let if_true_info = expression_inspection(if_true, template_info, reports, environment);
-//             let if_false_info =
-//                 expression_inspection(if_false, template_info, reports, environment);
-//             match (&if_true_info, &if_false_info) {
-//                 (
-//                     ExpressionResult::ArithmeticExpression(tag_0),
-//                     ExpressionResult::ArithmeticExpression(tag_1),
-//                 ) => {
-//                     if let SignalElementType::FieldElement = tag_0 {
-//                         if_true_info
-//                     } else if let SignalElementType::FieldElement = tag_1 {
-//                         if_false_info
-//                     } else {
-//                         if_true_info
-//                     }
-//                 }
-//                 _ => if_true_info,
-//             }
-//         }
-//         Variable { name, access, .. } => {
-//             variable_inspection(name, access, environment, template_info)
-//         }
-//         Number(..) => ExpressionResult::ArithmeticExpression(SignalElementType::FieldElement),
-//         Call { id, .. } => {
-//             if template_info.contains_key(id) {
-//                 ExpressionResult::Template(Option::Some(id.clone()))
-//             } else {
-//                 ExpressionResult::ArithmeticExpression(SignalElementType::FieldElement)
-//             }
-//         }
-//         ArrayInLine { .. } => {
-//             ExpressionResult::ArithmeticExpression(SignalElementType::FieldElement)
-//         }
-//         UniformArray { .. } => {
-//             ExpressionResult::ArithmeticExpression(SignalElementType::FieldElement)
-//         }
-//         _ => {unreachable!("Anonymous calls should not be reachable at this point."); }
-//     }
-// }
-
-// //************************************************* Statement support *************************************************
-// fn treat_sequence_of_statements(
-//     stmts: &[Statement],
-//     file_id: FileID,
-//     template_info: &TemplateInfo,
-//     reports: &mut ReportCollection,
-//     environment: &mut Environment,
-// ) {
-//     for stmt in stmts {
-//         statement_inspection(stmt, file_id, template_info, reports, environment);
-//     }
-// }
-// //************************************************* Expression support *************************************************
-
-// fn variable_inspection(
-//     symbol: &str,
-//     accesses: &[Access],
-//     environment: &Environment,
-//     template_info: &TemplateInfo,
-// ) -> ExpressionResult {
-//     use ExpressionResult::*;
-//     let mut result = if environment.has_component(symbol) {
-//         Template(environment.get_component_or_break(symbol, file!(), line!()).clone())
-//     } else if environment.has_signal(symbol) {
-//         ArithmeticExpression(*environment.get_signal_or_break(symbol, file!(), line!()))
-//     } else {
-//         ArithmeticExpression(SignalElementType::FieldElement)
-//     };
-
-//     for access in accesses {
-//         if let Access::ComponentAccess(signal) = access {
-//             let template =
-//                 environment.get_component_or_break(symbol, file!(), line!()).clone().unwrap();
-//             let input = template_info.get(&template).unwrap().get_input_info(signal);
-//             let output = template_info.get(&template).unwrap().get_output_info(signal);
-//             match (input, output) {
-//                 (Some((_, tag)), _) | (_, Some((_, tag))) => {
-//                     result = ArithmeticExpression(*tag);
-//                 }
-//                 _ => {
-//                     unreachable!()
-//                 }
-//             }
-//         }
-//     }
-//     result
-// }
-
-// //************************************************* Report support *************************************************
-// fn add_report(
-//     error_code: ReportCode,
-//     meta: &Meta,
-//     file_id: FileID,
-//     reports: &mut ReportCollection,
-// ) {
-//     use ReportCode::*;
-//     let mut report = Report::error("Typing error found".to_string(), error_code);
-//     let location = generate_file_location(meta.start, meta.end);
-//     let message = match error_code {
-//         WrongSignalTags => "Can not assign Field values to signals tagged as binary".to_string(),
-//         _ => panic!("Unimplemented error code"),
-//     };
-//     report.add_primary(location, file_id, message);
-//     reports.push(report);
-// }
+use program_structure::ast::{Access, AssignOp, Expression, Meta, SignalType, Statement, VariableType};
+use program_structure::error_code::ReportCode;
+use program_structure::error_definition::{Report, ReportCollection};
+use program_structure::file_definition::FileID;
+use program_structure::program_archive::ProgramArchive;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+// Checks before the instantiation of the circuit that the values assigned to
+// the inputs of the components carry the tags required by their declarations.
+// The tags of a value are approximated from above: an error is only reported
+// when no execution of the template can give the value the required tag.
+// As in the execution, the signals of a component only have the tags of
+// their declarations, while the signals and variables of the template also
+// inherit the tags of the values assigned to them.
+pub fn tag_analysis(program_archive: &ProgramArchive) -> Result<(), ReportCollection> {
+    let mut reports = ReportCollection::new();
+    let mut template_names: Vec<_> = program_archive.get_template_names().iter().cloned().collect();
+    template_names.sort();
+    for template_name in template_names {
+        let state = analyze(program_archive, &template_name);
+        let file_id = program_archive.get_template_data(&template_name).get_file_id();
+        check_input_assignments(program_archive, &state, file_id, &mut reports);
+    }
+    if reports.is_empty() {
+        Result::Ok(())
+    } else {
+        Result::Err(reports)
+    }
+}
+
+// Tags that a value may carry once the circuit is instantiated. The tags are
+// only propagated by the direct assignment of signals (<==) and variables (=),
+// the result of any operation or function call has no tags.
+#[derive(Clone, PartialEq, Eq)]
+enum PossibleTags {
+    Any,
+    Only(BTreeSet<String>),
+}
+
+impl PossibleTags {
+    fn none() -> PossibleTags {
+        PossibleTags::Only(BTreeSet::new())
+    }
+
+    fn may_have(&self, tag: &str) -> bool {
+        match self {
+            PossibleTags::Any => true,
+            PossibleTags::Only(tags) => tags.contains(tag),
+        }
+    }
+
+    // returns true if the tags changed
+    fn join(&mut self, other: &PossibleTags) -> bool {
+        match (&mut *self, other) {
+            (PossibleTags::Any, _) => false,
+            (_, PossibleTags::Any) => {
+                *self = PossibleTags::Any;
+                true
+            }
+            (PossibleTags::Only(tags), PossibleTags::Only(other_tags)) => {
+                let previous = tags.len();
+                tags.extend(other_tags.iter().cloned());
+                previous != tags.len()
+            }
+        }
+    }
+}
+
+struct Symbol<'a> {
+    declaration: &'a Meta,
+    // None for variables
+    signal_type: Option<SignalType>,
+    tags: PossibleTags,
+}
+
+struct Assignment<'a> {
+    meta: &'a Meta,
+    var: &'a str,
+    access: &'a [Access],
+    op: AssignOp,
+    rhe: &'a Expression,
+}
+
+#[derive(Default)]
+struct TemplateState<'a> {
+    symbols: HashMap<String, Symbol<'a>>,
+    // templates assigned to each component, None if one of them is unknown
+    components: HashMap<String, Option<HashSet<String>>>,
+    assignments: Vec<Assignment<'a>>,
+}
+
+impl<'a> TemplateState<'a> {
+    // template of the component when all its assignments agree on it
+    fn template_of(&self, component: &str) -> Option<&String> {
+        match self.components.get(component) {
+            Some(Some(templates)) if templates.len() == 1 => templates.iter().next(),
+            _ => None,
+        }
+    }
+
+    // assignments that propagate the tags of rhe to the symbol var
+    fn propagates_tags(&self, assignment: &Assignment) -> bool {
+        let is_component_access = assignment.access.iter().any(|acc| matches!(acc, Access::ComponentAccess(_)));
+        match self.symbols.get(assignment.var) {
+            Some(symbol) if !is_component_access => match symbol.signal_type {
+                None => true,
+                Some(SignalType::Input) => false,
+                Some(_) => assignment.op == AssignOp::AssignConstraintSignal,
+            },
+            _ => false,
+        }
+    }
+}
+
+fn analyze<'a>(program_archive: &'a ProgramArchive, template_name: &str) -> TemplateState<'a> {
+    let body = program_archive.get_template_data(template_name).get_body();
+    let mut state = TemplateState::default();
+    collect(body, &mut state);
+    loop {
+        let mut changed = false;
+        for index in 0..state.assignments.len() {
+            let assignment = &state.assignments[index];
+            if !state.propagates_tags(assignment) {
+                continue;
+            }
+            let tags = expression_tags(program_archive, &state, assignment.rhe);
+            let var = assignment.var;
+            changed |= state.symbols.get_mut(var).unwrap().tags.join(&tags);
+        }
+        if !changed {
+            break;
+        }
+    }
+    state
+}
+
+fn expression_tags(program_archive: &ProgramArchive, state: &TemplateState, expr: &Expression) -> PossibleTags {
+    use Expression::*;
+    match expr {
+        Variable { name, access, .. } => {
+            let field = access.iter().find_map(|acc| match acc {
+                Access::ComponentAccess(field) => Some(field),
+                _ => None,
+            });
+            match (state.symbols.get(name), field) {
+                // value of a tag
+                (Some(_), Some(_)) => PossibleTags::none(),
+                (Some(symbol), None) => symbol.tags.clone(),
+                (None, Some(signal)) if state.components.contains_key(name) => {
+                    match state.template_of(name) {
+                        Some(template) => component_signal_tags(program_archive, template, signal),
+                        None => PossibleTags::Any,
+                    }
+                }
+                // parameters of the template
+                _ => PossibleTags::none(),
+            }
+        }
+        InlineSwitchOp { if_true, if_false, .. } => {
+            let mut tags = expression_tags(program_archive, state, if_true);
+            tags.join(&expression_tags(program_archive, state, if_false));
+            tags
+        }
+        AnonymousComp { .. } | Tuple { .. } => PossibleTags::Any,
+        _ => PossibleTags::none(),
+    }
+}
+
+// the signals of a component only have the tags given in their declarations
+fn component_signal_tags(program_archive: &ProgramArchive, template_name: &str, signal: &str) -> PossibleTags {
+    let template = program_archive.get_template_data(template_name);
+    match template.get_input_info(signal).or_else(|| template.get_output_info(signal)) {
+        Some((_, tags)) => PossibleTags::Only(tags.iter().cloned().collect()),
+        None => PossibleTags::Any,
+    }
+}
+
+fn collect<'a>(stmt: &'a Statement, state: &mut TemplateState<'a>) {
+    use Statement::*;
+    match stmt {
+        IfThenElse { if_case, else_case, .. } => {
+            collect(if_case, state);
+            if let Some(else_stmt) = else_case {
+                collect(else_stmt, state);
+            }
+        }
+        While { stmt, .. } => collect(stmt, state),
+        Block { stmts, .. } => stmts.iter().for_each(|s| collect(s, state)),
+        InitializationBlock { initializations, .. } => {
+            initializations.iter().for_each(|s| collect(s, state))
+        }
+        Declaration { meta, xtype, name, .. } => match xtype {
+            VariableType::Var => {
+                let symbol = Symbol { declaration: meta, signal_type: None, tags: PossibleTags::none() };
+                state.symbols.entry(name.clone()).or_insert(symbol);
+            }
+            VariableType::Signal(signal_type, tags, _) => {
+                let tags = PossibleTags::Only(tags.iter().cloned().collect());
+                let symbol = Symbol { declaration: meta, signal_type: Some(*signal_type), tags };
+                state.symbols.insert(name.clone(), symbol);
+            }
+            VariableType::Component | VariableType::AnonymousComponent => {
+                state.components.entry(name.clone()).or_insert_with(|| Some(HashSet::new()));
+            }
+        },
+        Substitution { meta, var, access, op, rhe } => {
+            state.assignments.push(Assignment { meta, var, access, op: *op, rhe });
+        }
+        // the values of the tuple are not followed
+        MultSubstitution { lhe: Expression::Tuple { values, .. }, .. } => {
+            for value in values {
+                if let Expression::Variable { name, .. } = value {
+                    if let Some(symbol) = state.symbols.get_mut(name) {
+                        symbol.tags = PossibleTags::Any;
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    if let Substitution { var, access, rhe, .. } = stmt {
+        if access.iter().all(|acc| matches!(acc, Access::ArrayAccess(_))) {
+            if let Some(templates) = state.components.get_mut(var) {
+                match (templates.as_mut(), template_call(rhe)) {
+                    (Some(templates), Some(template)) => {
+                        templates.insert(template.clone());
+                    }
+                    (Some(_), None) => *templates = None,
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn template_call(expr: &Expression) -> Option<&String> {
+    use Expression::*;
+    match expr {
+        Call { id, .. } => Some(id),
+        ParallelOp { rhe, .. } => template_call(rhe),
+        _ => None,
+    }
+}
+
+fn check_input_assignments(
+    program_archive: &ProgramArchive,
+    state: &TemplateState,
+    file_id: FileID,
+    reports: &mut ReportCollection,
+) {
+    for assignment in &state.assignments {
+        let input = assignment.access.iter().find_map(|acc| match acc {
+            Access::ComponentAccess(signal) => Some(signal),
+            _ => None,
+        });
+        let (template_name, input) = match (state.template_of(assignment.var), input) {
+            (Some(template_name), Some(input)) => (template_name, input),
+            _ => continue,
+        };
+        let template = program_archive.get_template_data(template_name);
+        let required = match template.get_input_info(input) {
+            Some((_, tags)) if !tags.is_empty() => tags,
+            _ => continue,
+        };
+        let tags = expression_tags(program_archive, state, assignment.rhe);
+        let mut missing: Vec<_> = required.iter().filter(|tag| !tags.may_have(tag)).cloned().collect();
+        if missing.is_empty() {
+            continue;
+        }
+        missing.sort();
+        let mut report = Report::error(
+            "Invalid assignment: missing tags required by input signal".to_string(),
+            ReportCode::WrongSignalTags,
+        );
+        report.add_primary(
+            assignment.meta.location.clone(),
+            file_id,
+            format!(
+                "the input {} of {} requires the tag{} {}",
+                input,
+                template_name,
+                if missing.len() > 1 { "s" } else { "" },
+                missing.join(", ")
+            ),
+        );
+        explain_missing_tags(program_archive, state, assignment.rhe, &missing, file_id, &mut report);
+        reports.push(report);
+    }
+}
+
+// points to the places where the value assigned to the input lost the tags
+fn explain_missing_tags(
+    program_archive: &ProgramArchive,
+    state: &TemplateState,
+    rhe: &Expression,
+    missing: &[String],
+    file_id: FileID,
+    report: &mut Report,
+) {
+    let tags = missing.join(", ");
+    let (name, access) = match rhe {
+        Expression::Variable { name, access, .. } => (name, access),
+        _ => {
+            report.add_note(
+                "only signals and variables keep their tags, the result of an operation or a function call has no tags"
+                    .to_string(),
+            );
+            return;
+        }
+    };
+    if let Some(signal) = access.iter().find_map(|acc| match acc {
+        Access::ComponentAccess(signal) => Some(signal),
+        _ => None,
+    }) {
+        if let Some(template_name) = state.template_of(name) {
+            report.add_note(format!(
+                "the signal {} of {} is declared without the tag {}, the signals of a component only have the tags of their declarations",
+                signal, template_name, tags
+            ));
+        }
+        return;
+    }
+    let symbol = match state.symbols.get(name) {
+        Some(symbol) => symbol,
+        None => return,
+    };
+    let kind = if symbol.signal_type.is_some() { "signal" } else { "variable" };
+    report.add_secondary(
+        symbol.declaration.location.clone(),
+        file_id,
+        Some(format!("{} {} is declared without the tag {}", kind, name, tags)),
+    );
+    for assignment in state.assignments.iter().filter(|a| a.var == name.as_str()) {
+        if assignment.access.iter().any(|acc| matches!(acc, Access::ComponentAccess(_))) {
+            continue;
+        }
+        let message = if !state.propagates_tags(assignment) {
+            format!("{} receives a value here, but <-- does not propagate tags", name)
+        } else if missing.iter().any(|tag| !expression_tags(program_archive, state, assignment.rhe).may_have(tag)) {
+            format!("{} receives here a value without the tag {}", name, tags)
+        } else {
+            continue;
+        };
+        report.add_secondary(assignment.meta.location.clone(), file_id, Some(message));
+    }
+}
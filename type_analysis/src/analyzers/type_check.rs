@@ -71,28 +71,32 @@ pub fn type_check(program_archive: &ProgramArchive) -> Result<OutInfo, ReportCol
         environment: TypingEnvironment::new(),
         return_type: Option::None,
     };
-    let initial_expression = program_archive.get_main_expression();
-    let type_analysis_response =
-        type_expression(initial_expression, program_archive, &mut analysis_information);
-    let first_type = if let Result::Ok(t) = type_analysis_response {
-        t
-    } else {
-        return Result::Err(analysis_information.reports);
-    };
-    if !first_type.is_template() {
-        add_report(
-            ReportCode::WrongTypesInAssignOperation,
-            initial_expression.get_meta(),
-            &mut analysis_information.reports,
-        );
-    }
-
-    if check_main_has_tags(initial_expression, program_archive) {
+    // every main component is checked, the templates reached from any of them are kept
+    for main in program_archive.get_mains() {
+        analysis_information.file_id = main.file_id;
+        let initial_expression = &main.initial_template_call;
+        let type_analysis_response =
+            type_expression(initial_expression, program_archive, &mut analysis_information);
+        let first_type = if let Result::Ok(t) = type_analysis_response {
+            t
+        } else {
+            return Result::Err(analysis_information.reports);
+        };
+        if !first_type.is_template() {
             add_report(
-                ReportCode::MainComponentWithTags,
+                ReportCode::WrongTypesInAssignOperation,
                 initial_expression.get_meta(),
                 &mut analysis_information.reports,
             );
+        }
+
+        if check_main_has_tags(initial_expression, program_archive) {
+                add_report(
+                    ReportCode::MainComponentWithTags,
+                    initial_expression.get_meta(),
+                    &mut analysis_information.reports,
+                );
+        }
     }
 
 
@@ -142,7 +146,7 @@ fn type_statement(
                 }
             }
             match xtype {
-                VariableType::Signal(s_type, tags) => {
+                VariableType::Signal(s_type, tags, _) => {
                     if let SignalType::Input = s_type {
                         analysis_information.environment.add_input(name, (dimensions.len(),tags.clone()));
                     } else if let SignalType::Output = s_type {
@@ -285,25 +289,10 @@ fn type_statement(
             }
         }
         LogCall { args, meta } => {
-            for arglog in args {
-                if let LogArgument::LogExp(arg) = arglog{
-                    let arg_response = type_expression(arg, program_archive, analysis_information);
-                    let arg_type = if let Result::Ok(t) = arg_response {
-                        t
-                    } else {
-                        return;
-                    };
-                    if arg_type.is_template() || arg_type.dim() > 0 {
-                        add_report(
-                            ReportCode::MustBeSingleArithmetic,
-                            meta,
-                            &mut analysis_information.reports,
-                        )
-                    }
-                }
-            }
+            type_log_arguments(args, meta, program_archive, analysis_information);
         }
-        Assert { arg, meta } => {
+        Assert { arg, meta, message } => {
+            type_log_arguments(message, meta, program_archive, analysis_information);
             let arg_response = type_expression(arg, program_archive, analysis_information);
             let arg_type = if let Result::Ok(t) = arg_response {
                 t
@@ -378,6 +367,7 @@ fn type_statement(
             }
             analysis_information.environment.remove_variable_block();
         }
+        Break { .. } | Continue { .. } => {}
         MultSubstitution { .. } => unreachable!(),
         UnderscoreSubstitution { rhe , ..} => {
             let rhe_response = type_expression(rhe, program_archive, analysis_information);
@@ -396,6 +386,31 @@ fn type_statement(
         },
     }
 }
+fn type_log_arguments(
+    args: &[LogArgument],
+    meta: &Meta,
+    program_archive: &ProgramArchive,
+    analysis_information: &mut AnalysisInformation,
+) {
+    for arglog in args {
+        for arg in arglog.expressions() {
+            let arg_response = type_expression(arg, program_archive, analysis_information);
+            let arg_type = if let Result::Ok(t) = arg_response {
+                t
+            } else {
+                return;
+            };
+            if arg_type.is_template() || arg_type.dim() > 0 {
+                add_report(
+                    ReportCode::MustBeSingleArithmetic,
+                    meta,
+                    &mut analysis_information.reports,
+                )
+            }
+        }
+    }
+}
+
 fn type_expression(
     expression: &Expression,
     program_archive: &ProgramArchive,
@@ -858,6 +873,19 @@ fn type_template(
     debug_assert!(program_archive.contains_template(call_id));
     if analysis_information.registered_calls.get_instance(call_id, args_dims).is_none() {
         analysis_information.registered_calls.add_instance(call_id, args_dims.to_vec(), 0);
+        for requirement in program_archive.get_template_data(call_id).get_requirements() {
+            let condition_type = type_expression(&requirement.condition, program_archive, analysis_information);
+            if let Result::Ok(condition_type) = condition_type {
+                if condition_type.is_template() || condition_type.dim() > 0 {
+                    add_report(
+                        ReportCode::MustBeSingleArithmetic,
+                        requirement.condition.get_meta(),
+                        &mut analysis_information.reports,
+                    );
+                }
+            }
+            type_log_arguments(&requirement.message, &requirement.meta, program_archive, analysis_information);
+        }
         let stmts = program_archive.get_template_data(call_id).get_body_as_vec();
         treat_sequence_of_statements(stmts, program_archive, analysis_information);
     }
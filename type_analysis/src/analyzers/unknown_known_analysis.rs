@@ -18,6 +18,10 @@ struct ExitInformation {
     constraints_declared: bool,
     tags_modified: bool,
     modified_variables: HashSet<String>,
+    // the statement may execute a break or continue of the enclosing loop,
+    // and it may do it under an unknown condition
+    loop_exit: bool,
+    unknown_loop_exit: bool,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
@@ -59,7 +63,9 @@ fn analyze(stmt: &Statement, entry_information: EntryInformation) -> ExitInforma
         mut reports: ReportCollection,
         mut environment: Environment,
         file_id: FileID,
-    ) -> (bool, bool, ReportCollection, Environment, HashSet<String>) {
+    ) -> (bool, bool, ReportCollection, Environment, HashSet<String>, (bool, bool)) {
+        let mut loop_exit = false;
+        let mut unknown_loop_exit = false;
         let mut constraints_declared = false;
         let mut tags_modified = false;
         let mut modified_variables: HashSet<String> = HashSet::new();
@@ -69,12 +75,14 @@ fn analyze(stmt: &Statement, entry_information: EntryInformation) -> ExitInforma
             constraints_declared = constraints_declared || exit.constraints_declared;
             tags_modified = tags_modified || exit.tags_modified;
             modified_variables.extend(exit.modified_variables);
+            loop_exit = loop_exit || exit.loop_exit;
+            unknown_loop_exit = unknown_loop_exit || exit.unknown_loop_exit;
             for report in exit.reports {
                 reports.push(report);
             }
             environment = exit.environment;
         }
-        (constraints_declared, tags_modified, reports, environment, modified_variables)
+        (constraints_declared, tags_modified, reports, environment, modified_variables, (loop_exit, unknown_loop_exit))
     }
     let file_id = entry_information.file_id;
     let mut reports = ReportCollection::new();
@@ -82,6 +90,8 @@ fn analyze(stmt: &Statement, entry_information: EntryInformation) -> ExitInforma
     let mut modified_variables = HashSet::new();
     let mut constraints_declared = false;
     let mut tags_modified = false;
+    let mut loop_exit = false;
+    let mut unknown_loop_exit = false;
     match stmt {
         Declaration { xtype, name, dimensions, .. } => {
             if let VariableType::Signal(..) = xtype {
@@ -184,12 +194,18 @@ fn analyze(stmt: &Statement, entry_information: EntryInformation) -> ExitInforma
                     environment: new_entry_else_case.environment,
                     reports: ReportCollection::with_capacity(0),
                     modified_variables : HashSet::new(),
-                    tags_modified : false
+                    tags_modified : false,
+                    loop_exit: false,
+                    unknown_loop_exit: false,
                 }
             };
             constraints_declared =
                 else_case_info.constraints_declared || if_case_info.constraints_declared;
             tags_modified = else_case_info.tags_modified || if_case_info.tags_modified;
+            loop_exit = else_case_info.loop_exit || if_case_info.loop_exit;
+            unknown_loop_exit = else_case_info.unknown_loop_exit
+                || if_case_info.unknown_loop_exit
+                || (tag_cond == Unknown && loop_exit);
             modified_variables.extend(if_case_info.modified_variables);
             modified_variables.extend(else_case_info.modified_variables);
             for report in if_case_info.reports {
@@ -247,7 +263,9 @@ fn analyze(stmt: &Statement, entry_information: EntryInformation) -> ExitInforma
             for report in exit.reports {
                 reports.push(report);
             }
-            let tag_out = tag(cond, &environment);
+            // after a break or continue under an unknown condition the
+            // number of iterations is unknown, as with an unknown condition
+            let tag_out = if exit.unknown_loop_exit { Unknown } else { tag(cond, &environment) };
 
             if tag_out == Unknown{
                 for var in &exit.modified_variables{
@@ -277,8 +295,9 @@ fn analyze(stmt: &Statement, entry_information: EntryInformation) -> ExitInforma
         }
         Block { stmts, .. } => {
             environment.add_variable_block();
-            let (nc, tags, nr, ne, nm) = iterate_statements(stmts, reports, environment, file_id);
+            let (nc, tags, nr, ne, nm, exits) = iterate_statements(stmts, reports, environment, file_id);
             constraints_declared = nc;
+            (loop_exit, unknown_loop_exit) = exits;
             reports = nr;
             environment = ne;
             modified_variables = nm;
@@ -286,16 +305,28 @@ fn analyze(stmt: &Statement, entry_information: EntryInformation) -> ExitInforma
             tags_modified = tags;
         }
         InitializationBlock { initializations, .. } => {
-            let (nc, tags, nr, ne, nm) = iterate_statements(initializations, reports, environment, file_id);
+            let (nc, tags, nr, ne, nm, exits) = iterate_statements(initializations, reports, environment, file_id);
             constraints_declared = nc;
+            (loop_exit, unknown_loop_exit) = exits;
             reports = nr;
             environment = ne;
             modified_variables = nm;
             tags_modified = tags;
         }
+        Break { .. } | Continue { .. } => {
+            loop_exit = true;
+        }
         _ => {}
     }
-    ExitInformation { reports, environment, constraints_declared, modified_variables, tags_modified }
+    ExitInformation {
+        reports,
+        environment,
+        constraints_declared,
+        modified_variables,
+        tags_modified,
+        loop_exit,
+        unknown_loop_exit,
+    }
 }
 
 fn tag(expression: &Expression, environment: &Environment) -> Tag {
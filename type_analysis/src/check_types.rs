@@ -80,12 +80,16 @@ fn template_level_analyses(program_archive: &ProgramArchive, reports: &mut Repor
     for template_data in program_archive.get_templates().values() {
         let no_returns_in_template_result = free_of_returns(template_data);
         let signal_declaration_result = check_signal_correctness(template_data);
+        let requirements_result = check_requirements(template_data, program_archive);
         if let Result::Err(mut no_returns_reports) = no_returns_in_template_result {
             reports.append(&mut no_returns_reports);
         }
         if let Result::Err(mut signal_declaration_reports) = signal_declaration_result {
             reports.append(&mut signal_declaration_reports);
         }
+        if let Result::Err(mut requirements_reports) = requirements_result {
+            reports.append(&mut requirements_reports);
+        }
     }
 }
 
@@ -140,4 +144,7 @@ fn semantic_analyses(
             }
         }
     }
+    if let Result::Err(mut tag_reports) = tag_analysis(program_archive) {
+        errors.append(&mut tag_reports);
+    }
 }
@@ -289,10 +289,14 @@ fn expand_statement(stmt: &mut Statement, environment: &mut ExpressionHolder) {
         Substitution { access, rhe, .. } => expand_substitution(access, rhe, environment),
         ConstraintEquality { lhe, rhe, .. } => expand_constraint_equality(lhe, rhe, environment),
         LogCall { args, .. } => expand_log_call(args, environment),
-        Assert { arg, .. } => expand_assert(arg, environment),
+        Assert { arg, message, .. } => {
+            expand_assert(arg, environment);
+            expand_log_call(message, environment);
+        }
         Block { stmts, .. } => expand_block(stmts, environment),
         MultSubstitution { .. } => unreachable!(),
         UnderscoreSubstitution { rhe, .. } => expand_underscore_substitution(rhe, environment),
+        Break { .. } | Continue { .. } => {}
     }
 }
 
@@ -381,7 +385,7 @@ fn expand_constraint_equality(
 
 fn expand_log_call(args: &mut Vec<LogArgument>, environment: &ExpressionHolder) {
     for arglog in args {
-        if let LogArgument::LogExp(arg) = arglog {
+        for arg in arglog.expressions_mut() {
             *arg = expand_expression(arg.clone(), environment);
         }
     }
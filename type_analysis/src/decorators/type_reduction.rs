@@ -18,6 +18,10 @@ pub fn reduce_template(template_data: &mut TemplateData) {
     for param in template_data.get_name_of_params() {
         environment.add_variable(param, ());
     }
+    for requirement in template_data.get_mut_requirements() {
+        reduce_types_in_expression(&mut requirement.condition, &environment);
+        reduce_types_in_log_call(&mut requirement.message, &environment);
+    }
     let body = template_data.get_mut_body();
     reduce_types_in_statement(body, &mut environment);
 }
@@ -43,7 +47,10 @@ fn reduce_types_in_statement(stmt: &mut Statement, environment: &mut Environment
                 reduce_types_in_log_call(args, environment)
             
         },
-        Assert { arg, .. } => reduce_types_in_expression(arg, environment),
+        Assert { arg, message, .. } => {
+            reduce_types_in_expression(arg, environment);
+            reduce_types_in_log_call(message, environment);
+        }
         Return { value, .. } => reduce_types_in_expression(value, environment),
         ConstraintEquality { lhe, rhe, .. } => {
             reduce_types_in_constraint_equality(lhe, rhe, environment)
@@ -52,12 +59,13 @@ fn reduce_types_in_statement(stmt: &mut Statement, environment: &mut Environment
         UnderscoreSubstitution { rhe, .. } => {
             reduce_types_in_expression(rhe, environment);
         },
+        Break { .. } | Continue { .. } => {}
     }
 }
 
 fn reduce_types_in_log_call(args: &mut Vec<LogArgument>, environment: &Environment){
     for arg in args {
-        if let LogArgument::LogExp(exp) = arg {
+        for exp in arg.expressions_mut() {
             reduce_types_in_expression(exp, environment);
         }
     }